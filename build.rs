@@ -0,0 +1,27 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Gzip `commedia.bin`, the binary corpus written by `duca parse`, into
+/// `OUT_DIR` so the library can embed it compressed. Without `embed-data`
+/// the corpus is read at runtime, so there's nothing to do.
+fn main() {
+    println!("cargo:rerun-if-changed=commedia.bin");
+    if env::var_os("CARGO_FEATURE_EMBED_DATA").is_none() {
+        return;
+    }
+
+    let corpus = fs::read("commedia.bin").expect("commedia.bin is readable");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(&corpus)
+        .expect("in-memory write succeeds");
+    let compressed = encoder.finish().expect("in-memory write succeeds");
+
+    let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    fs::write(Path::new(&out_dir).join("commedia.bin.gz"), compressed)
+        .expect("OUT_DIR is writable");
+}
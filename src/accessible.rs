@@ -0,0 +1,313 @@
+//! Output for screen readers, with `--accessible` or `$DUCA_ACCESSIBLE`:
+//! plain lines that each say what they are, in reading order, with no
+//! colors, wrapping, pager or boxes. In place of the TUI there is a reading
+//! loop that takes one command per line, reads a tercet at a time and
+//! announces every move to another canto as a line of its own.
+
+use std::env;
+use std::io::{self, BufRead, Write};
+
+use duca::{CanticaId, Canto, DivinaCommedia, Passage, Verse};
+
+use crate::i18n::{tr, Text};
+
+/// Whether output should be accessible: asked for with the flag, or by
+/// setting `$DUCA_ACCESSIBLE` to anything.
+pub fn enabled(flag: bool) -> bool {
+    flag || env::var_os("DUCA_ACCESSIBLE").is_some_and(|v| !v.is_empty())
+}
+
+/// The line that opens a canto or excerpt of one, saying which lines follow,
+/// e.g. `Inferno, canto I, lines 1 to 136 of 136.`
+pub fn canto_heading(cantica: &str, canto: &Canto, shown: &[(usize, &Verse)]) -> String {
+    let first = shown.first().map_or(0, |(_, verse)| verse.line_number);
+    let last = shown.last().map_or(0, |(_, verse)| verse.line_number);
+    tr(
+        Text::AccessibleHeading,
+        &[
+            &cantica,
+            &canto.roman_numeral,
+            &first,
+            &last,
+            &canto.verses.len(),
+        ],
+    )
+}
+
+/// The label before a verse of `canto` output, naming the line cited with
+/// `--around`.
+pub fn line_label(line: usize, around: Option<usize>) -> String {
+    if around == Some(line) {
+        tr(Text::AccessibleCited, &[&line])
+    } else {
+        tr(Text::AccessibleLine, &[&line])
+    }
+}
+
+/// The label before the `n`th of `count` search results.
+pub fn match_label(n: usize, count: usize, part: &str, canto: u8, line: usize) -> String {
+    tr(Text::AccessibleMatch, &[&n, &count, &part, &canto, &line])
+}
+
+/// Where the reading loop is: a canto, and the tercet last read in it.
+struct Reader<'a> {
+    commedia: &'a DivinaCommedia,
+    cantica: CanticaId,
+    canto: &'a Canto,
+    /// The first line of the tercet last read, if one has been.
+    tercet: Option<usize>,
+}
+
+impl<'a> Reader<'a> {
+    /// The cantos in reading order, as the loop steps through them.
+    fn cantos(&self) -> Vec<(CanticaId, &'a Canto)> {
+        self.commedia.cantos().collect()
+    }
+
+    fn position(&self) -> usize {
+        self.cantos()
+            .iter()
+            .position(|(id, canto)| *id == self.cantica && canto.number == self.canto.number)
+            .unwrap_or(0)
+    }
+
+    /// Move to another canto and announce it.
+    fn enter(
+        &mut self,
+        cantica: CanticaId,
+        canto: &'a Canto,
+        output: &mut impl Write,
+    ) -> io::Result<()> {
+        self.cantica = cantica;
+        self.canto = canto;
+        self.tercet = None;
+        writeln!(
+            output,
+            "{}",
+            tr(
+                Text::ReaderCanto,
+                &[&cantica, &canto.roman_numeral, &canto.verses.len()]
+            )
+        )
+    }
+
+    /// Step to the next or previous canto, announcing where that leads.
+    fn step_canto(&mut self, forward: bool, output: &mut impl Write) -> io::Result<bool> {
+        let cantos = self.cantos();
+        let position = self.position();
+        let next = if forward {
+            cantos.get(position + 1)
+        } else {
+            position.checked_sub(1).and_then(|i| cantos.get(i))
+        };
+        match next {
+            Some(&(cantica, canto)) => {
+                self.enter(cantica, canto, output)?;
+                Ok(true)
+            }
+            None => {
+                let edge = if forward {
+                    Text::ReaderEnd
+                } else {
+                    Text::ReaderBeginning
+                };
+                writeln!(output, "{}", tr(edge, &[]))?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Read the tercet holding `line`, labeled with its lines.
+    fn read(&mut self, line: usize, output: &mut impl Write) -> io::Result<()> {
+        let Some(tercet) = self.canto.tercet_at(line) else {
+            return Ok(());
+        };
+        let (first, last) = (tercet[0].line_number, tercet[tercet.len() - 1].line_number);
+        let label = if first == last {
+            tr(Text::ReaderLine, &[&first])
+        } else {
+            tr(Text::ReaderLines, &[&first, &last])
+        };
+        writeln!(output, "{}", label)?;
+        for verse in tercet {
+            writeln!(output, "{}", verse.text)?;
+        }
+        self.tercet = Some(first);
+        Ok(())
+    }
+
+    fn next_tercet(&mut self, output: &mut impl Write) -> io::Result<()> {
+        let next = match self.tercet {
+            Some(first) => first + 3,
+            None => 1,
+        };
+        if self.canto.verse(next).is_some() {
+            self.read(next, output)
+        } else if self.step_canto(true, output)? {
+            self.read(1, output)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn previous_tercet(&mut self, output: &mut impl Write) -> io::Result<()> {
+        match self.tercet.and_then(|first| first.checked_sub(3)) {
+            Some(line) if line >= 1 => self.read(line, output),
+            _ => writeln!(output, "{}", tr(Text::ReaderBeginningOfCanto, &[])),
+        }
+    }
+
+    /// Go to a passage such as `inferno 5` or `purg 3.40`.
+    fn go(&mut self, passage: &str, output: &mut impl Write) -> io::Result<()> {
+        match passage.parse() {
+            Ok(passage) => self.go_to(passage, output),
+            Err(e) => writeln!(output, "{}", e),
+        }
+    }
+
+    /// Go to `passage`, reading its first tercet if it names lines.
+    fn go_to(&mut self, passage: Passage, output: &mut impl Write) -> io::Result<()> {
+        let canto = match self.commedia.require_canto(passage.cantica, passage.canto) {
+            Ok(canto) => canto,
+            Err(e) => return writeln!(output, "{}", e),
+        };
+        self.enter(passage.cantica, canto, output)?;
+        if let Some(lines) = passage.lines {
+            self.read(*lines.start(), output)?;
+        }
+        Ok(())
+    }
+
+    fn search(&self, pattern: &str, output: &mut impl Write) -> io::Result<()> {
+        let results = self.commedia.search(pattern, None);
+        if results.is_empty() {
+            return match self.commedia.suggest(pattern) {
+                Some(suggestion) => writeln!(
+                    output,
+                    "{}",
+                    tr(Text::NoMatchesSuggest, &[&pattern, &suggestion])
+                ),
+                None => writeln!(output, "{}", tr(Text::NoMatches, &[&pattern])),
+            };
+        }
+        writeln!(
+            output,
+            "{}",
+            tr(Text::FoundMatches, &[&results.len(), &pattern])
+        )?;
+        for m in &results {
+            writeln!(output, "{} {}.{}: {}", m.cantica, m.canto, m.line, m.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read `commedia` from `start`, or its beginning, taking commands from
+/// `input` one per line until `q` or the end of input.
+pub fn read_loop(
+    commedia: &DivinaCommedia,
+    start: Option<Passage>,
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let Some((cantica, canto)) = commedia.cantos().next() else {
+        return Ok(());
+    };
+    let mut reader = Reader {
+        commedia,
+        cantica,
+        canto,
+        tercet: None,
+    };
+    writeln!(output, "{}", tr(Text::ReaderWelcome, &[]))?;
+    match start {
+        Some(passage) => reader.go_to(passage, output)?,
+        None => reader.enter(cantica, canto, output)?,
+    }
+
+    for line in input.lines() {
+        let line = line?;
+        let (command, argument) = match line.trim().split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line.trim(), ""),
+        };
+        match command {
+            "" | "n" => reader.next_tercet(output)?,
+            "p" => reader.previous_tercet(output)?,
+            "r" => match reader.tercet {
+                Some(first) => reader.read(first, output)?,
+                None => reader.next_tercet(output)?,
+            },
+            "]" => {
+                reader.step_canto(true, output)?;
+            }
+            "[" => {
+                reader.step_canto(false, output)?;
+            }
+            "g" => reader.go(argument, output)?,
+            "w" => {
+                let here = reader.tercet.unwrap_or(1);
+                writeln!(
+                    output,
+                    "{} {}.{}",
+                    reader.cantica, reader.canto.number, here
+                )?;
+            }
+            "s" | "/" => reader.search(argument, output)?,
+            "h" | "?" => writeln!(output, "{}", tr(Text::ReaderHelp, &[]))?,
+            "q" => break,
+            _ => writeln!(output, "{}", tr(Text::ReaderUnknown, &[&command]))?,
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::load_commedia;
+
+    fn session(start: Option<&str>, input: &str) -> String {
+        let commedia = load_commedia().unwrap();
+        let start = start.map(|passage| passage.parse().unwrap());
+        let mut output = Vec::new();
+        read_loop(&commedia, start, input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_read_loop_reads_by_tercet() {
+        let output = session(None, "\nn\np\nq\nn\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "Inferno, canto I, 136 lines.");
+        assert_eq!(lines[2], "Lines 1 to 3:");
+        assert!(lines[3].starts_with("Nel mezzo del cammin"));
+        assert_eq!(lines[6], "Lines 4 to 6:");
+        assert_eq!(lines[10], "Lines 1 to 3:");
+        assert_eq!(lines.len(), 14, "nothing is read after q");
+    }
+
+    #[test]
+    fn test_read_loop_announces_cantos() {
+        let output = session(Some("Inferno 34.136"), "n\n[\nn\n");
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "Inferno, canto XXXIV, 139 lines.");
+        assert_eq!(lines[2], "Lines 136 to 138:");
+        assert_eq!(lines[6], "Line 139:");
+        assert_eq!(lines[8], "Inferno, canto XXXIII, 157 lines.");
+        assert_eq!(lines[9], "Lines 1 to 3:");
+
+        let output = session(Some("Paradiso 33.145"), "n\n");
+        assert!(output
+            .ends_with("Line 145:\nl’amor che move il sole e l’altre stelle.\nEnd of the poem.\n"));
+    }
+
+    #[test]
+    fn test_read_loop_commands() {
+        let output = session(None, "g purg 2\nw\ns xyznomatch123\nx\n");
+        assert!(output.contains("Purgatorio, canto II, 133 lines.\nPurgatorio 2.1\n"));
+        assert!(output.contains("No matches found for 'xyznomatch123'\n"));
+        assert!(output.ends_with("Unknown command 'x'; h for help.\n"));
+    }
+}
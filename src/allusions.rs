@@ -0,0 +1,158 @@
+//! Allusions of the poem to the Aeneid, the Bible and Ovid, from a curated
+//! list compiled into duca: the passage, the place alluded to and a note on
+//! what's borrowed, shown beside the passage's first verse.
+
+use serde::Deserialize;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{CanticaId, DucaError, Passage};
+
+/// The allusions compiled into duca.
+const BUILTIN: &str = include_str!("../allusions/allusions.toml");
+
+/// The work alluded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Aeneid,
+    Bible,
+    Ovid,
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Aeneid => "Aeneid",
+            Source::Bible => "Bible",
+            Source::Ovid => "Ovid",
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A passage alluding to another work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allusion {
+    pub passage: Passage,
+    pub source: Source,
+    /// The place alluded to, such as `Aeneid 4.23`.
+    pub reference: String,
+    pub note: String,
+}
+
+impl Allusion {
+    /// The verse it's shown beside: its passage's first.
+    pub fn line(&self) -> usize {
+        self.passage
+            .lines
+            .as_ref()
+            .map_or(1, |lines| *lines.start())
+    }
+}
+
+#[derive(Deserialize)]
+struct AllusionsFile {
+    #[serde(default)]
+    allusion: Vec<RawAllusion>,
+}
+
+#[derive(Deserialize)]
+struct RawAllusion {
+    passage: String,
+    source: Source,
+    reference: String,
+    note: String,
+}
+
+/// Parse a list of allusions; `name` identifies it in errors.
+pub fn parse(content: &str, name: &str) -> Result<Vec<Allusion>, DucaError> {
+    let invalid = |reason: String| DucaError::UserData {
+        path: name.to_string(),
+        reason,
+    };
+    let file: AllusionsFile =
+        toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+    file.allusion
+        .into_iter()
+        .map(|raw| {
+            let passage: Passage = raw
+                .passage
+                .parse()
+                .map_err(|e: DucaError| invalid(e.to_string()))?;
+            Ok(Allusion {
+                passage,
+                source: raw.source,
+                reference: raw.reference,
+                note: raw.note,
+            })
+        })
+        .collect()
+}
+
+/// The built-in allusions, in reading order.
+pub fn builtin() -> &'static [Allusion] {
+    static ALLUSIONS: OnceLock<Vec<Allusion>> = OnceLock::new();
+    ALLUSIONS.get_or_init(|| {
+        let mut allusions = parse(BUILTIN, "allusions.toml").expect("built-in allusions are valid");
+        allusions.sort_by_key(|a| (a.passage.cantica, a.passage.canto, a.line()));
+        allusions
+    })
+}
+
+/// The allusions of `allusions` shown beside verse `line` of a canto.
+pub fn for_line(
+    allusions: &[Allusion],
+    cantica: CanticaId,
+    canto: u8,
+    line: usize,
+) -> Vec<&Allusion> {
+    allusions
+        .iter()
+        .filter(|a| a.passage.cantica == cantica && a.passage.canto == canto && a.line() == line)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_allusions() {
+        let commedia = crate::commedia().unwrap();
+        for allusion in builtin() {
+            let passage = &allusion.passage;
+            let canto = commedia.canto(passage.cantica, passage.canto).unwrap();
+            let last = passage.lines.as_ref().map_or(1, |lines| *lines.end());
+            assert!(canto.verse(last).is_some(), "{}", passage);
+        }
+        let key = |a: &Allusion| (a.passage.cantica, a.passage.canto, a.line());
+        assert!(builtin().windows(2).all(|w| key(&w[0]) <= key(&w[1])));
+
+        let dido = for_line(builtin(), CanticaId::Purgatorio, 30, 48);
+        assert_eq!(dido[0].source, Source::Aeneid);
+        assert_eq!(dido[0].reference, "Aeneid 4.23");
+        // Shown beside the first verse of a passage only
+        assert_eq!(for_line(builtin(), CanticaId::Inferno, 13, 31).len(), 1);
+        assert!(for_line(builtin(), CanticaId::Inferno, 13, 32).is_empty());
+        let sources = [Source::Aeneid, Source::Bible, Source::Ovid];
+        assert!(sources
+            .iter()
+            .all(|source| builtin().iter().any(|a| a.source == *source)));
+    }
+
+    #[test]
+    fn test_parse_allusions() {
+        let bad = "[[allusion]]\npassage = \"Inferno 1.1\"\nsource = \"homer\"\n\
+                   reference = \"Iliad 1.1\"\nnote = \"\"\n";
+        assert!(parse(bad, "test.toml").is_err());
+        let bad = "[[allusion]]\npassage = \"Inferno\"\nsource = \"ovid\"\n\
+                   reference = \"\"\nnote = \"\"\n";
+        assert!(parse(bad, "test.toml").is_err());
+    }
+}
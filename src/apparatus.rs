@@ -0,0 +1,208 @@
+//! A critical apparatus: variant readings of single lines, read from the
+//! user's TOML files.
+//!
+//! An apparatus file names its source and lists variants by line, each with
+//! the reading it replaces (the lemma), if given, and the witnesses that
+//! carry it:
+//!
+//! ```toml
+//! source = "My apparatus"
+//!
+//! [[variant]]
+//! line = "Inferno 1.4"
+//! lemma = "Ahi quanto"
+//! reading = "E quanto"
+//! witnesses = "Ash, Ham"
+//! ```
+
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::{paths, CanticaId, Citation, DucaError};
+
+/// A variant reading of one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub citation: Citation,
+    /// The words of the text the variant replaces; `None` for the whole line.
+    pub lemma: Option<String>,
+    pub reading: String,
+    /// The manuscripts or editions with this reading.
+    pub witnesses: Option<String>,
+    /// The apparatus the variant comes from.
+    pub source: String,
+}
+
+impl fmt::Display for Variant {
+    /// Apparatus notation: `lemma] reading (witnesses)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(lemma) = &self.lemma {
+            write!(f, "{}] ", lemma)?;
+        }
+        write!(f, "{}", self.reading)?;
+        if let Some(witnesses) = &self.witnesses {
+            write!(f, " ({})", witnesses)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every loaded variant, in the order the files listed them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Apparatus {
+    variants: Vec<Variant>,
+}
+
+#[derive(Deserialize)]
+struct ApparatusFile {
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default, alias = "variants")]
+    variant: Vec<RawVariant>,
+}
+
+#[derive(Deserialize)]
+struct RawVariant {
+    line: String,
+    #[serde(default)]
+    lemma: Option<String>,
+    reading: String,
+    #[serde(default)]
+    witnesses: Option<String>,
+}
+
+impl Apparatus {
+    /// Parse one apparatus file's content. `name` identifies it in errors
+    /// and is the source for a file that doesn't name one.
+    pub fn parse(content: &str, name: &str) -> Result<Self, DucaError> {
+        let invalid = |reason: String| DucaError::UserData {
+            path: name.to_string(),
+            reason,
+        };
+        let file: ApparatusFile =
+            toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+        let source = file.source.unwrap_or_else(|| name.to_string());
+
+        let variants = file
+            .variant
+            .into_iter()
+            .map(|raw| {
+                Ok(Variant {
+                    citation: raw
+                        .line
+                        .parse()
+                        .map_err(|e: DucaError| invalid(e.to_string()))?,
+                    lemma: raw.lemma,
+                    reading: raw.reading,
+                    witnesses: raw.witnesses,
+                    source: source.clone(),
+                })
+            })
+            .collect::<Result<_, DucaError>>()?;
+        Ok(Self { variants })
+    }
+
+    /// Read every `.toml` file in `dir`, in file name order. A missing
+    /// directory is an empty apparatus.
+    pub fn load_dir(dir: &Path) -> Result<Self, DucaError> {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut files: Vec<_> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+
+        let mut apparatus = Self::default();
+        for path in files {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(&path).map_err(|e| DucaError::UserData {
+                path: name.clone(),
+                reason: e.to_string(),
+            })?;
+            let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+            apparatus.extend(Self::parse(&content, stem.as_deref().unwrap_or(&name))?);
+        }
+        Ok(apparatus)
+    }
+
+    /// The user's apparatus, from [`paths::apparatus_dir`].
+    pub fn load() -> Result<Self, DucaError> {
+        match paths::apparatus_dir() {
+            Some(dir) => Self::load_dir(&dir),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn extend(&mut self, other: Apparatus) {
+        self.variants.extend(other.variants);
+    }
+
+    /// The variants of one line, in file order.
+    pub fn for_line(&self, cantica: CanticaId, canto: u8, line: usize) -> Vec<&Variant> {
+        self.variants
+            .iter()
+            .filter(|v| {
+                v.citation.cantica == cantica
+                    && v.citation.canto == canto
+                    && v.citation.line == line
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+source = "Test apparatus"
+
+[[variant]]
+line = "Inferno 1.4"
+lemma = "Ahi quanto"
+reading = "E quanto"
+witnesses = "Ash, Ham"
+
+[[variant]]
+line = "Inf. I.4"
+reading = "Ai quanto a dir qual era è cosa dura"
+"#;
+
+    #[test]
+    fn test_parse_apparatus() {
+        let apparatus = Apparatus::parse(SAMPLE, "sample.toml").unwrap();
+        assert_eq!(apparatus.len(), 2);
+
+        let variants = apparatus.for_line(CanticaId::Inferno, 1, 4);
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].source, "Test apparatus");
+        assert_eq!(variants[0].to_string(), "Ahi quanto] E quanto (Ash, Ham)");
+        assert_eq!(
+            variants[1].to_string(),
+            "Ai quanto a dir qual era è cosa dura"
+        );
+        assert!(apparatus.for_line(CanticaId::Inferno, 1, 5).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_apparatus() {
+        let error = Apparatus::parse(
+            "[[variant]]\nline = \"Inferno 1\"\nreading = \"x\"",
+            "mine.toml",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("mine.toml"));
+    }
+}
@@ -0,0 +1,249 @@
+//! Recordings of the poem read aloud, such as LibriVox's, registered by the
+//! user in TOML files so the TUI can play a canto and follow it verse by
+//! verse.
+//!
+//! A file names its source and lists one recording per canto. A recording's
+//! file is found relative to the TOML file. Its timing is optional: `start`
+//! and `end` are the seconds at which the first line begins and the last
+//! one ends, and `timestamps` pins the start of any lines between, with the
+//! lines in between spread evenly:
+//!
+//! ```toml
+//! source = "LibriVox"
+//!
+//! [[recording]]
+//! canto = "Inferno 1"
+//! file = "inferno_01.mp3"
+//! start = 21.5
+//! end = 612
+//! timestamps = [[28, 130.0], [61, 282.5]]
+//! ```
+//!
+//! Without an `end` or timestamps there is nothing to follow the reading
+//! by, and the recording just plays.
+
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{paths, verify, CanticaId, DucaError, Passage};
+
+/// A recording of one canto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub file: PathBuf,
+    /// The reader or collection the recording comes from.
+    pub source: String,
+    /// Lines and the seconds they start at, in order of both; a line one
+    /// past the last stands for the end of the reading.
+    timing: Vec<(usize, f64)>,
+}
+
+impl Recording {
+    /// The line being read `seconds` into the recording of a canto of
+    /// `lines` lines, or `None` before the reading starts or if there is no
+    /// timing to tell by.
+    pub fn line_at(&self, seconds: f64, lines: usize) -> Option<usize> {
+        let last = self.timing.last()?;
+        if self.timing.len() < 2 || seconds < self.timing[0].1 {
+            return None;
+        }
+        if seconds >= last.1 {
+            return Some(last.0.min(lines));
+        }
+        let i = self.timing.partition_point(|&(_, start)| start <= seconds);
+        let ((line, start), (next_line, next_start)) = (self.timing[i - 1], self.timing[i]);
+        let fraction = (seconds - start) / (next_start - start);
+        let line = line + ((next_line - line) as f64 * fraction) as usize;
+        Some(line.min(lines))
+    }
+}
+
+/// Every registered recording, in the order the files listed them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recordings {
+    recordings: Vec<Recording>,
+}
+
+#[derive(Deserialize)]
+struct RecordingsFile {
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default, alias = "recordings")]
+    recording: Vec<RawRecording>,
+}
+
+#[derive(Deserialize)]
+struct RawRecording {
+    canto: String,
+    file: PathBuf,
+    #[serde(default)]
+    start: f64,
+    #[serde(default)]
+    end: Option<f64>,
+    #[serde(default)]
+    timestamps: Vec<(usize, f64)>,
+}
+
+impl Recordings {
+    /// Parse one recordings file's content. `name` identifies it in errors
+    /// and is the source for a file that doesn't name one; recordings' files
+    /// are relative to `dir`.
+    pub fn parse(content: &str, name: &str, dir: &Path) -> Result<Self, DucaError> {
+        let invalid = |reason: String| DucaError::UserData {
+            path: name.to_string(),
+            reason,
+        };
+        let file: RecordingsFile =
+            toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+        let source = file.source.unwrap_or_else(|| name.to_string());
+
+        let recordings = file
+            .recording
+            .into_iter()
+            .map(|raw| {
+                let passage: Passage = raw
+                    .canto
+                    .parse()
+                    .map_err(|e: DucaError| invalid(e.to_string()))?;
+                let lines = verify::expected_lines(passage.cantica, passage.canto)
+                    .ok_or_else(|| invalid(format!("{} has no such canto", passage.cantica)))?;
+
+                let mut timing = vec![(1, raw.start)];
+                timing.extend(raw.timestamps);
+                timing.extend(raw.end.map(|end| (lines + 1, end)));
+                timing.sort_by_key(|&(line, _)| line);
+                timing.dedup_by_key(|&mut (line, _)| line);
+                if timing.windows(2).any(|pair| pair[0].1 >= pair[1].1) {
+                    let reason = format!(
+                        "timestamps of {} {} must grow with the lines",
+                        passage.cantica, passage.canto
+                    );
+                    return Err(invalid(reason));
+                }
+
+                Ok(Recording {
+                    cantica: passage.cantica,
+                    canto: passage.canto,
+                    file: dir.join(raw.file),
+                    source: source.clone(),
+                    timing,
+                })
+            })
+            .collect::<Result<_, DucaError>>()?;
+        Ok(Self { recordings })
+    }
+
+    /// Read every `.toml` file in `dir`, in file name order. A missing
+    /// directory has no recordings.
+    pub fn load_dir(dir: &Path) -> Result<Self, DucaError> {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut files: Vec<_> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+
+        let mut recordings = Self::default();
+        for path in files {
+            let name = path.display().to_string();
+            let content = fs::read_to_string(&path).map_err(|e| DucaError::UserData {
+                path: name.clone(),
+                reason: e.to_string(),
+            })?;
+            let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+            let parsed = Self::parse(&content, stem.as_deref().unwrap_or(&name), dir)?;
+            recordings.recordings.extend(parsed.recordings);
+        }
+        Ok(recordings)
+    }
+
+    /// The user's recordings, from [`paths::audio_dir`].
+    pub fn load() -> Result<Self, DucaError> {
+        match paths::audio_dir() {
+            Some(dir) => Self::load_dir(&dir),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// The first recording of a canto.
+    pub fn for_canto(&self, cantica: CanticaId, canto: u8) -> Option<&Recording> {
+        self.recordings
+            .iter()
+            .find(|r| r.cantica == cantica && r.canto == canto)
+    }
+
+    pub fn len(&self) -> usize {
+        self.recordings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recordings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+source = "LibriVox"
+
+[[recording]]
+canto = "Inferno 1"
+file = "inferno_01.mp3"
+start = 20
+end = 701
+timestamps = [[28, 146.0]]
+
+[[recording]]
+canto = "Purg. II"
+file = "/music/purgatorio_02.ogg"
+"#;
+
+    #[test]
+    fn test_parse_recordings() {
+        let recordings = Recordings::parse(SAMPLE, "librivox", Path::new("/audio")).unwrap();
+        assert_eq!(recordings.len(), 2);
+
+        let inferno = recordings.for_canto(CanticaId::Inferno, 1).unwrap();
+        assert_eq!(inferno.file, Path::new("/audio/inferno_01.mp3"));
+        assert_eq!(inferno.source, "LibriVox");
+        let purgatorio = recordings.for_canto(CanticaId::Purgatorio, 2).unwrap();
+        assert_eq!(purgatorio.file, Path::new("/music/purgatorio_02.ogg"));
+        assert!(recordings.for_canto(CanticaId::Paradiso, 1).is_none());
+    }
+
+    #[test]
+    fn test_line_at() {
+        let recordings = Recordings::parse(SAMPLE, "librivox", Path::new("/audio")).unwrap();
+        let inferno = recordings.for_canto(CanticaId::Inferno, 1).unwrap();
+        assert_eq!(inferno.line_at(0.0, 136), None);
+        assert_eq!(inferno.line_at(20.0, 136), Some(1));
+        // 27 lines in 126 seconds, so a line every 4.67
+        assert_eq!(inferno.line_at(25.0, 136), Some(2));
+        assert_eq!(inferno.line_at(145.9, 136), Some(27));
+        assert_eq!(inferno.line_at(146.0, 136), Some(28));
+        assert_eq!(inferno.line_at(700.0, 136), Some(136));
+        assert_eq!(inferno.line_at(900.0, 136), Some(136));
+
+        // Without timing there's no following the reading
+        let purgatorio = recordings.for_canto(CanticaId::Purgatorio, 2).unwrap();
+        assert_eq!(purgatorio.line_at(60.0, 133), None);
+    }
+
+    #[test]
+    fn test_invalid_recordings() {
+        let backwards =
+            "[[recording]]\ncanto = \"Inferno 1\"\nfile = \"a.mp3\"\nstart = 30\nend = 10\n";
+        let err = Recordings::parse(backwards, "bad.toml", Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("must grow with the lines"));
+
+        let no_canto = "[[recording]]\ncanto = \"Inferno 35\"\nfile = \"a.mp3\"\n";
+        assert!(Recordings::parse(no_canto, "bad.toml", Path::new(".")).is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! Backups of the reader's notes, bookmarks and reading log as one JSON
+//! file, for moving them between machines: `duca data export` writes one,
+//! and `duca data import` merges one into the notes directory and the log.
+//!
+//! Merging adds what's missing and keeps what's there. A bookmark on a line
+//! already bookmarked only brings its label to one that has none, and the
+//! tags it lacks; a note is
+//! added unless the same lines already have a note with the same text; a
+//! reading session is added unless the log has the same line, and the log
+//! is then kept in date order. So importing a backup twice changes nothing,
+//! and two machines that import each other's backups end up with the same
+//! notes and the same log.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::notes::{self, CantoNotes, NoteStore};
+use crate::plan::Date;
+use crate::{verify, CanticaId, DucaError};
+
+/// The backup format's version, bumped when it changes incompatibly.
+/// Version 2 added the reading log.
+pub const VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkEntry {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteEntry {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Everything a backup holds, in reading order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    #[serde(default)]
+    pub bookmarks: Vec<BookmarkEntry>,
+    #[serde(default)]
+    pub notes: Vec<NoteEntry>,
+    /// The reading log's sessions, one line each as the log has them: the
+    /// date, minutes read, breaks taken and cantos read, tab-separated.
+    #[serde(default)]
+    pub reading_log: Vec<String>,
+}
+
+/// What a merge added.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Merged {
+    pub bookmarks: usize,
+    pub labels: usize,
+    pub tags: usize,
+    pub notes: usize,
+    pub sessions: usize,
+}
+
+impl Backup {
+    /// Everything in `store`, and the sessions in the reading log at
+    /// `reading_log` if there is one.
+    pub fn from_store(store: &NoteStore, reading_log: Option<&Path>) -> Result<Self, DucaError> {
+        let mut backup = Self {
+            version: VERSION,
+            bookmarks: Vec::new(),
+            notes: Vec::new(),
+            reading_log: match reading_log {
+                Some(path) => read_log(path)?,
+                None => Vec::new(),
+            },
+        };
+        for canto in store.all()? {
+            backup
+                .bookmarks
+                .extend(canto.bookmarks.iter().map(|bookmark| BookmarkEntry {
+                    cantica: canto.cantica,
+                    canto: canto.canto,
+                    line: bookmark.line,
+                    label: bookmark.label.clone(),
+                    tags: bookmark.tags.clone(),
+                }));
+            backup
+                .notes
+                .extend(canto.notes.iter().map(|note| NoteEntry {
+                    cantica: canto.cantica,
+                    canto: canto.canto,
+                    start: *note.lines.start(),
+                    end: *note.lines.end(),
+                    text: note.text.clone(),
+                }));
+        }
+        Ok(backup)
+    }
+
+    /// Parse a backup file's content, checking that its version is one this
+    /// build reads and that every entry is on lines the poem has. `name`
+    /// identifies it in errors.
+    pub fn parse(content: &str, name: &str) -> Result<Self, DucaError> {
+        let invalid = |reason: String| DucaError::UserData {
+            path: name.to_string(),
+            reason,
+        };
+        let backup: Self = serde_json::from_str(content).map_err(|e| invalid(e.to_string()))?;
+        if backup.version > VERSION {
+            return Err(invalid(format!(
+                "backup version {} is newer than this duca reads ({})",
+                backup.version, VERSION
+            )));
+        }
+
+        let check = |cantica: CanticaId, canto: u8, start: usize, end: usize| {
+            let lines = verify::expected_lines(cantica, canto)
+                .ok_or_else(|| invalid(format!("{} has no canto {}", cantica, canto)))?;
+            if start < 1 || start > end || end > lines {
+                return Err(invalid(format!(
+                    "{} {} has no lines {}-{}",
+                    cantica, canto, start, end
+                )));
+            }
+            Ok(())
+        };
+        for bookmark in &backup.bookmarks {
+            check(
+                bookmark.cantica,
+                bookmark.canto,
+                bookmark.line,
+                bookmark.line,
+            )?;
+        }
+        for note in &backup.notes {
+            check(note.cantica, note.canto, note.start, note.end)?;
+        }
+        for session in &backup.reading_log {
+            let fields: Vec<&str> = session.split('\t').collect();
+            if fields.len() != 4 || fields[0].parse::<Date>().is_err() {
+                return Err(invalid(format!("not a reading session: {:?}", session)));
+            }
+        }
+        Ok(backup)
+    }
+
+    /// Merge the backup into `store` under its lock, rewriting only the
+    /// cantos it adds to, and its sessions into the reading log at
+    /// `reading_log`, rewritten only if it gains one.
+    pub fn merge_into(
+        &self,
+        store: &NoteStore,
+        reading_log: Option<&Path>,
+    ) -> Result<Merged, DucaError> {
+        let lock = store.lock()?;
+        let cantos: BTreeSet<(CanticaId, u8)> = self
+            .bookmarks
+            .iter()
+            .map(|b| (b.cantica, b.canto))
+            .chain(self.notes.iter().map(|n| (n.cantica, n.canto)))
+            .collect();
+
+        let mut merged = Merged::default();
+        for (cantica, canto) in cantos {
+            store.resolve_conflicts(&lock, cantica, canto)?;
+            let mut local = store.load(cantica, canto)?;
+            let before = merged;
+            self.merge_canto(&mut local, &mut merged);
+            if merged != before {
+                store.save(&local)?;
+            }
+        }
+        if let Some(path) = reading_log {
+            merged.sessions = self.merge_log(path)?;
+        }
+        Ok(merged)
+    }
+
+    /// Add the sessions the log at `path` lacks, keeping it in date order,
+    /// returning how many were added.
+    fn merge_log(&self, path: &Path) -> Result<usize, DucaError> {
+        let mut log = read_log(path)?;
+        let before = log.len();
+        for session in &self.reading_log {
+            if !log.contains(session) {
+                log.push(session.clone());
+            }
+        }
+        let added = log.len() - before;
+        if added == 0 {
+            return Ok(0);
+        }
+        // Lines start with the date, so this sorts by it
+        log.sort();
+        let unwritable = |e: io::Error| DucaError::Save {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(unwritable)?;
+        }
+        let content: String = log.iter().map(|line| format!("{}\n", line)).collect();
+        notes::write_whole(path, content.as_bytes()).map_err(unwritable)?;
+        Ok(added)
+    }
+
+    /// Merge the backup's entries for `local`'s canto into it.
+    fn merge_canto(&self, local: &mut CantoNotes, merged: &mut Merged) {
+        let here = |cantica, canto| cantica == local.cantica && canto == local.canto;
+        let bookmarks: Vec<_> = self
+            .bookmarks
+            .iter()
+            .filter(|b| here(b.cantica, b.canto))
+            .collect();
+        let notes: Vec<_> = self
+            .notes
+            .iter()
+            .filter(|n| here(n.cantica, n.canto))
+            .collect();
+
+        for bookmark in bookmarks {
+            let label = bookmark.label.as_deref();
+            match local.bookmarks.iter().find(|b| b.line == bookmark.line) {
+                None => {
+                    local.set_bookmark(bookmark.line, label);
+                    merged.bookmarks += 1;
+                }
+                Some(existing) if existing.label.is_none() && label.is_some() => {
+                    local.set_bookmark(bookmark.line, label);
+                    merged.labels += 1;
+                }
+                Some(_) => {}
+            }
+            merged.tags += local.tag_bookmark(bookmark.line, &bookmark.tags);
+        }
+        for note in notes {
+            let lines = note.start..=note.end;
+            if !local.contains_note(&lines, &note.text) {
+                local.add_note(lines, &note.text);
+                merged.notes += 1;
+            }
+        }
+    }
+}
+
+/// The lines of the reading log at `path`; none if there's no log yet.
+fn read_log(path: &Path) -> Result<Vec<String>, DucaError> {
+    match fs::read_to_string(path) {
+        Ok(log) => Ok(log
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(DucaError::UserData {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn store(name: &str) -> NoteStore {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("duca-backup-{}-{}", name, std::process::id()));
+        NoteStore::new(dir)
+    }
+
+    #[test]
+    fn test_export_and_parse() {
+        let home = store("export");
+        let mut notes = home.load(CanticaId::Inferno, 26).unwrap();
+        notes.set_bookmark(118, Some("fatti non foste"));
+        notes.tag_bookmark(118, &["ulysses"]);
+        notes.add_note(112..=120, "Ulysses' orazion picciola");
+        home.save(&notes).unwrap();
+
+        let backup = Backup::from_store(&home, None).unwrap();
+        let json = serde_json::to_string_pretty(&backup).unwrap();
+        assert_eq!(Backup::parse(&json, "backup.json").unwrap(), backup);
+        assert_eq!(
+            backup.bookmarks[0].label.as_deref(),
+            Some("fatti non foste")
+        );
+        assert_eq!(backup.bookmarks[0].tags, ["ulysses"]);
+        assert_eq!(backup.notes[0].start, 112);
+
+        let bad = json.replace("\"line\": 118", "\"line\": 500");
+        assert!(Backup::parse(&bad, "backup.json").is_err());
+        let newer = json.replace("\"version\": 2", "\"version\": 99");
+        let err = Backup::parse(&newer, "backup.json").unwrap_err();
+        assert!(err.to_string().contains("newer"));
+        std::fs::remove_dir_all(home.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_merge() {
+        let (home, work) = (store("home"), store("work"));
+        let mut notes = home.load(CanticaId::Inferno, 26).unwrap();
+        notes.set_bookmark(118, None);
+        notes.tag_bookmark(118, &["ulysses"]);
+        notes.add_note(112..=120, "orazion picciola");
+        home.save(&notes).unwrap();
+
+        let mut notes = work.load(CanticaId::Inferno, 26).unwrap();
+        notes.set_bookmark(118, Some("fatti non foste"));
+        notes.tag_bookmark(118, &["exam", "ulysses"]);
+        notes.set_bookmark(142, None);
+        notes.add_note(112..=120, "orazion picciola");
+        notes.add_note(133..=135, "the mountain of Purgatory");
+        work.save(&notes).unwrap();
+
+        let from_work = Backup::from_store(&work, None).unwrap();
+        let merged = from_work.merge_into(&home, None).unwrap();
+        assert_eq!(
+            merged,
+            Merged {
+                bookmarks: 1,
+                labels: 1,
+                tags: 1,
+                notes: 1,
+                sessions: 0
+            }
+        );
+        assert_eq!(
+            from_work.merge_into(&home, None).unwrap(),
+            Merged::default()
+        );
+
+        // Each now has the other's, and merging back changes nothing
+        let from_home = Backup::from_store(&home, None).unwrap();
+        assert_eq!(
+            from_home.merge_into(&work, None).unwrap(),
+            Merged::default()
+        );
+        assert_eq!(from_home, Backup::from_store(&work, None).unwrap());
+
+        std::fs::remove_dir_all(home.dir()).unwrap();
+        std::fs::remove_dir_all(work.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_reading_log_round_trip() {
+        let (home, work) = (store("log-home"), store("log-work"));
+        let (home_log, work_log) = (
+            home.dir().join("reading-log.tsv"),
+            work.dir().join("log.tsv"),
+        );
+        fs::create_dir_all(home.dir()).unwrap();
+        fs::write(
+            &home_log,
+            "2025-01-12\t52\t2\tInferno 5, Inferno 6\n2025-01-20\t15\t0\tInferno 7\n",
+        )
+        .unwrap();
+        fs::create_dir_all(work.dir()).unwrap();
+        fs::write(&work_log, "2025-01-14\t20\t0\tInferno 6\n").unwrap();
+
+        let from_home = Backup::from_store(&home, Some(&home_log)).unwrap();
+        assert_eq!(from_home.reading_log.len(), 2);
+        let json = serde_json::to_string(&from_home).unwrap();
+        assert_eq!(Backup::parse(&json, "backup.json").unwrap(), from_home);
+        let bad = json.replace("2025-01-20", "yesterday");
+        assert!(Backup::parse(&bad, "backup.json").is_err());
+
+        // Sessions the log lacks are added, in date order, once
+        let merged = from_home.merge_into(&work, Some(&work_log)).unwrap();
+        assert_eq!(merged.sessions, 2);
+        assert_eq!(
+            fs::read_to_string(&work_log).unwrap(),
+            "2025-01-12\t52\t2\tInferno 5, Inferno 6\n\
+             2025-01-14\t20\t0\tInferno 6\n\
+             2025-01-20\t15\t0\tInferno 7\n"
+        );
+        let merged = from_home.merge_into(&work, Some(&work_log)).unwrap();
+        assert_eq!(merged, Merged::default());
+
+        // Merging back leaves both machines with the same log
+        let from_work = Backup::from_store(&work, Some(&work_log)).unwrap();
+        assert_eq!(
+            from_work
+                .merge_into(&home, Some(&home_log))
+                .unwrap()
+                .sessions,
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(&home_log).unwrap(),
+            fs::read_to_string(&work_log).unwrap()
+        );
+        // A machine that has never logged a session gets the whole log
+        let fresh = store("log-fresh");
+        let fresh_log = fresh.dir().join("state/reading-log.tsv");
+        assert_eq!(
+            from_work
+                .merge_into(&fresh, Some(&fresh_log))
+                .unwrap()
+                .sessions,
+            3
+        );
+
+        fs::remove_dir_all(home.dir()).unwrap();
+        fs::remove_dir_all(work.dir()).unwrap();
+        fs::remove_dir_all(fresh.dir()).unwrap();
+    }
+}
@@ -0,0 +1,24 @@
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Open `url` in the user's web browser, preferring `$BROWSER` when set.
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut command = match env::var("BROWSER") {
+        Ok(browser) if !browser.trim().is_empty() => Command::new(browser.trim()),
+        _ if cfg!(target_os = "macos") => Command::new("open"),
+        _ if cfg!(windows) => {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        _ => Command::new("xdg-open"),
+    };
+
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
@@ -0,0 +1,420 @@
+//! Commentary on the poem: notes attached to passages, read from TOML or
+//! JSON files.
+//!
+//! A commentary file names its source and lists entries keyed by passage:
+//!
+//! ```toml
+//! source = "My notes"
+//!
+//! [[entry]]
+//! passage = "Inferno 5.100-105"
+//! text = "Francesca's anaphora on Amor."
+//!
+//! [[entry]]
+//! passage = "Inferno 26"
+//! text = "The canto of Ulysses."
+//! ```
+//!
+//! The JSON form has the same fields: `{"source": …, "entry": [{"passage": …, "text": …}]}`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::{parse_canto_number, paths, CanticaId, DucaError, Passage};
+
+/// Longfellow's notes, generated by `duca parse` and compiled in.
+const LONGFELLOW: &str = include_str!("../commentary/longfellow.toml");
+
+/// A note on a passage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentaryEntry {
+    pub passage: Passage,
+    pub text: String,
+    /// The commentary the note comes from, e.g. "Longfellow".
+    pub source: String,
+}
+
+/// Every loaded commentary entry, in the order the files listed them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Commentary {
+    entries: Vec<CommentaryEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CommentaryFile {
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default, alias = "entries")]
+    entry: Vec<RawEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawEntry {
+    passage: String,
+    text: String,
+}
+
+/// The two file formats a commentary can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// The format for a file name's extension, if it is a commentary file.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+impl Commentary {
+    /// Parse one commentary file's content. `name` identifies it in errors
+    /// and is the source for a file that doesn't name one.
+    pub fn parse(content: &str, format: Format, name: &str) -> Result<Self, DucaError> {
+        let invalid = |reason: String| DucaError::UserData {
+            path: name.to_string(),
+            reason,
+        };
+        let file: CommentaryFile = match format {
+            Format::Toml => {
+                toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?
+            }
+            Format::Json => serde_json::from_str(content).map_err(|e| invalid(e.to_string()))?,
+        };
+
+        let source = file.source.unwrap_or_else(|| name.to_string());
+        let entries = file
+            .entry
+            .into_iter()
+            .map(|raw| {
+                Ok(CommentaryEntry {
+                    passage: raw
+                        .passage
+                        .parse()
+                        .map_err(|e: DucaError| invalid(e.to_string()))?,
+                    text: raw.text.trim().to_string(),
+                    source: source.clone(),
+                })
+            })
+            .collect::<Result<_, DucaError>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Read a `.toml` or `.json` commentary file.
+    pub fn load_file(path: &Path) -> Result<Self, DucaError> {
+        let name = path.display().to_string();
+        let format = Format::for_path(path).ok_or_else(|| DucaError::UserData {
+            path: name.clone(),
+            reason: "expected a .toml or .json file".to_string(),
+        })?;
+        let content = fs::read_to_string(path).map_err(|e| DucaError::UserData {
+            path: name.clone(),
+            reason: e.to_string(),
+        })?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+        Self::parse(&content, format, stem.as_deref().unwrap_or(&name))
+    }
+
+    /// Read every commentary file in `dir`, in file name order. A missing
+    /// directory is an empty commentary.
+    pub fn load_dir(dir: &Path) -> Result<Self, DucaError> {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return Ok(Self::default());
+        };
+        let mut files: Vec<_> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| Format::for_path(path).is_some())
+            .collect();
+        files.sort();
+
+        let mut commentary = Self::default();
+        for path in files {
+            commentary.extend(Self::load_file(&path)?);
+        }
+        Ok(commentary)
+    }
+
+    /// The commentaries compiled into duca: Longfellow's notes.
+    pub fn builtin() -> Self {
+        Self::parse(LONGFELLOW, Format::Toml, "longfellow.toml")
+            .expect("built-in commentary is valid")
+    }
+
+    /// The built-in commentaries followed by the user's own, from
+    /// [`paths::commentary_dir`].
+    pub fn load() -> Result<Self, DucaError> {
+        let mut commentary = Self::builtin();
+        if let Some(dir) = paths::commentary_dir() {
+            commentary.extend(Self::load_dir(&dir)?);
+        }
+        Ok(commentary)
+    }
+
+    /// Render as a TOML commentary file under the given source name.
+    pub fn to_toml(&self, source: &str) -> String {
+        let file = CommentaryFile {
+            source: Some(source.to_string()),
+            entry: self
+                .entries
+                .iter()
+                .map(|e| RawEntry {
+                    passage: e.passage.to_string(),
+                    text: e.text.clone(),
+                })
+                .collect(),
+        };
+        toml::to_string(&file).expect("commentary serializes to TOML")
+    }
+
+    /// Add another commentary's entries after this one's.
+    pub fn extend(&mut self, other: Commentary) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Entries on any part of a canto, ordered by where their passage starts,
+    /// with notes on the whole canto first.
+    pub fn for_canto(&self, cantica: CanticaId, canto: u8) -> Vec<&CommentaryEntry> {
+        let mut entries: Vec<&CommentaryEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.passage.cantica == cantica && e.passage.canto == canto)
+            .collect();
+        entries.sort_by_key(|e| e.passage.lines.as_ref().map(|lines| *lines.start()));
+        entries
+    }
+
+    /// Entries whose passage includes the given verse.
+    pub fn for_line(
+        &self,
+        cantica: CanticaId,
+        canto: u8,
+        line: usize,
+    ) -> impl Iterator<Item = &CommentaryEntry> {
+        self.entries
+            .iter()
+            .filter(move |e| e.passage.contains(cantica, canto, line))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Parse the notes from a Project Gutenberg edition of Longfellow's
+/// translation of one cantica. Notes follow a `Notes` heading, grouped under
+/// canto headings such as `Inferno I`, and each starts with the line or
+/// lines it glosses (`1. Midway upon the journey…`, `100-105. Love…`),
+/// running on until the next note or heading.
+pub fn parse_longfellow_notes(content: &str, cantica: CanticaId) -> Commentary {
+    let heading =
+        Regex::new(r"^(?i:inferno|purgatorio|paradiso|hell|purgatory|paradise)\s+([IVXLC]+)\.?$")
+            .unwrap();
+    let note = Regex::new(r"^(\d+)(?:\s*-\s*(\d+))?\.\s+(.*)$").unwrap();
+
+    let mut entries: Vec<CommentaryEntry> = Vec::new();
+    let mut canto = None;
+    let mut in_notes = false;
+    for line in content.lines().map(str::trim) {
+        if line.eq_ignore_ascii_case("notes") {
+            in_notes = true;
+            continue;
+        }
+        if !in_notes {
+            continue;
+        }
+        if let Some(caps) = heading.captures(line) {
+            canto = parse_canto_number(&caps[1]);
+            continue;
+        }
+        let Some(canto) = canto else { continue };
+
+        if let Some(caps) = note.captures(line) {
+            let start: usize = caps[1].parse().unwrap_or(0);
+            let end = caps.get(2).and_then(|m| m.as_str().parse().ok());
+            entries.push(CommentaryEntry {
+                passage: Passage {
+                    cantica,
+                    canto,
+                    lines: Some(start..=end.unwrap_or(start).max(start)),
+                },
+                text: caps[3].to_string(),
+                source: "Longfellow".to_string(),
+            });
+        } else if let Some(last) = entries.last_mut().filter(|_| !line.is_empty()) {
+            last.text.push(' ');
+            last.text.push_str(line);
+        }
+    }
+    Commentary { entries }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+source = "Test notes"
+
+[[entry]]
+passage = "Inferno 5.100-105"
+text = "Amor, three times."
+
+[[entry]]
+passage = "Inferno 5"
+text = "The lustful."
+
+[[entry]]
+passage = "Inferno 26.118"
+text = "Fatti non foste."
+"#;
+
+    #[test]
+    fn test_parse_toml() {
+        let commentary = Commentary::parse(TOML, Format::Toml, "notes").unwrap();
+        assert_eq!(commentary.len(), 3);
+
+        let canto: Vec<&str> = commentary
+            .for_canto(CanticaId::Inferno, 5)
+            .iter()
+            .map(|e| e.text.as_str())
+            .collect();
+        assert_eq!(canto, ["The lustful.", "Amor, three times."]);
+
+        let line: Vec<_> = commentary.for_line(CanticaId::Inferno, 5, 103).collect();
+        assert_eq!(line.len(), 2);
+        assert_eq!(line[0].source, "Test notes");
+        assert_eq!(commentary.for_line(CanticaId::Inferno, 5, 99).count(), 1);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let json = r#"{"entry": [{"passage": "Paradiso 33.145", "text": "The last line."}]}"#;
+        let commentary = Commentary::parse(json, Format::Json, "mine").unwrap();
+        let entry = &commentary.for_canto(CanticaId::Paradiso, 33)[0];
+        assert_eq!(entry.source, "mine");
+        assert_eq!(entry.passage.lines, Some(145..=145));
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let bad_passage = r#"{"entry": [{"passage": "Limbo 1", "text": "x"}]}"#;
+        let error = Commentary::parse(bad_passage, Format::Json, "bad.json").unwrap_err();
+        assert!(matches!(error, DucaError::UserData { ref path, .. } if path == "bad.json"));
+
+        assert!(Commentary::parse("entry = 3", Format::Toml, "bad.toml").is_err());
+    }
+
+    #[test]
+    fn test_builtin_round_trip() {
+        let builtin = Commentary::builtin();
+        assert!(builtin.entries.iter().all(|e| e.source == "Longfellow"));
+
+        let commentary = Commentary::parse(TOML, Format::Toml, "notes").unwrap();
+        let toml = commentary.to_toml("Test notes");
+        assert_eq!(
+            Commentary::parse(&toml, Format::Toml, "again").unwrap(),
+            commentary
+        );
+    }
+
+    #[test]
+    fn test_parse_longfellow_notes() {
+        let content = "\
+Inferno I
+1. Midway upon the journey of our life
+I found myself within a forest dark,
+
+Notes
+
+Inferno I
+
+1. Midway upon the journey of our life. The year 1300,
+when Dante was thirty-five.
+
+2-3. A forest dark. The forest of the world.
+
+Inferno II
+
+1. Day was departing.
+";
+        let commentary = parse_longfellow_notes(content, CanticaId::Inferno);
+        assert_eq!(commentary.len(), 3);
+
+        let canto_one = commentary.for_canto(CanticaId::Inferno, 1);
+        assert_eq!(
+            canto_one[0].text,
+            "Midway upon the journey of our life. The year 1300, when Dante was thirty-five."
+        );
+        assert_eq!(canto_one[1].passage.to_string(), "Inferno 1.2-3");
+        assert_eq!(commentary.for_canto(CanticaId::Inferno, 2).len(), 1);
+    }
+
+    #[test]
+    fn test_longfellow_notes_by_passage() {
+        let content = "\
+Notes
+
+Inferno I
+
+1. Midway upon the journey of our life. The year 1300.
+
+2-3. A forest dark. The forest of the world.
+";
+        // As `duca parse` writes them to longfellow.toml and duca reads them back
+        let notes = parse_longfellow_notes(content, CanticaId::Inferno);
+        let toml = notes.to_toml("Longfellow");
+        let commentary = Commentary::parse(&toml, Format::Toml, "longfellow.toml").unwrap();
+
+        let first: Vec<_> = commentary.for_line(CanticaId::Inferno, 1, 1).collect();
+        assert_eq!(first.len(), 1);
+        assert!(first[0].text.starts_with("Midway upon the journey"));
+        assert_eq!(first[0].source, "Longfellow");
+        let third = commentary
+            .for_line(CanticaId::Inferno, 1, 3)
+            .next()
+            .unwrap();
+        assert_eq!(third.passage.to_string(), "Inferno 1.2-3");
+        assert!(commentary
+            .for_line(CanticaId::Inferno, 1, 4)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("duca-commentary-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.toml"), TOML).unwrap();
+        fs::write(
+            dir.join("b.json"),
+            r#"{"entry": [{"passage": "Inferno 5.1", "text": "Second file."}]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "not commentary").unwrap();
+
+        let commentary = Commentary::load_dir(&dir).unwrap();
+        assert_eq!(commentary.len(), 4);
+        assert_eq!(
+            commentary
+                .for_line(CanticaId::Inferno, 5, 1)
+                .last()
+                .unwrap()
+                .source,
+            "b"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(Commentary::load_dir(&dir).unwrap().is_empty());
+    }
+}
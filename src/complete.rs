@@ -0,0 +1,114 @@
+//! Values the shell completes by asking `duca` as it goes, once registered
+//! with `duca completions SHELL --dynamic`: the cantos of the cantica given
+//! before, and the citations and tags of the reader's bookmarks.
+
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use clap_complete::Shell;
+use duca::notes::NoteStore;
+use duca::{CanticaId, DivinaCommedia};
+use std::env;
+
+/// The variable the registration script sets when it calls `duca` back.
+pub const VAR: &str = "COMPLETE";
+
+/// The completer of the registration script for `shell`.
+pub fn env_completer(shell: Shell) -> Option<&'static dyn EnvCompleter> {
+    match shell {
+        Shell::Bash => Some(&Bash),
+        Shell::Elvish => Some(&Elvish),
+        Shell::Fish => Some(&Fish),
+        Shell::PowerShell => Some(&Powershell),
+        Shell::Zsh => Some(&Zsh),
+        _ => None,
+    }
+}
+
+/// The words of the command line being completed, without the one under
+/// the cursor, as the registration script passes them after `--`.
+fn command_line() -> Vec<String> {
+    let mut words: Vec<String> = env::args().skip_while(|arg| arg != "--").skip(1).collect();
+    words.pop();
+    words
+}
+
+/// The numbers of the cantos of the cantica last named on the command line,
+/// or of the longest cantica without one.
+pub fn canto_numbers() -> Vec<CompletionCandidate> {
+    let cantica = command_line()
+        .iter()
+        .rev()
+        .find_map(|word| word.parse::<CanticaId>().ok());
+    duca::commedia()
+        .map(|commedia| cantos(commedia, cantica))
+        .unwrap_or_default()
+}
+
+/// Each canto of `cantica`, or of the cantica with the most without one,
+/// with its first verse to tell them apart.
+pub fn cantos(commedia: &DivinaCommedia, cantica: Option<CanticaId>) -> Vec<CompletionCandidate> {
+    let cantica = cantica.unwrap_or_else(|| {
+        CanticaId::ALL
+            .into_iter()
+            .max_by_key(|id| commedia.get(*id).cantos.len())
+            .unwrap_or(CanticaId::Inferno)
+    });
+    commedia
+        .get(cantica)
+        .cantos
+        .values()
+        .map(|canto| {
+            let first = canto.verses.first().map(|v| v.text.clone());
+            CompletionCandidate::new(canto.number.to_string()).help(first.map(Into::into))
+        })
+        .collect()
+}
+
+/// The citations of the reader's bookmarks, with their labels.
+pub fn bookmarks() -> Vec<CompletionCandidate> {
+    let Ok(all) = NoteStore::open().and_then(|store| store.all()) else {
+        return Vec::new();
+    };
+    all.iter()
+        .flat_map(|canto| {
+            canto.bookmarks.iter().map(|bookmark| {
+                let citation = format!("{} {}.{}", canto.cantica, canto.canto, bookmark.line);
+                CompletionCandidate::new(citation).help(bookmark.label.clone().map(Into::into))
+            })
+        })
+        .collect()
+}
+
+/// Every tag of the reader's bookmarks, once each.
+pub fn bookmark_tags() -> Vec<CompletionCandidate> {
+    let Ok(all) = NoteStore::open().and_then(|store| store.all()) else {
+        return Vec::new();
+    };
+    let mut tags: Vec<&String> = all
+        .iter()
+        .flat_map(|canto| canto.bookmarks.iter().flat_map(|b| &b.tags))
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags.into_iter().map(CompletionCandidate::new).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canto_candidates() {
+        let commedia = duca::commedia().unwrap();
+        let purgatorio = cantos(commedia, Some(CanticaId::Purgatorio));
+        assert_eq!(purgatorio.len(), 33);
+        assert_eq!(purgatorio[0].get_value(), "1");
+        assert_eq!(purgatorio[32].get_value(), "33");
+        assert_eq!(
+            purgatorio[0].get_help().unwrap().to_string(),
+            commedia.verse(CanticaId::Purgatorio, 1, 1).unwrap().text
+        );
+        // The longest cantica's, with none named
+        assert_eq!(cantos(commedia, None).len(), 34);
+    }
+}
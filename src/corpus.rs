@@ -0,0 +1,494 @@
+//! The binary corpus format written by `duca parse` as `commedia.bin`.
+//!
+//! The file starts with an index of every canto's byte range so a single
+//! canto can be decoded without touching the rest of the poem:
+//!
+//! ```text
+//! magic "DUCA" | version u8 | count u32 | words offset u32 | words length u32
+//! count × (cantica u8, canto u8, offset u32, length u32)
+//! bincode-encoded cantos, addressed by offset from the end of the index
+//! bincode-encoded word index, with its lemma index
+//! ```
+//!
+//! Integers are little-endian.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::normalize::Pipeline;
+use crate::rank::{self, ScoredMatch};
+use crate::speakers::{self, Speech};
+use crate::{
+    split_speaker_filter, CanticaId, Canto, DivinaCommedia, DucaError, Matcher, SearchMatch,
+    WordIndex,
+};
+
+const MAGIC: &[u8; 4] = b"DUCA";
+const VERSION: u8 = 6;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + 4 + 4;
+const ENTRY_LEN: usize = 1 + 1 + 4 + 4;
+
+/// Where one canto's encoding sits in the data section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    cantica: CanticaId,
+    canto: u8,
+    offset: usize,
+    len: usize,
+}
+
+/// Serialize the corpus to the compact binary form written by `duca parse`
+/// and embedded into the binary. `commedia.json` holds the same data for
+/// people and other tools to read.
+pub fn to_binary(commedia: &DivinaCommedia) -> Result<Vec<u8>, DucaError> {
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+    for (cantica, canto) in commedia.cantos() {
+        let encoded = bincode::serialize(canto)?;
+        index.push(Entry {
+            cantica,
+            canto: canto.number,
+            offset: data.len(),
+            len: encoded.len(),
+        });
+        data.extend_from_slice(&encoded);
+    }
+    let words = bincode::serialize(&WordIndex::build(commedia))?;
+    let words_offset = data.len();
+    data.extend_from_slice(&words);
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + index.len() * ENTRY_LEN + data.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(words_offset as u32).to_le_bytes());
+    bytes.extend_from_slice(&(words.len() as u32).to_le_bytes());
+    for entry in &index {
+        bytes.push(entry.cantica.index() as u8);
+        bytes.push(entry.canto);
+        bytes.extend_from_slice(&(entry.offset as u32).to_le_bytes());
+        bytes.extend_from_slice(&(entry.len as u32).to_le_bytes());
+    }
+    bytes.extend_from_slice(&data);
+    Ok(bytes)
+}
+
+/// An encoded corpus held in any byte buffer: a decompressed copy of the
+/// embedded data, a file read into memory, or a memory map.
+pub struct Corpus<B> {
+    bytes: B,
+    index: Vec<Entry>,
+    words: Range<usize>,
+    data_start: usize,
+}
+
+impl<B: AsRef<[u8]>> Corpus<B> {
+    /// Validate the header and read the canto index. No canto is decoded.
+    pub fn new(bytes: B) -> Result<Self, DucaError> {
+        let (index, words, data_start) = read_index(bytes.as_ref())?;
+        Ok(Self {
+            bytes,
+            index,
+            words,
+            data_start,
+        })
+    }
+
+    /// Decode a canto into an owned [`Canto`].
+    pub fn canto(&self, cantica: CanticaId, number: u8) -> Result<Option<Canto>, DucaError> {
+        self.index
+            .iter()
+            .find(|entry| entry.cantica == cantica && entry.canto == number)
+            .map(|entry| Ok(bincode::deserialize(self.slice(entry)?)?))
+            .transpose()
+    }
+
+    /// Decode a canto, borrowing its text from the buffer.
+    pub fn canto_view(
+        &self,
+        cantica: CanticaId,
+        number: u8,
+    ) -> Result<Option<CantoView<'_>>, DucaError> {
+        self.index
+            .iter()
+            .find(|entry| entry.cantica == cantica && entry.canto == number)
+            .map(|entry| Ok(bincode::deserialize(self.slice(entry)?)?))
+            .transpose()
+    }
+
+    /// The numbers of `cantica`'s cantos, in the order they're stored.
+    pub fn canto_numbers(&self, cantica: CanticaId) -> Vec<u8> {
+        self.index
+            .iter()
+            .filter(|entry| entry.cantica == cantica)
+            .map(|entry| entry.canto)
+            .collect()
+    }
+
+    /// Decode every canto of `cantica`, or of the poem, borrowing their text
+    /// from the buffer.
+    pub fn canto_views(
+        &self,
+        cantica_filter: Option<CanticaId>,
+    ) -> Result<Vec<(CanticaId, CantoView<'_>)>, DucaError> {
+        self.index
+            .iter()
+            .filter(|entry| cantica_filter.is_none_or(|filter| entry.cantica == filter))
+            .map(|entry| Ok((entry.cantica, bincode::deserialize(self.slice(entry)?)?)))
+            .collect()
+    }
+
+    /// Verses matching `pattern` through `pipeline`, as
+    /// [`DivinaCommedia::search_with`] finds them, their text borrowed from
+    /// the buffer. The word index is left encoded, so every verse is tested.
+    pub fn search_with(
+        &self,
+        pattern: &str,
+        cantica_filter: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<SearchMatch<'_>>, DucaError> {
+        let (pattern, speaker) = split_speaker_filter(pattern);
+        let matcher = (!pattern.is_empty() || speaker.is_none())
+            .then(|| Matcher::new(&pattern, pipeline))
+            .transpose()?;
+
+        let mut scratch = String::new();
+        let mut results = Vec::new();
+        for (cantica, canto) in self.canto_views(cantica_filter)? {
+            for (i, verse) in canto.verses.iter().enumerate() {
+                let spoken = speaker.as_ref().is_none_or(|filter| {
+                    canto
+                        .speaker(verse.line_number)
+                        .is_some_and(|speaker| speakers::matches_filter(speaker, filter))
+                });
+                if !spoken {
+                    continue;
+                }
+                let spans = match &matcher {
+                    Some(matcher) => {
+                        let next = canto.verses.get(i + 1).map(|v| v.text);
+                        let folded = canto.normalized.get(i).copied();
+                        let spans = matcher.find_in_verse(verse.text, next, folded, &mut scratch);
+                        if spans.is_empty() {
+                            continue;
+                        }
+                        spans
+                    }
+                    None => Vec::new(),
+                };
+                results.push(SearchMatch {
+                    cantica,
+                    canto: canto.number,
+                    line: verse.line_number,
+                    text: verse.text,
+                    spans,
+                });
+            }
+        }
+        results.sort_by_key(|m| (m.cantica, m.canto, m.line));
+        Ok(results)
+    }
+
+    /// The verses with any word of `query`, by [`rank::bm25`] score, their
+    /// text borrowed from the buffer.
+    pub fn bm25(
+        &self,
+        query: &str,
+        cantica_filter: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<ScoredMatch<'_>>, DucaError> {
+        let verses = self
+            .canto_views(None)?
+            .into_iter()
+            .flat_map(|(cantica, canto)| {
+                let number = canto.number;
+                canto.verses.into_iter().map(move |verse| SearchMatch {
+                    cantica,
+                    canto: number,
+                    line: verse.line_number,
+                    text: verse.text,
+                    spans: Vec::new(),
+                })
+            })
+            .collect();
+        Ok(rank::bm25_among(verses, query, cantica_filter, pipeline))
+    }
+
+    /// Decode the word index.
+    pub fn words(&self) -> Result<WordIndex, DucaError> {
+        Ok(bincode::deserialize(self.data(self.words.clone())?)?)
+    }
+
+    /// Decode every canto, and the word index, into a [`DivinaCommedia`].
+    pub fn to_commedia(&self) -> Result<DivinaCommedia, DucaError> {
+        let mut commedia = DivinaCommedia::new();
+        for entry in &self.index {
+            let canto = bincode::deserialize(self.slice(entry)?)?;
+            commedia
+                .get_mut(entry.cantica)
+                .cantos
+                .insert(entry.canto, canto);
+        }
+        commedia.words = Some(self.words()?);
+        Ok(commedia)
+    }
+
+    fn slice(&self, entry: &Entry) -> Result<&[u8], DucaError> {
+        let end = entry
+            .offset
+            .checked_add(entry.len)
+            .ok_or_else(|| corrupt("canto out of bounds"))?;
+        self.data(entry.offset..end)
+    }
+
+    fn data(&self, range: Range<usize>) -> Result<&[u8], DucaError> {
+        self.bytes.as_ref()[self.data_start..]
+            .get(range)
+            .ok_or_else(|| corrupt("data out of bounds"))
+    }
+}
+
+/// Memory-map a corpus file, so cantos are paged in from disk as they are
+/// read rather than held in memory.
+#[cfg(feature = "mmap")]
+pub fn open_mapped(path: &std::path::Path) -> Result<Corpus<memmap2::Mmap>, DucaError> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the map is read-only, and the caller must not modify or
+    // truncate the file while it's mapped: changing it under the live map
+    // is undefined behaviour.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    Corpus::new(map)
+}
+
+/// A canto decoded without copying its text: every string borrows from the
+/// buffer the corpus is held in. Fields mirror [`Canto`], whose encoding it
+/// reads.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CantoView<'a> {
+    pub number: u8,
+    pub roman_numeral: &'a str,
+    pub title: &'a str,
+    pub incipit: &'a str,
+    pub verse_count: usize,
+    #[serde(borrow)]
+    pub verses: Vec<VerseView<'a>>,
+    #[serde(borrow)]
+    pub normalized: Vec<&'a str>,
+    pub latin: Vec<usize>,
+    pub speeches: Vec<Speech>,
+}
+
+impl CantoView<'_> {
+    /// Who is speaking at `line`, if anyone is.
+    pub fn speaker(&self, line: usize) -> Option<&str> {
+        speakers::speaker_at(&self.speeches, line)
+    }
+}
+
+impl<'a> From<&'a Canto> for CantoView<'a> {
+    fn from(canto: &'a Canto) -> Self {
+        Self {
+            number: canto.number,
+            roman_numeral: &canto.roman_numeral,
+            title: &canto.title,
+            incipit: &canto.incipit,
+            verse_count: canto.verse_count,
+            verses: canto
+                .verses
+                .iter()
+                .map(|verse| VerseView {
+                    line_number: verse.line_number,
+                    text: &verse.text,
+                })
+                .collect(),
+            normalized: canto.normalized.iter().map(String::as_str).collect(),
+            latin: canto.latin.clone(),
+            speeches: canto.speeches.clone(),
+        }
+    }
+}
+
+/// A verse of a [`CantoView`], serialized as a [`crate::Verse`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerseView<'a> {
+    pub line_number: usize,
+    pub text: &'a str,
+}
+
+/// Decode the whole corpus.
+pub fn from_binary(bytes: &[u8]) -> Result<DivinaCommedia, DucaError> {
+    Corpus::new(bytes)?.to_commedia()
+}
+
+/// Decode a single canto, leaving the rest of the corpus untouched.
+pub fn read_canto(
+    bytes: &[u8],
+    cantica: CanticaId,
+    number: u8,
+) -> Result<Option<Canto>, DucaError> {
+    Corpus::new(bytes)?.canto(cantica, number)
+}
+
+/// Parse the index, returning it, the word index's range within the data,
+/// and where the data starts.
+fn read_index(bytes: &[u8]) -> Result<(Vec<Entry>, Range<usize>, usize), DucaError> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(corrupt("not a duca corpus"));
+    }
+    if bytes[MAGIC.len()] != VERSION {
+        return Err(corrupt("unsupported corpus version"));
+    }
+
+    let count = u32_at(bytes, MAGIC.len() + 1) as usize;
+    let words_offset = u32_at(bytes, MAGIC.len() + 5) as usize;
+    let words_len = u32_at(bytes, MAGIC.len() + 9) as usize;
+    let words = words_offset..words_offset.saturating_add(words_len);
+    let data_start = count
+        .checked_mul(ENTRY_LEN)
+        .and_then(|len| len.checked_add(HEADER_LEN))
+        .filter(|&start| start <= bytes.len())
+        .ok_or_else(|| corrupt("truncated index"))?;
+
+    let index = bytes[HEADER_LEN..data_start]
+        .chunks_exact(ENTRY_LEN)
+        .map(|entry| {
+            Ok(Entry {
+                cantica: *CanticaId::ALL
+                    .get(entry[0] as usize)
+                    .ok_or_else(|| corrupt("unknown cantica"))?,
+                canto: entry[1],
+                offset: u32_at(entry, 2) as usize,
+                len: u32_at(entry, 6) as usize,
+            })
+        })
+        .collect::<Result<Vec<_>, DucaError>>()?;
+
+    Ok((index, words, data_start))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("slice is 4 bytes"))
+}
+
+fn corrupt(reason: &str) -> DucaError {
+    DucaError::DataLoad(format!("corrupt corpus: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Verse;
+
+    fn sample() -> DivinaCommedia {
+        let mut commedia = DivinaCommedia::new();
+        for (cantica, number) in [(CanticaId::Inferno, 1), (CanticaId::Paradiso, 33)] {
+            let verses = vec![Verse {
+                line_number: 1,
+                text: format!("{} {}", cantica, number),
+            }];
+            commedia
+                .get_mut(cantica)
+                .cantos
+                .insert(number, Canto::new(cantica, number, verses));
+        }
+        commedia
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = to_binary(&sample()).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+
+        let commedia = from_binary(&bytes).unwrap();
+        assert_eq!(commedia.inferno.name, "Inferno");
+        assert_eq!(
+            commedia.verse(CanticaId::Paradiso, 33, 1).unwrap().text,
+            "Paradiso 33"
+        );
+        assert!(commedia.purgatorio.cantos.is_empty());
+        assert_eq!(
+            commedia.word_index().unwrap().lookup("paradiso")[0].canto,
+            33
+        );
+    }
+
+    #[test]
+    fn test_read_single_canto() {
+        let bytes = to_binary(&sample()).unwrap();
+
+        let canto = read_canto(&bytes, CanticaId::Paradiso, 33)
+            .unwrap()
+            .unwrap();
+        assert_eq!(canto.title, "Paradiso XXXIII");
+        assert!(read_canto(&bytes, CanticaId::Inferno, 2).unwrap().is_none());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_mapped() {
+        let corpus = open_mapped(std::path::Path::new("commedia.bin")).unwrap();
+        let canto = corpus.canto(CanticaId::Paradiso, 33).unwrap().unwrap();
+        assert_eq!(canto.incipit, "«Vergine Madre, figlia del tuo figlio,");
+        assert_eq!(canto.verse_count, canto.verses.len());
+    }
+
+    #[test]
+    fn test_canto_view_borrows_text() {
+        let bytes = to_binary(&sample()).unwrap();
+        let corpus = Corpus::new(bytes.as_slice()).unwrap();
+
+        let view = corpus.canto_view(CanticaId::Paradiso, 33).unwrap().unwrap();
+        assert_eq!(view.title, "Paradiso XXXIII");
+        assert_eq!(view.verses[0].text, "Paradiso 33");
+        assert!(bytes.as_ptr_range().contains(&view.verses[0].text.as_ptr()));
+        assert_eq!(corpus.canto_numbers(CanticaId::Inferno), [1]);
+        assert!(corpus.canto_numbers(CanticaId::Purgatorio).is_empty());
+    }
+
+    #[test]
+    fn test_search_as_decoded() {
+        let bytes = std::fs::read("commedia.bin").unwrap();
+        let corpus = Corpus::new(bytes.as_slice()).unwrap();
+        let commedia = corpus.to_commedia().unwrap();
+        let pipeline = Pipeline::default();
+
+        for pattern in [
+            "amor",
+            "selva oscura",
+            "speaker:virgilio",
+            "speaker:beatrice luce",
+        ] {
+            assert_eq!(
+                corpus.search_with(pattern, None, &pipeline).unwrap(),
+                commedia.search_with(pattern, None, &pipeline).unwrap(),
+                "{}",
+                pattern
+            );
+        }
+        assert_eq!(
+            corpus
+                .search_with("amor", Some(CanticaId::Purgatorio), &pipeline)
+                .unwrap(),
+            commedia
+                .search_with("amor", Some(CanticaId::Purgatorio), &pipeline)
+                .unwrap()
+        );
+        assert_eq!(
+            corpus.bm25("selva oscura", None, &pipeline).unwrap(),
+            rank::bm25(&commedia, "selva oscura", None, &pipeline)
+        );
+    }
+
+    #[test]
+    fn test_rejects_corrupt_data() {
+        assert!(from_binary(b"").is_err());
+        assert!(from_binary(b"JSON{}\0\0\0").is_err());
+
+        let mut bytes = to_binary(&sample()).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(from_binary(&bytes).is_err());
+        bytes.truncate(HEADER_LEN + 2 * ENTRY_LEN + 1);
+        assert!(read_canto(&bytes, CanticaId::Paradiso, 33).is_err());
+    }
+}
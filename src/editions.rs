@@ -0,0 +1,135 @@
+//! Editions of the Italian text, kept side by side.
+//!
+//! duca ships the Project Gutenberg transcription as the `gutenberg`
+//! edition. Other editions, such as a user's own copy of the Petrocchi text,
+//! are directories in [`paths::editions_dir`] named after the edition and
+//! holding `inferno.txt`, `purgatorio.txt` and `paradiso.txt` laid out like
+//! the Gutenberg files, with a `Canto I` heading before each canto's verses,
+//! or the same texts in TEI XML as read by [`crate::parse_dir`].
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::{load_commedia, parse_dir, paths, DivinaCommedia, DucaError};
+
+/// The edition compiled into duca.
+pub const DEFAULT: &str = "gutenberg";
+
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+/// The names of the installed editions, the built-in one first and the
+/// user's own in name order.
+pub fn available() -> Vec<String> {
+    let mut user: Vec<String> = paths::editions_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != DEFAULT)
+        .collect();
+    user.sort();
+
+    std::iter::once(DEFAULT.to_string()).chain(user).collect()
+}
+
+/// Load an edition by name.
+pub fn load(name: &str) -> Result<DivinaCommedia, DucaError> {
+    if name == DEFAULT {
+        return load_commedia();
+    }
+    match paths::editions_dir().map(|dir| dir.join(name)) {
+        Some(dir) if dir.is_dir() => load_dir(&dir, name),
+        _ => Err(DucaError::UnknownEdition {
+            name: name.to_string(),
+            available: available(),
+        }),
+    }
+}
+
+/// Parse the source texts in `dir` as the edition `name`. A cantica with
+/// no file is left empty, but an edition needs at least one canto.
+pub fn load_dir(dir: &Path, name: &str) -> Result<DivinaCommedia, DucaError> {
+    let mut commedia = parse_dir(dir)?;
+    if commedia.cantos().next().is_none() {
+        return Err(DucaError::UserData {
+            path: dir.display().to_string(),
+            reason:
+                "expected cantos in inferno.txt, purgatorio.txt, paradiso.txt or TEI .xml files"
+                    .to_string(),
+        });
+    }
+    commedia.edition = name.to_string();
+    Ok(commedia)
+}
+
+/// Choose the edition [`crate::commedia`] and [`crate::load_canto`] read.
+/// Call it before either is first used; the default is [`DEFAULT`].
+pub fn select(name: &str) -> Result<(), DucaError> {
+    if !available().iter().any(|edition| edition == name) {
+        return Err(DucaError::UnknownEdition {
+            name: name.to_string(),
+            available: available(),
+        });
+    }
+    SELECTED.get_or_init(|| name.to_string());
+    Ok(())
+}
+
+/// The edition chosen by [`select`].
+pub fn selected() -> &'static str {
+    SELECTED.get().map_or(DEFAULT, String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CanticaId;
+
+    #[test]
+    fn test_load_dir() {
+        let dir = std::env::temp_dir().join(format!("duca-edition-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("inferno.txt"),
+            "Canto I\n\nNel mezzo del cammin di nostra vita\nmi ritrovai per una selva oscura,\n",
+        )
+        .unwrap();
+
+        let commedia = load_dir(&dir, "mine").unwrap();
+        assert_eq!(commedia.edition, "mine");
+        let canto = commedia.canto(CanticaId::Inferno, 1).unwrap();
+        assert_eq!(
+            canto.verse(2).unwrap().text,
+            "mi ritrovai per una selva oscura,"
+        );
+        assert!(commedia.get(CanticaId::Paradiso).cantos.is_empty());
+
+        // TEI XML in place of the plain text
+        fs::remove_file(dir.join("inferno.txt")).unwrap();
+        fs::write(
+            dir.join("purgatorio.xml"),
+            "<TEI><div type=\"canto\" n=\"1\"><l>Per correr miglior acque</l></div></TEI>",
+        )
+        .unwrap();
+        let commedia = load_dir(&dir, "mine").unwrap();
+        assert!(commedia.canto(CanticaId::Purgatorio, 1).is_some());
+
+        fs::remove_file(dir.join("purgatorio.xml")).unwrap();
+        assert!(matches!(
+            load_dir(&dir, "mine"),
+            Err(DucaError::UserData { .. })
+        ));
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_edition() {
+        let error = select("no-such-edition").unwrap_err();
+        assert!(error.to_string().contains("gutenberg"));
+        assert_eq!(error.exit_code(), 2);
+        assert_eq!(selected(), DEFAULT);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+
+use crate::CanticaId;
+
+/// Failures from the data and lookup layer. The CLI, servers and TUI each
+/// decide how to present these; nothing here prints or exits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DucaError {
+    /// A name that isn't one of the three canticas or an accepted alias.
+    InvalidCantica(String),
+    /// A citation such as `Inferno 5.100` that couldn't be parsed.
+    InvalidCitation { input: String, reason: String },
+    /// A date that isn't a real `YYYY-MM-DD` one.
+    InvalidDate(String),
+    /// A search normalizer that isn't one of [`Normalizer::ALL`](crate::normalize::Normalizer::ALL).
+    InvalidNormalizer(String),
+    /// A search pattern that isn't a valid regular expression, or can't be
+    /// matched as one through its pipeline.
+    InvalidRegex { pattern: String, reason: String },
+    /// A search ranking that isn't one of [`Rank`](crate::rank::Rank)'s.
+    InvalidRank(String),
+    /// A canto number the cantica doesn't have.
+    CantoNotFound { cantica: CanticaId, canto: u8 },
+    /// A section number a part of another work doesn't have.
+    SectionNotFound { part: String, section: u8 },
+    /// The corpus couldn't be read or deserialized.
+    DataLoad(String),
+    /// An edition that isn't built in or installed in the editions directory.
+    UnknownEdition {
+        name: String,
+        available: Vec<String>,
+    },
+    /// A work that isn't the Commedia or installed in the works directory.
+    UnknownWork {
+        name: String,
+        available: Vec<String>,
+    },
+    /// `works add` refused a name, such as a built-in one or one in use.
+    Install { name: String, reason: String },
+    /// A source text couldn't be downloaded, or changed upstream.
+    Fetch { url: String, reason: String },
+    /// A user-supplied data file, such as a commentary, couldn't be read.
+    UserData { path: String, reason: String },
+    /// A file of the user's own, such as their notes, couldn't be written.
+    Save { path: String, reason: String },
+    /// The embeddings endpoint failed or returned something unusable.
+    #[cfg(feature = "semantic")]
+    Semantic(String),
+}
+
+impl DucaError {
+    /// Process exit code for the CLI: 2 for bad input, as clap uses for
+    /// usage errors, and 1 for everything else.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DucaError::InvalidCantica(_)
+            | DucaError::InvalidCitation { .. }
+            | DucaError::InvalidDate(_)
+            | DucaError::InvalidNormalizer(_)
+            | DucaError::InvalidRegex { .. }
+            | DucaError::InvalidRank(_)
+            | DucaError::UnknownEdition { .. }
+            | DucaError::UnknownWork { .. }
+            | DucaError::Install { .. } => 2,
+            DucaError::CantoNotFound { .. }
+            | DucaError::SectionNotFound { .. }
+            | DucaError::DataLoad(_)
+            | DucaError::Fetch { .. }
+            | DucaError::UserData { .. }
+            | DucaError::Save { .. } => 1,
+            #[cfg(feature = "semantic")]
+            DucaError::Semantic(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for DucaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DucaError::InvalidCantica(name) => write!(
+                f,
+                "Invalid cantica '{}'. Use: inferno, purgatorio, or paradiso",
+                name
+            ),
+            DucaError::InvalidCitation { input, reason } => {
+                write!(f, "Invalid citation '{}': {}", input, reason)
+            }
+            DucaError::InvalidDate(input) => {
+                write!(f, "Invalid date '{}': expected YYYY-MM-DD", input)
+            }
+            DucaError::InvalidRegex { pattern, reason } => {
+                write!(f, "Invalid regex '{}': {}", pattern, reason)
+            }
+            DucaError::InvalidRank(name) => {
+                write!(f, "Unknown ranking '{}'. Use: order or bm25", name)
+            }
+            DucaError::InvalidNormalizer(name) => write!(
+                f,
+                "Unknown normalizer '{}'. Use: lowercase, fold, orthography, lemma, or none",
+                name
+            ),
+            DucaError::CantoNotFound { cantica, canto } => {
+                write!(f, "Canto {} not found in {}", canto, cantica)
+            }
+            DucaError::SectionNotFound { part, section } => {
+                write!(f, "Section {} not found in {}", section, part)
+            }
+            DucaError::DataLoad(message) => write!(f, "Could not load the Commedia: {}", message),
+            DucaError::UnknownEdition { name, available } => write!(
+                f,
+                "Unknown edition '{}'. Available: {}",
+                name,
+                available.join(", ")
+            ),
+            DucaError::UnknownWork { name, available } => write!(
+                f,
+                "Unknown work '{}'. Available: {}",
+                name,
+                available.join(", ")
+            ),
+            DucaError::Install { name, reason } => {
+                write!(f, "Can't install '{}': {}", name, reason)
+            }
+            DucaError::Fetch { url, reason } => {
+                write!(f, "Could not fetch {}: {}", url, reason)
+            }
+            DucaError::UserData { path, reason } => {
+                write!(f, "Could not read {}: {}", path, reason)
+            }
+            DucaError::Save { path, reason } => {
+                write!(f, "Could not write {}: {}", path, reason)
+            }
+            #[cfg(feature = "semantic")]
+            DucaError::Semantic(message) => write!(f, "Semantic search failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DucaError {}
+
+impl From<std::io::Error> for DucaError {
+    fn from(e: std::io::Error) -> Self {
+        DucaError::DataLoad(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for DucaError {
+    fn from(e: bincode::Error) -> Self {
+        DucaError::DataLoad(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for DucaError {
+    fn from(e: serde_json::Error) -> Self {
+        DucaError::DataLoad(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_and_exit_codes() {
+        let error = DucaError::InvalidCantica("limbo".to_string());
+        assert_eq!(
+            error.to_string(),
+            "Invalid cantica 'limbo'. Use: inferno, purgatorio, or paradiso"
+        );
+        assert_eq!(error.exit_code(), 2);
+
+        let error = DucaError::InvalidRegex {
+            pattern: "amor(".to_string(),
+            reason: "unclosed group".to_string(),
+        };
+        assert_eq!(error.to_string(), "Invalid regex 'amor(': unclosed group");
+        assert_eq!(error.exit_code(), 2);
+
+        let error = DucaError::CantoNotFound {
+            cantica: CanticaId::Paradiso,
+            canto: 34,
+        };
+        assert_eq!(error.to_string(), "Canto 34 not found in Paradiso");
+        assert_eq!(error.exit_code(), 1);
+        let error = DucaError::SectionNotFound {
+            part: "Vita Nuova".to_string(),
+            section: 43,
+        };
+        assert_eq!(error.to_string(), "Section 43 not found in Vita Nuova");
+        assert_eq!(error.exit_code(), 1);
+
+        let error: DucaError = serde_json::from_str::<u8>("x").unwrap_err().into();
+        assert!(matches!(error, DucaError::DataLoad(_)));
+    }
+}
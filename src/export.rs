@@ -0,0 +1,273 @@
+//! The reader's notes and bookmarks written out for other tools, by `duca
+//! export`: an Org file for Emacs, or an Obsidian vault.
+//!
+//! The Org file has a heading for each canto with notes or bookmarks, in
+//! reading order. Under it each bookmark is a heading with its label and
+//! tags, quoting the verse it marks, and each note a heading with the date
+//! its canto's notes last changed, the notes not keeping a date of their
+//! own.
+//!
+//! The vault has a file for every canto of the poem, `Inferno/Inferno
+//! 05.md`, each verse a paragraph ending in a block ID, `^line-100`, and a
+//! file for each canto's notes, `Notes/Inferno 05 notes.md`. Its bookmarks,
+//! the headings of its notes and any citation in their text are wikilinks
+//! to the verses, `[[Inferno 05#^line-100|Inferno 5.100]]`, so Obsidian
+//! shows each verse's notes among its backlinks.
+
+use std::path::PathBuf;
+
+use regex::{Captures, Regex};
+
+use crate::notes::CantoNotes;
+use crate::plan::Date;
+use crate::{parse_canto_number, CanticaId, DivinaCommedia};
+
+/// Weekdays as Org timestamps abbreviate them, from a Thursday, the day of
+/// 1 January 1970.
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+/// `date` as an inactive Org timestamp, `[2026-10-16 Fri]`, which dates an
+/// entry without putting it on the agenda.
+fn org_timestamp(date: Date) -> String {
+    let weekday = WEEKDAYS[date.days().rem_euclid(7) as usize];
+    format!("[{} {}]", date, weekday)
+}
+
+/// `text` as the body of an Org entry: a line starting with `*` would be a
+/// heading, so it's indented a space.
+fn org_body(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.starts_with('*') {
+                format!(" {}\n", line)
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// The Org file of `cantos`' notes and bookmarks, quoting the verses of
+/// `commedia`. `changed_on` gives the date a canto's notes last changed.
+pub fn org(
+    commedia: &DivinaCommedia,
+    cantos: &[CantoNotes],
+    changed_on: impl Fn(CanticaId, u8) -> Option<Date>,
+) -> String {
+    let mut out = String::from("#+title: Divina Commedia: notes and bookmarks\n");
+    for notes in cantos.iter().filter(|notes| !notes.is_empty()) {
+        let canto = commedia.canto(notes.cantica, notes.canto);
+        let heading = canto.map_or_else(|| notes.canto.to_string(), |c| c.roman_numeral.clone());
+        out.push_str(&format!("\n* {} {}\n", notes.cantica, heading));
+
+        for bookmark in &notes.bookmarks {
+            let citation = format!("{} {}.{}", notes.cantica, notes.canto, bookmark.line);
+            out.push_str(&format!("** {}", citation));
+            if let Some(label) = &bookmark.label {
+                out.push_str(&format!(": {}", label));
+            }
+            if !bookmark.tags.is_empty() {
+                out.push_str(&format!(" :{}:", bookmark.tags.join(":")));
+            }
+            out.push('\n');
+            if let Some(verse) = canto.and_then(|c| c.verse(bookmark.line)) {
+                out.push_str(&format!("#+begin_quote\n{}\n#+end_quote\n", verse.text));
+            }
+        }
+
+        let changed = changed_on(notes.cantica, notes.canto);
+        for note in &notes.notes {
+            let (start, end) = (*note.lines.start(), *note.lines.end());
+            let lines = if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            };
+            out.push_str(&format!(
+                "** Note on {} {}.{}\n",
+                notes.cantica, notes.canto, lines
+            ));
+            if let Some(date) = changed {
+                out.push_str(&org_timestamp(date));
+                out.push('\n');
+            }
+            out.push_str(&org_body(&note.text));
+        }
+    }
+    out
+}
+
+/// The name of the vault file of a canto, `Inferno 05`, the number padded
+/// so the files sort in reading order.
+fn canto_file(cantica: CanticaId, canto: u8) -> String {
+    format!("{} {:02}", cantica, canto)
+}
+
+/// A wikilink to verse `line` of a canto, shown as `text`.
+fn wikilink(cantica: CanticaId, canto: u8, line: usize, text: &str) -> String {
+    format!("[[{}#^line-{}|{}]]", canto_file(cantica, canto), line, text)
+}
+
+/// `text` with each citation of a verse or verses, `Inferno 5.100` or
+/// `Inf. V 100-106`, made a wikilink to its first verse.
+fn link_citations(text: &str) -> String {
+    let citation =
+        Regex::new(r"\b(\p{L}+\.?)\s+(\d+|[IVXLCDMivxlcdm]+)(?:[.:]|\s+)(\d+)(?:\s*[-–]\s*\d+)?\b")
+            .unwrap();
+    citation
+        .replace_all(text, |caps: &Captures| {
+            let cantica = caps[1].parse::<CanticaId>();
+            let canto = parse_canto_number(&caps[2]);
+            let line = caps[3].parse::<usize>();
+            match (cantica, canto, line) {
+                (Ok(cantica), Some(canto), Ok(line)) => wikilink(cantica, canto, line, &caps[0]),
+                _ => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// The files of the Obsidian vault of `commedia` and `cantos`' notes and
+/// bookmarks, as paths within it and their content.
+pub fn obsidian(commedia: &DivinaCommedia, cantos: &[CantoNotes]) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    for (cantica, canto) in commedia.cantos() {
+        let mut out = format!("# {} {}\n", cantica, canto.roman_numeral);
+        for verse in &canto.verses {
+            out.push_str(&format!("\n{} ^line-{}\n", verse.text, verse.line_number));
+        }
+        let name = format!("{}.md", canto_file(cantica, canto.number));
+        files.push((PathBuf::from(cantica.name()).join(name), out));
+    }
+
+    for notes in cantos.iter().filter(|notes| !notes.is_empty()) {
+        let (cantica, canto) = (notes.cantica, notes.canto);
+        let file = canto_file(cantica, canto);
+        let mut out = format!("# Notes on [[{}]]\n", file);
+        if !notes.bookmarks.is_empty() {
+            out.push_str("\n## Bookmarks\n\n");
+            for bookmark in &notes.bookmarks {
+                let citation = format!("{} {}.{}", cantica, canto, bookmark.line);
+                out.push_str(&format!(
+                    "- {}",
+                    wikilink(cantica, canto, bookmark.line, &citation)
+                ));
+                if let Some(label) = &bookmark.label {
+                    out.push_str(&format!(": {}", label));
+                }
+                for tag in &bookmark.tags {
+                    out.push_str(&format!(" #{}", tag));
+                }
+                out.push('\n');
+            }
+        }
+        if !notes.notes.is_empty() {
+            out.push_str("\n## Notes\n");
+            for note in &notes.notes {
+                let (start, end) = (*note.lines.start(), *note.lines.end());
+                let citation = if start == end {
+                    format!("{} {}.{}", cantica, canto, start)
+                } else {
+                    format!("{} {}.{}-{}", cantica, canto, start, end)
+                };
+                out.push_str(&format!(
+                    "\n### {}\n\n",
+                    wikilink(cantica, canto, start, &citation)
+                ));
+                out.push_str(&link_citations(&note.text));
+                out.push('\n');
+            }
+        }
+        files.push((
+            PathBuf::from("Notes").join(format!("{} notes.md", file)),
+            out,
+        ));
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_org() {
+        let commedia = crate::commedia().unwrap();
+        let mut inferno = CantoNotes::new(CanticaId::Inferno, 5);
+        inferno.set_bookmark(121, Some("Nessun maggior dolore"));
+        inferno.tag_bookmark(121, &["sorrow", "francesca"]);
+        inferno.add_note(100..=106, "Amor, Amor, Amor\n* the anaphora");
+        let mut paradiso = CantoNotes::new(CanticaId::Paradiso, 33);
+        paradiso.set_bookmark(145, None);
+        let cantos = [inferno, CantoNotes::new(CanticaId::Purgatorio, 1), paradiso];
+
+        let changed =
+            |cantica, _| (cantica == CanticaId::Inferno).then(|| Date::new(2026, 10, 16).unwrap());
+        assert_eq!(
+            org(commedia, &cantos, changed),
+            "#+title: Divina Commedia: notes and bookmarks\n\
+             \n\
+             * Inferno V\n\
+             ** Inferno 5.121: Nessun maggior dolore :francesca:sorrow:\n\
+             #+begin_quote\n\
+             E quella a me: «Nessun maggior dolore\n\
+             #+end_quote\n\
+             ** Note on Inferno 5.100-106\n\
+             [2026-10-16 Fri]\n\
+             Amor, Amor, Amor\n\
+             \x20* the anaphora\n\
+             \n\
+             * Paradiso XXXIII\n\
+             ** Paradiso 33.145\n\
+             #+begin_quote\n\
+             l’amor che move il sole e l’altre stelle.\n\
+             #+end_quote\n"
+        );
+    }
+
+    #[test]
+    fn test_link_citations() {
+        assert_eq!(
+            link_citations("As at Inferno 5.100-106, and Purg. XXVI 117."),
+            "As at [[Inferno 05#^line-100|Inferno 5.100-106]], and \
+             [[Purgatorio 26#^line-117|Purg. XXVI 117]]."
+        );
+        assert_eq!(
+            link_citations("Amor 3 volte, Inferno 51"),
+            "Amor 3 volte, Inferno 51"
+        );
+    }
+
+    #[test]
+    fn test_obsidian() {
+        let commedia = crate::commedia().unwrap();
+        let mut notes = CantoNotes::new(CanticaId::Inferno, 5);
+        notes.set_bookmark(121, Some("Nessun maggior dolore"));
+        notes.tag_bookmark(121, &["francesca"]);
+        notes.add_note(100..=106, "Amor, Amor, Amor; cf. Inferno 5.121");
+        let files = obsidian(commedia, &[notes, CantoNotes::new(CanticaId::Inferno, 6)]);
+        assert_eq!(files.len(), commedia.cantos().count() + 1);
+
+        let (path, canto) = &files[4];
+        assert_eq!(path, &PathBuf::from("Inferno/Inferno 05.md"));
+        assert!(canto.starts_with("# Inferno V\n\n"));
+        assert!(canto.contains("\nE quella a me: «Nessun maggior dolore ^line-121\n"));
+
+        let (path, notes) = files.last().unwrap();
+        assert_eq!(path, &PathBuf::from("Notes/Inferno 05 notes.md"));
+        assert_eq!(
+            notes,
+            "# Notes on [[Inferno 05]]\n\
+             \n\
+             ## Bookmarks\n\
+             \n\
+             - [[Inferno 05#^line-121|Inferno 5.121]]: Nessun maggior dolore #francesca\n\
+             \n\
+             ## Notes\n\
+             \n\
+             ### [[Inferno 05#^line-100|Inferno 5.100-106]]\n\
+             \n\
+             Amor, Amor, Amor; cf. [[Inferno 05#^line-121|Inferno 5.121]]\n"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! RSS 2.0 feeds of the reading plan, for reading groups to follow from a
+//! static host: an item for each day of the current round up to a date,
+//! newest first, with the canto's opening tercet and citation.
+
+use std::fmt::Write;
+
+use duca::plan::{self, Date, Plan};
+use duca::{DivinaCommedia, DucaError, Passage};
+
+use crate::i18n::{tr, Text};
+use crate::links;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// `text` with the characters XML reserves escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Midnight UTC of `date` as RSS writes dates, e.g.
+/// `Sun, 12 Jan 2025 00:00:00 +0000`.
+fn rfc822(date: Date) -> String {
+    // 1 January 1970 was a Thursday
+    let weekday = WEEKDAYS[(date.days() + 3).rem_euclid(7) as usize];
+    format!(
+        "{}, {:02} {} {} 00:00:00 +0000",
+        weekday,
+        date.day,
+        MONTHS[date.month as usize - 1],
+        date.year
+    )
+}
+
+/// The feed of `plan`'s days up to `through`, its channel linking to
+/// `link` and each item to the canto on the commentary site `open-web` uses.
+pub fn rss(
+    commedia: &DivinaCommedia,
+    plan: &Plan,
+    through: Date,
+    link: &str,
+) -> Result<String, DucaError> {
+    let template = links::template(None);
+    let mut feed = String::new();
+    let mut line = |text: String| {
+        feed.push_str(&text);
+        feed.push('\n');
+    };
+    line(r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string());
+    line(r#"<rss version="2.0">"#.to_string());
+    line("  <channel>".to_string());
+    let title = tr(Text::PlanFeedTitle, &[&plan.start]);
+    line(format!("    <title>{}</title>", escape(&title)));
+    line(format!("    <link>{}</link>", escape(link)));
+    let description = tr(Text::PlanFeedDescription, &[]);
+    line(format!(
+        "    <description>{}</description>",
+        escape(&description)
+    ));
+    line("    <language>it</language>".to_string());
+    line(format!(
+        "    <lastBuildDate>{}</lastBuildDate>",
+        rfc822(through)
+    ));
+
+    let round = plan.round_start(through);
+    for day in (1..=plan.day(through).unwrap_or(0)).rev() {
+        let (cantica, number) = plan::canto(day);
+        let canto = commedia.require_canto(cantica, number)?;
+        let tercet = canto.tercet_at(1).unwrap_or_default();
+        let citation = Passage {
+            cantica,
+            canto: number,
+            lines: Some(1..=tercet.len()),
+        };
+        let date = round.add_days(day as i64 - 1);
+
+        let mut excerpt = String::from("<p>");
+        for (i, verse) in tercet.iter().enumerate() {
+            if i > 0 {
+                excerpt.push_str("<br>");
+            }
+            excerpt.push_str(&escape(&verse.text));
+        }
+        let _ = write!(excerpt, "</p><p>{}</p>", escape(&citation.to_string()));
+
+        let title = tr(
+            Text::TodayCanto,
+            &[&day, &plan::DAYS, &cantica, &canto.roman_numeral],
+        );
+        line("    <item>".to_string());
+        line(format!("      <title>{}</title>", escape(&title)));
+        line(format!(
+            "      <link>{}</link>",
+            escape(&links::url(&template, cantica, number, 1))
+        ));
+        line(format!(
+            "      <guid isPermaLink=\"false\">{} {}</guid>",
+            date, citation
+        ));
+        line(format!("      <pubDate>{}</pubDate>", rfc822(date)));
+        line(format!("      <category>{}</category>", cantica));
+        line(format!(
+            "      <description>{}</description>",
+            escape(&excerpt)
+        ));
+        line("    </item>".to_string());
+    }
+
+    line("  </channel>".to_string());
+    line("</rss>".to_string());
+    Ok(feed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::load_commedia;
+
+    #[test]
+    fn test_rfc822() {
+        assert_eq!(
+            rfc822("2025-01-12".parse().unwrap()),
+            "Sun, 12 Jan 2025 00:00:00 +0000"
+        );
+        assert_eq!(
+            rfc822("2024-02-29".parse().unwrap()),
+            "Thu, 29 Feb 2024 00:00:00 +0000"
+        );
+    }
+
+    #[test]
+    fn test_rss() {
+        let commedia = load_commedia().unwrap();
+        let plan = Plan::new("2025-01-01".parse().unwrap());
+        let feed = rss(
+            &commedia,
+            &plan,
+            "2025-04-12".parse().unwrap(),
+            "https://example.org/?a&b",
+        )
+        .unwrap();
+
+        let document = roxmltree::Document::parse(&feed).unwrap();
+        let items: Vec<_> = document
+            .descendants()
+            .filter(|node| node.has_tag_name("item"))
+            .collect();
+        // The second round's first two days, newest first
+        assert_eq!(items.len(), 2);
+        let child = |item: roxmltree::Node, name| {
+            item.children()
+                .find(|node| node.has_tag_name(name))
+                .and_then(|node| node.text())
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(child(items[0], "title"), "Day 2 of 100: Inferno II");
+        assert_eq!(child(items[1], "guid"), "2025-04-11 Inferno 1.1-3");
+        assert_eq!(
+            child(items[1], "pubDate"),
+            "Fri, 11 Apr 2025 00:00:00 +0000"
+        );
+        assert!(child(items[1], "description")
+            .starts_with("<p>Nel mezzo del cammin di nostra vita<br>"));
+        assert!(feed.contains("<link>https://example.org/?a&amp;b</link>"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Downloading the Project Gutenberg texts that `duca parse` reads.
+//!
+//! Downloads are kept in [`paths::cache_dir`], and the SHA-256 of each is
+//! recorded in `checksums.toml` there the first time it is fetched. After
+//! that the cached copy is used without touching the network, so parsing
+//! works offline. A refresh downloads the text again and refuses one whose
+//! checksum no longer matches the record unless forced, so an upstream file
+//! that changed silently can't slip into the corpus.
+
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{paths, DucaError};
+
+/// Where the texts are downloaded from unless `$DUCA_FETCH_URL` says
+/// otherwise, e.g. a Gutenberg mirror.
+pub const DEFAULT_URL: &str = "https://www.gutenberg.org/cache/epub";
+
+/// The cache's record of known-good checksums, by file name.
+const CHECKSUMS: &str = "checksums.toml";
+
+/// A text to download: a Gutenberg ebook and the file `parse` expects it in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Source {
+    pub file: &'static str,
+    pub ebook: u32,
+}
+
+/// The three canticas of the built-in edition.
+pub const SOURCES: [Source; 3] = [
+    Source {
+        file: "inferno.txt",
+        ebook: 997,
+    },
+    Source {
+        file: "purgatorio.txt",
+        ebook: 998,
+    },
+    Source {
+        file: "paradiso.txt",
+        ebook: 999,
+    },
+];
+
+impl Source {
+    /// The URL of the ebook's plain text under `base`.
+    pub fn url(&self, base: &str) -> String {
+        format!(
+            "{}/{}/pg{}.txt",
+            base.trim_end_matches('/'),
+            self.ebook,
+            self.ebook
+        )
+    }
+}
+
+/// How [`fetch`] treats the cache and the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// Download again even when the cache has a good copy.
+    pub refresh: bool,
+    /// Accept a download whose checksum differs from the recorded one.
+    pub force: bool,
+    /// Never use the network; fail if the cache can't serve the file.
+    pub offline: bool,
+}
+
+/// Where the text [`fetch`] returned came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fetched {
+    /// The cached copy, which matched its checksum.
+    Cached,
+    /// A new download, recorded if it was the first or matching the record.
+    Downloaded,
+    /// A download that differed from the record and replaced it.
+    Replaced,
+}
+
+/// The URL texts are downloaded from: `$DUCA_FETCH_URL`, or [`DEFAULT_URL`].
+pub fn base_url() -> String {
+    env::var("DUCA_FETCH_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_URL.to_string())
+}
+
+/// The hex SHA-256 of `bytes`.
+pub fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// The checksums recorded in the cache at `dir`, by file name.
+fn read_checksums(dir: &Path) -> Result<BTreeMap<String, String>, DucaError> {
+    let path = dir.join(CHECKSUMS);
+    match fs::read_to_string(&path) {
+        Ok(content) => toml::from_str(&content).map_err(|e| DucaError::UserData {
+            path: path.display().to_string(),
+            reason: e.message().to_string(),
+        }),
+        Err(_) => Ok(BTreeMap::new()),
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, DucaError> {
+    let bytes = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .and_then(|client| client.get(url).send())
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.bytes());
+    bytes
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| DucaError::Fetch {
+            url: url.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// The contents of `source`, from the cache when it holds a copy matching
+/// the recorded checksum and otherwise downloaded from `base` and cached.
+pub fn fetch(
+    source: &Source,
+    base: &str,
+    options: FetchOptions,
+) -> Result<(Vec<u8>, Fetched), DucaError> {
+    let url = source.url(base);
+    let dir = paths::cache_dir().ok_or_else(|| DucaError::Fetch {
+        url: url.clone(),
+        reason: "no cache directory; set $HOME or $XDG_CACHE_HOME".to_string(),
+    })?;
+    let cached = dir.join(source.file);
+    let mut checksums = read_checksums(&dir)?;
+    let recorded = checksums.get(source.file).cloned();
+
+    if !options.refresh {
+        if let (Ok(bytes), Some(recorded)) = (fs::read(&cached), &recorded) {
+            if sha256(&bytes) == *recorded {
+                return Ok((bytes, Fetched::Cached));
+            }
+        }
+    }
+    if options.offline {
+        return Err(DucaError::Fetch {
+            url,
+            reason: "no good copy in the cache; run `duca fetch` online first".to_string(),
+        });
+    }
+
+    let bytes = download(&url)?;
+    let checksum = sha256(&bytes);
+    let fetched = match recorded {
+        Some(recorded) if recorded != checksum => {
+            if !options.force {
+                return Err(DucaError::Fetch {
+                    url,
+                    reason: format!(
+                        "changed upstream: SHA-256 {} where {} was recorded; \
+                         use --force to accept it",
+                        checksum, recorded
+                    ),
+                });
+            }
+            Fetched::Replaced
+        }
+        _ => Fetched::Downloaded,
+    };
+
+    let unwritable = |e: std::io::Error| DucaError::Fetch {
+        url: url.clone(),
+        reason: format!("{}: {}", dir.display(), e),
+    };
+    fs::create_dir_all(&dir).map_err(unwritable)?;
+    fs::write(&cached, &bytes).map_err(unwritable)?;
+    checksums.insert(source.file.to_string(), checksum);
+    let record = toml::to_string(&checksums).expect("checksums serialize to TOML");
+    fs::write(dir.join(CHECKSUMS), record).map_err(unwritable)?;
+    Ok((bytes, fetched))
+}
+
+/// Fetch every one of [`SOURCES`] into `out_dir`, where `parse` will find
+/// them, returning each with where it came from.
+pub fn fetch_all(
+    out_dir: &Path,
+    options: FetchOptions,
+) -> Result<Vec<(PathBuf, Fetched)>, DucaError> {
+    let base = base_url();
+    fs::create_dir_all(out_dir)?;
+    SOURCES
+        .iter()
+        .map(|source| {
+            let (bytes, fetched) = fetch(source, &base, options)?;
+            let path = out_dir.join(source.file);
+            fs::write(&path, bytes)?;
+            Ok((path, fetched))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_urls() {
+        assert_eq!(
+            SOURCES[0].url(DEFAULT_URL),
+            "https://www.gutenberg.org/cache/epub/997/pg997.txt"
+        );
+        assert_eq!(
+            SOURCES[2].url("http://mirror.example/"),
+            "http://mirror.example/999/pg999.txt"
+        );
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Glosses on archaic and poetic words, for readers of Dante's Italian.
+//!
+//! The glossary is a TOML file of entries keyed by headword, with other
+//! spellings listed as `forms`:
+//!
+//! ```toml
+//! [[entry]]
+//! word = "convene"
+//! forms = ["conven", "convien"]
+//! definition = "it is fitting, it is needful"
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::words::word_ranges;
+use crate::{normalize, DucaError};
+
+/// The glossary compiled into duca.
+const BUILTIN: &str = include_str!("../glossary/glossary.toml");
+
+/// A glossed word.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GlossEntry {
+    pub word: String,
+    #[serde(default)]
+    pub forms: Vec<String>,
+    pub definition: String,
+}
+
+/// Glossary entries, looked up by any of their folded forms.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Glossary {
+    entries: Vec<GlossEntry>,
+    forms: BTreeMap<String, usize>,
+}
+
+#[derive(Deserialize)]
+struct GlossaryFile {
+    #[serde(default, alias = "entries")]
+    entry: Vec<GlossEntry>,
+}
+
+impl Glossary {
+    /// Parse a glossary file's content; `name` identifies it in errors.
+    pub fn parse(content: &str, name: &str) -> Result<Self, DucaError> {
+        let file: GlossaryFile = toml::from_str(content).map_err(|e| DucaError::UserData {
+            path: name.to_string(),
+            reason: e.message().to_string(),
+        })?;
+
+        let mut glossary = Self::default();
+        for entry in file.entry {
+            let i = glossary.entries.len();
+            for form in std::iter::once(&entry.word).chain(&entry.forms) {
+                glossary.forms.entry(normalize::fold(form)).or_insert(i);
+            }
+            glossary.entries.push(entry);
+        }
+        Ok(glossary)
+    }
+
+    /// The glossary compiled into duca.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN, "glossary.toml").expect("built-in glossary is valid")
+    }
+
+    /// The entry for `word` in any of its forms, ignoring case and accents.
+    pub fn lookup(&self, word: &str) -> Option<&GlossEntry> {
+        self.forms
+            .get(&normalize::fold(word))
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Byte ranges of the words of `text` that have a gloss.
+    pub fn glossed_words(&self, text: &str) -> Vec<Range<usize>> {
+        word_ranges(text)
+            .into_iter()
+            .filter(|range| self.lookup(&text[range.clone()]).is_some())
+            .collect()
+    }
+
+    /// Every entry, in file order.
+    pub fn iter(&self) -> impl Iterator<Item = &GlossEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_glossary() {
+        let glossary = Glossary::builtin();
+        assert!(!glossary.is_empty());
+        assert_eq!(glossary.lookup("Altrui").unwrap().word, "altrui");
+        assert_eq!(glossary.lookup("convien").unwrap().word, "convene");
+        assert_eq!(glossary.lookup("teste").unwrap().word, "testé");
+        assert!(glossary.lookup("selva").is_none());
+    }
+
+    #[test]
+    fn test_glossed_words() {
+        let glossary = Glossary::builtin();
+        let text = "Io non so ben ridir com’ i’ v’intrai, tant’ era pien di sonno a quel punto";
+        assert!(glossary.glossed_words(text).is_empty());
+
+        let text = "lo duca mio, e dissi: «Omai sovr’ altrui»";
+        let words: Vec<&str> = glossary
+            .glossed_words(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(words, ["duca", "Omai", "sovr", "altrui"]);
+    }
+
+    #[test]
+    fn test_invalid_glossary() {
+        let error = Glossary::parse("[[entry]]\nword = \"onde\"", "mine.toml").unwrap_err();
+        assert!(error.to_string().contains("mine.toml"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Which characters of the poem appear in the same cantos, from the
+//! [speakers](crate::speakers) of its direct speech, for `duca graph
+//! characters`: written for Graphviz as DOT or for Gephi as GEXF.
+//!
+//! A character is in a canto when they speak in it, so one who is only
+//! spoken of isn't; two characters are joined by an edge weighted by the
+//! number of cantos they both speak in.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::DivinaCommedia;
+
+/// The characters of the poem and the cantos they share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterGraph {
+    /// Each character, alphabetically, with the number of cantos they speak in.
+    pub characters: Vec<(String, usize)>,
+    /// Each pair of characters speaking in the same cantos, as indexes into
+    /// `characters`, with the number of cantos they share.
+    pub edges: Vec<(usize, usize, usize)>,
+}
+
+impl CharacterGraph {
+    /// The graph of the speakers of `commedia`'s cantos.
+    pub fn new(commedia: &DivinaCommedia) -> Self {
+        let cantos: Vec<BTreeSet<&str>> = commedia
+            .cantos()
+            .map(|(_, canto)| {
+                canto
+                    .speeches
+                    .iter()
+                    .map(|speech| speech.speaker.trim())
+                    .filter(|speaker| !speaker.is_empty())
+                    .collect()
+            })
+            .collect();
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for speakers in &cantos {
+            for speaker in speakers {
+                *counts.entry(speaker).or_default() += 1;
+            }
+        }
+        let index = |name: &str| counts.keys().position(|k| *k == name).unwrap_or_default();
+
+        let mut shared: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for speakers in &cantos {
+            let speakers: Vec<&str> = speakers.iter().copied().collect();
+            for (i, a) in speakers.iter().enumerate() {
+                for b in &speakers[i + 1..] {
+                    *shared.entry((index(a), index(b))).or_default() += 1;
+                }
+            }
+        }
+
+        Self {
+            characters: counts
+                .iter()
+                .map(|(name, count)| (name.to_string(), *count))
+                .collect(),
+            edges: shared.into_iter().map(|((a, b), n)| (a, b, n)).collect(),
+        }
+    }
+
+    /// The graph in Graphviz's DOT language, each edge labeled with its
+    /// weight, for `dot` or `neato` to lay out.
+    pub fn dot(&self) -> String {
+        let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("graph characters {\n");
+        for (name, cantos) in &self.characters {
+            let _ = writeln!(out, "    {} [cantos={}];", quote(name), cantos);
+        }
+        for (a, b, weight) in &self.edges {
+            let _ = writeln!(
+                out,
+                "    {} -- {} [weight={weight}, label={weight}];",
+                quote(&self.characters[*a].0),
+                quote(&self.characters[*b].0),
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The graph as a GEXF 1.3 document, each character's cantos an
+    /// attribute, for Gephi.
+    pub fn gexf(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n\
+             \x20 <graph mode=\"static\" defaultedgetype=\"undirected\">\n\
+             \x20   <attributes class=\"node\">\n\
+             \x20     <attribute id=\"cantos\" title=\"cantos\" type=\"integer\"/>\n\
+             \x20   </attributes>\n\
+             \x20   <nodes>\n",
+        );
+        for (i, (name, cantos)) in self.characters.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "      <node id=\"{i}\" label=\"{}\"><attvalues><attvalue for=\"cantos\" value=\"{cantos}\"/></attvalues></node>",
+                escape(name)
+            );
+        }
+        out.push_str("    </nodes>\n    <edges>\n");
+        for (i, (a, b, weight)) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "      <edge id=\"{i}\" source=\"{a}\" target=\"{b}\" weight=\"{weight}\"/>"
+            );
+        }
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        out
+    }
+}
+
+/// `text` with the characters XML reserves escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_graph() {
+        let graph = CharacterGraph::new(crate::commedia().unwrap());
+        let index = |name: &str| {
+            graph
+                .characters
+                .iter()
+                .position(|(n, _)| n == name)
+                .unwrap()
+        };
+        let (dante, virgilio) = (index("Dante"), index("Virgilio"));
+        assert!(graph.characters[dante].1 >= graph.characters[index("Francesca")].1);
+        assert!(graph
+            .edges
+            .iter()
+            .any(|&(a, b, n)| (a, b) == (dante, virgilio) && n > 1));
+        assert!(graph.edges.iter().all(|&(a, b, _)| a < b));
+
+        let dot = graph.dot();
+        assert!(dot.starts_with("graph characters {\n"));
+        assert!(dot.contains("    \"Dante\" -- \"Virgilio\" [weight="));
+        let gexf = graph.gexf();
+        assert!(gexf.contains("<node id=\"0\" label=\"Alberigo\">"));
+        assert_eq!(gexf.matches("<edge ").count(), graph.edges.len());
+    }
+}
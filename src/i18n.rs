@@ -0,0 +1,689 @@
+//! The CLI's and TUI's own words in English and Italian. The poem is never
+//! translated; headers, status messages, help and the TUI's titles are.
+//!
+//! The language is `$DUCA_LANG` if set, or else the locale's, from
+//! `$LC_ALL`, `$LC_MESSAGES` or `$LANG` as POSIX orders them; anything but
+//! Italian gets English.
+
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    Italian,
+}
+
+impl Locale {
+    /// The locale a tag such as `it`, `it_IT.UTF-8` or `en-GB` names, if
+    /// it's one duca speaks.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "it" => Some(Locale::Italian),
+            "en" | "c" | "posix" => Some(Locale::English),
+            _ => None,
+        }
+    }
+
+    /// The locale asked for in the environment.
+    pub fn from_env() -> Self {
+        let tag = |name| env::var(name).ok().filter(|tag| !tag.is_empty());
+        let tag = tag("DUCA_LANG")
+            .or_else(|| tag("LC_ALL"))
+            .or_else(|| tag("LC_MESSAGES"))
+            .or_else(|| tag("LANG"));
+        tag.and_then(|tag| Self::from_tag(&tag))
+            .unwrap_or(Locale::English)
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// The locale of this run, read from the environment on first use.
+pub fn locale() -> Locale {
+    *LOCALE.get_or_init(Locale::from_env)
+}
+
+/// A string shown to the user, with `{}` where its arguments go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "tui"), allow(dead_code))]
+pub enum Text {
+    FoundMatches,
+    NoMatches,
+    NoMatchesSuggest,
+    HeatmapMost,
+    FrequencyPer,
+    ExplainScore,
+    ExplainTerm,
+    CantoNotFound,
+    SectionNotFound,
+    NoCrossReferences,
+    EditionVerified,
+    EditionFailed,
+    TodayCanto,
+    TodayNotStarted,
+    PlanFeedTitle,
+    PlanFeedDescription,
+    TuiCanticas,
+    TuiCantos,
+    TuiSelectCanto,
+    TuiHelp,
+    TuiSearch,
+    TuiCompletions,
+    TuiAccentsIgnored,
+    TuiAccentsExact,
+    TuiWordForms,
+    TuiOldSpellings,
+    TuiRegex,
+    TuiResults,
+    TuiNoMatches,
+    TuiContext,
+    TuiContextTitle,
+    TuiNoContext,
+    TuiCommentary,
+    TuiNoCommentary,
+    TuiRelated,
+    TuiNoRelated,
+    TuiFootnote,
+    TuiFootnotes,
+    TuiNoFootnotes,
+    TuiStats,
+    TuiStatsSize,
+    TuiStatsRhymes,
+    TuiStatsKeywords,
+    TuiStatsNotes,
+    TuiStatsRead,
+    TuiStatsUnread,
+    TuiTerms,
+    TuiHeatmap,
+    TuiNoTerms,
+    TuiFinding,
+    TuiNotFound,
+    TuiFound,
+    TuiPlaying,
+    TuiPaused,
+    TuiSessionReading,
+    TuiSessionBreakDue,
+    TuiSessionBreak,
+    TuiBookmarkTags,
+    TuiBookmarks,
+    TuiNoBookmarks,
+    AccessibleHeading,
+    AccessibleLine,
+    AccessibleCited,
+    AccessibleMatch,
+    AccessibleSummary,
+    ReaderWelcome,
+    ReaderHelp,
+    ReaderCanto,
+    ReaderLines,
+    ReaderLine,
+    ReaderBeginningOfCanto,
+    ReaderBeginning,
+    ReaderEnd,
+    ReaderUnknown,
+}
+
+impl Text {
+    #[cfg(test)]
+    const ALL: [Text; 74] = [
+        Text::FoundMatches,
+        Text::NoMatches,
+        Text::NoMatchesSuggest,
+        Text::HeatmapMost,
+        Text::FrequencyPer,
+        Text::ExplainScore,
+        Text::ExplainTerm,
+        Text::CantoNotFound,
+        Text::SectionNotFound,
+        Text::NoCrossReferences,
+        Text::EditionVerified,
+        Text::EditionFailed,
+        Text::TodayCanto,
+        Text::TodayNotStarted,
+        Text::PlanFeedTitle,
+        Text::PlanFeedDescription,
+        Text::TuiCanticas,
+        Text::TuiCantos,
+        Text::TuiSelectCanto,
+        Text::TuiHelp,
+        Text::TuiSearch,
+        Text::TuiCompletions,
+        Text::TuiAccentsIgnored,
+        Text::TuiAccentsExact,
+        Text::TuiWordForms,
+        Text::TuiOldSpellings,
+        Text::TuiRegex,
+        Text::TuiResults,
+        Text::TuiNoMatches,
+        Text::TuiContext,
+        Text::TuiContextTitle,
+        Text::TuiNoContext,
+        Text::TuiCommentary,
+        Text::TuiNoCommentary,
+        Text::TuiRelated,
+        Text::TuiNoRelated,
+        Text::TuiFootnote,
+        Text::TuiFootnotes,
+        Text::TuiNoFootnotes,
+        Text::TuiStats,
+        Text::TuiStatsSize,
+        Text::TuiStatsRhymes,
+        Text::TuiStatsKeywords,
+        Text::TuiStatsNotes,
+        Text::TuiStatsRead,
+        Text::TuiStatsUnread,
+        Text::TuiTerms,
+        Text::TuiHeatmap,
+        Text::TuiNoTerms,
+        Text::TuiFinding,
+        Text::TuiNotFound,
+        Text::TuiFound,
+        Text::TuiPlaying,
+        Text::TuiPaused,
+        Text::TuiSessionReading,
+        Text::TuiSessionBreakDue,
+        Text::TuiSessionBreak,
+        Text::TuiBookmarkTags,
+        Text::TuiBookmarks,
+        Text::TuiNoBookmarks,
+        Text::AccessibleHeading,
+        Text::AccessibleLine,
+        Text::AccessibleCited,
+        Text::AccessibleMatch,
+        Text::AccessibleSummary,
+        Text::ReaderWelcome,
+        Text::ReaderHelp,
+        Text::ReaderCanto,
+        Text::ReaderLines,
+        Text::ReaderLine,
+        Text::ReaderBeginningOfCanto,
+        Text::ReaderBeginning,
+        Text::ReaderEnd,
+        Text::ReaderUnknown,
+    ];
+
+    /// The English and Italian templates.
+    fn templates(self) -> (&'static str, &'static str) {
+        match self {
+            Text::FoundMatches => (
+                "Found {} matches for '{}':",
+                "Trovate {} corrispondenze per '{}':",
+            ),
+            Text::NoMatches => (
+                "No matches found for '{}'",
+                "Nessuna corrispondenza per '{}'",
+            ),
+            Text::NoMatchesSuggest => (
+                "No matches found for '{}' — did you mean '{}'?",
+                "Nessuna corrispondenza per '{}' — forse cercavi '{}'?",
+            ),
+            Text::HeatmapMost => (
+                "'{}': {} matching verses, the most in {} {} ({})",
+                "'{}': {} versi corrispondenti, di più in {} {} ({})",
+            ),
+            Text::FrequencyPer => (
+                "Uses per 10,000 words of each cantica",
+                "Occorrenze ogni 10.000 parole di ciascuna cantica",
+            ),
+            Text::ExplainScore => (
+                "score {} ({} words, {} of the average verse)",
+                "punteggio {} ({} parole, {} del verso medio)",
+            ),
+            Text::ExplainTerm => (
+                "{} at word {}: idf {} × frequency {} × weight {} = {}",
+                "{} alla parola {}: idf {} × frequenza {} × peso {} = {}",
+            ),
+            Text::CantoNotFound => ("Canto {} not found in {}", "Canto {} non trovato in {}"),
+            Text::SectionNotFound => ("Section {} not found in {}", "Sezione {} non trovata in {}"),
+            Text::NoCrossReferences => {
+                ("No cross-references listed for {}", "Nessun rimando per {}")
+            }
+            Text::EditionVerified => (
+                "Edition '{}' verified: {} cantos, {} lines",
+                "Edizione '{}' verificata: {} canti, {} versi",
+            ),
+            Text::EditionFailed => (
+                "Edition '{}' failed verification with {} deviations",
+                "L'edizione '{}' non ha superato la verifica: {} difformità",
+            ),
+            Text::TodayCanto => ("Day {} of {}: {} {}", "Giorno {} di {}: {} {}"),
+            Text::TodayNotStarted => (
+                "The reading plan starts on {}",
+                "Il piano di lettura comincia il {}",
+            ),
+            Text::PlanFeedTitle => (
+                "The Divine Comedy, a canto a day from {}",
+                "La Divina Commedia, un canto al giorno dal {}",
+            ),
+            Text::PlanFeedDescription => (
+                "Each day's canto of Dante's Commedia, with its opening tercet",
+                "Il canto di ogni giorno della Commedia di Dante, con la sua prima terzina",
+            ),
+            Text::TuiCanticas => ("Cantica", "Cantica"),
+            Text::TuiCantos => ("Cantos", "Canti"),
+            Text::TuiSelectCanto => ("{} - Select a Canto", "{} - Scegli un canto"),
+            Text::TuiHelp => (
+                "Navigation:\n\
+                 h/← l/→  - Switch Cantica\n\
+                 j/↓ k/↑  - Select Canto, then move the cursor\n\
+                 [ ]      - Previous and next canto\n\
+                 J K      - Scroll verses\n\
+                 /        - Interactive Search (fzf-like)\n\
+                 f n N    - Find in the canto, next, previous\n\
+                 c        - Toggle commentary\n\
+                 x X      - Toggle related passages, follow one\n\
+                 i        - Toggle canto statistics\n\
+                 t        - The canto's words, sized by use\n\
+                 v        - Toggle variant readings\n\
+                 a        - Toggle allusions to the Aeneid, Bible, Ovid\n\
+                 F . ,    - Toggle footnotes, show the next, previous\n\
+                 w        - Open passage in a web commentary\n\
+                 s        - Speak the tercet with $DUCA_TTS_CMD\n\
+                 p        - Play or pause the canto's recording\n\
+                 b        - Bookmarks, filtered by tag\n\
+                 z        - Take a break from a timed session\n\
+                 Ctrl-o Ctrl-n - Back and forward through jumps (or Ctrl-i)\n\
+                 Tab K    - Select a word, then look it up\n\
+                 q        - Quit\n\
+                 \n\
+                 Search Features:\n\
+                 • Live filtering as you type\n\
+                 • Fuzzy matching with scoring\n\
+                 • Tab to complete a word of the poem\n\
+                 • Ctrl-t for a heatmap of the results by canto\n\
+                 • Enter to view in context\n\
+                 • Esc to return",
+                "Navigazione:\n\
+                 h/← l/→  - Cambia cantica\n\
+                 j/↓ k/↑  - Scegli il canto, poi muovi il cursore\n\
+                 [ ]      - Canto precedente e successivo\n\
+                 J K      - Scorri i versi\n\
+                 /        - Ricerca interattiva (come fzf)\n\
+                 f n N    - Trova nel canto, successivo, precedente\n\
+                 c        - Mostra o nascondi il commento\n\
+                 x X      - Mostra o nascondi i passi correlati, seguine uno\n\
+                 i        - Mostra o nascondi le statistiche del canto\n\
+                 t        - Le parole del canto, grandi quanto usate\n\
+                 v        - Mostra o nascondi le varianti\n\
+                 a        - Mostra o nascondi le allusioni a Eneide, Bibbia, Ovidio\n\
+                 F . ,    - Mostra o nascondi le note, la successiva, la precedente\n\
+                 w        - Apri il passo in un commento online\n\
+                 s        - Leggi ad alta voce la terzina con $DUCA_TTS_CMD\n\
+                 p        - Ascolta o metti in pausa la registrazione del canto\n\
+                 b        - Segnalibri, filtrati per tag\n\
+                 z        - Fai una pausa in una sessione cronometrata\n\
+                 Ctrl-o Ctrl-n - Indietro e avanti tra i salti (o Ctrl-i)\n\
+                 Tab K    - Scegli una parola, poi cercala\n\
+                 q        - Esci\n\
+                 \n\
+                 La ricerca:\n\
+                 • Filtra mentre scrivi\n\
+                 • Corrispondenze approssimate, in ordine di punteggio\n\
+                 • Tab per completare una parola del poema\n\
+                 • Ctrl-t per una mappa dei risultati canto per canto\n\
+                 • Invio per vedere il contesto\n\
+                 • Esc per tornare",
+            ),
+            Text::TuiSearch => (
+                "Interactive Search (type to filter) - {} (Ctrl-a, Ctrl-l, Ctrl-s, Ctrl-r)",
+                "Ricerca interattiva (digita per filtrare) - {} (Ctrl-a, Ctrl-l, Ctrl-s, Ctrl-r)",
+            ),
+            Text::TuiCompletions => ("Tab completes: {}", "Tab completa: {}"),
+            Text::TuiAccentsIgnored => ("accents ignored", "accenti ignorati"),
+            Text::TuiAccentsExact => ("accents exact", "accenti esatti"),
+            Text::TuiWordForms => ("word forms", "forme delle parole"),
+            Text::TuiOldSpellings => ("old spellings", "grafie antiche"),
+            Text::TuiRegex => ("regex", "espressione regolare"),
+            Text::TuiResults => (
+                "Results ({}) - Enter to view context",
+                "Risultati ({}) - Invio per il contesto",
+            ),
+            Text::TuiNoMatches => ("No matches found", "Nessuna corrispondenza"),
+            Text::TuiContext => ("Context View", "Contesto"),
+            Text::TuiContextTitle => (
+                "{} Canto {} - Context View (Esc to return)",
+                "{} Canto {} - Contesto (Esc per tornare)",
+            ),
+            Text::TuiNoContext => ("No context available", "Nessun contesto disponibile"),
+            Text::TuiCommentary => ("Commentary (c to hide)", "Commento (c per nasconderlo)"),
+            Text::TuiStats => (
+                "Canto statistics (i to hide)",
+                "Statistiche del canto (i per nasconderle)",
+            ),
+            Text::TuiStatsSize => (
+                "{} lines, {} words ({} different)",
+                "{} versi, {} parole ({} diverse)",
+            ),
+            Text::TuiStatsRhymes => ("{} rhyme chains", "{} catene di rime"),
+            Text::TuiStatsKeywords => ("Keywords", "Parole chiave"),
+            Text::TuiStatsNotes => ("{} note(s), {} bookmark(s)", "{} note, {} segnalibri"),
+            Text::TuiStatsRead => (
+                "Read in {} timed session(s), last on {}",
+                "Letto in {} sessioni cronometrate, l'ultima il {}",
+            ),
+            Text::TuiStatsUnread => (
+                "Not yet read in a timed session",
+                "Non ancora letto in una sessione cronometrata",
+            ),
+            Text::TuiTerms => (
+                "{} Canto {} - its words (t or Esc to close)",
+                "{} Canto {} - le sue parole (t o Esc per chiudere)",
+            ),
+            Text::TuiHeatmap => (
+                "Results by canto (Ctrl-t to hide)",
+                "Risultati per canto (Ctrl-t per nasconderli)",
+            ),
+            Text::TuiNoTerms => (
+                "No word stands out in this canto",
+                "Nessuna parola spicca in questo canto",
+            ),
+            Text::TuiNoCommentary => (
+                "No commentary on this canto.",
+                "Nessun commento a questo canto.",
+            ),
+            Text::TuiRelated => (
+                "Related passages (x to hide, X to follow)",
+                "Passi correlati (x per nasconderli, X per seguirli)",
+            ),
+            Text::TuiNoRelated => (
+                "No related passages listed for this canto.",
+                "Nessun passo correlato per questo canto.",
+            ),
+            Text::TuiFootnote => (
+                "Footnote {} of {} (. and , to cycle, F to hide)",
+                "Nota {} di {} (. e , per scorrerle, F per nasconderle)",
+            ),
+            Text::TuiFootnotes => ("Footnotes (F to hide)", "Note (F per nasconderle)"),
+            Text::TuiNoFootnotes => (
+                "No footnotes on this canto.",
+                "Nessuna nota a questo canto.",
+            ),
+            Text::TuiFinding => (
+                " - Find: {}_ (Enter to keep, Esc to close)",
+                " - Trova: {}_ (Invio per confermare, Esc per chiudere)",
+            ),
+            Text::TuiNotFound => (
+                " - '{}' not found (Esc to close)",
+                " - '{}' non trovato (Esc per chiudere)",
+            ),
+            Text::TuiFound => (
+                " - '{}' {}/{} (n/N, Esc to close)",
+                " - '{}' {}/{} (n/N, Esc per chiudere)",
+            ),
+            Text::TuiPlaying => (" - playing (p to pause)", " - in ascolto (p per la pausa)"),
+            Text::TuiPaused => (" - paused (p to play)", " - in pausa (p per riprendere)"),
+            Text::TuiSessionReading => ("Reading {} ", "Lettura {} "),
+            Text::TuiSessionBreakDue => (
+                "Reading {} - time for a break (z) ",
+                "Lettura {} - è ora di una pausa (z) ",
+            ),
+            Text::TuiSessionBreak => (
+                "Break: {} left (z to read on) ",
+                "Pausa: ancora {} (z per riprendere) ",
+            ),
+            Text::TuiBookmarkTags => (
+                "Bookmarks - type tags to filter (Esc to close)",
+                "Segnalibri - digita dei tag per filtrare (Esc per chiudere)",
+            ),
+            Text::TuiBookmarks => (
+                "Bookmarks ({}) - Enter to go there",
+                "Segnalibri ({}) - Invio per andarci",
+            ),
+            Text::TuiNoBookmarks => ("No bookmarks", "Nessun segnalibro"),
+            Text::AccessibleHeading => (
+                "{}, canto {}, lines {} to {} of {}.",
+                "{}, canto {}, versi da {} a {} di {}.",
+            ),
+            Text::AccessibleLine => ("Line {}: ", "Verso {}: "),
+            Text::AccessibleCited => ("Line {}, cited: ", "Verso {}, citato: "),
+            Text::AccessibleMatch => (
+                "Match {} of {}, {} canto {} line {}: ",
+                "Risultato {} di {}, {} canto {} verso {}: ",
+            ),
+            Text::AccessibleSummary => ("Matches by cantica: {}", "Risultati per cantica: {}"),
+            Text::ReaderWelcome => (
+                "Reading the Commedia. Enter reads the next tercet; h for help.",
+                "Lettura della Commedia. Invio legge la terzina successiva; h per l'aiuto.",
+            ),
+            Text::ReaderHelp => (
+                "Enter or n: next tercet\n\
+                 p: previous tercet\n\
+                 r: read the tercet again\n\
+                 ] and [: next and previous canto\n\
+                 g and a passage, e.g. g inferno 5.100: go there\n\
+                 w: where you are\n\
+                 s and a pattern: search the poem\n\
+                 q: quit",
+                "Invio o n: terzina successiva\n\
+                 p: terzina precedente\n\
+                 r: rileggi la terzina\n\
+                 ] e [: canto successivo e precedente\n\
+                 g e un passo, per esempio g inferno 5.100: vai lì\n\
+                 w: dove sei\n\
+                 s e un testo: cerca nel poema\n\
+                 q: esci",
+            ),
+            Text::ReaderCanto => ("{}, canto {}, {} lines.", "{}, canto {}, {} versi."),
+            Text::ReaderLines => ("Lines {} to {}:", "Versi da {} a {}:"),
+            Text::ReaderLine => ("Line {}:", "Verso {}:"),
+            Text::ReaderBeginningOfCanto => ("Beginning of the canto.", "Inizio del canto."),
+            Text::ReaderBeginning => ("Beginning of the poem.", "Inizio del poema."),
+            Text::ReaderEnd => ("End of the poem.", "Fine del poema."),
+            Text::ReaderUnknown => (
+                "Unknown command '{}'; h for help.",
+                "Comando sconosciuto '{}'; h per l'aiuto.",
+            ),
+        }
+    }
+
+    /// The template in `locale`.
+    pub fn template(self, locale: Locale) -> &'static str {
+        let (english, italian) = self.templates();
+        match locale {
+            Locale::English => english,
+            Locale::Italian => italian,
+        }
+    }
+}
+
+/// `text` in `locale`, with `args` in place of its `{}`s in order.
+pub fn format(locale: Locale, text: Text, args: &[&dyn fmt::Display]) -> String {
+    let mut parts = text.template(locale).split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+/// `text` in this run's locale, with `args` in place of its `{}`s.
+pub fn tr(text: Text, args: &[&dyn fmt::Display]) -> String {
+    format(locale(), text, args)
+}
+
+/// Italian help for the program and each subcommand, by name. English
+/// help is the `about` of each command in main.rs.
+const ITALIAN_HELP: &[(&str, &str)] = &[
+    ("duca", "Leggi la Divina Commedia di Dante dal terminale"),
+    ("search", "Cerca un testo in tutte le cantiche"),
+    ("canto", "Mostra un canto"),
+    (
+        "schema",
+        "Stampa lo schema JSON dell'output --format json di un comando",
+    ),
+    (
+        "editions",
+        "Elenca le edizioni del testo, incluse e installate",
+    ),
+    (
+        "verify",
+        "Controlla il testo: canti mancanti, numero di versi sbagliato, versi vuoti",
+    ),
+    ("gloss", "Spiega una parola arcaica col glossario incluso"),
+    ("latin", "Elenca i versi che citano il latino"),
+    (
+        "speeches",
+        "Elenca i discorsi diretti, con chi parla dove si sa",
+    ),
+    (
+        "xref",
+        "Elenca i passi del poema legati a un canto: profezie, immagini, echi",
+    ),
+    (
+        "allusions",
+        "Elenca le allusioni all'Eneide, alla Bibbia e a Ovidio",
+    ),
+    ("graph", "Scrivi un grafo del poema per Graphviz o Gephi"),
+    (
+        "compare-freq",
+        "Confronta quanto spesso ogni cantica usa alcune parole",
+    ),
+    (
+        "open-citation",
+        "Mostra un verso citato nel suo contesto (per esempio scelto dall'output di --picker)",
+    ),
+    (
+        "tercet",
+        "Stampa la terzina a cui appartiene un verso citato",
+    ),
+    (
+        "read-aloud",
+        "Leggi ad alta voce un canto, o alcuni suoi versi, con un comando di sintesi vocale",
+    ),
+    (
+        "open-web",
+        "Apri un passo in un commento online nel browser",
+    ),
+    (
+        "today",
+        "Mostra il canto di oggi del piano di lettura, o invialo come notifica",
+    ),
+    (
+        "plan",
+        "Elenca i giorni del piano di lettura, o scrivili come feed RSS",
+    ),
+    ("note", "Aggiungi, elenca o elimina le tue note sul poema"),
+    ("bookmark", "Aggiungi, elenca o elimina i segnalibri"),
+    (
+        "data",
+        "Esporta le tue note e i segnalibri in un file, o uniscine uno",
+    ),
+    (
+        "export",
+        "Scrivi le tue note e i segnalibri per un altro programma",
+    ),
+    ("tui", "Modalità interattiva (TUI)"),
+    (
+        "read",
+        "Leggi il poema una terzina alla volta, un comando per riga, per i lettori di schermo",
+    ),
+    ("serve", "Servi un lettore web e un'API HTTP JSON"),
+    (
+        "rpc",
+        "Rispondi a richieste JSON-RPC su stdin/stdout (lookup, search, random)",
+    ),
+    (
+        "mcp",
+        "Avvia un server Model Context Protocol su stdin/stdout",
+    ),
+    (
+        "embed",
+        "Calcola gli embedding di ogni verso per `search --semantic`",
+    ),
+    (
+        "completions",
+        "Genera gli script di completamento per la shell",
+    ),
+    (
+        "works",
+        "Gestisci le opere e le edizioni installate nella cartella dei dati",
+    ),
+    (
+        "fetch",
+        "Scarica i testi del Progetto Gutenberg per `parse`, tramite una cache verificata",
+    ),
+    (
+        "parse",
+        "Analizza i testi sorgente in commedia.json e commedia.bin",
+    ),
+];
+
+/// `command` with its help in `locale`.
+pub fn localize(command: clap::Command, locale: Locale) -> clap::Command {
+    if locale == Locale::English {
+        return command;
+    }
+    let about = |name: &str| {
+        ITALIAN_HELP
+            .iter()
+            .find(|(command, _)| *command == name)
+            .map(|&(_, about)| about)
+    };
+
+    let mut command = match about(command.get_name()) {
+        Some(text) => command.about(text),
+        None => command,
+    };
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        if let Some(text) = about(&name) {
+            command = command.mut_subcommand(name, |sub| sub.about(text));
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("it_IT.UTF-8"), Some(Locale::Italian));
+        assert_eq!(Locale::from_tag("it"), Some(Locale::Italian));
+        assert_eq!(Locale::from_tag("en-GB"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("C.UTF-8"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("de_DE"), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format(Locale::English, Text::FoundMatches, &[&3, &"amor"]),
+            "Found 3 matches for 'amor':"
+        );
+        assert_eq!(
+            format(Locale::Italian, Text::CantoNotFound, &[&34, &"Paradiso"]),
+            "Canto 34 non trovato in Paradiso"
+        );
+        assert_eq!(format(Locale::Italian, Text::TuiCantos, &[]), "Canti");
+    }
+
+    #[test]
+    fn test_translations_take_the_same_arguments() {
+        for text in Text::ALL {
+            let (english, italian) = text.templates();
+            assert_eq!(
+                english.matches("{}").count(),
+                italian.matches("{}").count(),
+                "{:?}",
+                text
+            );
+            assert_eq!(
+                english.lines().count(),
+                italian.lines().count(),
+                "{:?}",
+                text
+            );
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! A tantivy full-text index over the verses, for ranked search.
+//!
+//! The index lives in memory and is built from a loaded corpus in well under
+//! a second, so there is nothing to keep in sync on disk. Queries use
+//! tantivy's syntax: words are ranked by BM25, `"quoted words"` match as a
+//! phrase, `"nel mez"*` matches a phrase ending in a prefix, and a bare
+//! `amo*` matches any word starting with `amo`. Like [`DivinaCommedia::search`],
+//! matching ignores case and accents. A bare [stopword](crate::stopwords)
+//! counts for a tenth of another word, so `la selva` ranks verses by `selva`.
+
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, BoostQuery, Occur, Query, QueryParser, RegexQuery, TermQuery};
+use tantivy::schema::{
+    Field, IndexRecordOption, OwnedValue, Schema, TextFieldIndexing, TextOptions, INDEXED, STORED,
+};
+use tantivy::tokenizer::{AsciiFoldingFilter, LowerCaser, SimpleTokenizer, TextAnalyzer};
+use tantivy::{Index, IndexReader, TantivyDocument, Term};
+
+use crate::{normalize, stopwords, CanticaId, DivinaCommedia, DucaError};
+
+const TOKENIZER: &str = "folded";
+
+/// A verse returned by [`FullTextIndex::search`], best match first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedMatch<'a> {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub line: usize,
+    pub text: &'a str,
+    pub score: f32,
+}
+
+/// An in-memory tantivy index of every verse in a [`DivinaCommedia`].
+pub struct FullTextIndex<'a> {
+    commedia: &'a DivinaCommedia,
+    index: Index,
+    reader: IndexReader,
+    cantica: Field,
+    canto: Field,
+    line: Field,
+    text: Field,
+}
+
+impl<'a> FullTextIndex<'a> {
+    /// Index every verse of `commedia`.
+    pub fn build(commedia: &'a DivinaCommedia) -> Result<Self, DucaError> {
+        let mut schema = Schema::builder();
+        let cantica = schema.add_u64_field("cantica", INDEXED | STORED);
+        let canto = schema.add_u64_field("canto", STORED);
+        let line = schema.add_u64_field("line", STORED);
+        let text = schema.add_text_field(
+            "text",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(TOKENIZER)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+
+        let index = Index::create_in_ram(schema.build());
+        index.tokenizers().register(
+            TOKENIZER,
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(LowerCaser)
+                .filter(AsciiFoldingFilter)
+                .build(),
+        );
+
+        let mut writer = index.writer_with_num_threads(1, 15_000_000)?;
+        for v in commedia.verses() {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(cantica, v.cantica.index() as u64);
+            doc.add_u64(canto, v.canto.number as u64);
+            doc.add_u64(line, v.verse.line_number as u64);
+            doc.add_text(text, &v.verse.text);
+            writer.add_document(doc)?;
+        }
+        writer.commit()?;
+
+        Ok(Self {
+            commedia,
+            reader: index.reader()?,
+            index,
+            cantica,
+            canto,
+            line,
+            text,
+        })
+    }
+
+    /// The corpus this index was built from.
+    pub fn commedia(&self) -> &'a DivinaCommedia {
+        self.commedia
+    }
+
+    /// Run `query`, returning up to `limit` verses ranked by relevance.
+    /// Malformed syntax is matched as well as it can be rather than rejected.
+    pub fn search(
+        &self,
+        query: &str,
+        cantica_filter: Option<CanticaId>,
+        limit: usize,
+    ) -> Result<Vec<RankedMatch<'a>>, DucaError> {
+        let mut query = self.parse(query)?;
+        if let Some(id) = cantica_filter {
+            let term = Term::from_field_u64(self.cantica, id.index() as u64);
+            let filter = TermQuery::new(term, IndexRecordOption::Basic);
+            query = Box::new(BooleanQuery::new(vec![
+                (Occur::Must, query),
+                (Occur::Must, Box::new(filter)),
+            ]));
+        }
+
+        let searcher = self.reader.searcher();
+        let hits = searcher.search(&query, &TopDocs::with_limit(limit.max(1)))?;
+
+        let mut results = Vec::with_capacity(hits.len());
+        for (score, address) in hits {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let number = |field| match doc.get_first(field) {
+                Some(OwnedValue::U64(n)) => *n as usize,
+                _ => 0,
+            };
+            let cantica = CanticaId::ALL[number(self.cantica)];
+            let (canto, line) = (number(self.canto) as u8, number(self.line));
+            let Some(verse) = self.commedia.verse(cantica, canto, line) else {
+                continue;
+            };
+            results.push(RankedMatch {
+                cantica,
+                canto,
+                line,
+                text: &verse.text,
+                score,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Parse a query, handling bare `prefix*` words that tantivy's own
+    /// parser only accepts at the end of a phrase, and down-weighting bare
+    /// stopwords.
+    fn parse(&self, query: &str) -> Result<Box<dyn Query>, DucaError> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        let mut rest = Vec::new();
+        let mut in_phrase = false;
+        for word in query.split_whitespace() {
+            let bare = !in_phrase && !word.is_empty() && word.chars().all(char::is_alphanumeric);
+            let prefix = word
+                .strip_suffix('*')
+                .filter(|p| !in_phrase && !p.is_empty() && p.chars().all(char::is_alphanumeric));
+            if let Some(prefix) = prefix {
+                let pattern = format!("{}.*", regex::escape(&normalize::fold(prefix)));
+                let query = RegexQuery::from_pattern(&pattern, self.text)?;
+                clauses.push((Occur::Should, Box::new(query)));
+            } else if bare && stopwords::is_stopword(word) {
+                let term = Term::from_field_text(self.text, &normalize::fold(word));
+                let query = TermQuery::new(term, IndexRecordOption::WithFreqs);
+                let query = BoostQuery::new(Box::new(query), stopwords::STOPWORD_WEIGHT);
+                clauses.push((Occur::Should, Box::new(query)));
+            } else {
+                rest.push(word);
+            }
+            in_phrase ^= word.matches('"').count() % 2 == 1;
+        }
+
+        if !rest.is_empty() {
+            let parser = QueryParser::for_index(&self.index, vec![self.text]);
+            let (query, _errors) = parser.parse_query_lenient(&rest.join(" "));
+            clauses.push((Occur::Should, query));
+        }
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+}
+
+impl From<tantivy::TantivyError> for DucaError {
+    fn from(e: tantivy::TantivyError) -> Self {
+        DucaError::DataLoad(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranked_search() {
+        let commedia = crate::commedia().unwrap();
+        let index = FullTextIndex::build(commedia).unwrap();
+
+        let results = index.search("selva oscura", None, 5).unwrap();
+        assert_eq!(
+            (results[0].cantica, results[0].canto, results[0].line),
+            (CanticaId::Inferno, 1, 2)
+        );
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_phrase_and_prefix_queries() {
+        let commedia = crate::commedia().unwrap();
+        let index = FullTextIndex::build(commedia).unwrap();
+
+        let results = index.search("\"diritta via\"", None, 10).unwrap();
+        assert!(results.iter().all(|m| m.text.contains("diritta via")));
+        assert!(!results.is_empty());
+
+        let results = index
+            .search("smarri*", Some(CanticaId::Inferno), 50)
+            .unwrap();
+        assert!(results.iter().all(|m| m.cantica == CanticaId::Inferno));
+        assert!(results.iter().any(|m| m.text.contains("smarrita")));
+
+        // Accents are folded on both sides
+        let results = index.search("pieta", Some(CanticaId::Inferno), 50).unwrap();
+        assert!(results.iter().any(|m| m.text.contains("pietà")));
+    }
+
+    #[test]
+    fn test_stopwords_down_weighted() {
+        let commedia = crate::commedia().unwrap();
+        let index = FullTextIndex::build(commedia).unwrap();
+
+        // A verse with many of the common words doesn't outrank those with
+        // the one that matters
+        let results = index
+            .search("che non e la di per mi ti se selva", None, 3)
+            .unwrap();
+        assert!(
+            results.iter().all(|m| m.text.contains("selva")),
+            "{:?}",
+            results
+        );
+        assert!(!index.search("che e la", None, 5).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Installing works and editions into the data directory.
+//!
+//! [`add`] copies a directory of source files into [`paths::works_dir`] if it
+//! holds a `work.toml`, or else into [`paths::editions_dir`], after checking
+//! that it parses. Beside the copied files it writes `installed.toml`,
+//! recording where they came from and their SHA-256 checksum, so later
+//! changes to the installed files can be spotted.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{editions, paths, works, DucaError};
+
+/// The record [`add`] leaves beside the installed files.
+const RECORD: &str = "installed.toml";
+
+/// What an installed directory holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Work,
+    Edition,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Work => "work",
+            Kind::Edition => "edition",
+        })
+    }
+}
+
+/// A work or edition in the data directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installed {
+    pub name: String,
+    pub kind: Kind,
+    pub dir: PathBuf,
+    /// Where [`add`] copied it from; `None` if it was put there by hand.
+    pub origin: Option<String>,
+    /// The checksum [`add`] recorded.
+    pub recorded_checksum: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    origin: String,
+    sha256: String,
+}
+
+impl Installed {
+    fn read(name: &str, kind: Kind, dir: PathBuf) -> Self {
+        let record: Option<Record> = fs::read_to_string(dir.join(RECORD))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok());
+        Self {
+            name: name.to_string(),
+            kind,
+            dir,
+            origin: record.as_ref().map(|r| r.origin.clone()),
+            recorded_checksum: record.map(|r| r.sha256),
+        }
+    }
+
+    /// The checksum of the files as they are now.
+    pub fn checksum(&self) -> Result<String, DucaError> {
+        checksum(&self.dir)
+    }
+}
+
+fn kind_dir(kind: Kind) -> Option<PathBuf> {
+    match kind {
+        Kind::Work => paths::works_dir(),
+        Kind::Edition => paths::editions_dir(),
+    }
+}
+
+/// The SHA-256 of the files in `dir`, taken in name order with each name
+/// hashed before its contents. The install record is left out.
+pub fn checksum(dir: &Path) -> Result<String, DucaError> {
+    let mut hasher = Sha256::new();
+    for path in files(dir)? {
+        hasher.update(path.file_name().unwrap_or_default().as_encoded_bytes());
+        hasher.update([0]);
+        hasher.update(fs::read(&path)?);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The regular files in `dir` other than the install record, by name.
+fn files(dir: &Path) -> Result<Vec<PathBuf>, DucaError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| DucaError::UserData {
+            path: dir.display().to_string(),
+            reason: e.to_string(),
+        })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && !path.ends_with(RECORD))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Every installed work and edition, works first, each in name order.
+pub fn installed() -> Vec<Installed> {
+    let works = works::available()
+        .into_iter()
+        .skip(1)
+        .map(|n| (n, Kind::Work));
+    let editions = editions::available()
+        .into_iter()
+        .skip(1)
+        .map(|n| (n, Kind::Edition));
+    works
+        .chain(editions)
+        .filter_map(|(name, kind)| {
+            let dir = kind_dir(kind)?.join(&name);
+            Some(Installed::read(&name, kind, dir))
+        })
+        .collect()
+}
+
+/// The installed work or edition called `name`, looking at works first.
+pub fn find(name: &str) -> Option<Installed> {
+    installed().into_iter().find(|i| i.name == name)
+}
+
+/// Copy the source files in `source` into the data directory as `name`,
+/// which defaults to the directory's own name. An existing install of that
+/// name is replaced only if `force` is set.
+pub fn add(source: &Path, name: Option<&str>, force: bool) -> Result<Installed, DucaError> {
+    let invalid = |reason: String| DucaError::UserData {
+        path: source.display().to_string(),
+        reason,
+    };
+    let source = source.canonicalize().map_err(|e| invalid(e.to_string()))?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| invalid("can't name an install after this path".to_string()))?,
+    };
+    let refuse = |reason: &str| DucaError::Install {
+        name: name.clone(),
+        reason: reason.to_string(),
+    };
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(refuse("not a usable directory name"));
+    }
+    if name == works::COMMEDIA || name == editions::DEFAULT {
+        return Err(refuse("that name is built in"));
+    }
+
+    // Parse before copying anything, so a broken source leaves no trace
+    let kind = if source.join("work.toml").is_file() {
+        works::Work::load_dir(&source, &name)?;
+        Kind::Work
+    } else {
+        editions::load_dir(&source, &name)?;
+        Kind::Edition
+    };
+
+    let target = kind_dir(kind)
+        .ok_or_else(|| refuse("no data directory; set $HOME or $XDG_DATA_HOME"))?
+        .join(&name);
+    let unwritable = |e: std::io::Error| DucaError::Install {
+        name: name.clone(),
+        reason: format!("{}: {}", target.display(), e),
+    };
+    if target.exists() {
+        if !force {
+            return Err(refuse(&format!(
+                "a {} of that name is already installed; use --force to replace it",
+                kind
+            )));
+        }
+        fs::remove_dir_all(&target).map_err(unwritable)?;
+    }
+    fs::create_dir_all(&target).map_err(unwritable)?;
+    for path in files(&source)? {
+        fs::copy(&path, target.join(path.file_name().unwrap_or_default())).map_err(unwritable)?;
+    }
+
+    let record = Record {
+        origin: source.display().to_string(),
+        sha256: checksum(&target)?,
+    };
+    let record = toml::to_string(&record).expect("install record serializes to TOML");
+    fs::write(target.join(RECORD), record).map_err(unwritable)?;
+    Ok(Installed::read(&name, kind, target))
+}
+
+/// Delete the installed work or edition called `name`.
+pub fn remove(name: &str) -> Result<Installed, DucaError> {
+    let installed = find(name).ok_or_else(|| DucaError::UnknownWork {
+        name: name.to_string(),
+        available: installed().into_iter().map(|i| i.name).collect(),
+    })?;
+    fs::remove_dir_all(&installed.dir)?;
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        let dir = std::env::temp_dir().join(format!("duca-checksum-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("inferno.txt"), "Canto I\nNel mezzo\n").unwrap();
+        let before = checksum(&dir).unwrap();
+        assert_eq!(before.len(), 64);
+
+        // The install record doesn't count; the files do
+        fs::write(dir.join(RECORD), "origin = \"x\"\nsha256 = \"y\"\n").unwrap();
+        assert_eq!(checksum(&dir).unwrap(), before);
+        fs::write(dir.join("inferno.txt"), "Canto I\nNel mezo\n").unwrap();
+        assert_ne!(checksum(&dir).unwrap(), before);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
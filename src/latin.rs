@@ -0,0 +1,122 @@
+//! Latin in the poem: the hymns, psalms and scripture the souls sing, and a
+//! few scholastic tags, found by matching a curated list of phrases.
+
+use std::ops::Range;
+
+use crate::normalize;
+
+/// Latin phrases of the Commedia, folded by [`normalize::fold`]. Matches
+/// must start and end at word boundaries, so short entries such as `quia`
+/// don't match inside Italian words.
+const PHRASES: &[&str] = &[
+    "vexilla regis prodeunt inferni",
+    "miserere",
+    "miserere mei",
+    "in exitu israel de aegypto",
+    "quia",
+    "salve, regina",
+    "te lucis ante",
+    "te deum laudamus",
+    "osanna",
+    "beati pauperes spiritu",
+    "beati misericordes",
+    "agnus dei",
+    "qui lugent",
+    "adhaesit pavimento anima mea",
+    "scias quod ego fui successor petri",
+    "gloria in excelsis",
+    "sitiunt",
+    "labia mea, domine",
+    "summae deus clementiae",
+    "beati mundo corde",
+    "venite, benedicti patris mei",
+    "delectasti",
+    "beati quorum tecta sunt peccata",
+    "benedicta tue",
+    "veni, sponsa, de libano",
+    "benedictus qui venis",
+    "manibus, oh, date lilia plenis",
+    "in te, domine, speravi",
+    "pedes meos",
+    "asperges me",
+    "deus, venerunt gentes",
+    "modicum, et non videbitis me",
+    "et iterum",
+    "modicum, et vos videbitis me",
+    "ave",
+    "ave, maria, gratia plena",
+    "sanctus deus sabaoth",
+    "superillustrans claritate tua",
+    "felices ignes horum malacoth",
+    "et coram patre",
+    "necesse",
+    "est dare primum motum esse",
+    "o sanguis meus, o superinfusa",
+    "gratia dei, sicut tibi cui",
+    "bis unquam celi ianua reclusa",
+    "diligite iustitiam",
+    "qui iudicatis terram",
+    "regina celi",
+    "sperent in te",
+];
+
+/// Byte ranges of the Latin phrases in `text`, sorted, with overlapping
+/// phrases merged.
+pub fn latin_ranges(text: &str) -> Vec<Range<usize>> {
+    let folded = normalize::fold(text);
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+
+    let mut ranges: Vec<Range<usize>> = PHRASES
+        .iter()
+        .flat_map(|phrase| {
+            folded
+                .match_indices(phrase)
+                .map(|(start, _)| start..start + phrase.len())
+        })
+        .filter(|range| {
+            !is_word(folded[..range.start].chars().next_back())
+                && !is_word(folded[range.end..].chars().next())
+        })
+        .map(|range| normalize::original_range(text, &folded, range))
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Whether `text` quotes any Latin.
+pub fn is_latin(text: &str) -> bool {
+    !latin_ranges(text).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin_ranges() {
+        let text = "cantando ‘Ave, Maria, gratïa plena’,";
+        let phrases: Vec<&str> = latin_ranges(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(phrases, ["Ave, Maria, gratïa plena"]);
+
+        let text = "«Osanna, sanctus Deus sabaòth,";
+        let phrases: Vec<&str> = latin_ranges(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(phrases, ["Osanna", "sanctus Deus sabaòth"]);
+    }
+
+    #[test]
+    fn test_is_latin() {
+        assert!(is_latin("«Vexilla regis prodeunt inferni"));
+        assert!(is_latin("State contenti, umana gente, al quia;"));
+        assert!(!is_latin("Nel mezzo del cammin di nostra vita"));
+        // "ave" inside an Italian word is not Latin
+        assert!(!is_latin("e poi ch’avea parlato"));
+    }
+}
@@ -0,0 +1,114 @@
+//! A light Italian stemmer for `search --lemma`, so `occhi` finds `occhio`
+//! and `amore` finds `amor`, and the index of the forms each stem takes in
+//! the poem, built by `duca parse` with the word index.
+//!
+//! Stemming only strips the endings of number and gender, which is most of
+//! what sets the forms of Dante's nouns and adjectives apart: `stella`,
+//! `stelle`; `amico`, `amici`, `amiche`; `gloria`, `glorie`. Verbs keep
+//! their conjugations, and a handful of irregular plurals are listed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::normalize;
+
+/// Endings stripped to find a stem, longest first.
+const ENDINGS: [&str; 8] = ["io", "ia", "ie", "ii", "i", "e", "a", "o"];
+
+/// Letters left after stripping an ending, at the least: shorter words,
+/// such as `che`, `mio` or `via`, are their own stems.
+const SHORTEST_STEM: usize = 3;
+
+/// Plurals the endings don't bring back to their singular.
+const IRREGULAR: [(&str, &str); 2] = [("uomini", "uomo"), ("templi", "tempio")];
+
+/// The stem of `word`, folded: `occhi` and `occhio` both give `occ`.
+pub fn lemma(word: &str) -> String {
+    let folded = normalize::fold(word);
+    let word = IRREGULAR
+        .iter()
+        .find(|(plural, _)| *plural == folded)
+        .map_or(folded.as_str(), |(_, singular)| singular);
+    let stem = ENDINGS
+        .iter()
+        .find_map(|ending| {
+            word.strip_suffix(ending)
+                .filter(|stem| stem.chars().count() >= SHORTEST_STEM)
+        })
+        .unwrap_or(word);
+    // The h keeping c and g hard before e and i, as in amiche and occhi
+    match stem.strip_suffix('h') {
+        Some(hard) if hard.ends_with('c') || hard.ends_with('g') => hard.to_string(),
+        _ => stem.to_string(),
+    }
+}
+
+/// Every stem of the poem with the folded words that share it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LemmaIndex {
+    forms: BTreeMap<String, Vec<String>>,
+}
+
+impl LemmaIndex {
+    /// Group folded `words` by their stems.
+    pub fn build<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut forms: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for word in words {
+            let words = forms.entry(lemma(word)).or_default();
+            if !words.iter().any(|w| w == word) {
+                words.push(word.to_string());
+            }
+        }
+        Self { forms }
+    }
+
+    /// The words of the poem sharing `word`'s stem, `word` among them if
+    /// the poem uses it.
+    pub fn forms(&self, word: &str) -> &[String] {
+        self.stem_forms(&lemma(word))
+    }
+
+    /// The words of the poem with the stem `stem`.
+    pub fn stem_forms(&self, stem: &str) -> &[String] {
+        self.forms.get(stem).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Number of distinct stems.
+    pub fn len(&self) -> usize {
+        self.forms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forms.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lemma() {
+        assert_eq!(lemma("occhi"), lemma("occhio"));
+        assert_eq!(lemma("Occhi"), "occ");
+        assert_eq!(lemma("amore"), lemma("amor"));
+        assert_eq!(lemma("amori"), "amor");
+        assert_eq!(lemma("amiche"), lemma("amico"));
+        assert_eq!(lemma("stelle"), lemma("stella"));
+        assert_eq!(lemma("uomini"), lemma("uomo"));
+        assert_eq!(lemma("pietà"), lemma("pieta"));
+        // Too short to have an ending stripped
+        assert_eq!(lemma("che"), "che");
+        assert_eq!(lemma("mio"), "mio");
+        assert_ne!(lemma("mia"), lemma("mio"));
+    }
+
+    #[test]
+    fn test_lemma_index() {
+        let index = LemmaIndex::build(["occhi", "occhio", "occhi", "amor", "amore", "vita"]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.forms("occhio"), ["occhi", "occhio"]);
+        assert_eq!(index.forms("Amori"), ["amor", "amore"]);
+        assert!(index.forms("selva").is_empty());
+    }
+}
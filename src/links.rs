@@ -0,0 +1,95 @@
+//! Links from a passage to commentary sites on the web.
+
+use clap::ValueEnum;
+use duca::CanticaId;
+use std::env;
+
+/// Commentary sites `open-web` knows how to link to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Site {
+    /// Columbia's Digital Dante, with Teodolinda Barolini's Commento Baroliniano
+    #[default]
+    DigitalDante,
+    /// Dartmouth Dante Lab, with the Dartmouth Dante Project commentaries
+    DanteLab,
+    /// Princeton Dante Project
+    Princeton,
+}
+
+impl Site {
+    /// URL template for this site; see [`url`] for the placeholders.
+    pub fn template(self) -> &'static str {
+        match self {
+            Site::DigitalDante => {
+                "https://digitaldante.columbia.edu/dante/divine-comedy/{cantica}/{cantica}-{canto}/"
+            }
+            Site::DanteLab => {
+                "https://dantelab.dartmouth.edu/reader?reader%5Bcantica%5D={cantica_number}&reader%5Bcanto%5D={canto}"
+            }
+            Site::Princeton => {
+                "https://dante.princeton.edu/cgi-bin/dante/campuscgi/mpb/GetCantoSection.pl?INP_POEM={abbr}&INP_SECT={canto}&INP_START={line}&INP_LEN=15&LANG=2"
+            }
+        }
+    }
+}
+
+/// The template to link with: `site` when given, else `$DUCA_WEB_URL`, else
+/// the default site.
+pub fn template(site: Option<Site>) -> String {
+    match site {
+        Some(site) => site.template().to_string(),
+        None => env::var("DUCA_WEB_URL")
+            .ok()
+            .filter(|template| !template.trim().is_empty())
+            .unwrap_or_else(|| Site::default().template().to_string()),
+    }
+}
+
+/// Fill in `template` for a passage. Placeholders are `{cantica}`
+/// (`inferno`), `{Cantica}` (`Inferno`), `{abbr}` (`Inf`),
+/// `{cantica_number}` (1 to 3), `{canto}` and `{line}`.
+pub fn url(template: &str, cantica: CanticaId, canto: u8, line: usize) -> String {
+    let abbr = match cantica {
+        CanticaId::Inferno => "Inf",
+        CanticaId::Purgatorio => "Purg",
+        CanticaId::Paradiso => "Par",
+    };
+    template
+        .replace("{cantica}", cantica.key())
+        .replace("{Cantica}", cantica.name())
+        .replace("{abbr}", abbr)
+        .replace("{cantica_number}", &(cantica.index() + 1).to_string())
+        .replace("{canto}", &canto.to_string())
+        .replace("{line}", &line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        assert_eq!(
+            url(Site::DigitalDante.template(), CanticaId::Inferno, 5, 100),
+            "https://digitaldante.columbia.edu/dante/divine-comedy/inferno/inferno-5/"
+        );
+        assert_eq!(
+            url(Site::Princeton.template(), CanticaId::Paradiso, 33, 145),
+            "https://dante.princeton.edu/cgi-bin/dante/campuscgi/mpb/GetCantoSection.pl?INP_POEM=Par&INP_SECT=33&INP_START=145&INP_LEN=15&LANG=2"
+        );
+        assert_eq!(
+            url(
+                "https://example.org/{Cantica}/{cantica_number}/{canto}#{line}",
+                CanticaId::Purgatorio,
+                1,
+                7
+            ),
+            "https://example.org/Purgatorio/2/1#7"
+        );
+    }
+
+    #[test]
+    fn test_site_overrides_template() {
+        assert_eq!(template(Some(Site::DanteLab)), Site::DanteLab.template());
+    }
+}
@@ -1,37 +1,71 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, CompleteEnv, Shell};
+use duca::allusions;
+use duca::apparatus::Apparatus;
+use duca::backup::Backup;
+use duca::commentary::parse_longfellow_notes;
+use duca::commentary::{Commentary, CommentaryEntry};
+use duca::editions;
+use duca::export;
+use duca::fetch;
+use duca::glossary::{GlossEntry, Glossary};
+use duca::graph::CharacterGraph;
+use duca::install;
+use duca::latin;
+use duca::notes::{self, NoteStore};
+use duca::output::{self, CantoOutput, Document, MatchOutput, SearchOutput};
+use duca::paths;
+use duca::plan::{self, Date, Plan};
+use duca::rank::{self, Rank};
+#[cfg(feature = "semantic")]
+use duca::semantic::{self, Embedder, VerseEmbeddings};
+use duca::stats;
+use duca::verify;
+use duca::works::{self, Work};
+use duca::xrefs;
+use duca::{
+    commedia, load_canto, parse_canto_number, CanticaId, Canto, Citation, DucaError, Normalizer,
+    Passage, Pipeline, Verse,
+};
+use duca::{expand_globs, parse_paths_with, to_binary};
+use std::fmt::{self, Write};
 use std::fs;
-
+use std::io;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+
+mod accessible;
+mod browser;
+mod complete;
+mod feed;
+mod i18n;
+mod links;
+mod mcp;
+mod notify;
+mod pager;
+#[cfg(feature = "tui")]
+mod player;
+mod rpc;
+mod server;
+#[cfg(feature = "tui")]
+mod session;
+mod speech;
+mod style;
+mod text;
+#[cfg(feature = "tui")]
 mod tui;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Verse {
-    pub line_number: usize,
-    pub text: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Canto {
-    pub number: u8,
-    pub roman_numeral: String,
-    pub verses: Vec<Verse>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Cantica {
-    pub name: String,
-    pub cantos: HashMap<u8, Canto>,
-}
+use i18n::{tr, Text};
+use style::{ColorChoice, Mark, Styler};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DivinaCommedia {
-    pub inferno: Cantica,
-    pub purgatorio: Cantica,
-    pub paradiso: Cantica,
-}
+/// How many verses `search --semantic` returns per query.
+#[cfg(feature = "semantic")]
+const SEMANTIC_LIMIT: usize = 10;
 
 #[derive(Parser)]
 #[command(name = "duca")]
@@ -39,784 +73,2903 @@ pub struct DivinaCommedia {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[arg(long, global = true, help = "Never pipe output through a pager")]
+    no_pager: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "When to use colors in output"
+    )]
+    color: ColorChoice,
+    #[arg(
+        long,
+        global = true,
+        help = "Edition of the text to read (see `duca editions`)"
+    )]
+    edition: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Work to read instead of the Commedia; `canto` and `search` take its part names"
+    )]
+    work: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Labeled plain text for screen readers, without colors, wrapping or pager; `tui` becomes `read`"
+    )]
+    accessible: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Search for text across all canticas")]
     Search {
-        #[arg(help = "Pattern to search for")]
-        pattern: String,
-        #[arg(short, long, help = "Limit search to specific cantica")]
+        #[arg(
+            required_unless_present = "patterns_file",
+            help = "Pattern to search for"
+        )]
+        pattern: Option<String>,
+        #[arg(
+            short = 'f',
+            long,
+            value_name = "FILE",
+            conflicts_with = "pattern",
+            help = "Read patterns from FILE, one per line ('-' for stdin)"
+        )]
+        patterns_file: Option<PathBuf>,
+        #[arg(
+            short,
+            long,
+            value_parser = CanticaNameParser,
+            help = "Limit search to specific cantica"
+        )]
         cantica: Option<String>,
+        #[arg(
+            long,
+            help = "Print one compact line per match, for launchers like rofi or dmenu"
+        )]
+        picker: bool,
+        #[arg(
+            long,
+            help = "Like --picker, but separate matches with NUL instead of newline"
+        )]
+        print0: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["picker", "print0"],
+            help = "Print only how many verses match in each cantica"
+        )]
+        summary_only: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["picker", "print0", "summary_only", "format"],
+            help = "Chart how many verses match in each canto, a cell for each"
+        )]
+        heatmap: bool,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Format::Text,
+            conflicts_with_all = ["picker", "print0", "summary_only"],
+            help = "Print matches as text, as JSON, one object per pattern per line, or as quickfix lines for editors"
+        )]
+        format: Format,
+        #[arg(
+            long,
+            value_name = "STEPS",
+            help = "Normalizers to put the pattern and verses through, separated by commas: lowercase, fold, orthography, lemma, or none [default: lowercase,fold]"
+        )]
+        normalize: Option<Pipeline>,
+        #[arg(
+            long,
+            help = "Match each word in any of its forms, so occhio also finds occhi (adds lemma)"
+        )]
+        lemma: bool,
+        #[arg(
+            long,
+            help = "Match whole words in modern spelling against the old, so che intrate finds ch'intrate (adds orthography)"
+        )]
+        modern: bool,
+        #[arg(
+            long,
+            conflicts_with = "lemma",
+            help = "Read the pattern as a regular expression, matched against the verses through the normalizers"
+        )]
+        regex: bool,
+        #[arg(
+            long,
+            value_name = "ORDER",
+            default_value_t = Rank::Order,
+            help = "Order matches by place in the poem (order), or find the verses with any of the pattern's words, most relevant first (bm25)"
+        )]
+        rank: Rank,
+        #[arg(
+            long,
+            conflicts_with_all = ["picker", "print0", "summary_only", "heatmap", "format"],
+            help = "Show under each match of --rank bm25 what its score is made of"
+        )]
+        explain: bool,
+        #[cfg(feature = "semantic")]
+        #[arg(
+            long,
+            conflicts_with_all = ["normalize", "lemma", "modern", "regex", "rank"],
+            help = "Find the verses closest in meaning, using embeddings from `duca embed`"
+        )]
+        semantic: bool,
     },
     #[command(about = "Show specific canto")]
     Canto {
-        #[arg(help = "Cantica (inferno, purgatorio, paradiso)")]
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Cantica to read from"
+        )]
+        cantica: String,
+        #[arg(
+            value_parser = CantoNumberParser,
+            add = ArgValueCandidates::new(complete::canto_numbers),
+            help = "Canto number, in arabic or roman numerals (26 or XXVI)"
+        )]
+        number: u8,
+        #[arg(
+            long,
+            help = "Print only the verse text, without header or line numbers"
+        )]
+        plain: bool,
+        #[arg(
+            long,
+            conflicts_with = "plain",
+            help = "Show commentary notes from the commentary directory under the lines they discuss"
+        )]
+        commentary: bool,
+        #[arg(
+            long,
+            conflicts_with = "plain",
+            help = "Show variant readings from the apparatus directory under their lines"
+        )]
+        variants: bool,
+        #[arg(
+            long,
+            conflicts_with = "plain",
+            help = "Show the allusions to the Aeneid, the Bible and Ovid under the lines they start on"
+        )]
+        allusions: bool,
+        #[arg(
+            long,
+            value_name = "TERM",
+            conflicts_with = "plain",
+            help = "Highlight every match of TERM, a word or pattern as in search"
+        )]
+        highlight: Option<String>,
+        #[arg(
+            long,
+            value_name = "LINE",
+            help = "Show only the lines around LINE, marking it, for quoting"
+        )]
+        around: Option<usize>,
+        #[arg(
+            long,
+            default_value_t = 5,
+            requires = "around",
+            help = "Lines of context before and after --around"
+        )]
+        context: usize,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Format::Text,
+            conflicts_with_all = ["plain", "commentary", "variants", "allusions", "highlight"],
+            help = "Print the canto as text, as JSON for scripts, or as quickfix lines for editors"
+        )]
+        format: Format,
+    },
+    #[command(about = "Print the JSON Schema of a command's --format json output")]
+    Schema {
+        #[arg(value_enum, help = "Command whose output to describe")]
+        command: SchemaCommand,
+    },
+    #[command(about = "List the editions of the text, built in and installed")]
+    Editions,
+    #[command(about = "Check the text for missing cantos, wrong line counts and empty verses")]
+    Verify,
+    #[command(about = "Explain an archaic word from the built-in glossary")]
+    Gloss {
+        #[arg(help = "Word to look up, in any spelling; omit to list the whole glossary")]
+        word: Option<String>,
+    },
+    #[command(about = "List the verses that quote Latin")]
+    Latin {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Only list verses from this cantica"
+        )]
+        cantica: Option<String>,
+    },
+    #[command(about = "List the passages of direct speech, with their speakers where known")]
+    Speeches {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Only list speeches from this cantica"
+        )]
+        cantica: Option<String>,
+    },
+    #[command(
+        about = "List the passages elsewhere in the poem that bear on a canto: prophecies, images, echoes"
+    )]
+    Xref {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Cantica of the canto"
+        )]
         cantica: String,
-        #[arg(help = "Canto number")]
+        #[arg(
+            value_parser = CantoNumberParser,
+            add = ArgValueCandidates::new(complete::canto_numbers),
+            help = "Canto number, in arabic or roman numerals (26 or XXVI)"
+        )]
         number: u8,
     },
+    #[command(about = "List the allusions to the Aeneid, the Bible and Ovid")]
+    Allusions {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Only list allusions from this cantica"
+        )]
+        cantica: Option<String>,
+    },
+    #[command(about = "Write a graph of the poem for Graphviz or Gephi")]
+    Graph {
+        #[command(subcommand)]
+        graph: GraphKind,
+    },
+    #[command(about = "Compare how often each cantica uses some words")]
+    CompareFreq {
+        #[arg(
+            required = true,
+            help = "Words to count, ignoring case and accents; end one with * to count every word it begins"
+        )]
+        terms: Vec<String>,
+    },
+    #[command(about = "Show a cited verse in context (e.g. a line picked from --picker output)")]
+    OpenCitation {
+        #[arg(help = "Citation such as \"Inferno 5.100\"; trailing text after ':' is ignored")]
+        citation: String,
+        #[arg(long, default_value_t = 5, help = "Lines of context before and after")]
+        context: usize,
+    },
+    #[command(about = "Print the tercet a cited line belongs to")]
+    Tercet {
+        #[arg(help = "Citation of a single line, such as \"Inferno 5.100\"")]
+        citation: String,
+        #[arg(
+            long,
+            help = "Print only the verse text, without header or line numbers"
+        )]
+        plain: bool,
+    },
+    #[command(about = "Speak a canto, or some of its lines, with a text-to-speech command")]
+    ReadAloud {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Cantica to read from"
+        )]
+        cantica: String,
+        #[arg(
+            value_parser = CantoNumberParser,
+            add = ArgValueCandidates::new(complete::canto_numbers),
+            help = "Canto number, in arabic or roman numerals (26 or XXVI)"
+        )]
+        number: u8,
+        #[arg(long, value_name = "LINE", help = "First line to speak")]
+        from: Option<usize>,
+        #[arg(long, value_name = "LINE", help = "Last line to speak")]
+        to: Option<usize>,
+        #[arg(
+            long,
+            value_name = "MS",
+            default_value_t = speech::DEFAULT_PAUSE_MS,
+            help = "Milliseconds of silence after each verse"
+        )]
+        pause: u64,
+    },
+    #[command(about = "Open a passage in a commentary site in the web browser")]
+    OpenWeb {
+        #[arg(
+            value_parser = CanticaNameParser,
+            help = "Cantica name (inferno, purgatorio, paradiso)"
+        )]
+        cantica: String,
+        #[arg(
+            value_parser = CantoNumberParser,
+            add = ArgValueCandidates::new(complete::canto_numbers),
+            help = "Canto number, in arabic or roman numerals (26 or XXVI)"
+        )]
+        number: u8,
+        #[arg(default_value_t = 1, help = "Line number")]
+        line: usize,
+        #[arg(
+            long,
+            value_enum,
+            help = "Site to open instead of the $DUCA_WEB_URL template or Digital Dante"
+        )]
+        site: Option<links::Site>,
+        #[arg(long, help = "Print the URL instead of opening it")]
+        print: bool,
+    },
+    #[command(
+        about = "Show today's canto of the reading plan, or send it as a desktop notification"
+    )]
+    Today {
+        #[arg(
+            long,
+            value_name = "YYYY-MM-DD",
+            help = "Date to show the canto of instead of today's in UTC"
+        )]
+        date: Option<Date>,
+        #[arg(
+            long,
+            value_name = "YYYY-MM-DD",
+            help = "First day of the plan instead of $DUCA_PLAN_START or 1 January"
+        )]
+        start: Option<Date>,
+        #[arg(
+            long,
+            help = "Send a desktop notification instead of printing the canto"
+        )]
+        notify: bool,
+    },
+    #[command(about = "List the reading plan's days, or write them as an RSS feed")]
+    Plan {
+        #[arg(
+            long,
+            value_name = "YYYY-MM-DD",
+            help = "First day of the plan instead of $DUCA_PLAN_START or 1 January"
+        )]
+        start: Option<Date>,
+        #[arg(
+            long,
+            value_name = "YYYY-MM-DD",
+            help = "Date whose round to list, or the feed's last day, instead of today's in UTC"
+        )]
+        date: Option<Date>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write an RSS feed of the round's days up to the date, newest first"
+        )]
+        rss: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "URL",
+            default_value = "https://digitaldante.columbia.edu/",
+            help = "Link of the feed, such as the page of the group reading along"
+        )]
+        link: String,
+    },
+    #[command(about = "Add, list or remove your notes on the poem")]
+    Note {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+    #[command(about = "Add, list or remove bookmarks")]
+    Bookmark {
+        #[command(subcommand)]
+        action: BookmarkAction,
+    },
+    #[command(about = "Export your notes, bookmarks and reading log to a file, or merge one in")]
+    Data {
+        #[command(subcommand)]
+        action: DataAction,
+    },
+    #[command(about = "Write your notes and bookmarks out for another program")]
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+    #[cfg(feature = "tui")]
     #[command(about = "Interactive TUI mode")]
-    Tui,
-    #[cfg(debug_assertions)]
-    #[command(about = "Parse and prepare text data (development only)")]
-    Parse,
+    Tui {
+        #[arg(
+            long,
+            help = "Draw with bold, underline and reverse video instead of colors"
+        )]
+        monochrome: bool,
+        #[arg(
+            long,
+            value_name = "LINES",
+            help = "Verses to keep shown above and below the cursor [default: $DUCA_SCROLLOFF or 0]"
+        )]
+        scrolloff: Option<usize>,
+        #[arg(
+            long,
+            value_enum,
+            value_name = "CASE",
+            help = "How search results are scored for case: ignore it, smart (only for a query with a capital), or respect it [default: $DUCA_FUZZY_CASE or ignore]"
+        )]
+        fuzzy_case: Option<tui::FuzzyCase>,
+        #[arg(
+            long,
+            value_name = "SCORE",
+            help = "Drop search results scoring less than this [default: $DUCA_FUZZY_THRESHOLD or 0]"
+        )]
+        fuzzy_threshold: Option<i64>,
+        #[arg(
+            long,
+            help = "Time the reading session in a status bar, and log it when done"
+        )]
+        timer: bool,
+        #[arg(
+            long,
+            value_name = "MINUTES",
+            help = "Remind to take a break after this much reading, timing the session [default: $DUCA_BREAK_EVERY]"
+        )]
+        break_every: Option<u64>,
+        #[arg(
+            long,
+            value_name = "MINUTES",
+            help = "How long breaks last [default: $DUCA_BREAK_LENGTH or 5]"
+        )]
+        break_length: Option<u64>,
+    },
+    #[command(
+        about = "Read the poem a tercet at a time, one command per line, for screen readers"
+    )]
+    Read {
+        #[arg(help = "Passage to start from, e.g. \"Inferno 5\" or \"Purg 3.40\"")]
+        passage: Option<String>,
+    },
+    #[command(about = "Serve a web reader and JSON HTTP API")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1", help = "Address to bind to")]
+        host: String,
+        #[arg(short, long, default_value_t = 8080, help = "Port to listen on")]
+        port: u16,
+        #[arg(long, help = "Open the web reader in a browser")]
+        open: bool,
+    },
+    #[command(about = "Answer JSON-RPC requests on stdin/stdout (lookup, search, random)")]
+    Rpc,
+    #[command(about = "Run a Model Context Protocol server on stdin/stdout")]
+    Mcp,
+    #[cfg(feature = "semantic")]
+    #[command(about = "Embed every verse for `search --semantic`")]
+    Embed,
+    #[command(about = "Generate shell completion scripts")]
+    Completions {
+        #[arg(value_enum, help = "Shell to generate completions for")]
+        shell: Shell,
+        #[arg(
+            long,
+            help = "Print a script that asks duca for canto numbers and bookmarks as you type, to source on shell startup"
+        )]
+        dynamic: bool,
+    },
+    #[command(hide = true, about = "Write roff man pages for packagers")]
+    Man {
+        #[arg(default_value = "man", help = "Directory to write man pages into")]
+        out_dir: PathBuf,
+    },
+    #[command(about = "Manage the works and editions installed in the data directory")]
+    Works {
+        #[command(subcommand)]
+        action: WorksAction,
+    },
+    #[command(
+        about = "Download the Project Gutenberg texts for `parse`, through a checksummed cache"
+    )]
+    Fetch {
+        #[arg(default_value = ".", help = "Directory to write the texts into")]
+        out_dir: PathBuf,
+        #[arg(long, help = "Download again even if the cache has a good copy")]
+        refresh: bool,
+        #[arg(
+            long,
+            help = "Accept texts that changed upstream since they were first fetched"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            conflicts_with = "refresh",
+            help = "Only use the cache, never the network"
+        )]
+        offline: bool,
+    },
+    #[command(about = "Parse source texts into commedia.json and commedia.bin")]
+    Parse {
+        #[arg(
+            help = "Source files or directories, default the current directory; a quoted glob such as 'texts/*.txt' is expanded"
+        )]
+        inputs: Vec<PathBuf>,
+        #[arg(
+            short,
+            long,
+            default_value = ".",
+            help = "Directory to write commedia.json, commedia.bin and the Longfellow commentary into"
+        )]
+        output: PathBuf,
+    },
 }
 
-impl Default for DivinaCommedia {
-    fn default() -> Self {
-        Self::new()
-    }
+#[derive(Subcommand)]
+enum WorksAction {
+    #[command(about = "List the works and editions, built in and installed")]
+    List,
+    #[command(
+        about = "Install a directory of source files as a work (with work.toml) or an edition"
+    )]
+    Add {
+        #[arg(help = "Directory holding work.toml and its texts, or an edition's texts")]
+        path: PathBuf,
+        #[arg(
+            long,
+            help = "Name to install it under instead of the directory's name"
+        )]
+        name: Option<String>,
+        #[arg(long, help = "Replace an install of the same name")]
+        force: bool,
+    },
+    #[command(about = "Delete an installed work or edition")]
+    Remove {
+        #[arg(help = "Name of the work or edition")]
+        name: String,
+    },
+    #[command(about = "Show where a work or edition came from, its checksum and its size")]
+    Info {
+        #[arg(help = "Name of the work or edition, including the built-in commedia and gutenberg")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NoteAction {
+    #[command(about = "Write a note on some lines")]
+    Add {
+        #[arg(help = "Lines to note, such as \"Inferno 5.100-106\" or \"Inferno 5.121\"")]
+        passage: String,
+        #[arg(help = "The note, in Markdown")]
+        text: String,
+    },
+    #[command(about = "Print your notes, on a canto or lines or on the whole poem")]
+    List {
+        #[arg(help = "Canto or lines whose notes to print, such as \"Inferno 5\"")]
+        passage: Option<String>,
+    },
+    #[command(about = "Delete the notes on exactly some lines")]
+    Remove {
+        #[arg(help = "Lines the notes are on, such as \"Inferno 5.100-106\"")]
+        passage: String,
+    },
+    #[cfg(feature = "encryption")]
+    #[command(about = "Encrypt your notes and bookmarks with a passphrase")]
+    Encrypt,
+    #[cfg(feature = "encryption")]
+    #[command(about = "Decrypt your notes and bookmarks back to Markdown")]
+    Decrypt,
 }
 
-impl DivinaCommedia {
-    pub fn new() -> Self {
-        Self {
-            inferno: Cantica {
-                name: "Inferno".to_string(),
-                cantos: HashMap::new(),
-            },
-            purgatorio: Cantica {
-                name: "Purgatorio".to_string(),
-                cantos: HashMap::new(),
-            },
-            paradiso: Cantica {
-                name: "Paradiso".to_string(),
-                cantos: HashMap::new(),
-            },
+#[derive(Subcommand)]
+enum BookmarkAction {
+    #[command(about = "Bookmark a line, or relabel or tag its bookmark")]
+    Add {
+        #[arg(
+            help = "Citation of the line, such as \"Inferno 5.121\"",
+            add = ArgValueCandidates::new(complete::bookmarks)
+        )]
+        citation: String,
+        #[arg(long, help = "What the bookmark is for; an empty label removes it")]
+        label: Option<String>,
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            add = ArgValueCandidates::new(complete::bookmark_tags),
+            help = "Tag the bookmark, such as \"similes\"; can be repeated"
+        )]
+        tags: Vec<String>,
+    },
+    #[command(about = "List the bookmarks in reading order")]
+    List {
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            add = ArgValueCandidates::new(complete::bookmark_tags),
+            help = "Only bookmarks with the tag; repeated, only those with all of them"
+        )]
+        tags: Vec<String>,
+    },
+    #[command(about = "Delete a line's bookmark, or some of its tags")]
+    Remove {
+        #[arg(
+            help = "Citation of the line, such as \"Inferno 5.121\"",
+            add = ArgValueCandidates::new(complete::bookmarks)
+        )]
+        citation: String,
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            add = ArgValueCandidates::new(complete::bookmark_tags),
+            help = "Remove the tag instead of the bookmark; can be repeated"
+        )]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    #[command(
+        about = "An Org file for Emacs: a heading per canto, bookmarked verses quoted, notes dated"
+    )]
+    Org {
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            help = "File to write instead of standard output"
+        )]
+        out: Option<PathBuf>,
+    },
+    #[command(
+        about = "An Obsidian vault: a file per canto, and per canto's notes, linked verse by verse"
+    )]
+    Obsidian {
+        #[arg(value_name = "DIR", help = "Directory to write the vault into")]
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum DataAction {
+    #[command(about = "Write every note, bookmark and reading session to a JSON backup")]
+    Export {
+        #[arg(
+            long,
+            short,
+            value_name = "FILE",
+            help = "File to write instead of standard output"
+        )]
+        out: Option<PathBuf>,
+    },
+    #[command(
+        about = "Merge a JSON backup into your notes, bookmarks and reading log, keeping what's there"
+    )]
+    Import {
+        #[arg(help = "Backup written by `duca data export`")]
+        file: PathBuf,
+    },
+}
+
+/// How a command prints what it found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Quickfix,
+}
+
+#[derive(Subcommand)]
+enum GraphKind {
+    #[command(about = "Characters joined by the cantos they both speak in, weighted by how many")]
+    Characters {
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = GraphFormat::Dot,
+            help = "Write the graph as DOT for Graphviz or as GEXF for Gephi"
+        )]
+        format: GraphFormat,
+    },
+}
+
+/// The file formats `duca graph` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    Dot,
+    Gexf,
+}
+
+/// The commands whose JSON output `duca schema` describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SchemaCommand {
+    Canto,
+    Search,
+}
+
+impl From<SchemaCommand> for Document {
+    fn from(command: SchemaCommand) -> Self {
+        match command {
+            SchemaCommand::Canto => Document::Canto,
+            SchemaCommand::Search => Document::Search,
         }
     }
+}
+
+/// Accepts any cantica name (validation happens where it's looked up) but
+/// advertises the canonical names so shell completions can offer them.
+#[derive(Clone)]
+struct CanticaNameParser;
 
-    pub fn search(
+impl TypedValueParser for CanticaNameParser {
+    type Value = String;
+
+    fn parse_ref(
         &self,
-        pattern: &str,
-        cantica_filter: Option<&str>,
-    ) -> Vec<(String, u8, usize, String)> {
-        let regex = Regex::new(&format!("(?i){}", pattern))
-            .unwrap_or_else(|_| Regex::new(&regex::escape(pattern)).unwrap());
-
-        let mut results = Vec::new();
-
-        let canticas = match cantica_filter {
-            Some("inferno") => vec![&self.inferno],
-            Some("purgatorio") => vec![&self.purgatorio],
-            Some("paradiso") => vec![&self.paradiso],
-            _ => vec![&self.inferno, &self.purgatorio, &self.paradiso],
-        };
-
-        for cantica in canticas {
-            // Sort cantos by number to ensure consistent ordering
-            let mut canto_numbers: Vec<_> = cantica.cantos.keys().collect();
-            canto_numbers.sort();
-
-            for &canto_number in canto_numbers {
-                let canto = &cantica.cantos[&canto_number];
-                for verse in &canto.verses {
-                    if regex.is_match(&verse.text) {
-                        results.push((
-                            cantica.name.clone(),
-                            canto.number,
-                            verse.line_number,
-                            verse.text.clone(),
-                        ));
-                    }
-                }
-            }
-        }
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<String, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
 
-        // Sort results by cantica order (Inferno, Purgatorio, Paradiso), then canto, then line
-        results.sort_by(|a, b| {
-            // First compare by cantica order
-            let cantica_order = |name: &str| match name {
-                "Inferno" => 0,
-                "Purgatorio" => 1,
-                "Paradiso" => 2,
-                _ => 3,
-            };
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            ["inferno", "purgatorio", "paradiso"]
+                .into_iter()
+                .map(PossibleValue::new),
+        ))
+    }
+}
 
-            let cantica_cmp = cantica_order(&a.0).cmp(&cantica_order(&b.0));
-            if cantica_cmp != std::cmp::Ordering::Equal {
-                return cantica_cmp;
-            }
+/// Parses canto numbers given either as digits or as roman numerals.
+#[derive(Clone)]
+struct CantoNumberParser;
 
-            // Then compare by canto number
-            let canto_cmp = a.1.cmp(&b.1);
-            if canto_cmp != std::cmp::Ordering::Equal {
-                return canto_cmp;
-            }
+impl TypedValueParser for CantoNumberParser {
+    type Value = u8;
 
-            // Finally compare by line number
-            a.2.cmp(&b.2)
-        });
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<u8, clap::Error> {
+        let text = value.to_string_lossy();
+        if text.chars().all(|c| c.is_ascii_digit()) {
+            // Keep clap's own range errors for out-of-range numbers
+            return clap::value_parser!(u8).parse_ref(cmd, arg, value);
+        }
 
-        results
+        parse_canto_number(&text).ok_or_else(|| {
+            clap::Error::raw(
+                clap::error::ErrorKind::ValueValidation,
+                format!(
+                    "invalid canto number '{}': expected e.g. 26 or XXVI\n",
+                    text
+                ),
+            )
+            .with_cmd(cmd)
+        })
     }
 }
 
-fn parse_text_files() -> Result<DivinaCommedia> {
-    let mut commedia = DivinaCommedia::new();
-
-    // Parse each cantica from separate files
-    let files = [
-        ("inferno.txt", "inferno"),
-        ("purgatorio.txt", "purgatorio"),
-        ("paradiso.txt", "paradiso"),
-    ];
+/// Report a core error on stderr and exit with its exit code.
+fn fail(error: DucaError, color: ColorChoice) -> ! {
+    eprintln!("{}", Styler::stderr(color).error(&error_message(&error)));
+    std::process::exit(error.exit_code())
+}
 
-    for (filename, cantica_name) in files {
-        if let Ok(content) = fs::read_to_string(filename) {
-            parse_cantica_content(&content, cantica_name, &mut commedia)?;
-        }
+/// `error` as the CLI reports it, in the chosen language where it has a
+/// translation.
+fn error_message(error: &DucaError) -> String {
+    match error {
+        DucaError::CantoNotFound { cantica, canto } => tr(Text::CantoNotFound, &[canto, cantica]),
+        DucaError::SectionNotFound { part, section } => tr(Text::SectionNotFound, &[section, part]),
+        error => error.to_string(),
     }
+}
 
-    Ok(commedia)
+/// The reading plan starting on `start`, or as configured, and the date to
+/// read it on: `date`, or today.
+fn reading_plan(start: Option<Date>, date: Option<Date>, color: ColorChoice) -> (Plan, Date) {
+    let date = date.unwrap_or_else(Date::today);
+    let plan = match start {
+        Some(start) => Plan::new(start),
+        None => Plan::from_env(date).unwrap_or_else(|e| fail(e, color)),
+    };
+    (plan, date)
 }
 
-fn parse_cantica_content(
-    content: &str,
-    cantica_name: &str,
-    commedia: &mut DivinaCommedia,
-) -> Result<()> {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut current_canto_number = 0u8;
-    let mut current_verses = Vec::new();
-    let mut line_number_in_canto = 0usize;
-    let mut in_canto = false;
+/// `input` as lines of a canto, such as `Inferno 5.100-106`, checking
+/// that the canto has them; fails on anything else.
+fn passage_lines(input: &str, color: ColorChoice) -> Result<(Passage, RangeInclusive<usize>)> {
+    let passage: Passage = input.parse().unwrap_or_else(|e| fail(e, color));
+    let canto = commedia()?
+        .require_canto(passage.cantica, passage.canto)
+        .unwrap_or_else(|e| fail(e, color));
+    let reason = match &passage.lines {
+        None => "name the lines, e.g. Inferno 5.100-106".to_string(),
+        Some(lines) if canto.verse(*lines.end()).is_none() => {
+            format!("the canto has lines 1 to {}", canto.verses.len())
+        }
+        Some(lines) => return Ok((passage.clone(), lines.clone())),
+    };
+    let input = input.to_string();
+    fail(DucaError::InvalidCitation { input, reason }, color)
+}
 
-    let canto_regex = Regex::new(r"^Canto\s+([IVXLCDM]+)\.?$").unwrap();
+/// The user's notes, unlocked with their passphrase if encrypted.
+fn open_store(color: ColorChoice) -> NoteStore {
+    unlock_store(NoteStore::open().unwrap_or_else(|e| fail(e, color)), color)
+}
 
-    for line in lines {
-        let trimmed = line.trim();
+/// `store`, unlocked with the notes' passphrase if encrypted.
+#[cfg(feature = "encryption")]
+fn unlock_store(mut store: NoteStore, color: ColorChoice) -> NoteStore {
+    if store.is_encrypted() {
+        let passphrase = read_passphrase("Passphrase for your notes: ", color);
+        store.unlock(&passphrase).unwrap_or_else(|e| fail(e, color));
+    }
+    store
+}
 
-        if trimmed.is_empty() {
-            continue;
-        }
+/// `store`; an encrypted one can't be unlocked without the `encryption`
+/// feature, and says so when read.
+#[cfg(not(feature = "encryption"))]
+fn unlock_store(store: NoteStore, _color: ColorChoice) -> NoteStore {
+    store
+}
 
-        // Stop parsing when we hit the Gutenberg end marker
-        if trimmed.starts_with("Updated editions will replace") {
-            break;
+/// The notes' passphrase: `$DUCA_NOTES_PASSPHRASE`, or else asked for
+/// without echoing it.
+#[cfg(feature = "encryption")]
+fn read_passphrase(prompt: &str, color: ColorChoice) -> String {
+    if let Ok(passphrase) = std::env::var("DUCA_NOTES_PASSPHRASE") {
+        if !passphrase.is_empty() {
+            return passphrase;
         }
+    }
+    rpassword::prompt_password(prompt)
+        .unwrap_or_else(|e| fail_with(&format!("Could not read the passphrase: {}", e), color))
+}
 
-        if let Some(caps) = canto_regex.captures(trimmed) {
-            // Save previous canto if exists
-            if in_canto && current_canto_number > 0 {
-                let canto = Canto {
-                    number: current_canto_number,
-                    roman_numeral: roman_to_number(current_canto_number),
-                    verses: current_verses.clone(),
-                };
+/// Print `message` as an error and exit with code 1.
+fn fail_with(message: &str, color: ColorChoice) -> ! {
+    eprintln!("{}", Styler::stderr(color).error(message));
+    std::process::exit(1)
+}
 
-                match cantica_name {
-                    "inferno" => {
-                        commedia.inferno.cantos.insert(current_canto_number, canto);
-                    }
-                    "purgatorio" => {
-                        commedia
-                            .purgatorio
-                            .cantos
-                            .insert(current_canto_number, canto);
-                    }
-                    "paradiso" => {
-                        commedia.paradiso.cantos.insert(current_canto_number, canto);
-                    }
-                    _ => {}
-                }
-            }
+/// Columns to wrap output to: the terminal's, unless output is piped or
+/// accessible.
+fn output_width(accessible: bool) -> Option<usize> {
+    if accessible {
+        None
+    } else {
+        text::terminal_width()
+    }
+}
 
-            let roman = caps.get(1).unwrap().as_str();
-            current_canto_number = roman_to_arabic(roman);
-            current_verses.clear();
-            line_number_in_canto = 0;
-            in_canto = true;
-            continue;
+/// Write `prefix` followed by `text`, wrapping to `width` columns with
+/// continuation lines indented to align under the start of the text.
+/// `marks` are sorted byte ranges of `text` to style.
+fn write_wrapped(
+    output: &mut String,
+    prefix: &str,
+    prefix_width: usize,
+    text: &str,
+    marks: &[(Range<usize>, Mark)],
+    styler: &Styler,
+    width: Option<usize>,
+) -> fmt::Result {
+    let segments = match width {
+        Some(width) => text::wrap_ranges(text, width.saturating_sub(prefix_width)),
+        None => vec![Range {
+            start: 0,
+            end: text.len(),
+        }],
+    };
+
+    for (i, segment) in segments.into_iter().enumerate() {
+        if i == 0 {
+            output.push_str(prefix);
+        } else {
+            output.push_str(&" ".repeat(prefix_width));
         }
 
-        if in_canto && !trimmed.starts_with("*** ") && !trimmed.contains("Project Gutenberg") {
-            line_number_in_canto += 1;
-            current_verses.push(Verse {
-                line_number: line_number_in_canto,
-                text: trimmed.to_string(),
-            });
+        // Clip each mark to this segment so highlights survive a line break
+        let clipped: Vec<(Range<usize>, Mark)> = marks
+            .iter()
+            .filter(|(m, _)| m.start < segment.end && m.end > segment.start)
+            .map(|(m, mark)| {
+                let start = m.start.max(segment.start) - segment.start;
+                (start..m.end.min(segment.end) - segment.start, *mark)
+            })
+            .collect();
+
+        writeln!(output, "{}", styler.mark_ranges(&text[segment], &clipped))?;
+    }
+
+    Ok(())
+}
+
+/// Write the heatmap `search --heatmap` draws for `pattern`: a row of
+/// `cells` for each cantica, shaded by the verses matching in each canto,
+/// over a ruler numbering the cantos.
+fn write_heatmap(
+    output: &mut String,
+    pattern: &str,
+    cells: &[(CanticaId, u8, usize)],
+    styler: &Styler,
+) -> fmt::Result {
+    let total: usize = cells.iter().map(|(_, _, count)| count).sum();
+    // The first of the cantos with the most, as max_by_key picks the last
+    let Some(&(cantica, canto, most)) = cells
+        .iter()
+        .rev()
+        .filter(|(_, _, count)| *count > 0)
+        .max_by_key(|(_, _, count)| *count)
+    else {
+        return writeln!(output, "{}", tr(Text::NoMatches, &[&pattern]));
+    };
+    writeln!(
+        output,
+        "{}\n",
+        tr(
+            Text::HeatmapMost,
+            &[&pattern, &total, &cantica, &canto, &most]
+        )
+    )?;
+
+    let label_width = CanticaId::ALL
+        .iter()
+        .map(|id| id.name().len())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let mut longest = 0;
+    for id in CanticaId::ALL {
+        let row: String = cells
+            .iter()
+            .filter(|(cantica, _, _)| *cantica == id)
+            .map(|&(_, _, count)| stats::shade(count, most))
+            .collect();
+        if row.is_empty() {
+            continue;
         }
+        longest = longest.max(row.chars().count());
+        let label = text::pad_end(id.name(), label_width);
+        writeln!(output, "{}{}", styler.location(&label), row)?;
     }
 
-    // Save last canto
-    if in_canto && current_canto_number > 0 {
-        let canto = Canto {
-            number: current_canto_number,
-            roman_numeral: roman_to_number(current_canto_number),
-            verses: current_verses,
-        };
+    // Numbers under the first canto and every tenth
+    let mut ruler = " ".repeat(label_width);
+    let mut column = 0;
+    for canto in std::iter::once(1).chain((10..=longest).step_by(10)) {
+        let pad = (canto - 1).saturating_sub(column);
+        ruler.push_str(&" ".repeat(pad));
+        ruler.push_str(&canto.to_string());
+        column += pad + canto.to_string().len();
+    }
+    writeln!(output, "{}", styler.note(&ruler))
+}
 
-        match cantica_name {
-            "inferno" => {
-                commedia.inferno.cantos.insert(current_canto_number, canto);
-            }
-            "purgatorio" => {
-                commedia
-                    .purgatorio
-                    .cantos
-                    .insert(current_canto_number, canto);
+/// Columns of the longest bar `compare-freq` draws.
+const FREQUENCY_BAR: usize = 30;
+
+/// A bar chart of how often each cantica uses each of `terms`, per 10,000
+/// words, the bars of all the terms to one scale and the cantica using a
+/// term most highlighted.
+fn write_frequencies(
+    output: &mut String,
+    terms: &[String],
+    frequencies: &[stats::CanticaFrequency],
+    styler: &Styler,
+) -> fmt::Result {
+    let highest = frequencies
+        .iter()
+        .flat_map(|f| (0..terms.len()).map(|term| f.rate(term)))
+        .fold(0.0, f64::max);
+    let label_width = CanticaId::ALL
+        .iter()
+        .map(|id| id.name().len())
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    writeln!(output, "{}", styler.note(&tr(Text::FrequencyPer, &[])))?;
+    for (term, name) in terms.iter().enumerate() {
+        writeln!(output, "\n{}", styler.header(name))?;
+        let most = frequencies.iter().map(|f| f.rate(term)).fold(0.0, f64::max);
+        for frequency in frequencies {
+            let rate = frequency.rate(term);
+            let mut bar = 0;
+            if highest > 0.0 {
+                bar = (rate / highest * FREQUENCY_BAR as f64).round() as usize;
             }
-            "paradiso" => {
-                commedia.paradiso.cantos.insert(current_canto_number, canto);
+            // Any use at all shows, however short its bar
+            if frequency.counts[term] > 0 {
+                bar = bar.max(1);
             }
-            _ => {}
+            let bar = text::pad_end(&"█".repeat(bar), FREQUENCY_BAR);
+            let bar = if most > 0.0 && rate == most {
+                styler.highlight(&bar)
+            } else {
+                bar
+            };
+            let label = text::pad_end(frequency.cantica.name(), label_width);
+            writeln!(
+                output,
+                "{}{} {:>5.1} ({})",
+                styler.location(&label),
+                bar,
+                rate,
+                frequency.counts[term]
+            )?;
         }
     }
+    Ok(())
+}
 
+/// Lines under a match of `search --explain`: its score and the verse's
+/// length, then what each word of the query it has adds.
+fn write_explanation(
+    output: &mut String,
+    scored: &rank::ScoredMatch,
+    styler: &Styler,
+) -> fmt::Result {
+    let score = tr(
+        Text::ExplainScore,
+        &[
+            &format!("{:.2}", scored.score),
+            &scored.length,
+            &format!("{:.2}", scored.relative_length),
+        ],
+    );
+    writeln!(output, "    {}", styler.note(&score))?;
+    for term in &scored.terms {
+        let positions: Vec<String> = term.positions.iter().map(usize::to_string).collect();
+        let line = tr(
+            Text::ExplainTerm,
+            &[
+                &term.term,
+                &positions.join(", "),
+                &format!("{:.2}", term.idf),
+                &format!("{:.2}", term.frequency),
+                &format!("{:.1}", term.weight),
+                &format!("{:.2}", term.score),
+            ],
+        );
+        writeln!(output, "      {}", styler.note(&line))?;
+    }
     Ok(())
 }
 
-fn roman_to_arabic(roman: &str) -> u8 {
-    let mut result = 0;
-    let mut prev_value = 0;
-
-    for c in roman.chars().rev() {
-        let value = match c {
-            'I' => 1,
-            'V' => 5,
-            'X' => 10,
-            'L' => 50,
-            'C' => 100,
-            'D' => 500,
-            'M' => 1000,
-            _ => 0,
-        };
-
-        if value < prev_value {
-            result -= value;
-        } else {
-            result += value;
-        }
-        prev_value = value;
+/// The pipeline `search` runs patterns through: `--normalize`'s or the
+/// default, with `--lemma` and `--modern` adding to it, reading regexes
+/// with `--regex`.
+fn search_pipeline(
+    normalize: Option<Pipeline>,
+    lemma: bool,
+    modern: bool,
+    regex: bool,
+) -> Pipeline {
+    let mut pipeline = normalize.unwrap_or_default().with_regex(regex);
+    if lemma {
+        pipeline = pipeline.with(Normalizer::Lemma);
     }
+    if modern {
+        pipeline = pipeline.with(Normalizer::Orthography);
+    }
+    pipeline
+}
 
-    result as u8
-}
-
-fn roman_to_number(num: u8) -> String {
-    let values = [
-        (1000, "M"),
-        (900, "CM"),
-        (500, "D"),
-        (400, "CD"),
-        (100, "C"),
-        (90, "XC"),
-        (50, "L"),
-        (40, "XL"),
-        (10, "X"),
-        (9, "IX"),
-        (5, "V"),
-        (4, "IV"),
-        (1, "I"),
-    ];
-
-    let mut result = String::new();
-    let mut n = num as usize;
-
-    for &(value, numeral) in &values {
-        while n >= value {
-            result.push_str(numeral);
-            n -= value;
-        }
+/// How many verses matched in each cantica, or part of a work, e.g.
+/// `Inferno: 34, Purgatorio: 12, Paradiso: 51`.
+fn match_summary<'a>(counts: impl Iterator<Item = (&'a str, usize)>) -> String {
+    counts
+        .map(|(name, count)| format!("{}: {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Write the line `search --summary-only` prints for `pattern`, naming the
+/// pattern when there are several.
+fn write_summary_only(
+    output: &mut String,
+    pattern: &str,
+    patterns: usize,
+    summary: &str,
+) -> fmt::Result {
+    if patterns > 1 {
+        write!(output, "'{}': ", pattern)?;
     }
+    writeln!(output, "{}", summary)
+}
+
+/// The verses `canto` shows, with their indices: those within `context`
+/// lines of `around`, or else all of them.
+fn excerpt(
+    canto: &Canto,
+    around: Option<usize>,
+    context: usize,
+) -> Result<Vec<(usize, &Verse)>, DucaError> {
+    if let Some(line) = around.filter(|&line| canto.verse(line).is_none()) {
+        return Err(DucaError::InvalidCitation {
+            input: format!("{}.{}", canto.title, line),
+            reason: format!("the canto has {} lines", canto.verses.len()),
+        });
+    }
+    Ok(canto
+        .verses
+        .iter()
+        .enumerate()
+        .filter(|(_, verse)| around.is_none_or(|line| verse.line_number.abs_diff(line) <= context))
+        .collect())
+}
+
+/// The gutter and marks of a verse in `canto` output. With `--around`, the
+/// line asked for is pointed at and highlighted whole, and the rest are
+/// indented to match.
+fn canto_line(
+    verse: &Verse,
+    around: Option<usize>,
+    glossed: Vec<Range<usize>>,
+    found: Option<&Vec<Range<usize>>>,
+) -> (String, Vec<(Range<usize>, Mark)>) {
+    let gutter = text::gutter(verse.line_number);
+    match around {
+        Some(line) if line == verse.line_number => (
+            format!("> {}", gutter),
+            vec![(0..verse.text.len(), Mark::Match)],
+        ),
+        Some(_) => (format!("  {}", gutter), verse_marks(glossed, found)),
+        None => (gutter, verse_marks(glossed, found)),
+    }
+}
 
-    result
+/// The marks for a verse shown whole: its glossed words underlined, and
+/// the matches of `canto --highlight` highlighted over any they overlap.
+fn verse_marks(
+    glossed: Vec<Range<usize>>,
+    found: Option<&Vec<Range<usize>>>,
+) -> Vec<(Range<usize>, Mark)> {
+    let found = found.map_or(&[][..], Vec::as_slice);
+    let mut marks: Vec<_> = glossed
+        .into_iter()
+        .filter(|word| {
+            !found
+                .iter()
+                .any(|m| m.start < word.end && word.start < m.end)
+        })
+        .map(|range| (range, Mark::Gloss))
+        .chain(found.iter().map(|range| (range.clone(), Mark::Match)))
+        .collect();
+    marks.sort_by_key(|(range, _)| range.start);
+    marks
 }
 
-fn load_commedia() -> Result<DivinaCommedia> {
-    // Try to load from embedded data first, then fall back to external files
-    const EMBEDDED_DATA: &str = include_str!("../commedia.json");
+/// Write a commentary note indented under the verse text.
+fn write_note(
+    output: &mut String,
+    note: &CommentaryEntry,
+    styler: &Styler,
+    width: Option<usize>,
+) -> fmt::Result {
+    let text = format!("[{}] {}: {}", note.source, note.passage, note.text);
+    write_aside(output, &text, styler, width)
+}
+
+/// Write `text` dimmed and indented under a verse, wrapped to `width`.
+fn write_aside(
+    output: &mut String,
+    text: &str,
+    styler: &Styler,
+    width: Option<usize>,
+) -> fmt::Result {
+    const INDENT: &str = "     ";
+    let segments = match width {
+        Some(width) => text::wrap_ranges(text, width.saturating_sub(INDENT.len())),
+        None => vec![Range {
+            start: 0,
+            end: text.len(),
+        }],
+    };
+    for segment in segments {
+        writeln!(output, "{}{}", INDENT, styler.note(&text[segment]))?;
+    }
+    Ok(())
+}
+
+/// Write a glossary entry as `word (other forms): definition`.
+fn write_gloss(output: &mut String, entry: &GlossEntry, styler: &Styler) -> fmt::Result {
+    let mut word = styler.header(&entry.word);
+    if !entry.forms.is_empty() {
+        write!(word, " ({})", entry.forms.join(", "))?;
+    }
+    writeln!(output, "{}: {}", word, entry.definition)
+}
 
-    if !EMBEDDED_DATA.trim().is_empty() {
-        serde_json::from_str(EMBEDDED_DATA).map_err(|e| e.into())
-    } else if fs::metadata("commedia.json").is_ok() {
-        let json = fs::read_to_string("commedia.json")?;
-        serde_json::from_str(&json).map_err(|e| e.into())
+/// Read search patterns one per line from `path`, or from stdin for `-`,
+/// skipping blank lines.
+fn read_patterns(path: &Path) -> Result<Vec<String>> {
+    let content = if path == Path::new("-") {
+        io::read_to_string(io::stdin())?
     } else {
-        parse_text_files()
+        fs::read_to_string(path)?
+    };
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Render `duca.1` plus a `duca-<subcommand>.1` page for every visible
+/// subcommand into `out_dir`, returning the paths written.
+fn write_man_pages(out_dir: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(out_dir)?;
+
+    let mut command = Cli::command();
+    command.build();
+
+    let mut pages = vec![command.clone()];
+    for subcommand in command.get_subcommands().filter(|s| !s.is_hide_set()) {
+        let name = format!("duca-{}", subcommand.get_name());
+        pages.push(subcommand.clone().name(name.clone()).bin_name(name));
+    }
+
+    let mut written = Vec::new();
+    for page in pages {
+        let path = out_dir.join(format!("{}.1", page.get_name()));
+        let mut buffer = Vec::new();
+        clap_mangen::Man::new(page).render(&mut buffer)?;
+        fs::write(&path, buffer)?;
+        written.push(path);
     }
+
+    Ok(written)
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Run `canto` or `search` on a work other than the Commedia, whose parts
+/// stand in for the canticas. Other commands only read the Commedia.
+fn run_work(cli: Cli, work: &Work) -> Result<()> {
+    let styler = Styler::stdout(cli.color);
+    let width = output_width(cli.accessible);
+    let part = |name: &str| match work.part(name) {
+        Some(part) => part,
+        None => {
+            let names: Vec<&str> = work.parts.iter().map(|p| p.name.as_str()).collect();
+            let message = format!(
+                "No part '{}' in {}. Parts: {}",
+                name,
+                work.title,
+                names.join(", ")
+            );
+            eprintln!("{}", Styler::stderr(cli.color).error(&message));
+            std::process::exit(2)
+        }
+    };
+    let mut output = String::new();
 
     match cli.command {
-        #[cfg(debug_assertions)]
-        Commands::Parse => {
-            println!("Parsing Divine Comedy text from all three files...");
-            let commedia = parse_text_files()?;
+        Commands::Canto {
+            cantica,
+            number,
+            plain,
+            commentary: false,
+            variants: false,
+            allusions: false,
+            highlight,
+            around,
+            context,
+            format,
+        } => {
+            let part = part(&cantica);
+            let Some(section) = part.sections.get(&number) else {
+                let error = DucaError::SectionNotFound {
+                    part: part.name.clone(),
+                    section: number,
+                };
+                fail(error, cli.color);
+            };
+            let shown = excerpt(section, around, context).unwrap_or_else(|e| fail(e, cli.color));
+            if format == Format::Quickfix {
+                let mut output = String::new();
+                for (_, verse) in &shown {
+                    let line =
+                        output::quickfix_line(&part.name, number, verse.line_number, &verse.text);
+                    writeln!(output, "{}", line)?;
+                }
+                pager::print(&output, true)?;
+                return Ok(());
+            }
+            if format == Format::Json {
+                let verses = shown.iter().map(|&(_, verse)| verse);
+                let canto = CantoOutput::new(&part.name, section, verses);
+                let json = serde_json::to_string_pretty(&canto)? + "\n";
+                pager::print(&json, true)?;
+                return Ok(());
+            }
+            let found = highlight
+                .map(|term| section.find(&term))
+                .unwrap_or_default();
+            if plain {
+                for (_, verse) in &shown {
+                    writeln!(output, "{}", verse.text)?;
+                }
+            } else {
+                if cli.accessible {
+                    let heading = accessible::canto_heading(&part.name, section, &shown);
+                    writeln!(output, "{}\n", heading)?;
+                } else {
+                    writeln!(output, "{}\n", styler.header(&section.title))?;
+                }
+                for &(i, verse) in &shown {
+                    let (mut gutter, marks) = canto_line(verse, around, Vec::new(), found.get(i));
+                    if cli.accessible {
+                        gutter = accessible::line_label(verse.line_number, around);
+                    }
+                    write_wrapped(
+                        &mut output,
+                        &styler.line_number(&gutter),
+                        text::display_width(&gutter),
+                        &verse.text,
+                        &marks,
+                        &styler,
+                        width,
+                    )?;
+                }
+            }
+        }
+        Commands::Search {
+            pattern,
+            patterns_file,
+            cantica,
+            picker,
+            print0,
+            summary_only,
+            heatmap,
+            format,
+            normalize,
+            lemma,
+            modern,
+            regex,
+            rank,
+            ..
+        } => {
+            if heatmap {
+                fail_with("--heatmap charts the cantos of the Commedia", cli.color);
+            }
+            if rank == Rank::Bm25 {
+                fail_with("--rank bm25 ranks the verses of the Commedia", cli.color);
+            }
+            let pipeline = search_pipeline(normalize, lemma, modern, regex);
+            let part = cantica.as_deref().map(part);
+            let patterns = match patterns_file {
+                Some(path) => read_patterns(&path)?,
+                None => pattern.into_iter().collect(),
+            };
+            for (i, pattern) in patterns.iter().enumerate() {
+                let results = work
+                    .search_with(pattern, part, &pipeline)
+                    .unwrap_or_else(|e| fail(e, cli.color));
+                if format == Format::Quickfix {
+                    for m in &results {
+                        let line = output::quickfix_line(m.part, m.section, m.line, m.text);
+                        writeln!(output, "{}", line)?;
+                    }
+                    continue;
+                }
+                if format == Format::Json {
+                    let results = results
+                        .iter()
+                        .map(|m| MatchOutput {
+                            cantica: m.part,
+                            canto: m.section,
+                            line: m.line,
+                            text: m.text,
+                            spans: m.spans.clone(),
+                        })
+                        .collect();
+                    let search = SearchOutput::new(pattern, results);
+                    writeln!(output, "{}", serde_json::to_string(&search)?)?;
+                    continue;
+                }
+                if picker || print0 {
+                    let separator = if print0 { '\0' } else { '\n' };
+                    for m in results {
+                        let location = format!("{} {}.{}", m.part, m.section, m.line);
+                        write!(output, "{}: {}{}", location, m.text, separator)?;
+                    }
+                    continue;
+                }
 
-            let json = serde_json::to_string_pretty(&commedia)?;
-            fs::write("commedia.json", json)?;
+                let counts = work
+                    .parts
+                    .iter()
+                    .filter(|p| part.is_none_or(|part| part.name == p.name))
+                    .map(|p| {
+                        let count = results.iter().filter(|m| m.part == p.name).count();
+                        (p.name.as_str(), count)
+                    });
+                let summary = match_summary(counts);
+                if summary_only {
+                    write_summary_only(&mut output, pattern, patterns.len(), &summary)?;
+                    continue;
+                }
 
-            println!("Parsed and saved to commedia.json");
-            println!("Inferno cantos: {}", commedia.inferno.cantos.len());
-            println!("Purgatorio cantos: {}", commedia.purgatorio.cantos.len());
-            println!("Paradiso cantos: {}", commedia.paradiso.cantos.len());
+                if i > 0 {
+                    output.push('\n');
+                }
+                if results.is_empty() {
+                    writeln!(output, "{}", tr(Text::NoMatches, &[pattern]))?;
+                    continue;
+                }
+                writeln!(
+                    output,
+                    "{}\n",
+                    tr(Text::FoundMatches, &[&results.len(), pattern])
+                )?;
+                for (n, m) in results.iter().enumerate() {
+                    let location = if cli.accessible {
+                        accessible::match_label(n + 1, results.len(), m.part, m.section, m.line)
+                    } else {
+                        format!("{} {}.{}: ", m.part, m.section, m.line)
+                    };
+                    let marks: Vec<_> = m.spans.iter().map(|s| (s.clone(), Mark::Match)).collect();
+                    write_wrapped(
+                        &mut output,
+                        &styler.location(&location),
+                        text::display_width(&location),
+                        m.text,
+                        &marks,
+                        &styler,
+                        width,
+                    )?;
+                }
+                if cli.accessible {
+                    writeln!(output, "\n{}", tr(Text::AccessibleSummary, &[&summary]))?;
+                } else {
+                    writeln!(output, "\n{}", styler.note(&summary))?;
+                }
+            }
+            if picker || print0 || format != Format::Text {
+                pager::print(&output, true)?;
+                return Ok(());
+            }
         }
+        _ => {
+            let message = format!(
+                "{} is not the Commedia; with --work only `canto` and `search` are available, without commentary or variants",
+                work.title
+            );
+            eprintln!("{}", Styler::stderr(cli.color).error(&message));
+            std::process::exit(2)
+        }
+    }
 
-        Commands::Search { pattern, cantica } => {
-            let commedia = load_commedia()?;
+    pager::print(&output, cli.no_pager)?;
+    Ok(())
+}
 
-            let results = commedia.search(&pattern, cantica.as_deref());
+fn main() -> Result<()> {
+    // Answers the script of `completions --dynamic`, when it calls back
+    CompleteEnv::with_factory(|| i18n::localize(Cli::command(), i18n::locale()))
+        .var(complete::VAR)
+        .complete();
+    let matches = i18n::localize(Cli::command(), i18n::locale()).get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if accessible::enabled(cli.accessible) {
+        cli.accessible = true;
+        cli.color = ColorChoice::Never;
+        cli.no_pager = true;
+    }
+    if let Some(edition) = &cli.edition {
+        if let Err(e) = editions::select(edition) {
+            fail(e, cli.color);
+        }
+    }
+    if let Some(work) = cli.work.as_deref().filter(|work| *work != works::COMMEDIA) {
+        let work = works::load(work).unwrap_or_else(|e| fail(e, cli.color));
+        return run_work(cli, &work);
+    }
 
-            if results.is_empty() {
-                println!("No matches found for '{}'", pattern);
+    match cli.command {
+        Commands::Parse { inputs, output } => {
+            let inputs = if inputs.is_empty() {
+                vec![PathBuf::from(".")]
             } else {
-                println!("Found {} matches for '{}':\n", results.len(), pattern);
-                for (cantica_name, canto_num, line_num, text) in results {
-                    println!("{} {}.{}: {}", cantica_name, canto_num, line_num, text);
+                inputs
+            };
+            let inputs = expand_globs(&inputs).unwrap_or_else(|e| fail(e, cli.color));
+            let names: Vec<String> = inputs.iter().map(|p| p.display().to_string()).collect();
+            println!("Parsing Divine Comedy text from {}", names.join(", "));
+            let commedia = parse_paths_with(&inputs, |path, cantos| {
+                println!("  {}: {} cantos", path.display(), cantos)
+            })
+            .unwrap_or_else(|e| fail(e, cli.color));
+
+            fs::create_dir_all(&output)?;
+            let json = serde_json::to_string_pretty(&commedia)?;
+            fs::write(output.join("commedia.json"), json)?;
+            fs::write(output.join("commedia.bin"), to_binary(&commedia)?)?;
+
+            println!(
+                "Parsed and saved to {} and {}",
+                output.join("commedia.json").display(),
+                output.join("commedia.bin").display()
+            );
+
+            // Longfellow's translation sits beside the sources it annotates
+            let mut source_dirs: Vec<&Path> = inputs
+                .iter()
+                .map(|p| match p.parent() {
+                    Some(parent) if !p.is_dir() => parent,
+                    _ => p.as_path(),
+                })
+                .collect();
+            source_dirs.dedup();
+            let mut notes = Commentary::default();
+            for dir in source_dirs {
+                for id in CanticaId::ALL {
+                    let path = dir.join(format!("longfellow-{}.txt", id.key()));
+                    if let Ok(content) = fs::read_to_string(path) {
+                        notes.extend(parse_longfellow_notes(&content, id));
+                    }
                 }
             }
+            if !notes.is_empty() {
+                let header = "# Longfellow's notes to his translation (public domain), \
+                              generated by `duca parse`.\n\n";
+                fs::create_dir_all(output.join("commentary"))?;
+                fs::write(
+                    output.join("commentary/longfellow.toml"),
+                    format!("{}{}", header, notes.to_toml("Longfellow")),
+                )?;
+                println!("Saved {} Longfellow notes", notes.len());
+            }
+            // A summary to check against the sources, warning of likely damage
+            for (id, cantica) in commedia.canticas() {
+                let lines: Vec<String> = cantica
+                    .cantos
+                    .values()
+                    .map(|canto| canto.verses.len().to_string())
+                    .collect();
+                let total: usize = cantica.cantos.values().map(|c| c.verses.len()).sum();
+                println!("{}: {} cantos, {} lines", id, lines.len(), total);
+                if !lines.is_empty() {
+                    println!("  lines per canto: {}", lines.join(" "));
+                }
+            }
+            let styler = Styler::stderr(cli.color);
+            for deviation in verify::suspicious(&commedia) {
+                eprintln!("{}", styler.error(&format!("warning: {}", deviation)));
+            }
         }
 
-        Commands::Canto { cantica, number } => {
-            let commedia = load_commedia()?;
+        Commands::Fetch {
+            out_dir,
+            refresh,
+            force,
+            offline,
+        } => {
+            let options = fetch::FetchOptions {
+                refresh,
+                force,
+                offline,
+            };
+            let fetched =
+                fetch::fetch_all(&out_dir, options).unwrap_or_else(|e| fail(e, cli.color));
+            for (path, how) in fetched {
+                let how = match how {
+                    fetch::Fetched::Cached => "from the cache",
+                    fetch::Fetched::Downloaded => "downloaded",
+                    fetch::Fetched::Replaced => "downloaded, replacing a changed copy",
+                };
+                println!("{} ({})", path.display(), how);
+            }
+        }
 
-            let cantica_data = match cantica.to_lowercase().as_str() {
-                "inferno" => &commedia.inferno,
-                "purgatorio" => &commedia.purgatorio,
-                "paradiso" => &commedia.paradiso,
-                _ => {
-                    eprintln!("Invalid cantica. Use: inferno, purgatorio, or paradiso");
-                    return Ok(());
+        Commands::Search {
+            pattern,
+            patterns_file,
+            cantica,
+            picker,
+            print0,
+            summary_only,
+            heatmap,
+            format,
+            normalize,
+            lemma,
+            modern,
+            regex,
+            rank,
+            explain,
+            #[cfg(feature = "semantic")]
+            semantic,
+        } => {
+            if explain && rank != Rank::Bm25 {
+                fail_with(
+                    "--explain explains the scores of --rank bm25; matches in order have none",
+                    cli.color,
+                );
+            }
+            if regex && rank == Rank::Bm25 {
+                fail_with(
+                    "--rank bm25 weighs the pattern's words; a regex has none",
+                    cli.color,
+                );
+            }
+            let commedia = commedia()?;
+            let pipeline = search_pipeline(normalize, lemma, modern, regex);
+
+            let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+                Ok(cantica) => cantica,
+                Err(e) => fail(e, cli.color),
+            };
+
+            #[cfg(feature = "semantic")]
+            let semantic = semantic.then(|| {
+                let path = Path::new(semantic::EMBEDDINGS_FILE);
+                if !path.exists() {
+                    let message = format!("no {}; run `duca embed` first", path.display());
+                    fail(DucaError::Semantic(message), cli.color);
+                }
+                match VerseEmbeddings::load(path) {
+                    Ok(embeddings) => (embeddings, Embedder::from_env()),
+                    Err(e) => fail(e, cli.color),
                 }
+            });
+            let search = |pattern: &str| {
+                #[cfg(feature = "semantic")]
+                if let Some((embeddings, embedder)) = &semantic {
+                    return embeddings
+                        .search(commedia, embedder, pattern, cantica, SEMANTIC_LIMIT)
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                }
+                if rank == Rank::Bm25 {
+                    return rank::bm25(commedia, pattern, cantica, &pipeline)
+                        .into_iter()
+                        .map(|m| m.matched)
+                        .collect();
+                }
+                commedia
+                    .search_with(pattern, cantica, &pipeline)
+                    .unwrap_or_else(|e| fail(e, cli.color))
             };
 
-            if let Some(canto) = cantica_data.cantos.get(&number) {
-                println!("{} Canto {}\n", cantica_data.name, canto.roman_numeral);
-                for verse in &canto.verses {
-                    println!("{:3}: {}", verse.line_number, verse.text);
+            let patterns = match patterns_file {
+                Some(path) => read_patterns(&path)?,
+                None => pattern.into_iter().collect(),
+            };
+
+            let mut output = String::new();
+
+            if format == Format::Json {
+                for pattern in &patterns {
+                    let matches = search(pattern);
+                    let results = matches.iter().map(MatchOutput::from).collect();
+                    let search = SearchOutput::new(pattern, results);
+                    writeln!(output, "{}", serde_json::to_string(&search)?)?;
+                }
+                pager::print(&output, true)?;
+                return Ok(());
+            }
+
+            if format == Format::Quickfix {
+                for pattern in &patterns {
+                    for m in search(pattern) {
+                        let line = output::quickfix_line(m.cantica.name(), m.canto, m.line, m.text);
+                        writeln!(output, "{}", line)?;
+                    }
+                }
+                pager::print(&output, true)?;
+                return Ok(());
+            }
+
+            if picker || print0 {
+                let separator = if print0 { '\0' } else { '\n' };
+                for pattern in &patterns {
+                    for m in search(pattern) {
+                        write!(
+                            output,
+                            "{} {}.{}: {}{}",
+                            m.cantica, m.canto, m.line, m.text, separator
+                        )?;
+                    }
+                }
+                pager::print(&output, true)?;
+                return Ok(());
+            }
+
+            let styler = Styler::stdout(cli.color);
+            let width = output_width(cli.accessible);
+
+            for (i, pattern) in patterns.iter().enumerate() {
+                // Scored here when they're to be explained, else searched as asked
+                let scored = explain.then(|| rank::bm25(commedia, pattern, cantica, &pipeline));
+                let results = match &scored {
+                    Some(scored) => scored.iter().map(|m| m.matched.clone()).collect(),
+                    None => search(pattern),
+                };
+
+                if heatmap {
+                    if i > 0 {
+                        output.push('\n');
+                    }
+                    let hits = results.iter().map(|m| (m.cantica, m.canto));
+                    let cells: Vec<_> = stats::hits_by_canto(commedia, hits)
+                        .into_iter()
+                        .filter(|(id, _, _)| cantica.is_none_or(|cantica| cantica == *id))
+                        .collect();
+                    write_heatmap(&mut output, pattern, &cells, &styler)?;
+                    continue;
+                }
+
+                let counts = CanticaId::ALL
+                    .into_iter()
+                    .filter(|id| cantica.is_none_or(|cantica| cantica == *id))
+                    .map(|id| {
+                        (
+                            id.name(),
+                            results.iter().filter(|m| m.cantica == id).count(),
+                        )
+                    });
+                let summary = match_summary(counts);
+                if summary_only {
+                    write_summary_only(&mut output, pattern, patterns.len(), &summary)?;
+                    continue;
+                }
+
+                if i > 0 {
+                    output.push('\n');
+                }
+
+                if results.is_empty() {
+                    match commedia.suggest(pattern).filter(|_| !regex) {
+                        Some(suggestion) => writeln!(
+                            output,
+                            "{}",
+                            tr(Text::NoMatchesSuggest, &[pattern, &suggestion])
+                        )?,
+                        None => writeln!(output, "{}", tr(Text::NoMatches, &[pattern]))?,
+                    }
+                    continue;
+                }
+
+                writeln!(
+                    output,
+                    "{}\n",
+                    tr(Text::FoundMatches, &[&results.len(), pattern])
+                )?;
+                for (n, m) in results.iter().enumerate() {
+                    let location = if cli.accessible {
+                        let name = m.cantica.name();
+                        accessible::match_label(n + 1, results.len(), name, m.canto, m.line)
+                    } else {
+                        format!("{} {}.{}: ", m.cantica, m.canto, m.line)
+                    };
+                    let marks: Vec<_> = m.spans.iter().map(|s| (s.clone(), Mark::Match)).collect();
+                    write_wrapped(
+                        &mut output,
+                        &styler.location(&location),
+                        text::display_width(&location),
+                        m.text,
+                        &marks,
+                        &styler,
+                        width,
+                    )?;
+                    if let Some(scored) = &scored {
+                        write_explanation(&mut output, &scored[n], &styler)?;
+                    }
+                }
+                if cli.accessible {
+                    writeln!(output, "\n{}", tr(Text::AccessibleSummary, &[&summary]))?;
+                } else {
+                    writeln!(output, "\n{}", styler.note(&summary))?;
                 }
-            } else {
-                println!("Canto {} not found in {}", number, cantica_data.name);
             }
+
+            pager::print(&output, cli.no_pager)?;
         }
 
-        Commands::Tui => {
-            let commedia = load_commedia()?;
+        Commands::Canto {
+            cantica,
+            number,
+            plain,
+            commentary,
+            variants,
+            allusions,
+            highlight,
+            around,
+            context,
+            format,
+        } => {
+            let cantica = match cantica.parse::<CanticaId>() {
+                Ok(id) => id,
+                Err(e) => fail(e, cli.color),
+            };
+
+            if let Some(canto) = load_canto(cantica, number)? {
+                let styler = Styler::stdout(cli.color);
+                let shown = excerpt(&canto, around, context).unwrap_or_else(|e| fail(e, cli.color));
+                if format == Format::Quickfix {
+                    let mut output = String::new();
+                    for (_, verse) in &shown {
+                        let line = output::quickfix_line(
+                            cantica.name(),
+                            number,
+                            verse.line_number,
+                            &verse.text,
+                        );
+                        writeln!(output, "{}", line)?;
+                    }
+                    pager::print(&output, true)?;
+                    return Ok(());
+                }
+                if format == Format::Json {
+                    let verses = shown.iter().map(|&(_, verse)| verse);
+                    let canto = CantoOutput::new(cantica.name(), &canto, verses);
+                    let json = serde_json::to_string_pretty(&canto)? + "\n";
+                    pager::print(&json, true)?;
+                    return Ok(());
+                }
+                let mut output = String::new();
+                if plain {
+                    for (_, verse) in &shown {
+                        writeln!(output, "{}", verse.text)?;
+                    }
+                } else {
+                    let width = output_width(cli.accessible);
+                    if cli.accessible {
+                        let heading = accessible::canto_heading(cantica.name(), &canto, &shown);
+                        writeln!(output, "{}\n", heading)?;
+                    } else {
+                        let header = format!("{} Canto {}", cantica, canto.roman_numeral);
+                        let header = match width {
+                            Some(width) => text::truncate(&header, width),
+                            None => header,
+                        };
+                        writeln!(output, "{}\n", styler.header(&header))?;
+                    }
 
-            tui::run_tui(commedia)?;
+                    let notes = if commentary {
+                        Commentary::load().unwrap_or_else(|e| fail(e, cli.color))
+                    } else {
+                        Commentary::default()
+                    };
+                    let notes = notes.for_canto(cantica, number);
+                    // Whole-canto notes come first, the rest after their passage's last line
+                    let mut notes = notes.iter().peekable();
+                    while let Some(note) = notes.next_if(|note| note.passage.lines.is_none()) {
+                        write_note(&mut output, note, &styler, width)?;
+                    }
+                    let notes: Vec<_> = notes.collect();
+                    let apparatus = if variants {
+                        Apparatus::load().unwrap_or_else(|e| fail(e, cli.color))
+                    } else {
+                        Apparatus::default()
+                    };
+
+                    let glossary = Glossary::builtin();
+                    let found = highlight.map(|term| canto.find(&term)).unwrap_or_default();
+                    for &(i, verse) in &shown {
+                        let glossed = glossary.glossed_words(&verse.text);
+                        let (mut gutter, marks) = canto_line(verse, around, glossed, found.get(i));
+                        if cli.accessible {
+                            gutter = accessible::line_label(verse.line_number, around);
+                        }
+                        write_wrapped(
+                            &mut output,
+                            &styler.line_number(&gutter),
+                            text::display_width(&gutter),
+                            &verse.text,
+                            &marks,
+                            &styler,
+                            width,
+                        )?;
+                        for variant in apparatus.for_line(cantica, number, verse.line_number) {
+                            let text = format!("[{}] {}", variant.source, variant);
+                            write_aside(&mut output, &text, &styler, width)?;
+                        }
+                        let alluding = if allusions {
+                            allusions::for_line(
+                                allusions::builtin(),
+                                cantica,
+                                number,
+                                verse.line_number,
+                            )
+                        } else {
+                            Vec::new()
+                        };
+                        for allusion in alluding {
+                            let text = format!("[{}] {}", allusion.reference, allusion.note);
+                            write_aside(&mut output, &text, &styler, width)?;
+                        }
+                        for note in notes.iter().filter(|note| {
+                            note.passage
+                                .lines
+                                .as_ref()
+                                .is_some_and(|lines| *lines.end() == verse.line_number)
+                        }) {
+                            write_note(&mut output, note, &styler, width)?;
+                        }
+                    }
+                }
+
+                pager::print(&output, cli.no_pager)?;
+            } else {
+                let error = DucaError::CantoNotFound {
+                    cantica,
+                    canto: number,
+                };
+                fail(error, cli.color);
+            }
         }
-    }
 
-    Ok(())
-}
+        Commands::Verify => {
+            let commedia = commedia()?;
+            let deviations = verify::verify(commedia);
+            let styler = Styler::stdout(cli.color);
+            for deviation in &deviations {
+                println!("{}", styler.error(&deviation.to_string()));
+            }
+            if !deviations.is_empty() {
+                eprintln!(
+                    "{}",
+                    Styler::stderr(cli.color).error(&tr(
+                        Text::EditionFailed,
+                        &[&editions::selected(), &deviations.len()]
+                    ))
+                );
+                std::process::exit(1);
+            }
+            println!(
+                "{}",
+                tr(
+                    Text::EditionVerified,
+                    &[
+                        &editions::selected(),
+                        &commedia.cantos().count(),
+                        &verify::TOTAL_LINES
+                    ]
+                )
+            );
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_roman_to_arabic() {
-        assert_eq!(roman_to_arabic("I"), 1);
-        assert_eq!(roman_to_arabic("II"), 2);
-        assert_eq!(roman_to_arabic("III"), 3);
-        assert_eq!(roman_to_arabic("IV"), 4);
-        assert_eq!(roman_to_arabic("V"), 5);
-        assert_eq!(roman_to_arabic("IX"), 9);
-        assert_eq!(roman_to_arabic("X"), 10);
-        assert_eq!(roman_to_arabic("XIV"), 14);
-        assert_eq!(roman_to_arabic("XIX"), 19);
-        assert_eq!(roman_to_arabic("XX"), 20);
-        assert_eq!(roman_to_arabic("XXXIII"), 33);
-        assert_eq!(roman_to_arabic("XXXIV"), 34);
-    }
+        Commands::Schema { command } => {
+            let schema = output::schema(command.into());
+            let json = serde_json::to_string_pretty(&schema)? + "\n";
+            pager::print(&json, true)?;
+        }
 
-    #[test]
-    fn test_roman_to_number() {
-        assert_eq!(roman_to_number(1), "I");
-        assert_eq!(roman_to_number(2), "II");
-        assert_eq!(roman_to_number(3), "III");
-        assert_eq!(roman_to_number(4), "IV");
-        assert_eq!(roman_to_number(5), "V");
-        assert_eq!(roman_to_number(9), "IX");
-        assert_eq!(roman_to_number(10), "X");
-        assert_eq!(roman_to_number(14), "XIV");
-        assert_eq!(roman_to_number(19), "XIX");
-        assert_eq!(roman_to_number(20), "XX");
-        assert_eq!(roman_to_number(33), "XXXIII");
-        assert_eq!(roman_to_number(34), "XXXIV");
-    }
+        Commands::Editions => {
+            for name in editions::available() {
+                let mut line = name.clone();
+                if name == editions::DEFAULT {
+                    line.push_str(" (built in)");
+                }
+                if name == editions::selected() {
+                    line.push_str(" *");
+                }
+                println!("{}", line);
+            }
+        }
 
-    #[test]
-    fn test_divina_commedia_new() {
-        let commedia = DivinaCommedia::new();
-        assert_eq!(commedia.inferno.name, "Inferno");
-        assert_eq!(commedia.purgatorio.name, "Purgatorio");
-        assert_eq!(commedia.paradiso.name, "Paradiso");
-        assert!(commedia.inferno.cantos.is_empty());
-        assert!(commedia.purgatorio.cantos.is_empty());
-        assert!(commedia.paradiso.cantos.is_empty());
-    }
+        Commands::Works { action } => {
+            let styler = Styler::stdout(cli.color);
+            match action {
+                WorksAction::List => {
+                    let installed = install::installed();
+                    let origin = |name: &str, kind: install::Kind| match installed
+                        .iter()
+                        .find(|i| i.name == name && i.kind == kind)
+                    {
+                        Some(i) => i.origin.as_deref().unwrap_or("added by hand"),
+                        None => "built in",
+                    };
+                    println!("{}", styler.header("Works"));
+                    for name in works::available() {
+                        let origin = origin(&name, install::Kind::Work);
+                        println!("  {} {}", text::pad_end(&name, 16), styler.note(origin));
+                    }
+                    println!("{}", styler.header("Editions of the Commedia"));
+                    for name in editions::available() {
+                        let origin = origin(&name, install::Kind::Edition);
+                        println!("  {} {}", text::pad_end(&name, 16), styler.note(origin));
+                    }
+                }
+                WorksAction::Add { path, name, force } => {
+                    let installed = install::add(&path, name.as_deref(), force)
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    println!(
+                        "Installed {} '{}' in {}",
+                        installed.kind,
+                        installed.name,
+                        installed.dir.display()
+                    );
+                }
+                WorksAction::Remove { name } => {
+                    let removed = install::remove(&name).unwrap_or_else(|e| fail(e, cli.color));
+                    println!("Removed {} '{}'", removed.kind, removed.name);
+                }
+                WorksAction::Info { name } => {
+                    let installed = install::find(&name);
+                    let is_edition = match &installed {
+                        Some(installed) => installed.kind == install::Kind::Edition,
+                        None => editions::available().contains(&name),
+                    };
+                    let work = if is_edition {
+                        editions::load(&name).map(|commedia| Work::commedia(&commedia))
+                    } else {
+                        works::load(&name)
+                    };
+                    let work = work.unwrap_or_else(|e| fail(e, cli.color));
+
+                    let kind = if is_edition {
+                        "edition of the Commedia"
+                    } else {
+                        "work"
+                    };
+                    println!("{} ({})", styler.header(&name), kind);
+                    if !is_edition {
+                        println!("Title:    {}", work.title);
+                        if !work.author.is_empty() {
+                            println!("Author:   {}", work.author);
+                        }
+                    }
+                    let parts: Vec<&str> = work.parts.iter().map(|p| p.name.as_str()).collect();
+                    println!("Parts:    {}", parts.join(", "));
+                    println!("Sections: {}", work.section_count());
+                    println!("Lines:    {}", work.line_count());
+                    match installed {
+                        Some(installed) => {
+                            let checksum =
+                                installed.checksum().unwrap_or_else(|e| fail(e, cli.color));
+                            let origin = installed.origin.as_deref().unwrap_or("unknown");
+                            println!("Origin:   {}", origin);
+                            println!("Path:     {}", installed.dir.display());
+                            print!("SHA-256:  {}", checksum);
+                            match installed.recorded_checksum {
+                                Some(recorded) if recorded != checksum => {
+                                    println!(" {}", styler.error("(changed since it was added)"))
+                                }
+                                _ => println!(),
+                            }
+                        }
+                        None => println!("Origin:   built in"),
+                    }
+                }
+            }
+        }
 
-    #[test]
-    fn test_search_functionality() {
-        let mut commedia = DivinaCommedia::new();
-
-        // Add test data
-        let canto = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![
-                Verse {
-                    line_number: 1,
-                    text: "Nel mezzo del cammin di nostra vita".to_string(),
-                },
-                Verse {
-                    line_number: 2,
-                    text: "mi ritrovai per una selva oscura".to_string(),
+        Commands::Gloss { word } => {
+            let glossary = Glossary::builtin();
+            let styler = Styler::stdout(cli.color);
+            let mut output = String::new();
+            match word {
+                Some(word) => match glossary.lookup(&word) {
+                    Some(entry) => write_gloss(&mut output, entry, &styler)?,
+                    None => writeln!(output, "No gloss for '{}'", word)?,
                 },
-                Verse {
-                    line_number: 3,
-                    text: "ché la diritta via era smarrita".to_string(),
-                },
-            ],
-        };
-        commedia.inferno.cantos.insert(1, canto);
-
-        // Test search
-        let results = commedia.search("selva", None);
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].0, "Inferno");
-        assert_eq!(results[0].1, 1);
-        assert_eq!(results[0].2, 2);
-        assert!(results[0].3.contains("selva"));
-
-        // Test case insensitive search
-        let results = commedia.search("SELVA", None);
-        assert_eq!(results.len(), 1);
-
-        // Test no matches
-        let results = commedia.search("nonexistent", None);
-        assert_eq!(results.len(), 0);
-
-        // Test cantica filter
-        let results = commedia.search("selva", Some("purgatorio"));
-        assert_eq!(results.len(), 0);
-
-        let results = commedia.search("selva", Some("inferno"));
-        assert_eq!(results.len(), 1);
-    }
+                None => {
+                    for entry in glossary.iter() {
+                        write_gloss(&mut output, entry, &styler)?;
+                    }
+                }
+            }
+            pager::print(&output, cli.no_pager)?;
+        }
 
-    #[test]
-    fn test_parse_cantica_content() {
-        let sample_text = r#"
-Some header text
-*** START OF THE PROJECT GUTENBERG EBOOK ***
+        Commands::Latin { cantica } => {
+            let commedia = commedia()?;
+            let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+                Ok(cantica) => cantica,
+                Err(e) => fail(e, cli.color),
+            };
 
-Canto I
+            let styler = Styler::stdout(cli.color);
+            let width = output_width(cli.accessible);
+            let mut output = String::new();
+            for v in commedia
+                .latin_verses()
+                .filter(|v| cantica.is_none_or(|id| v.cantica == id))
+            {
+                let location =
+                    format!("{} {}.{}: ", v.cantica, v.canto.number, v.verse.line_number);
+                let marks: Vec<_> = latin::latin_ranges(&v.verse.text)
+                    .into_iter()
+                    .map(|range| (range, Mark::Latin))
+                    .collect();
+                write_wrapped(
+                    &mut output,
+                    &styler.location(&location),
+                    text::display_width(&location),
+                    &v.verse.text,
+                    &marks,
+                    &styler,
+                    width,
+                )?;
+            }
+            pager::print(&output, cli.no_pager)?;
+        }
 
-Nel mezzo del cammin di nostra vita
-mi ritrovai per una selva oscura
-ché la diritta via era smarrita.
+        Commands::Speeches { cantica } => {
+            let commedia = commedia()?;
+            let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+                Ok(cantica) => cantica,
+                Err(e) => fail(e, cli.color),
+            };
 
-Canto II
+            let styler = Styler::stdout(cli.color);
+            let width = output_width(cli.accessible);
+            let mut output = String::new();
+            for (id, canto) in commedia
+                .cantos()
+                .filter(|(id, _)| cantica.is_none_or(|cantica| *id == cantica))
+            {
+                for speech in canto.direct_speech() {
+                    let passage = Passage {
+                        cantica: id,
+                        canto: canto.number,
+                        lines: Some(speech.lines.clone()),
+                    };
+                    let heading = match speech.speaker {
+                        Some(speaker) => format!("{passage} ({speaker})"),
+                        None => passage.to_string(),
+                    };
+                    writeln!(output, "{}", styler.location(&heading))?;
+                    for verse in canto
+                        .verses
+                        .iter()
+                        .filter(|v| speech.lines.contains(&v.line_number))
+                    {
+                        let gutter = text::gutter(verse.line_number);
+                        write_wrapped(
+                            &mut output,
+                            &styler.line_number(&gutter),
+                            text::display_width(&gutter),
+                            &verse.text,
+                            &[],
+                            &styler,
+                            width,
+                        )?;
+                    }
+                    writeln!(output)?;
+                }
+            }
+            pager::print(&output, cli.no_pager)?;
+        }
 
-Per me si va ne la città dolente,
-per me si va ne l'etterno dolore,
-per me si va tra la perduta gente.
+        Commands::Xref { cantica, number } => {
+            let cantica = match cantica.parse::<CanticaId>() {
+                Ok(id) => id,
+                Err(e) => fail(e, cli.color),
+            };
+            if let Err(e) = commedia()?.require_canto(cantica, number) {
+                fail(e, cli.color);
+            }
 
-Updated editions will replace the previous one
-This should be ignored
-"#;
+            let related = xrefs::for_canto(xrefs::builtin(), cantica, number);
+            if related.is_empty() {
+                let canto = format!("{} {}", cantica, number);
+                println!("{}", tr(Text::NoCrossReferences, &[&canto]));
+                return Ok(());
+            }
+            let styler = Styler::stdout(cli.color);
+            let width = output_width(cli.accessible);
+            let mut output = String::new();
+            for r in related {
+                let heading = format!("{} → {} ({})", r.here, r.there, r.kind);
+                writeln!(output, "{}", styler.location(&heading))?;
+                write_wrapped(&mut output, "  ", 2, r.note, &[], &styler, width)?;
+                writeln!(output)?;
+            }
+            pager::print(&output, cli.no_pager)?;
+        }
 
-        let mut commedia = DivinaCommedia::new();
-        let result = parse_cantica_content(sample_text, "inferno", &mut commedia);
+        Commands::Allusions { cantica } => {
+            let commedia = commedia()?;
+            let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+                Ok(cantica) => cantica,
+                Err(e) => fail(e, cli.color),
+            };
 
-        assert!(result.is_ok());
-        assert_eq!(commedia.inferno.cantos.len(), 2);
+            let styler = Styler::stdout(cli.color);
+            let width = output_width(cli.accessible);
+            let mut output = String::new();
+            for allusion in allusions::builtin()
+                .iter()
+                .filter(|a| cantica.is_none_or(|cantica| a.passage.cantica == cantica))
+            {
+                let passage = &allusion.passage;
+                let heading = format!("{}: {}", passage, allusion.reference);
+                writeln!(output, "{}", styler.location(&heading))?;
+                let Some(canto) = commedia.canto(passage.cantica, passage.canto) else {
+                    continue;
+                };
+                for verse in canto
+                    .verses
+                    .iter()
+                    .filter(|v| passage.contains(passage.cantica, passage.canto, v.line_number))
+                {
+                    let gutter = text::gutter(verse.line_number);
+                    write_wrapped(
+                        &mut output,
+                        &styler.line_number(&gutter),
+                        text::display_width(&gutter),
+                        &verse.text,
+                        &[],
+                        &styler,
+                        width,
+                    )?;
+                }
+                write_aside(&mut output, &allusion.note, &styler, width)?;
+                writeln!(output)?;
+            }
+            pager::print(&output, cli.no_pager)?;
+        }
 
-        let canto1 = commedia.inferno.cantos.get(&1).unwrap();
-        assert_eq!(canto1.number, 1);
-        assert_eq!(canto1.roman_numeral, "I");
-        assert_eq!(canto1.verses.len(), 3);
-        assert!(canto1.verses[0].text.contains("Nel mezzo"));
+        Commands::Graph { graph } => match graph {
+            GraphKind::Characters { format } => {
+                let graph = CharacterGraph::new(commedia()?);
+                match format {
+                    GraphFormat::Dot => print!("{}", graph.dot()),
+                    GraphFormat::Gexf => print!("{}", graph.gexf()),
+                }
+            }
+        },
+
+        Commands::CompareFreq { terms } => {
+            let commedia = commedia()?;
+            let frequencies = stats::frequencies(commedia, &terms);
+            let styler = Styler::stdout(cli.color);
+            let mut output = String::new();
+            write_frequencies(&mut output, &terms, &frequencies, &styler)?;
+            pager::print(&output, cli.no_pager)?;
+        }
 
-        let canto2 = commedia.inferno.cantos.get(&2).unwrap();
-        assert_eq!(canto2.number, 2);
-        assert_eq!(canto2.roman_numeral, "II");
-        assert_eq!(canto2.verses.len(), 3);
-        assert!(canto2.verses[0].text.contains("Per me si va"));
-    }
+        Commands::OpenCitation { citation, context } => {
+            let commedia = commedia()?;
+            let styler = Styler::stdout(cli.color);
 
-    #[test]
-    fn test_verse_and_canto_structures() {
-        let verse = Verse {
-            line_number: 42,
-            text: "Test verse text".to_string(),
-        };
-        assert_eq!(verse.line_number, 42);
-        assert_eq!(verse.text, "Test verse text");
-
-        let canto = Canto {
-            number: 5,
-            roman_numeral: "V".to_string(),
-            verses: vec![verse],
-        };
-        assert_eq!(canto.number, 5);
-        assert_eq!(canto.roman_numeral, "V");
-        assert_eq!(canto.verses.len(), 1);
-    }
+            let citation: Citation = match citation.parse() {
+                Ok(citation) => citation,
+                Err(e) => fail(e, cli.color),
+            };
+            let canto = match commedia.require_canto(citation.cantica, citation.canto) {
+                Ok(canto) => canto,
+                Err(e) => fail(e, cli.color),
+            };
 
-    #[test]
-    fn test_regex_patterns() {
-        let canto_regex = regex::Regex::new(r"^Canto\s+([IVXLCDM]+)\.?$").unwrap();
+            let mut output = String::new();
+            writeln!(
+                output,
+                "{}\n",
+                styler.header(&format!(
+                    "{} Canto {}",
+                    citation.cantica, canto.roman_numeral
+                ))
+            )?;
+            let first = citation.line.saturating_sub(context);
+            let last = citation.line.saturating_add(context);
+            for verse in canto
+                .verses
+                .iter()
+                .filter(|v| v.line_number >= first && v.line_number <= last)
+            {
+                if verse.line_number == citation.line {
+                    writeln!(
+                        output,
+                        "> {} {}",
+                        styler.line_number(&format!("{:3}:", verse.line_number)),
+                        styler.highlight(&verse.text)
+                    )?;
+                } else {
+                    writeln!(
+                        output,
+                        "  {} {}",
+                        styler.line_number(&format!("{:3}:", verse.line_number)),
+                        verse.text
+                    )?;
+                }
+            }
 
-        assert!(canto_regex.is_match("Canto I"));
-        assert!(canto_regex.is_match("Canto II"));
-        assert!(canto_regex.is_match("Canto XXXIII"));
-        assert!(canto_regex.is_match("Canto XIV."));
+            pager::print(&output, cli.no_pager)?;
+        }
 
-        assert!(!canto_regex.is_match("canto i"));
-        assert!(!canto_regex.is_match("Canto 1"));
-        assert!(!canto_regex.is_match("Cantoi"));
-        assert!(!canto_regex.is_match("Some other text"));
-    }
+        Commands::Tercet { citation, plain } => {
+            let commedia = commedia()?;
+            let styler = Styler::stdout(cli.color);
 
-    #[test]
-    fn test_gutenberg_marker_detection() {
-        let test_lines = vec![
-            "Normal verse text",
-            "Updated editions will replace the previous one",
-            "This should not be parsed",
-        ];
+            let citation: Citation = match citation.parse() {
+                Ok(citation) => citation,
+                Err(e) => fail(e, cli.color),
+            };
+            let canto = match commedia.require_canto(citation.cantica, citation.canto) {
+                Ok(canto) => canto,
+                Err(e) => fail(e, cli.color),
+            };
+            let Some(tercet) = canto.tercet_at(citation.line) else {
+                fail(
+                    DucaError::InvalidCitation {
+                        input: format!("{} {}.{}", citation.cantica, citation.canto, citation.line),
+                        reason: format!("the canto has {} lines", canto.verses.len()),
+                    },
+                    cli.color,
+                )
+            };
 
-        // Simulate the parsing loop logic
-        let mut should_continue = true;
-        for line in test_lines {
-            if line.starts_with("Updated editions will replace") {
-                should_continue = false;
-                break;
+            let mut output = String::new();
+            if plain {
+                for verse in tercet {
+                    writeln!(output, "{}", verse.text)?;
+                }
+            } else {
+                writeln!(
+                    output,
+                    "{}\n",
+                    styler.header(&format!(
+                        "{} Canto {}",
+                        citation.cantica, canto.roman_numeral
+                    ))
+                )?;
+                for verse in tercet {
+                    let text = if verse.line_number == citation.line {
+                        styler.highlight(&verse.text)
+                    } else {
+                        verse.text.clone()
+                    };
+                    let gutter = text::gutter(verse.line_number);
+                    writeln!(output, "{}{}", styler.line_number(&gutter), text)?;
+                }
             }
+
+            pager::print(&output, cli.no_pager)?;
         }
 
-        assert!(!should_continue);
-    }
+        Commands::ReadAloud {
+            cantica,
+            number,
+            from,
+            to,
+            pause,
+        } => {
+            let cantica = match cantica.parse::<CanticaId>() {
+                Ok(id) => id,
+                Err(e) => fail(e, cli.color),
+            };
+            let canto = commedia()?
+                .require_canto(cantica, number)
+                .unwrap_or_else(|e| fail(e, cli.color));
+            let first = from.unwrap_or(1);
+            let last = to.unwrap_or(canto.verses.len());
+            let reason = if first > last {
+                Some("range ends before it starts".to_string())
+            } else if canto.verse(first).is_none() || canto.verse(last).is_none() {
+                Some(format!("the canto has lines 1 to {}", canto.verses.len()))
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                let input = format!("{} {}.{}-{}", cantica, number, first, last);
+                fail(DucaError::InvalidCitation { input, reason }, cli.color);
+            }
 
-    #[test]
-    fn test_load_commedia() {
-        // Test that load_commedia works with embedded data
-        let result = load_commedia();
-        assert!(result.is_ok());
-
-        let commedia = result.unwrap();
-        assert_eq!(commedia.inferno.name, "Inferno");
-        assert_eq!(commedia.purgatorio.name, "Purgatorio");
-        assert_eq!(commedia.paradiso.name, "Paradiso");
-
-        // Should have the expected number of cantos
-        assert!(commedia.inferno.cantos.len() > 30); // Expecting 34
-        assert!(commedia.purgatorio.cantos.len() > 30); // Expecting 33
-        assert!(commedia.paradiso.cantos.len() > 30); // Expecting 33
-    }
+            let command = speech::command();
+            let styler = Styler::stdout(cli.color);
+            let mut stdout = io::stdout();
+            for verse in &canto.verses[first - 1..last] {
+                println!(
+                    "{}{}",
+                    styler.line_number(&text::gutter(verse.line_number)),
+                    verse.text
+                );
+                io::Write::flush(&mut stdout)?;
+                if let Err(e) = speech::speak(&command, &verse.text, Stdio::inherit()) {
+                    let message = format!("text-to-speech failed: {}; set $DUCA_TTS_CMD", e);
+                    fail_with(&message, cli.color);
+                }
+                std::thread::sleep(Duration::from_millis(pause));
+            }
+        }
 
-    #[test]
-    fn test_search_results_ordering() {
-        let mut commedia = DivinaCommedia::new();
-
-        // Add test data with specific ordering to verify sorting
-        // Canto 3 comes before Canto 1 in creation order to test sorting
-        let canto3 = Canto {
-            number: 3,
-            roman_numeral: "III".to_string(),
-            verses: vec![
-                Verse {
-                    line_number: 1,
-                    text: "test third canto first verse".to_string(),
-                },
-                Verse {
-                    line_number: 5,
-                    text: "test third canto fifth verse".to_string(),
-                },
-            ],
-        };
-        commedia.inferno.cantos.insert(3, canto3);
-
-        let canto1 = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![
-                Verse {
-                    line_number: 2,
-                    text: "test first canto second verse".to_string(),
-                },
-                Verse {
-                    line_number: 1,
-                    text: "test first canto first verse".to_string(),
-                },
-            ],
-        };
-        commedia.inferno.cantos.insert(1, canto1);
-
-        let canto2 = Canto {
-            number: 2,
-            roman_numeral: "II".to_string(),
-            verses: vec![Verse {
-                line_number: 1,
-                text: "test second canto first verse".to_string(),
-            }],
-        };
-        commedia.inferno.cantos.insert(2, canto2);
-
-        // Search for "test" which should match all verses
-        let results = commedia.search("test", None);
-
-        // Results should be ordered by canto number, then by line number
-        assert_eq!(results.len(), 5);
-
-        // Check ordering: should be sorted by (cantica, canto, line)
-        assert_eq!(
-            results[0],
-            (
-                "Inferno".to_string(),
-                1,
-                1,
-                "test first canto first verse".to_string()
-            )
-        );
-        assert_eq!(
-            results[1],
-            (
-                "Inferno".to_string(),
-                1,
-                2,
-                "test first canto second verse".to_string()
-            )
-        );
-        assert_eq!(
-            results[2],
-            (
-                "Inferno".to_string(),
-                2,
-                1,
-                "test second canto first verse".to_string()
-            )
-        );
-        assert_eq!(
-            results[3],
-            (
-                "Inferno".to_string(),
-                3,
-                1,
-                "test third canto first verse".to_string()
-            )
-        );
-        assert_eq!(
-            results[4],
-            (
-                "Inferno".to_string(),
-                3,
-                5,
-                "test third canto fifth verse".to_string()
-            )
-        );
-    }
+        Commands::OpenWeb {
+            cantica,
+            number,
+            line,
+            site,
+            print,
+        } => {
+            let cantica = match cantica.parse::<CanticaId>() {
+                Ok(id) => id,
+                Err(e) => fail(e, cli.color),
+            };
+            if let Err(e) = commedia()?.require_canto(cantica, number) {
+                fail(e, cli.color);
+            }
 
-    #[test]
-    fn test_search_results_cross_cantica_ordering() {
-        let mut commedia = DivinaCommedia::new();
-
-        // Add test data across multiple canticas to verify cross-cantica sorting
-        let paradiso_canto1 = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![Verse {
-                line_number: 1,
-                text: "test paradiso canto one".to_string(),
-            }],
-        };
-        commedia.paradiso.cantos.insert(1, paradiso_canto1);
-
-        let inferno_canto2 = Canto {
-            number: 2,
-            roman_numeral: "II".to_string(),
-            verses: vec![Verse {
-                line_number: 1,
-                text: "test inferno canto two".to_string(),
-            }],
-        };
-        commedia.inferno.cantos.insert(2, inferno_canto2);
-
-        let purgatorio_canto1 = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![
-                Verse {
-                    line_number: 3,
-                    text: "test purgatorio canto one".to_string(),
-                },
-                Verse {
-                    line_number: 1,
-                    text: "test purgatorio canto one first".to_string(),
-                },
-            ],
-        };
-        commedia.purgatorio.cantos.insert(1, purgatorio_canto1);
-
-        let inferno_canto1 = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![Verse {
-                line_number: 2,
-                text: "test inferno canto one".to_string(),
-            }],
-        };
-        commedia.inferno.cantos.insert(1, inferno_canto1);
-
-        // Search for "test" which should match all verses
-        let results = commedia.search("test", None);
-
-        assert_eq!(results.len(), 5);
-
-        // Results should be ordered: Inferno (1.2, 2.1), Purgatorio (1.1, 1.3), Paradiso (1.1)
-        assert_eq!(
-            results[0],
-            (
-                "Inferno".to_string(),
-                1,
-                2,
-                "test inferno canto one".to_string()
-            )
-        );
-        assert_eq!(
-            results[1],
-            (
-                "Inferno".to_string(),
-                2,
-                1,
-                "test inferno canto two".to_string()
-            )
-        );
-        assert_eq!(
-            results[2],
-            (
-                "Purgatorio".to_string(),
-                1,
-                1,
-                "test purgatorio canto one first".to_string()
-            )
-        );
-        assert_eq!(
-            results[3],
-            (
-                "Purgatorio".to_string(),
-                1,
-                3,
-                "test purgatorio canto one".to_string()
-            )
-        );
-        assert_eq!(
-            results[4],
-            (
-                "Paradiso".to_string(),
-                1,
-                1,
-                "test paradiso canto one".to_string()
-            )
-        );
+            let url = links::url(&links::template(site), cantica, number, line);
+            if print {
+                println!("{}", url);
+            } else if let Err(e) = browser::open_url(&url) {
+                eprintln!("Could not open a browser: {}", e);
+                println!("{}", url);
+            }
+        }
+
+        Commands::Today {
+            date,
+            start,
+            notify,
+        } => {
+            let (plan, date) = reading_plan(start, date, cli.color);
+            match plan.day(date) {
+                None => println!("{}", tr(Text::TodayNotStarted, &[&plan.start])),
+                Some(day) => {
+                    let (cantica, number) = plan::canto(day);
+                    let canto = commedia()?
+                        .require_canto(cantica, number)
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    let summary = tr(
+                        Text::TodayCanto,
+                        &[&day, &plan::DAYS, &cantica, &canto.roman_numeral],
+                    );
+                    if notify {
+                        if let Err(e) = notify::send(&summary, &canto.incipit) {
+                            let message =
+                                format!("notification failed: {}; set $DUCA_NOTIFY_CMD", e);
+                            fail_with(&message, cli.color);
+                        }
+                    } else {
+                        let styler = Styler::stdout(cli.color);
+                        println!("{}\n{}", styler.header(&summary), canto.incipit);
+                    }
+                }
+            }
+        }
+
+        Commands::Plan {
+            start,
+            date,
+            rss,
+            link,
+        } => {
+            let (plan, date) = reading_plan(start, date, cli.color);
+            let commedia = commedia()?;
+            if let Some(path) = rss {
+                let feed =
+                    feed::rss(commedia, &plan, date, &link).unwrap_or_else(|e| fail(e, cli.color));
+                fs::write(&path, feed)?;
+                println!("Wrote {}", path.display());
+            } else {
+                let styler = Styler::stdout(cli.color);
+                let round = plan.round_start(date);
+                let mut output = String::new();
+                for day in 1..=plan::DAYS {
+                    let (cantica, number) = plan::canto(day);
+                    let canto = commedia
+                        .require_canto(cantica, number)
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    let heading = format!("{} {}", cantica, canto.roman_numeral);
+                    let row = format!(
+                        "{}  {:3}  {}  {}",
+                        round.add_days(day as i64 - 1),
+                        day,
+                        text::pad_end(&heading, 16),
+                        canto.incipit
+                    );
+                    if plan.day(date) == Some(day) {
+                        writeln!(output, "{}", styler.highlight(&row))?;
+                    } else {
+                        writeln!(output, "{}", row)?;
+                    }
+                }
+                pager::print(&output, cli.no_pager)?;
+            }
+        }
+
+        Commands::Note { action } => {
+            let store = open_store(cli.color);
+            match action {
+                NoteAction::Add { passage, text } => {
+                    let (passage, lines) = passage_lines(&passage, cli.color)?;
+                    store
+                        .update(passage.cantica, passage.canto, |notes| {
+                            notes.add_note(lines, &text)
+                        })
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    let file = store.file(passage.cantica, passage.canto);
+                    println!("Noted {} in {}", passage, file.display());
+                }
+                NoteAction::List { passage } => {
+                    let filter: Option<Passage> = match passage.as_deref().map(str::parse) {
+                        Some(Ok(passage)) => Some(passage),
+                        Some(Err(e)) => fail(e, cli.color),
+                        None => None,
+                    };
+                    let styler = Styler::stdout(cli.color);
+                    let mut output = String::new();
+                    for canto in store.all().unwrap_or_else(|e| fail(e, cli.color)) {
+                        for note in &canto.notes {
+                            let shown = filter.as_ref().is_none_or(|filter| {
+                                note.lines
+                                    .clone()
+                                    .any(|line| filter.contains(canto.cantica, canto.canto, line))
+                            });
+                            if !shown {
+                                continue;
+                            }
+                            let noted = Passage {
+                                cantica: canto.cantica,
+                                canto: canto.canto,
+                                lines: Some(note.lines.clone()),
+                            };
+                            writeln!(output, "{}", styler.location(&noted.to_string()))?;
+                            for line in note.text.lines() {
+                                writeln!(output, "  {}", line)?;
+                            }
+                            writeln!(output)?;
+                        }
+                    }
+                    if output.is_empty() {
+                        println!("No notes");
+                    } else {
+                        pager::print(&output, cli.no_pager)?;
+                    }
+                }
+                NoteAction::Remove { passage } => {
+                    let (passage, lines) = passage_lines(&passage, cli.color)?;
+                    let removed = store
+                        .update(passage.cantica, passage.canto, |notes| {
+                            notes.remove_notes(&lines)
+                        })
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    if removed == 0 {
+                        fail_with(&format!("No notes on {}", passage), cli.color);
+                    }
+                    println!("Removed {} note(s) on {}", removed, passage);
+                }
+                #[cfg(feature = "encryption")]
+                NoteAction::Encrypt => {
+                    if store.is_encrypted() {
+                        fail_with("Your notes are already encrypted", cli.color);
+                    }
+                    let passphrase = read_passphrase("New passphrase for your notes: ", cli.color);
+                    if passphrase.is_empty() {
+                        fail_with("The passphrase is empty", cli.color);
+                    }
+                    if std::env::var_os("DUCA_NOTES_PASSPHRASE").is_none()
+                        && read_passphrase("Repeat it: ", cli.color) != passphrase
+                    {
+                        fail_with("The passphrases differ", cli.color);
+                    }
+                    let mut store = store;
+                    let count = store
+                        .encrypt(&passphrase)
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    println!(
+                        "Encrypted {} canto file(s) in {}",
+                        count,
+                        store.dir().display()
+                    );
+                }
+                #[cfg(feature = "encryption")]
+                NoteAction::Decrypt => {
+                    if !store.is_encrypted() {
+                        fail_with("Your notes aren't encrypted", cli.color);
+                    }
+                    let mut store = store;
+                    let count = store.decrypt().unwrap_or_else(|e| fail(e, cli.color));
+                    println!(
+                        "Decrypted {} canto file(s) in {}",
+                        count,
+                        store.dir().display()
+                    );
+                }
+            }
+        }
+
+        Commands::Bookmark { action } => {
+            let store = open_store(cli.color);
+            let one_line = |citation: &str| -> Result<(Passage, usize)> {
+                let (passage, lines) = passage_lines(citation, cli.color)?;
+                if lines.start() != lines.end() {
+                    let input = citation.to_string();
+                    let reason = "a bookmark is on a single line".to_string();
+                    fail(DucaError::InvalidCitation { input, reason }, cli.color);
+                }
+                Ok((passage, *lines.start()))
+            };
+            let valid_tags = |tags: &[String]| -> Vec<String> {
+                tags.iter()
+                    .map(|tag| {
+                        notes::tag(tag).unwrap_or_else(|| {
+                            let reason = "a tag is a letter followed by letters, digits, - or _";
+                            fail_with(&format!("Invalid tag '{}': {}", tag, reason), cli.color)
+                        })
+                    })
+                    .collect()
+            };
+            match action {
+                BookmarkAction::Add {
+                    citation,
+                    label,
+                    tags,
+                } => {
+                    let (passage, line) = one_line(&citation)?;
+                    let tags = valid_tags(&tags);
+                    store
+                        .update(passage.cantica, passage.canto, |notes| {
+                            // Without --label, a bookmark already there keeps its own
+                            if label.is_some() || !notes.bookmarks.iter().any(|b| b.line == line) {
+                                notes.set_bookmark(line, label.as_deref());
+                            }
+                            notes.tag_bookmark(line, &tags)
+                        })
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    println!("Bookmarked {}", passage);
+                }
+                BookmarkAction::List { tags } => {
+                    let tags = valid_tags(&tags);
+                    let commedia = commedia()?;
+                    let styler = Styler::stdout(cli.color);
+                    let mut output = String::new();
+                    for canto in store.all().unwrap_or_else(|e| fail(e, cli.color)) {
+                        for bookmark in canto.bookmarks.iter().filter(|b| b.has_tags(&tags)) {
+                            let location =
+                                format!("{} {}.{}", canto.cantica, canto.canto, bookmark.line);
+                            let text = match &bookmark.label {
+                                Some(label) => label.clone(),
+                                None => commedia
+                                    .verse(canto.cantica, canto.canto, bookmark.line)
+                                    .map_or_else(String::new, |verse| styler.note(&verse.text)),
+                            };
+                            write!(
+                                output,
+                                "{} {}",
+                                styler.location(&text::pad_end(&location, 18)),
+                                text
+                            )?;
+                            for tag in &bookmark.tags {
+                                write!(output, " {}", styler.note(&format!("#{}", tag)))?;
+                            }
+                            writeln!(output)?;
+                        }
+                    }
+                    if output.is_empty() && !tags.is_empty() {
+                        println!("No bookmarks tagged #{}", tags.join(" #"));
+                    } else if output.is_empty() {
+                        println!("No bookmarks");
+                    } else {
+                        pager::print(&output, cli.no_pager)?;
+                    }
+                }
+                BookmarkAction::Remove { citation, tags } if !tags.is_empty() => {
+                    let (passage, line) = one_line(&citation)?;
+                    let tags = valid_tags(&tags);
+                    let removed = store
+                        .update(passage.cantica, passage.canto, |notes| {
+                            notes.untag_bookmark(line, &tags)
+                        })
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    if removed == 0 {
+                        let tags = tags.join(" #");
+                        fail_with(
+                            &format!("No bookmark on {} tagged #{}", passage, tags),
+                            cli.color,
+                        );
+                    }
+                    println!(
+                        "Removed {} tag(s) from the bookmark on {}",
+                        removed, passage
+                    );
+                }
+                BookmarkAction::Remove { citation, .. } => {
+                    let (passage, line) = one_line(&citation)?;
+                    let removed = store
+                        .update(passage.cantica, passage.canto, |notes| {
+                            notes.remove_bookmark(line)
+                        })
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    if !removed {
+                        fail_with(&format!("No bookmark on {}", passage), cli.color);
+                    }
+                    println!("Removed the bookmark on {}", passage);
+                }
+            }
+        }
+
+        Commands::Export { format } => {
+            let store = open_store(cli.color);
+            let cantos = store.all().unwrap_or_else(|e| fail(e, cli.color));
+            let commedia = commedia()?;
+            match format {
+                ExportFormat::Org { out } => {
+                    let org = export::org(commedia, &cantos, |cantica, canto| {
+                        store.changed_on(cantica, canto)
+                    });
+                    match out {
+                        Some(path) => {
+                            fs::write(&path, org)?;
+                            println!(
+                                "Exported the notes and bookmarks of {} canto(s) to {}",
+                                cantos.len(),
+                                path.display()
+                            );
+                        }
+                        None => print!("{}", org),
+                    }
+                }
+                ExportFormat::Obsidian { dir } => {
+                    let files = export::obsidian(commedia, &cantos);
+                    for (path, content) in &files {
+                        let path = dir.join(path);
+                        if let Some(parent) = path.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::write(path, content)?;
+                    }
+                    println!(
+                        "Exported {} canto(s) and the notes of {} to {}",
+                        commedia.cantos().count(),
+                        files.len() - commedia.cantos().count(),
+                        dir.display()
+                    );
+                }
+            }
+        }
+
+        Commands::Data { action } => {
+            let store = open_store(cli.color);
+            match action {
+                DataAction::Export { out } => {
+                    let log = paths::reading_log();
+                    let backup = Backup::from_store(&store, log.as_deref())
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    let json = serde_json::to_string_pretty(&backup)? + "\n";
+                    match out {
+                        Some(path) => {
+                            fs::write(&path, json)?;
+                            println!(
+                                "Exported {} bookmark(s), {} note(s) and {} reading session(s) to {}",
+                                backup.bookmarks.len(),
+                                backup.notes.len(),
+                                backup.reading_log.len(),
+                                path.display()
+                            );
+                        }
+                        None => print!("{}", json),
+                    }
+                }
+                DataAction::Import { file } => {
+                    let name = file.display().to_string();
+                    let content = fs::read_to_string(&file).unwrap_or_else(|e| {
+                        let reason = e.to_string();
+                        fail(
+                            DucaError::UserData {
+                                path: name.clone(),
+                                reason,
+                            },
+                            cli.color,
+                        )
+                    });
+                    let log = paths::reading_log();
+                    let merged = Backup::parse(&content, &name)
+                        .and_then(|backup| backup.merge_into(&store, log.as_deref()))
+                        .unwrap_or_else(|e| fail(e, cli.color));
+                    println!(
+                        "Imported {} bookmark(s), {} label(s), {} tag(s), {} note(s) and {} reading session(s) from {}",
+                        merged.bookmarks, merged.labels, merged.tags, merged.notes, merged.sessions, name
+                    );
+                }
+            }
+        }
+
+        #[cfg(feature = "tui")]
+        Commands::Tui { .. } if cli.accessible => {
+            accessible::read_loop(commedia()?, None, io::stdin().lock(), &mut io::stdout())?;
+        }
+
+        #[cfg(feature = "tui")]
+        Commands::Tui {
+            monochrome,
+            scrolloff,
+            fuzzy_case,
+            fuzzy_threshold,
+            timer,
+            break_every,
+            break_length,
+        } => {
+            let store = NoteStore::open()
+                .ok()
+                .map(|store| unlock_store(store, cli.color));
+            let break_every = session::break_every(break_every);
+            let session = (timer || break_every.is_some()).then(|| {
+                session::Session::new(
+                    break_every,
+                    session::break_length(break_length),
+                    std::time::Instant::now(),
+                )
+            });
+            tui::run_tui(
+                commedia()?,
+                tui::Theme::choose(monochrome, cli.color),
+                tui::scrolloff(scrolloff),
+                tui::FuzzyOptions::choose(fuzzy_case, fuzzy_threshold),
+                store,
+                session,
+            )?;
+        }
+
+        Commands::Read { passage } => {
+            let start = match passage.as_deref().map(str::parse).transpose() {
+                Ok(start) => start,
+                Err(e) => fail(e, cli.color),
+            };
+            accessible::read_loop(commedia()?, start, io::stdin().lock(), &mut io::stdout())?;
+        }
+
+        Commands::Serve { host, port, open } => {
+            #[cfg(feature = "mmap")]
+            if let Some(corpus) = duca::mapped_corpus()? {
+                server::serve_mapped(&corpus, &host, port, open)?;
+                return Ok(());
+            }
+            server::serve(commedia()?, &host, port, open)?;
+        }
+
+        Commands::Rpc => {
+            rpc::run(commedia()?, io::stdin().lock(), io::stdout().lock())?;
+        }
+
+        Commands::Mcp => {
+            mcp::run(commedia()?, io::stdin().lock(), io::stdout().lock())?;
+        }
+
+        #[cfg(feature = "semantic")]
+        Commands::Embed => {
+            let embedder = Embedder::from_env();
+            eprintln!(
+                "Embedding verses with {} at {}",
+                embedder.model, embedder.url
+            );
+            let embeddings = VerseEmbeddings::compute(commedia()?, &embedder, |done, total| {
+                eprint!("\r{}/{} verses", done, total);
+            })
+            .unwrap_or_else(|e| {
+                eprintln!();
+                fail(e, cli.color)
+            });
+            eprintln!();
+            embeddings.save(Path::new(semantic::EMBEDDINGS_FILE))?;
+            println!(
+                "Saved {} embeddings to {}",
+                embeddings.len(),
+                semantic::EMBEDDINGS_FILE
+            );
+        }
+
+        Commands::Completions {
+            shell,
+            dynamic: false,
+        } => {
+            clap_complete::generate(shell, &mut Cli::command(), "duca", &mut io::stdout());
+        }
+
+        Commands::Completions {
+            shell,
+            dynamic: true,
+        } => {
+            let Some(completer) = complete::env_completer(shell) else {
+                fail_with(&format!("No dynamic completions for {}", shell), cli.color);
+            };
+            completer.write_registration(
+                complete::VAR,
+                "duca",
+                "duca",
+                "duca",
+                &mut io::stdout(),
+            )?;
+        }
+
+        Commands::Man { out_dir } => {
+            for path in write_man_pages(&out_dir)? {
+                println!("Wrote {}", path.display());
+            }
+        }
     }
+
+    Ok(())
 }
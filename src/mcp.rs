@@ -0,0 +1,354 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::io::{BufRead, Write as IoWrite};
+
+use crate::rpc::{self, RpcError, INVALID_PARAMS, METHOD_NOT_FOUND};
+use duca::{Cantica, CanticaId, DivinaCommedia, DucaError, Pipeline};
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArgs {
+    pattern: String,
+    cantica: Option<String>,
+    limit: Option<usize>,
+    normalize: Option<String>,
+    #[serde(default)]
+    regex: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CantoArgs {
+    cantica: String,
+    canto: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerseArgs {
+    cantica: String,
+    canto: u8,
+    line: usize,
+    #[serde(default)]
+    context: usize,
+}
+
+/// Run a Model Context Protocol server over stdio, exposing the text as tools.
+pub fn run(commedia: &DivinaCommedia, reader: impl BufRead, writer: impl IoWrite) -> Result<()> {
+    rpc::serve(reader, writer, |method, params| {
+        dispatch(commedia, method, params)
+    })
+}
+
+fn dispatch(commedia: &DivinaCommedia, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "duca", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => {
+            let call: Option<ToolCall> = rpc::parse_params(params)?;
+            let call = call.ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing params"))?;
+            Ok(call_tool(commedia, &call.name, call.arguments))
+        }
+        // Notifications such as `notifications/initialized` need no handling
+        _ if method.starts_with("notifications/") => Ok(Value::Null),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+fn tool_definitions() -> Value {
+    let cantica = json!({
+        "type": "string",
+        "enum": ["inferno", "purgatorio", "paradiso"],
+        "description": "Cantica of the Divine Comedy",
+    });
+
+    json!([
+        {
+            "name": "search_verses",
+            "description": "Search the Italian text of Dante's Divine Comedy for verses matching a pattern, ignoring case and accents unless told otherwise. Returns citations (Cantica canto.line) with the verse text.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Text, or with regex a regular expression, to search for" },
+                    "cantica": cantica,
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of verses to return (default 50)" },
+                    "normalize": { "type": "string", "description": "Comma-separated normalizers to match through: lowercase, fold (accents), orthography (old spellings), lemma (word forms), or none (default lowercase,fold)" },
+                    "regex": { "type": "boolean", "description": "Read the pattern as a regular expression, matched through the normalizers (default false)" },
+                },
+                "required": ["pattern"],
+            },
+        },
+        {
+            "name": "get_canto",
+            "description": "Get the full text of one canto of the Divine Comedy with line numbers.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "cantica": cantica,
+                    "canto": { "type": "integer", "minimum": 1, "description": "Canto number" },
+                },
+                "required": ["cantica", "canto"],
+            },
+        },
+        {
+            "name": "get_verse",
+            "description": "Get a single verse of the Divine Comedy by citation, optionally with surrounding lines for context.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "cantica": cantica,
+                    "canto": { "type": "integer", "minimum": 1, "description": "Canto number" },
+                    "line": { "type": "integer", "minimum": 1, "description": "Line number within the canto" },
+                    "context": { "type": "integer", "minimum": 0, "description": "Lines of context before and after (default 0)" },
+                },
+                "required": ["cantica", "canto", "line"],
+            },
+        },
+    ])
+}
+
+/// Run a tool, reporting failures in the result (as MCP expects) rather than
+/// as protocol errors.
+fn call_tool(commedia: &DivinaCommedia, name: &str, arguments: Value) -> Value {
+    let result = match name {
+        "search_verses" => parse_args(arguments).and_then(|args| search_verses(commedia, args)),
+        "get_canto" => parse_args(arguments).and_then(|args| get_canto(commedia, args)),
+        "get_verse" => parse_args(arguments).and_then(|args| get_verse(commedia, args)),
+        _ => Err(format!("Unknown tool '{}'", name)),
+    };
+
+    match result {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+        Err(message) => {
+            json!({ "content": [{ "type": "text", "text": message }], "isError": true })
+        }
+    }
+}
+
+fn parse_args<T: for<'de> Deserialize<'de>>(arguments: Value) -> Result<T, String> {
+    serde_json::from_value(arguments).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+fn find_cantica<'a>(commedia: &'a DivinaCommedia, name: &str) -> Result<&'a Cantica, String> {
+    name.parse()
+        .map(|id| commedia.get(id))
+        .map_err(|e: DucaError| e.to_string())
+}
+
+fn search_verses(commedia: &DivinaCommedia, args: SearchArgs) -> Result<String, String> {
+    let cantica = args
+        .cantica
+        .as_deref()
+        .map(str::parse::<CanticaId>)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let pipeline: Pipeline = match args.normalize.as_deref() {
+        Some(steps) => steps.parse().map_err(|e: DucaError| e.to_string())?,
+        None => Pipeline::default(),
+    };
+    let results = commedia
+        .search_with(&args.pattern, cantica, &pipeline.with_regex(args.regex))
+        .map_err(|e| e.to_string())?;
+    let limit = args.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    if results.is_empty() {
+        return Ok(format!("No matches found for '{}'", args.pattern));
+    }
+
+    let mut text = format!("Found {} matches for '{}'", results.len(), args.pattern);
+    if results.len() > limit {
+        let _ = write!(text, " (showing first {})", limit);
+    }
+    text.push_str(":\n");
+    for m in results.into_iter().take(limit) {
+        let _ = writeln!(text, "{} {}.{}: {}", m.cantica, m.canto, m.line, m.text);
+    }
+    Ok(text)
+}
+
+fn get_canto(commedia: &DivinaCommedia, args: CantoArgs) -> Result<String, String> {
+    let cantica = find_cantica(commedia, &args.cantica)?;
+    let canto = cantica
+        .canto(args.canto)
+        .ok_or_else(|| format!("Canto {} not found in {}", args.canto, cantica.name))?;
+
+    let mut text = format!("{} Canto {}\n\n", cantica.name, canto.roman_numeral);
+    for verse in &canto.verses {
+        let _ = writeln!(text, "{:3}: {}", verse.line_number, verse.text);
+    }
+    Ok(text)
+}
+
+fn get_verse(commedia: &DivinaCommedia, args: VerseArgs) -> Result<String, String> {
+    let cantica = find_cantica(commedia, &args.cantica)?;
+    let canto = cantica
+        .canto(args.canto)
+        .ok_or_else(|| format!("Canto {} not found in {}", args.canto, cantica.name))?;
+    if canto.verse(args.line).is_none() {
+        return Err(format!(
+            "Line {} not found in {} Canto {}",
+            args.line, cantica.name, canto.roman_numeral
+        ));
+    }
+
+    let first = args.line.saturating_sub(args.context);
+    let last = args.line.saturating_add(args.context);
+    let mut text = String::new();
+    for verse in canto
+        .verses
+        .iter()
+        .filter(|v| v.line_number >= first && v.line_number <= last)
+    {
+        let _ = writeln!(
+            text,
+            "{} {}.{}: {}",
+            cantica.name, canto.number, verse.line_number, verse.text
+        );
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::{Canto, Verse};
+
+    fn create_test_commedia() -> DivinaCommedia {
+        let mut commedia = DivinaCommedia::new();
+        commedia.inferno.cantos.insert(
+            1,
+            Canto::new(
+                CanticaId::Inferno,
+                1,
+                vec![
+                    Verse {
+                        line_number: 1,
+                        text: "Nel mezzo del cammin di nostra vita".to_string(),
+                    },
+                    Verse {
+                        line_number: 2,
+                        text: "mi ritrovai per una selva oscura".to_string(),
+                    },
+                    Verse {
+                        line_number: 3,
+                        text: "ché la diritta via era smarrita".to_string(),
+                    },
+                ],
+            ),
+        );
+        commedia
+    }
+
+    fn tool_text(result: &Value) -> &str {
+        result["content"][0]["text"].as_str().unwrap()
+    }
+
+    #[test]
+    fn test_initialize_and_list_tools() {
+        let commedia = create_test_commedia();
+
+        let result = dispatch(&commedia, "initialize", json!({})).ok().unwrap();
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(result["serverInfo"]["name"], "duca");
+
+        let result = dispatch(&commedia, "tools/list", Value::Null).ok().unwrap();
+        let names: Vec<&str> = result["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["search_verses", "get_canto", "get_verse"]);
+    }
+
+    #[test]
+    fn test_search_verses_tool() {
+        let commedia = create_test_commedia();
+
+        let result = call_tool(&commedia, "search_verses", json!({"pattern": "selva"}));
+        assert_eq!(result["isError"], false);
+        assert!(tool_text(&result).contains("Inferno 1.2: mi ritrovai per una selva oscura"));
+
+        let result = call_tool(
+            &commedia,
+            "search_verses",
+            json!({"pattern": "a", "limit": 1}),
+        );
+        assert!(tool_text(&result).contains("showing first 1"));
+
+        let result = call_tool(
+            &commedia,
+            "search_verses",
+            json!({"pattern": "selve oscure", "normalize": "lemma"}),
+        );
+        assert!(tool_text(&result).contains("Inferno 1.2"));
+        let result = call_tool(
+            &commedia,
+            "search_verses",
+            json!({"pattern": "selva", "normalize": "stem"}),
+        );
+        assert_eq!(result["isError"], true);
+        let result = call_tool(
+            &commedia,
+            "search_verses",
+            json!({"pattern": "^mi .* oscura$", "regex": true}),
+        );
+        assert!(tool_text(&result).contains("Inferno 1.2"));
+        let result = call_tool(
+            &commedia,
+            "search_verses",
+            json!({"pattern": "selv(", "regex": true}),
+        );
+        assert_eq!(result["isError"], true);
+    }
+
+    #[test]
+    fn test_get_canto_and_verse_tools() {
+        let commedia = create_test_commedia();
+
+        let result = call_tool(
+            &commedia,
+            "get_canto",
+            json!({"cantica": "inferno", "canto": 1}),
+        );
+        assert!(tool_text(&result).starts_with("Inferno Canto I"));
+
+        let result = call_tool(
+            &commedia,
+            "get_verse",
+            json!({"cantica": "inferno", "canto": 1, "line": 2, "context": 1}),
+        );
+        assert_eq!(tool_text(&result).lines().count(), 3);
+
+        let result = call_tool(
+            &commedia,
+            "get_verse",
+            json!({"cantica": "inferno", "canto": 1, "line": 9}),
+        );
+        assert_eq!(result["isError"], true);
+
+        let result = call_tool(
+            &commedia,
+            "get_canto",
+            json!({"cantica": "limbo", "canto": 1}),
+        );
+        assert_eq!(result["isError"], true);
+    }
+}
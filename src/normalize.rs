@@ -0,0 +1,445 @@
+//! Case and diacritic folding for search, the [`Pipeline`] of normalizers
+//! every search runs its pattern and the verses through, and the joining of
+//! words broken across lines.
+//!
+//! Folding maps each character to exactly one character, so a match found
+//! in folded text sits at the same character positions in the original and
+//! can be highlighted there. Normalizers that work on whole words, the
+//! [old spellings](crate::orthography) and [lemmas](crate::lemma), match a
+//! pattern's words in turn instead.
+
+use std::fmt;
+use std::iter;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::orthography::{self, Word};
+use crate::{lemma, DucaError};
+
+/// Fold a single character: lowercase it, strip accents and diaereses, and
+/// straighten typographic quotes.
+pub fn fold_char(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ò' | 'ó' | 'ô' | 'ö' | 'õ' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        '’' | '‘' => '\'',
+        '“' | '”' | '«' | '»' => '"',
+        c => c,
+    }
+}
+
+/// Fold a whole string with [`fold_char`].
+pub fn fold(text: &str) -> String {
+    text.chars().map(fold_char).collect()
+}
+
+/// Like [`fold`], writing into `buffer` so repeated calls can reuse its
+/// allocation.
+pub fn fold_into(text: &str, buffer: &mut String) {
+    buffer.clear();
+    buffer.extend(text.chars().map(fold_char));
+}
+
+/// `c` without its accent or diaeresis, keeping its case, and typographic
+/// quotes straightened, as [`fold_char`] does them.
+fn strip_accent(c: char) -> char {
+    let folded = fold_char(c);
+    if folded == c.to_lowercase().next().unwrap_or(c) {
+        c
+    } else if c.is_uppercase() {
+        folded.to_uppercase().next().unwrap_or(folded)
+    } else {
+        folded
+    }
+}
+
+/// A step of a search's [`Pipeline`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Normalizer {
+    /// Ignore case.
+    Lowercase,
+    /// Ignore accents and diaereses, and the shape of quotes.
+    Fold,
+    /// Match modern spelling against the old, as [`orthography`] does.
+    Orthography,
+    /// Match every form of a word, as [`lemma`] stems them. Stems are
+    /// folded, so a pipeline with it ignores case and accents in any event.
+    Lemma,
+}
+
+impl Normalizer {
+    /// Every normalizer, in the order a pipeline runs them.
+    pub const ALL: [Normalizer; 4] = [
+        Normalizer::Lowercase,
+        Normalizer::Fold,
+        Normalizer::Orthography,
+        Normalizer::Lemma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Normalizer::Lowercase => "lowercase",
+            Normalizer::Fold => "fold",
+            Normalizer::Orthography => "orthography",
+            Normalizer::Lemma => "lemma",
+        }
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Normalizer {
+    type Err = DucaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "lowercase" | "case" => Ok(Normalizer::Lowercase),
+            "fold" | "accents" => Ok(Normalizer::Fold),
+            "orthography" | "modern" => Ok(Normalizer::Orthography),
+            "lemma" | "lemmas" => Ok(Normalizer::Lemma),
+            _ => Err(DucaError::InvalidNormalizer(s.to_string())),
+        }
+    }
+}
+
+/// The normalizers a search runs its pattern and each verse through before
+/// comparing them, always in the order of [`Normalizer::ALL`]. The default
+/// ignores case and accents, as search always has; the CLI's flags, the
+/// TUI's toggles and the servers' parameters each build one per query.
+///
+/// A pipeline [with regex](Pipeline::with_regex) reads patterns as regular
+/// expressions, matched against the text through its normalizers; without
+/// it, every pattern is plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pipeline {
+    steps: [bool; Normalizer::ALL.len()],
+    regex: bool,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::exact()
+            .with(Normalizer::Lowercase)
+            .with(Normalizer::Fold)
+    }
+}
+
+impl Pipeline {
+    /// A pipeline that changes nothing, matching the text exactly.
+    pub fn exact() -> Self {
+        Self {
+            steps: [false; Normalizer::ALL.len()],
+            regex: false,
+        }
+    }
+
+    pub fn with(mut self, normalizer: Normalizer) -> Self {
+        self.steps[normalizer as usize] = true;
+        self
+    }
+
+    pub fn without(mut self, normalizer: Normalizer) -> Self {
+        self.steps[normalizer as usize] = false;
+        self
+    }
+
+    /// The pipeline with `normalizer` added, or taken out if it's in.
+    pub fn toggle(self, normalizer: Normalizer) -> Self {
+        if self.contains(normalizer) {
+            self.without(normalizer)
+        } else {
+            self.with(normalizer)
+        }
+    }
+
+    pub fn contains(&self, normalizer: Normalizer) -> bool {
+        self.steps[normalizer as usize]
+    }
+
+    /// The pipeline reading patterns as regular expressions, or as plain
+    /// text.
+    pub fn with_regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    pub fn is_regex(&self) -> bool {
+        self.regex
+    }
+
+    /// The normalizers in the pipeline, in the order they run.
+    pub fn normalizers(&self) -> impl Iterator<Item = Normalizer> + '_ {
+        Normalizer::ALL.into_iter().filter(|n| self.contains(*n))
+    }
+
+    /// Whether the pipeline matches whole words, in turn, rather than a
+    /// pattern anywhere in the text.
+    pub fn matches_words(&self) -> bool {
+        self.contains(Normalizer::Orthography) || self.contains(Normalizer::Lemma)
+    }
+
+    /// `c` through the pipeline's normalizers that map one character to
+    /// another.
+    pub fn normalize_char(&self, c: char) -> char {
+        let mut c = c;
+        if self.contains(Normalizer::Lowercase) {
+            c = c.to_lowercase().next().unwrap_or(c);
+        }
+        if self.contains(Normalizer::Fold) {
+            c = strip_accent(c);
+        }
+        if self.contains(Normalizer::Orthography) {
+            c = orthography::respell_char(c);
+        }
+        c
+    }
+
+    /// `text` through [`Pipeline::normalize_char`], a character for each
+    /// of its characters.
+    pub fn normalize(&self, text: &str) -> String {
+        text.chars().map(|c| self.normalize_char(c)).collect()
+    }
+
+    /// Like [`Pipeline::normalize`], writing into `buffer`.
+    pub fn normalize_into(&self, text: &str, buffer: &mut String) {
+        buffer.clear();
+        buffer.extend(text.chars().map(|c| self.normalize_char(c)));
+    }
+
+    /// The regular expression `pattern` ready to match text through the
+    /// pipeline: its characters normalized but for case, which is left to
+    /// the `(?i)` flag, and the character after each backslash, so escapes
+    /// such as `\W` keep their meaning.
+    pub fn normalize_regex(&self, pattern: &str) -> String {
+        let literal = self.without(Normalizer::Lowercase);
+        let mut escaped = false;
+        let mut normalized: String = pattern
+            .chars()
+            .map(|c| {
+                let c = if escaped {
+                    c
+                } else {
+                    literal.normalize_char(c)
+                };
+                escaped = !escaped && c == '\\';
+                c
+            })
+            .collect();
+        if self.contains(Normalizer::Lowercase) {
+            normalized.insert_str(0, "(?i)");
+        }
+        normalized
+    }
+
+    /// The words of `text`, each normalized and, with [`Normalizer::Lemma`],
+    /// stemmed.
+    pub fn words(&self, text: &str) -> Vec<Word> {
+        orthography::words_with(text, |word| {
+            let word = self.normalize(word);
+            if self.contains(Normalizer::Lemma) {
+                lemma::lemma(&word)
+            } else {
+                word
+            }
+        })
+    }
+
+    /// Byte ranges in `text` of each run of its words matching `query`, the
+    /// [words](Pipeline::words) of a pattern, in turn. With
+    /// [`Normalizer::Orthography`], an elided word matches the word it
+    /// shortens.
+    pub fn find_words(&self, text: &str, query: &[Word]) -> Vec<Range<usize>> {
+        let words = self.words(text);
+        if self.contains(Normalizer::Orthography) {
+            orthography::find_runs(&words, query, orthography::same_word)
+        } else {
+            orthography::find_runs(&words, query, |a, b| a.spelling == b.spelling)
+        }
+    }
+}
+
+impl fmt::Display for Pipeline {
+    /// The normalizers' names separated by commas, or `none`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.normalizers().map(Normalizer::name).collect();
+        if names.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&names.join(","))
+        }
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = DucaError;
+
+    /// Normalizers separated by commas, in any order; `none`, or nothing,
+    /// for an exact pipeline.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pipeline = Self::exact();
+        for name in s.split(',').filter(|name| !name.trim().is_empty()) {
+            if name.trim().eq_ignore_ascii_case("none") {
+                continue;
+            }
+            pipeline = pipeline.with(name.parse()?);
+        }
+        Ok(pipeline)
+    }
+}
+
+/// Translate a byte range in `fold(original)` into the matching byte range
+/// of `original`.
+pub fn original_range(original: &str, folded: &str, range: Range<usize>) -> Range<usize> {
+    let start_char = folded[..range.start].chars().count();
+    let len = folded[range].chars().count();
+
+    let mut offsets = original
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(iter::once(original.len()));
+    let start = offsets.nth(start_char).unwrap_or(original.len());
+    let end = match len {
+        0 => start,
+        len => offsets.nth(len - 1).unwrap_or(original.len()),
+    };
+    start..end
+}
+
+/// `line` joined to the first word of `next` when it ends by breaking a
+/// word with a hyphen, as in Paradiso XXIV's "differente- / mente" and in
+/// transcriptions that hyphenate at line ends. The hyphen is dropped, and
+/// the byte offset where it stood is returned with the joined text, so
+/// ranges before it still index `line`.
+pub fn join_hyphenated(line: &str, next: &str) -> Option<(String, usize)> {
+    let trimmed = line.trim_end();
+    let cut = trimmed.strip_suffix(['-', '\u{ad}'])?.len();
+    if !trimmed[..cut].chars().next_back()?.is_alphabetic() {
+        return None;
+    }
+    let next = next.trim_start();
+    let word_end = next
+        .find(|c: char| !c.is_alphanumeric())
+        .unwrap_or(next.len());
+    if word_end == 0 {
+        return None;
+    }
+    Some((format!("{}{}", &trimmed[..cut], &next[..word_end]), cut))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("Pietà"), "pieta");
+        assert_eq!(fold("l’oblïo"), "l'oblio");
+        assert_eq!(fold("«Vergine Madre"), "\"vergine madre");
+        assert_eq!(fold("È"), "e");
+
+        let mut buffer = String::from("leftover");
+        fold_into("Pietà", &mut buffer);
+        assert_eq!(buffer, "pieta");
+    }
+
+    #[test]
+    fn test_fold_preserves_character_count() {
+        let text = "Amor, ch’a nullo amato amar perdona,";
+        assert_eq!(fold(text).chars().count(), text.chars().count());
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let pipeline = Pipeline::default();
+        assert_eq!(
+            pipeline.normalize("Pietà, «Virgilio»"),
+            "pieta, \"virgilio\""
+        );
+        assert_eq!(pipeline.to_string(), "lowercase,fold");
+        assert!(!pipeline.matches_words());
+
+        // Each step alone, keeping a character for each character
+        let accents = Pipeline::exact().with(Normalizer::Fold);
+        assert_eq!(accents.normalize("Pietà, È"), "Pieta, E");
+        let case = Pipeline::exact().with(Normalizer::Lowercase);
+        assert_eq!(case.normalize("Pietà"), "pietà");
+        let modern = "fold,orthography,lowercase".parse::<Pipeline>().unwrap();
+        assert_eq!(modern.normalize("Vidi Jacopo"), "uidi iacopo");
+        assert_eq!(modern.to_string(), "lowercase,fold,orthography");
+
+        let lemmas = pipeline.with(Normalizer::Lemma);
+        let query = lemmas.words("occhio bello");
+        assert_eq!(lemmas.find_words("li occhi belli", &query), vec![3..14]);
+        assert!(lemmas.find_words("belli occhi", &query).is_empty());
+        let query = modern.words("che intrate");
+        assert_eq!(modern.find_words("voi ch’intrate", &query), vec![4..16]);
+        assert!(pipeline
+            .find_words("voi ch’intrate", &pipeline.words("che"))
+            .is_empty());
+
+        assert_eq!("none".parse::<Pipeline>().unwrap(), Pipeline::exact());
+        assert_eq!(Pipeline::exact().to_string(), "none");
+        assert_eq!(
+            pipeline.toggle(Normalizer::Fold),
+            Pipeline::exact().with(Normalizer::Lowercase)
+        );
+        let err = "fold,stem".parse::<Pipeline>().unwrap_err();
+        assert_eq!(err, DucaError::InvalidNormalizer("stem".to_string()));
+    }
+
+    #[test]
+    fn test_normalize_regex() {
+        let pipeline = Pipeline::default().with_regex(true);
+        assert!(pipeline.is_regex() && !Pipeline::default().is_regex());
+        assert_eq!(pipeline.normalize_regex(r"Pietà\W"), r"(?i)Pieta\W");
+        assert_eq!(pipeline.normalize_regex(r"\\È"), r"(?i)\\E");
+
+        let modern = Pipeline::exact()
+            .with(Normalizer::Orthography)
+            .with_regex(true);
+        assert_eq!(modern.normalize_regex(r"vita\v"), r"uita\v");
+    }
+
+    #[test]
+    fn test_original_range() {
+        let original = "ché la diritta via";
+        let folded = fold(original);
+        let start = folded.find("la diritta").unwrap();
+        let range = original_range(original, &folded, start..start + "la diritta".len());
+        assert_eq!(&original[range], "la diritta");
+
+        let original = "l’oblïo";
+        let folded = fold(original);
+        let range = original_range(original, &folded, 2..7);
+        assert_eq!(&original[range], "oblïo");
+    }
+
+    #[test]
+    fn test_join_hyphenated() {
+        assert_eq!(
+            join_hyphenated(
+                "così quelle carole, differente-",
+                "mente danzando, de la sua"
+            ),
+            Some(("così quelle carole, differentemente".to_string(), 31))
+        );
+        assert_eq!(
+            join_hyphenated("la selva oscu\u{ad} ", "ra e forte"),
+            Some(("la selva oscura".to_string(), 13))
+        );
+        // A dash standing alone isn't a broken word
+        assert_eq!(join_hyphenated("e disse: -", "vieni"), None);
+        assert_eq!(join_hyphenated("differente-", "«mente"), None);
+        assert_eq!(join_hyphenated("ché la diritta via", "era smarrita"), None);
+    }
+}
@@ -0,0 +1,1046 @@
+//! The reader's own notes and bookmarks, kept as one Markdown file per canto
+//! so the directory can live under git: `inferno/05.md` holds Inferno V's.
+//!
+//! Files are written in a fixed order, bookmarks by line and notes by the
+//! lines they cover, so adding or removing one changes only its own lines:
+//!
+//! ```markdown
+//! # Inferno 5
+//!
+//! ## Bookmarks
+//!
+//! - 100 #similes
+//! - 121: Nessun maggior dolore #francesca #exam
+//!
+//! ## Notes
+//!
+//! ### 100-106
+//!
+//! Francesca's three tercets opening with Amor.
+//! ```
+//!
+//! A bookmark's tags end its line, each a word after a `#`. Note text is
+//! Markdown of its own; a line of it starting with `#` is
+//! written with a backslash before it, so it isn't taken for a heading.
+//!
+//! The directory can also be shared between machines by a sync service such
+//! as Dropbox or Syncthing. Changes are made under a lock file, `.lock`,
+//! that other duca processes wait for, and that a machine the directory is
+//! synced to sees too. When two machines change a canto at once anyway and
+//! the service keeps both files, the one written last wins.
+//!
+//! With the `encryption` feature the directory can be encrypted with a
+//! passphrase, for notes kept on a shared or synced disk. Each canto file is
+//! then an armored age file, `inferno/05.md.age`, encrypted to a key of the
+//! store's own that `.key.age` keeps encrypted with the passphrase; so the
+//! passphrase is stretched once a session rather than once a file.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::plan::Date;
+use crate::{paths, CanticaId, DucaError};
+
+/// A bookmarked line, optionally labeled and tagged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub line: usize,
+    pub label: Option<String>,
+    /// Sorted, without duplicates.
+    pub tags: Vec<String>,
+}
+
+impl Bookmark {
+    /// Whether the bookmark has every one of `tags`.
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+    }
+}
+
+/// `tag` as bookmarks keep it, lowercased and without a leading `#`, if it
+/// is a tag: a letter followed by letters, digits, `-` or `_`.
+pub fn tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    let tag = tag.strip_prefix('#').unwrap_or(tag).to_lowercase();
+    let mut chars = tag.chars();
+    let valid = chars.next().is_some_and(char::is_alphabetic)
+        && chars.all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(tag)
+}
+
+/// A note on some lines of a canto.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub lines: RangeInclusive<usize>,
+    pub text: String,
+}
+
+/// The notes and bookmarks of one canto, as its file holds them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CantoNotes {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    /// By line, one per line.
+    pub bookmarks: Vec<Bookmark>,
+    /// By the lines they cover; notes on the same lines in the order added.
+    pub notes: Vec<Note>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Heading,
+    Bookmarks,
+    Notes,
+}
+
+/// `lines` as a note's heading writes them: `100` or `100-106`.
+fn format_lines(lines: &RangeInclusive<usize>) -> String {
+    if lines.start() == lines.end() {
+        lines.start().to_string()
+    } else {
+        format!("{}-{}", lines.start(), lines.end())
+    }
+}
+
+fn parse_lines(text: &str) -> Option<RangeInclusive<usize>> {
+    let (start, end) = text.split_once('-').unwrap_or((text, text));
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (1 <= start && start <= end).then_some(start..=end)
+}
+
+/// `text` as a note keeps it, without blank lines around it or spaces at
+/// the ends of its lines.
+fn note_text(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A bookmark item split into what comes before its tags, and the tags.
+fn split_tags(item: &str) -> (&str, Vec<&str>) {
+    let mut rest = item.trim_end();
+    let mut tags = Vec::new();
+    while let Some((before, last)) = rest.rsplit_once(char::is_whitespace) {
+        if !last.starts_with('#') || tag(last).is_none() {
+            break;
+        }
+        tags.push(last);
+        rest = before.trim_end();
+    }
+    (rest, tags)
+}
+
+/// Whether a line of note text would read as a heading, or as one escaped.
+fn needs_escape(line: &str) -> bool {
+    line.trim_start_matches('\\').starts_with('#')
+}
+
+impl CantoNotes {
+    pub fn new(cantica: CanticaId, canto: u8) -> Self {
+        Self {
+            cantica,
+            canto,
+            bookmarks: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty() && self.notes.is_empty()
+    }
+
+    /// Add a note after any others on the same lines.
+    pub fn add_note(&mut self, lines: RangeInclusive<usize>, text: &str) {
+        let key = (*lines.start(), *lines.end());
+        let at = self
+            .notes
+            .partition_point(|note| (*note.lines.start(), *note.lines.end()) <= key);
+        self.notes.insert(
+            at,
+            Note {
+                lines,
+                text: note_text(text),
+            },
+        );
+    }
+
+    /// Whether `lines` have a note saying `text`.
+    pub fn contains_note(&self, lines: &RangeInclusive<usize>, text: &str) -> bool {
+        let text = note_text(text);
+        self.notes
+            .iter()
+            .any(|note| note.lines == *lines && note.text == text)
+    }
+
+    /// Remove the notes on exactly `lines`, returning how many there were.
+    pub fn remove_notes(&mut self, lines: &RangeInclusive<usize>) -> usize {
+        let before = self.notes.len();
+        self.notes.retain(|note| note.lines != *lines);
+        before - self.notes.len()
+    }
+
+    /// Bookmark `line`, replacing the label of a bookmark already there and
+    /// keeping its tags.
+    pub fn set_bookmark(&mut self, line: usize, label: Option<&str>) {
+        let label = label
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(String::from);
+        match self.bookmarks.binary_search_by_key(&line, |b| b.line) {
+            Ok(i) => self.bookmarks[i].label = label,
+            Err(i) => self.bookmarks.insert(
+                i,
+                Bookmark {
+                    line,
+                    label,
+                    tags: Vec::new(),
+                },
+            ),
+        }
+    }
+
+    /// Add `tags` to the bookmark on `line`, skipping any that aren't tags,
+    /// and return how many it didn't have.
+    pub fn tag_bookmark<S: AsRef<str>>(&mut self, line: usize, tags: &[S]) -> usize {
+        let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.line == line) else {
+            return 0;
+        };
+        let before = bookmark.tags.len();
+        bookmark
+            .tags
+            .extend(tags.iter().filter_map(|t| tag(t.as_ref())));
+        bookmark.tags.sort();
+        bookmark.tags.dedup();
+        bookmark.tags.len() - before
+    }
+
+    /// Remove `tags` from the bookmark on `line`, returning how many it had.
+    pub fn untag_bookmark<S: AsRef<str>>(&mut self, line: usize, tags: &[S]) -> usize {
+        let Some(bookmark) = self.bookmarks.iter_mut().find(|b| b.line == line) else {
+            return 0;
+        };
+        let tags: Vec<String> = tags.iter().filter_map(|t| tag(t.as_ref())).collect();
+        let before = bookmark.tags.len();
+        bookmark.tags.retain(|t| !tags.contains(t));
+        before - bookmark.tags.len()
+    }
+
+    /// Remove the bookmark on `line`, returning whether there was one.
+    pub fn remove_bookmark(&mut self, line: usize) -> bool {
+        let before = self.bookmarks.len();
+        self.bookmarks.retain(|b| b.line != line);
+        before != self.bookmarks.len()
+    }
+
+    /// The canto's file content.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {} {}\n", self.cantica, self.canto);
+        if !self.bookmarks.is_empty() {
+            out.push_str("\n## Bookmarks\n\n");
+            for bookmark in &self.bookmarks {
+                out.push_str(&format!("- {}", bookmark.line));
+                if let Some(label) = &bookmark.label {
+                    out.push_str(&format!(": {}", label));
+                }
+                for tag in &bookmark.tags {
+                    out.push_str(&format!(" #{}", tag));
+                }
+                out.push('\n');
+            }
+        }
+        if !self.notes.is_empty() {
+            out.push_str("\n## Notes\n");
+            for note in &self.notes {
+                out.push_str(&format!("\n### {}\n\n", format_lines(&note.lines)));
+                for line in note.text.lines() {
+                    if needs_escape(line) {
+                        out.push('\\');
+                    }
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+        out
+    }
+
+    /// Parse a canto's file content. `name` identifies it in errors.
+    pub fn parse(
+        content: &str,
+        cantica: CanticaId,
+        canto: u8,
+        name: &str,
+    ) -> Result<Self, DucaError> {
+        let invalid = |number: usize, reason: &str| DucaError::UserData {
+            path: name.to_string(),
+            reason: format!("line {}: {}", number + 1, reason),
+        };
+        let mut notes = Self::new(cantica, canto);
+        let mut section = Section::Heading;
+        let mut note: Option<(RangeInclusive<usize>, Vec<&str>)> = None;
+        let finish = |note: &mut Option<(RangeInclusive<usize>, Vec<&str>)>, notes: &mut Self| {
+            if let Some((lines, text)) = note.take() {
+                notes.notes.push(Note {
+                    lines,
+                    text: text.join("\n").trim().to_string(),
+                });
+            }
+        };
+
+        for (number, line) in content.lines().enumerate() {
+            if let Some(heading) = line.strip_prefix("## ") {
+                finish(&mut note, &mut notes);
+                section = match heading.trim() {
+                    "Bookmarks" => Section::Bookmarks,
+                    "Notes" => Section::Notes,
+                    _ => return Err(invalid(number, "expected ## Bookmarks or ## Notes")),
+                };
+            } else if let Some(heading) = line.strip_prefix("### ") {
+                if section != Section::Notes {
+                    return Err(invalid(number, "a note outside ## Notes"));
+                }
+                finish(&mut note, &mut notes);
+                let lines = parse_lines(heading)
+                    .ok_or_else(|| invalid(number, "expected lines such as 100 or 100-106"))?;
+                note = Some((lines, Vec::new()));
+            } else if let Some((_, text)) = &mut note {
+                match line.strip_prefix('\\') {
+                    Some(unescaped) if needs_escape(unescaped) => text.push(unescaped),
+                    _ => text.push(line),
+                }
+            } else if line.trim().is_empty() || (number == 0 && line.starts_with("# ")) {
+                continue;
+            } else if section == Section::Bookmarks {
+                let bookmark = line
+                    .strip_prefix("- ")
+                    .and_then(|item| {
+                        let (item, tags) = split_tags(item);
+                        let (line, label) = item.split_once(':').unwrap_or((item, ""));
+                        let line = line.trim().parse().ok().filter(|&line| line >= 1)?;
+                        Some((line, label.trim(), tags))
+                    })
+                    .ok_or_else(|| invalid(number, "expected a bookmark such as - 121: label"))?;
+                notes.set_bookmark(bookmark.0, Some(bookmark.1));
+                notes.tag_bookmark(bookmark.0, &bookmark.2);
+            } else {
+                return Err(invalid(number, "text outside a note"));
+            }
+        }
+        finish(&mut note, &mut notes);
+        notes
+            .notes
+            .sort_by_key(|note| (*note.lines.start(), *note.lines.end()));
+        Ok(notes)
+    }
+}
+
+/// The lock file held while the store changes.
+const LOCK_FILE: &str = ".lock";
+
+/// The key the canto files are encrypted to, itself encrypted with the
+/// reader's passphrase. The store is encrypted while it exists.
+const KEY_FILE: &str = ".key.age";
+
+/// Why encrypted notes can't be read or written.
+#[cfg(feature = "encryption")]
+const LOCKED: &str = "the notes are encrypted; unlock them with their passphrase";
+#[cfg(not(feature = "encryption"))]
+const LOCKED: &str = "the notes are encrypted; build duca with --features encryption to read them";
+
+/// How old a lock may get before it's taken for one left behind by a crash,
+/// or by a machine that stopped syncing, and taken over.
+const LOCK_STALE: Duration = Duration::from_secs(30);
+
+/// How long to wait for another process's lock before giving up.
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// The store's lock, released when dropped.
+#[derive(Debug)]
+pub struct StoreLock {
+    path: PathBuf,
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// This machine's name, to say who holds a lock.
+fn host() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+/// Whether `name` is a copy of `file` that a sync service made on a
+/// conflict: Dropbox's and Nextcloud's `05 (conflicted copy 2025-01-02).md`,
+/// or Syncthing's `05.sync-conflict-20250102-101500-ABCDEFG.md`. Both go
+/// before the last extension, so `05.md.age`'s are `05.md (…).age`.
+fn is_conflicted_copy(name: &str, file: &str) -> bool {
+    let Some((stem, extension)) = file.rsplit_once('.') else {
+        return false;
+    };
+    name.ends_with(&format!(".{}", extension))
+        && name.strip_prefix(stem).is_some_and(|rest| {
+            rest.starts_with(".sync-conflict-")
+                || (rest.starts_with(" (") && rest.contains("conflicted copy"))
+        })
+}
+
+/// Write `content` to `path` whole: to a `.partial` file first, renamed over
+/// `path`, so an interrupted write leaves the old file.
+pub(crate) fn write_whole(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    fs::write(&partial, content)?;
+    fs::rename(&partial, path)
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// A directory of canto files.
+#[derive(Clone)]
+pub struct NoteStore {
+    dir: PathBuf,
+    /// The key the canto files are encrypted to, once unlocked.
+    #[cfg(feature = "encryption")]
+    identity: Option<age::x25519::Identity>,
+}
+
+impl std::fmt::Debug for NoteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoteStore").field("dir", &self.dir).finish()
+    }
+}
+
+impl NoteStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            #[cfg(feature = "encryption")]
+            identity: None,
+        }
+    }
+
+    /// The user's notes, in [`paths::notes_dir`].
+    pub fn open() -> Result<Self, DucaError> {
+        paths::notes_dir()
+            .map(Self::new)
+            .ok_or_else(|| DucaError::Save {
+                path: "notes".to_string(),
+                reason: "no data directory; set $DUCA_STATE_DIR, $HOME or $XDG_DATA_HOME"
+                    .to_string(),
+            })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Whether the canto files are encrypted, which the key file says.
+    pub fn is_encrypted(&self) -> bool {
+        self.dir.join(KEY_FILE).exists()
+    }
+
+    /// The canto files' extension.
+    fn extension(&self) -> &'static str {
+        if self.is_encrypted() {
+            "md.age"
+        } else {
+            "md"
+        }
+    }
+
+    /// Where a canto's notes are kept, e.g. `inferno/05.md`, or
+    /// `inferno/05.md.age` when encrypted.
+    pub fn file(&self, cantica: CanticaId, canto: u8) -> PathBuf {
+        self.dir
+            .join(cantica.key())
+            .join(format!("{:02}.{}", canto, self.extension()))
+    }
+
+    /// The date, in UTC, a canto's notes last changed, if it has any.
+    pub fn changed_on(&self, cantica: CanticaId, canto: u8) -> Option<Date> {
+        let modified = fs::metadata(self.file(cantica, canto))
+            .and_then(|metadata| metadata.modified())
+            .ok()?;
+        Some(Date::of(modified))
+    }
+
+    /// Take the store's lock, waiting for another process to release it.
+    pub fn lock(&self) -> Result<StoreLock, DucaError> {
+        let path = self.dir.join(LOCK_FILE);
+        let unwritable = |reason: String| DucaError::Save {
+            path: path.display().to_string(),
+            reason,
+        };
+        fs::create_dir_all(&self.dir).map_err(|e| unwritable(e.to_string()))?;
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = writeln!(file, "{} {}", host(), std::process::id());
+                    return Ok(StoreLock { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let age = modified(&path).elapsed().unwrap_or_default();
+                    if age > LOCK_STALE {
+                        let _ = fs::remove_file(&path);
+                    } else if started.elapsed() > LOCK_WAIT {
+                        let holder = fs::read_to_string(&path).unwrap_or_default();
+                        return Err(unwritable(format!(
+                            "locked by {}; delete the lock if no duca is using it",
+                            holder.trim()
+                        )));
+                    } else {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                }
+                Err(e) => return Err(unwritable(e.to_string())),
+            }
+        }
+    }
+
+    /// Change a canto's notes and bookmarks under the lock, saving them if
+    /// `change` changed them, and return what it returns.
+    pub fn update<T>(
+        &self,
+        cantica: CanticaId,
+        canto: u8,
+        change: impl FnOnce(&mut CantoNotes) -> T,
+    ) -> Result<T, DucaError> {
+        let lock = self.lock()?;
+        self.resolve_conflicts(&lock, cantica, canto)?;
+        let mut notes = self.load(cantica, canto)?;
+        let before = notes.clone();
+        let result = change(&mut notes);
+        if notes != before {
+            self.save(&notes)?;
+        }
+        Ok(result)
+    }
+
+    /// The copies a sync service made of a canto's file in a conflict, and
+    /// the one written last if it's newer than the file: the one to keep.
+    fn conflicted_copies(&self, cantica: CanticaId, canto: u8) -> (Vec<PathBuf>, Option<PathBuf>) {
+        let path = self.file(cantica, canto);
+        let (Some(dir), Some(file)) = (path.parent(), path.file_name()) else {
+            return (Vec::new(), None);
+        };
+        let file = file.to_string_lossy();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return (Vec::new(), None);
+        };
+        let copies: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| is_conflicted_copy(&entry.file_name().to_string_lossy(), &file))
+            .map(|entry| entry.path())
+            .collect();
+        let newest = copies
+            .iter()
+            .max_by_key(|copy| modified(copy))
+            .filter(|newest| !path.exists() || modified(newest) > modified(&path))
+            .cloned();
+        (copies, newest)
+    }
+
+    /// Settle a canto's sync conflicts by keeping whichever of its file and
+    /// the copies a sync service made of it was written last. This renames
+    /// and deletes files, so it's only done holding the store's `lock`.
+    pub fn resolve_conflicts(
+        &self,
+        _lock: &StoreLock,
+        cantica: CanticaId,
+        canto: u8,
+    ) -> Result<(), DucaError> {
+        let path = self.file(cantica, canto);
+        let unwritable = |e: io::Error| DucaError::Save {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+        let (copies, newest) = self.conflicted_copies(cantica, canto);
+        for copy in &copies {
+            if newest.as_ref() == Some(copy) {
+                fs::rename(copy, &path).map_err(unwritable)?;
+            } else {
+                fs::remove_file(copy).map_err(unwritable)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A canto's notes and bookmarks; none if it has no file. Reading
+    /// changes nothing on disk: where a sync conflict left copies of the
+    /// file, the one written last is read, and the others are only cleared
+    /// away by a change made under the lock.
+    pub fn load(&self, cantica: CanticaId, canto: u8) -> Result<CantoNotes, DucaError> {
+        let path = match self.conflicted_copies(cantica, canto) {
+            (_, Some(newest)) => newest,
+            _ => self.file(cantica, canto),
+        };
+        let unreadable = |reason: String| DucaError::UserData {
+            path: path.display().to_string(),
+            reason,
+        };
+        let content = match fs::read(&path) {
+            Ok(content) => self.decode(content).map_err(unreadable)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(CantoNotes::new(cantica, canto))
+            }
+            Err(e) => return Err(unreadable(e.to_string())),
+        };
+        let content = String::from_utf8(content).map_err(|e| unreadable(e.to_string()))?;
+        CantoNotes::parse(&content, cantica, canto, &path.display().to_string())
+    }
+
+    /// Write a canto's file, or delete it once it has nothing left. The file
+    /// is replaced whole, so an interrupted write leaves the old one.
+    pub fn save(&self, notes: &CantoNotes) -> Result<(), DucaError> {
+        let path = self.file(notes.cantica, notes.canto);
+        let unwritable = |e: io::Error| DucaError::Save {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+        if notes.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(unwritable(e)),
+                _ => {
+                    // Only succeeds once the cantica has no files left
+                    let _ = path.parent().map(fs::remove_dir);
+                    Ok(())
+                }
+            };
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(unwritable)?;
+        }
+        let content = self
+            .encode(notes.to_markdown())
+            .map_err(|reason| DucaError::Save {
+                path: path.display().to_string(),
+                reason,
+            })?;
+        write_whole(&path, &content).map_err(unwritable)
+    }
+
+    /// A canto file's Markdown from its `content`, decrypted if the store
+    /// is encrypted.
+    fn decode(&self, content: Vec<u8>) -> Result<Vec<u8>, String> {
+        if !self.is_encrypted() {
+            return Ok(content);
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(identity) = &self.identity {
+            return age::decrypt(identity, &content).map_err(|e| e.to_string());
+        }
+        Err(LOCKED.to_string())
+    }
+
+    /// What to write to a canto file for its `markdown`, encrypted if the
+    /// store is encrypted.
+    fn encode(&self, markdown: String) -> Result<Vec<u8>, String> {
+        if !self.is_encrypted() {
+            return Ok(markdown.into_bytes());
+        }
+        #[cfg(feature = "encryption")]
+        if let Some(identity) = &self.identity {
+            return age::encrypt_and_armor(&identity.to_public(), markdown.as_bytes())
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string());
+        }
+        Err(LOCKED.to_string())
+    }
+
+    /// Every canto with notes or bookmarks, in reading order. Like
+    /// [`NoteStore::load`], this only reads.
+    pub fn all(&self) -> Result<Vec<CantoNotes>, DucaError> {
+        self.cantos()
+            .into_iter()
+            .map(|(cantica, canto)| self.load(cantica, canto))
+            .collect()
+    }
+
+    /// Settle every canto's sync conflicts, holding the store's `lock`.
+    pub fn resolve_all_conflicts(&self, lock: &StoreLock) -> Result<(), DucaError> {
+        for (cantica, canto) in self.cantos() {
+            self.resolve_conflicts(lock, cantica, canto)?;
+        }
+        Ok(())
+    }
+
+    /// Every canto with a file, or a conflicted copy of one, in reading order.
+    fn cantos(&self) -> Vec<(CanticaId, u8)> {
+        let mut all = Vec::new();
+        for cantica in CanticaId::ALL {
+            let Ok(entries) = fs::read_dir(self.dir.join(cantica.key())) else {
+                continue;
+            };
+            // Conflicted copies keep only the last extension
+            let suffix = format!(
+                ".{}",
+                self.extension().rsplit('.').next().unwrap_or_default()
+            );
+            let mut cantos: Vec<u8> = entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_str()?.strip_suffix(&suffix)?;
+                    // A conflicted copy's name starts with its canto's
+                    let digits = name.find(|c: char| !c.is_ascii_digit());
+                    name[..digits.unwrap_or(name.len())].parse().ok()
+                })
+                .collect();
+            cantos.sort();
+            cantos.dedup();
+            all.extend(cantos.into_iter().map(|canto| (cantica, canto)));
+        }
+        all
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl NoteStore {
+    /// Decrypt the store's key with `passphrase`, so its notes can be read
+    /// and written. Does nothing if the store isn't encrypted.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), DucaError> {
+        use age::secrecy::SecretString;
+
+        if !self.is_encrypted() {
+            return Ok(());
+        }
+        let path = self.dir.join(KEY_FILE);
+        let unreadable = |reason: String| DucaError::UserData {
+            path: path.display().to_string(),
+            reason,
+        };
+        let content = fs::read(&path).map_err(|e| unreadable(e.to_string()))?;
+        let passphrase = age::scrypt::Identity::new(SecretString::from(passphrase.to_string()));
+        let key = age::decrypt(&passphrase, &content).map_err(|e| match e {
+            age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => {
+                unreadable("wrong passphrase".to_string())
+            }
+            e => unreadable(e.to_string()),
+        })?;
+        let identity = String::from_utf8(key)
+            .ok()
+            .and_then(|key| key.trim().parse().ok())
+            .ok_or_else(|| unreadable("not a duca key".to_string()))?;
+        self.identity = Some(identity);
+        Ok(())
+    }
+
+    /// Encrypt every canto file to a new key, kept encrypted with
+    /// `passphrase`, and return how many there were.
+    pub fn encrypt(&mut self, passphrase: &str) -> Result<usize, DucaError> {
+        use age::secrecy::{ExposeSecret, SecretString};
+
+        let lock = self.lock()?;
+        let key_path = self.dir.join(KEY_FILE);
+        let unwritable = |path: &Path, reason: String| DucaError::Save {
+            path: path.display().to_string(),
+            reason,
+        };
+        if self.is_encrypted() {
+            return Err(unwritable(
+                &key_path,
+                "the notes are already encrypted".to_string(),
+            ));
+        }
+        self.resolve_all_conflicts(&lock)?;
+        let cantos = self.all()?;
+        let identity = age::x25519::Identity::generate();
+        self.identity = Some(identity.clone());
+
+        // The encrypted files are ignored until the key is written, so an
+        // interruption before then leaves the notes as they were
+        let mut plain = Vec::new();
+        for notes in &cantos {
+            let path = self.file(notes.cantica, notes.canto);
+            let content =
+                age::encrypt_and_armor(&identity.to_public(), notes.to_markdown().as_bytes())
+                    .map_err(|e| unwritable(&path, e.to_string()))?;
+            let encrypted = path.with_extension("md.age");
+            write_whole(&encrypted, content.as_bytes())
+                .map_err(|e| unwritable(&encrypted, e.to_string()))?;
+            plain.push(path);
+        }
+        let passphrase = age::scrypt::Recipient::new(SecretString::from(passphrase.to_string()));
+        let key =
+            age::encrypt_and_armor(&passphrase, identity.to_string().expose_secret().as_bytes())
+                .map_err(|e| unwritable(&key_path, e.to_string()))?;
+        write_whole(&key_path, key.as_bytes()).map_err(|e| unwritable(&key_path, e.to_string()))?;
+        for path in plain {
+            fs::remove_file(&path).map_err(|e| unwritable(&path, e.to_string()))?;
+        }
+        Ok(cantos.len())
+    }
+
+    /// Decrypt every canto file of the unlocked store back to Markdown and
+    /// drop the key, returning how many files there were.
+    pub fn decrypt(&mut self) -> Result<usize, DucaError> {
+        let lock = self.lock()?;
+        let key_path = self.dir.join(KEY_FILE);
+        let unwritable = |path: &Path, reason: String| DucaError::Save {
+            path: path.display().to_string(),
+            reason,
+        };
+        if !self.is_encrypted() {
+            return Err(unwritable(
+                &key_path,
+                "the notes aren't encrypted".to_string(),
+            ));
+        }
+        self.resolve_all_conflicts(&lock)?;
+        let cantos = self.all()?;
+
+        // The Markdown files are ignored until the key is removed
+        let mut encrypted = Vec::new();
+        for notes in &cantos {
+            let path = self.file(notes.cantica, notes.canto);
+            let plain = path.with_extension("");
+            write_whole(&plain, notes.to_markdown().as_bytes())
+                .map_err(|e| unwritable(&plain, e.to_string()))?;
+            encrypted.push(path);
+        }
+        fs::remove_file(&key_path).map_err(|e| unwritable(&key_path, e.to_string()))?;
+        self.identity = None;
+        for path in encrypted {
+            fs::remove_file(&path).map_err(|e| unwritable(&path, e.to_string()))?;
+        }
+        Ok(cantos.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let mut notes = CantoNotes::new(CanticaId::Inferno, 5);
+        notes.add_note(121..=123, "Boethius, *Consolation* II.4");
+        notes.add_note(100..=106, "Amor, Amor, Amor");
+        notes.add_note(100..=106, "# not a heading\nsecond line");
+        notes.set_bookmark(121, Some("Nessun maggior dolore"));
+        notes.set_bookmark(100, None);
+        assert_eq!(notes.tag_bookmark(121, &["#exam", "Francesca", "2"]), 2);
+        assert_eq!(notes.tag_bookmark(121, &["exam"]), 0);
+        notes.tag_bookmark(100, &["similes"]);
+
+        let markdown = notes.to_markdown();
+        assert_eq!(
+            markdown,
+            "# Inferno 5\n\n## Bookmarks\n\n- 100 #similes\n\
+             - 121: Nessun maggior dolore #exam #francesca\n\n\
+             ## Notes\n\n### 100-106\n\nAmor, Amor, Amor\n\n\
+             ### 100-106\n\n\\# not a heading\nsecond line\n\n\
+             ### 121-123\n\nBoethius, *Consolation* II.4\n"
+        );
+        let parsed = CantoNotes::parse(&markdown, CanticaId::Inferno, 5, "05.md").unwrap();
+        assert_eq!(parsed, notes);
+        assert!(parsed.bookmarks[1].has_tags(&["francesca".to_string()]));
+        assert!(!parsed.bookmarks[0].has_tags(&["similes".to_string(), "exam".to_string()]));
+
+        // Relabeling keeps the tags
+        notes.set_bookmark(121, Some("Francesca"));
+        assert_eq!(notes.bookmarks[1].tags, ["exam", "francesca"]);
+        assert_eq!(notes.untag_bookmark(121, &["exam", "ulysses"]), 1);
+        assert_eq!(notes.bookmarks[1].tags, ["francesca"]);
+
+        assert_eq!(notes.remove_notes(&(100..=106)), 2);
+        assert!(notes.remove_bookmark(100));
+        assert!(!notes.remove_bookmark(100));
+        notes.remove_bookmark(121);
+        notes.remove_notes(&(121..=123));
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_notes() {
+        let parse = |content| CantoNotes::parse(content, CanticaId::Inferno, 1, "01.md");
+        assert!(parse("# Inferno 1\n\n## Bookmarks\n\n- one\n").is_err());
+        assert!(parse("## Notes\n\n### 10-2\n").is_err());
+        let err = parse("# Inferno 1\n\nstray text\n").unwrap_err();
+        assert!(err.to_string().contains("line 3: text outside a note"));
+
+        // A heading-like line written by hand inside a note stays in it
+        let notes = parse("## Notes\n\n### 5\n\n# kept\n\\# also kept\n").unwrap();
+        assert_eq!(notes.notes[0].text, "# kept\n# also kept");
+
+        // Only words after a # at the end of a bookmark are its tags
+        let notes = parse("## Bookmarks\n\n- 13: canto #1 #Exam\n- 14: #\n").unwrap();
+        assert_eq!(notes.bookmarks[0].label.as_deref(), Some("canto #1"));
+        assert_eq!(notes.bookmarks[0].tags, ["exam"]);
+        assert_eq!(notes.bookmarks[1].label.as_deref(), Some("#"));
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = std::env::temp_dir().join(format!("duca-notes-{}", std::process::id()));
+        let store = NoteStore::new(dir.clone());
+        let mut notes = store.load(CanticaId::Purgatorio, 2).unwrap();
+        assert!(notes.is_empty());
+        notes.set_bookmark(112, Some("Amor che ne la mente mi ragiona"));
+        store.save(&notes).unwrap();
+        assert!(dir.join("purgatorio/02.md").exists());
+        assert_eq!(store.all().unwrap(), vec![notes.clone()]);
+
+        notes.remove_bookmark(112);
+        store.save(&notes).unwrap();
+        assert!(!dir.join("purgatorio").exists());
+        assert!(store.all().unwrap().is_empty());
+        assert!(!store.is_encrypted());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock() {
+        let dir = std::env::temp_dir().join(format!("duca-notes-lock-{}", std::process::id()));
+        let store = NoteStore::new(dir.clone());
+        let lock = store.lock().unwrap();
+        assert!(dir.join(LOCK_FILE).exists());
+        drop(lock);
+        assert!(!dir.join(LOCK_FILE).exists());
+
+        // A lock left behind long ago is taken over at once
+        let stale = fs::File::create(dir.join(LOCK_FILE)).unwrap();
+        stale
+            .set_modified(SystemTime::now() - LOCK_STALE * 2)
+            .unwrap();
+        let started = Instant::now();
+        let removed = store
+            .update(CanticaId::Inferno, 1, |notes| notes.remove_bookmark(1))
+            .unwrap();
+        assert!(!removed);
+        assert!(started.elapsed() < LOCK_WAIT);
+        assert!(!dir.join(LOCK_FILE).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sync_conflicts() {
+        assert!(is_conflicted_copy(
+            "05 (conflicted copy 2025-01-02).md",
+            "05.md"
+        ));
+        assert!(is_conflicted_copy(
+            "05 (laptop's conflicted copy 2025-01-02).md",
+            "05.md"
+        ));
+        assert!(is_conflicted_copy(
+            "05.sync-conflict-20250102-101500-ABCDEFG.md",
+            "05.md"
+        ));
+        assert!(!is_conflicted_copy("05.md", "05.md"));
+        assert!(!is_conflicted_copy("15.md", "05.md"));
+        assert!(is_conflicted_copy(
+            "05.md.sync-conflict-20250102-101500-ABCDEFG.age",
+            "05.md.age"
+        ));
+        assert!(!is_conflicted_copy("05.md", "05.md.age"));
+
+        let dir = std::env::temp_dir().join(format!("duca-notes-sync-{}", std::process::id()));
+        let store = NoteStore::new(dir.clone());
+        let write = |name: &str, line: usize, age: u64| {
+            let mut notes = CantoNotes::new(CanticaId::Inferno, 5);
+            notes.set_bookmark(line, None);
+            let path = dir.join("inferno").join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, notes.to_markdown()).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+        };
+        write("05.md", 100, 60);
+        write("05.sync-conflict-20250102-101500-ABCDEFG.md", 121, 10);
+        write("05 (conflicted copy 2025-01-01).md", 82, 120);
+
+        // The copy written last is read, and reading leaves the files be
+        let all = store.all().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].bookmarks[0].line, 121);
+        assert_eq!(fs::read_dir(dir.join("inferno")).unwrap().count(), 3);
+
+        // Settling them under the lock keeps it, and the others are gone
+        store.resolve_all_conflicts(&store.lock().unwrap()).unwrap();
+        assert_eq!(
+            store.load(CanticaId::Inferno, 5).unwrap().bookmarks[0].line,
+            121
+        );
+        assert_eq!(fs::read_dir(dir.join("inferno")).unwrap().count(), 1);
+
+        // An older copy loses to the file, and a change settles it
+        write("05 (conflicted copy 2025-01-01).md", 82, 600);
+        assert_eq!(
+            store.load(CanticaId::Inferno, 5).unwrap().bookmarks[0].line,
+            121
+        );
+        store
+            .update(CanticaId::Inferno, 5, |notes| notes.set_bookmark(5, None))
+            .unwrap();
+        let lines: Vec<usize> = store
+            .load(CanticaId::Inferno, 5)
+            .unwrap()
+            .bookmarks
+            .iter()
+            .map(|b| b.line)
+            .collect();
+        assert_eq!(lines, [5, 121]);
+        assert_eq!(fs::read_dir(dir.join("inferno")).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encryption() {
+        let dir = std::env::temp_dir().join(format!("duca-notes-age-{}", std::process::id()));
+        let mut store = NoteStore::new(dir.clone());
+        let mut notes = CantoNotes::new(CanticaId::Paradiso, 33);
+        notes.add_note(145..=145, "l'amor che move il sole e l'altre stelle");
+        store.save(&notes).unwrap();
+
+        assert_eq!(store.encrypt("beatrice").unwrap(), 1);
+        assert!(store.is_encrypted());
+        let file = dir.join("paradiso/33.md.age");
+        assert!(!dir.join("paradiso/33.md").exists());
+        let content = fs::read_to_string(&file).unwrap();
+        assert!(content.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert!(!content.contains("amor"));
+        assert_eq!(store.all().unwrap(), vec![notes.clone()]);
+
+        // Another process has to unlock the store first
+        let mut other = NoteStore::new(dir.clone());
+        assert!(other.all().unwrap_err().to_string().contains("encrypted"));
+        let err = other.unlock("virgilio").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+        other.unlock("beatrice").unwrap();
+        other
+            .update(CanticaId::Paradiso, 33, |notes| {
+                notes.set_bookmark(145, None)
+            })
+            .unwrap();
+        notes.set_bookmark(145, None);
+        assert_eq!(store.all().unwrap(), vec![notes.clone()]);
+
+        assert_eq!(other.decrypt().unwrap(), 1);
+        assert!(!store.is_encrypted());
+        assert!(!file.exists());
+        assert_eq!(store.all().unwrap(), vec![notes]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+//! Desktop notifications, sent by running a command rather than through a
+//! notification library: `notify-send` or `osascript`, which desktops
+//! already have, or whatever `$DUCA_NOTIFY_CMD` names, so reminders from
+//! cron or a systemd timer can go anywhere.
+
+use std::env;
+use std::io::{self, ErrorKind};
+use std::process::{Command, Stdio};
+
+/// The command sending a desktop notification: `$DUCA_NOTIFY_CMD` when set,
+/// given the summary and body as its last two arguments, or else
+/// `notify-send` and, on macOS, `osascript`. `None` on other systems, where
+/// there is no notifier to fall back on.
+fn invocation(notify_env: Option<String>, summary: &str, body: &str) -> Option<Vec<String>> {
+    if let Some(command) = notify_env.filter(|command| !command.trim().is_empty()) {
+        let mut invocation: Vec<String> = command.split_whitespace().map(String::from).collect();
+        invocation.extend([summary.to_string(), body.to_string()]);
+        return Some(invocation);
+    }
+    if cfg!(target_os = "macos") {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display notification {} with title {}",
+            quote(body),
+            quote(summary)
+        );
+        Some(vec!["osascript".to_string(), "-e".to_string(), script])
+    } else if cfg!(unix) {
+        Some(vec![
+            "notify-send".to_string(),
+            "--app-name=duca".to_string(),
+            summary.to_string(),
+            body.to_string(),
+        ])
+    } else {
+        None
+    }
+}
+
+/// Show a desktop notification, waiting for the command to hand it over.
+/// Fails, rather than doing nothing, when there's no notifier to run.
+pub fn send(summary: &str, body: &str) -> io::Result<()> {
+    let invocation = invocation(env::var("DUCA_NOTIFY_CMD").ok(), summary, body)
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no desktop notifier on this system"))?;
+    let (program, args) = invocation
+        .split_first()
+        .expect("a notification command has a program");
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => io::Error::new(
+                ErrorKind::NotFound,
+                format!("no notifier found: {} isn't installed", program),
+            ),
+            kind => io::Error::new(kind, format!("{}: {}", program, e)),
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_invocation() {
+        assert_eq!(
+            invocation(
+                Some("notify-send -u low".to_string()),
+                "Inferno I",
+                "Nel mezzo"
+            )
+            .unwrap(),
+            ["notify-send", "-u", "low", "Inferno I", "Nel mezzo"]
+        );
+        let default = invocation(None, "Inferno I", "Nel mezzo");
+        if cfg!(unix) {
+            assert!(default.unwrap().iter().any(|arg| arg.contains("Nel mezzo")));
+        } else {
+            assert!(default.is_none());
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Old-Italian spelling for `search --modern`, so a query in modern spelling
+//! finds the fourteenth-century text.
+//!
+//! Words are compared folded, with the letters the old orthography used
+//! interchangeably spelled one way: `v` as `u` and `j` as `i`. An elided
+//! word matches the word it shortens by a vowel, however the elision is
+//! marked: `ch’` is `che`, `de’` is `dei`, `com’` is `come`, and the `’l` of
+//! `che ’l sole` is `il`, so `che il sole` finds it.
+
+use std::ops::Range;
+
+use crate::normalize;
+use crate::words::word_ranges;
+
+/// A word of a verse or query, as [`words`] splits them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    /// The word folded and [respelled](respell).
+    pub spelling: String,
+    /// Where it is in the text.
+    pub range: Range<usize>,
+    /// Whether an apostrophe before it marks a vowel cut from its start,
+    /// as in `’l`.
+    pub cut_before: bool,
+    /// Whether an apostrophe after it marks a vowel cut from its end, as
+    /// in `ch’`.
+    pub cut_after: bool,
+}
+
+/// `c` spelled as this module compares it: `v` as `u` and `j` as `i`, in
+/// either case.
+pub fn respell_char(c: char) -> char {
+    match c {
+        'v' => 'u',
+        'V' => 'U',
+        'j' => 'i',
+        'J' => 'I',
+        c => c,
+    }
+}
+
+/// `word` folded, with `v` spelled `u` and `j` spelled `i`.
+pub fn respell(word: &str) -> String {
+    normalize::fold(word).chars().map(respell_char).collect()
+}
+
+fn is_apostrophe(c: char) -> bool {
+    normalize::fold_char(c) == '\''
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiou".contains(c)
+}
+
+/// The words of `text`, respelled and with their elisions noted.
+pub fn words(text: &str) -> Vec<Word> {
+    words_with(text, respell)
+}
+
+/// The words of `text` with their elisions noted, each spelled by `spell`.
+pub fn words_with(text: &str, spell: impl Fn(&str) -> String) -> Vec<Word> {
+    word_ranges(text)
+        .into_iter()
+        .map(|range| {
+            let mut before = text[..range.start].chars().rev();
+            let cut_before = before.next().is_some_and(is_apostrophe)
+                && before.next().is_none_or(|c| !c.is_alphanumeric());
+            let cut_after = text[range.end..].chars().next().is_some_and(is_apostrophe);
+            Word {
+                spelling: spell(&text[range.clone()]),
+                range,
+                cut_before,
+                cut_after,
+            }
+        })
+        .collect()
+}
+
+/// Whether `short`, elided, is `full` with a vowel cut.
+fn shortens(short: &Word, full: &Word) -> bool {
+    let one_vowel = |rest: &str| {
+        let mut chars = rest.chars();
+        chars.next().is_some_and(is_vowel) && chars.next().is_none()
+    };
+    (short.cut_after
+        && full
+            .spelling
+            .strip_prefix(short.spelling.as_str())
+            .is_some_and(one_vowel))
+        || (short.cut_before
+            && full
+                .spelling
+                .strip_suffix(short.spelling.as_str())
+                .is_some_and(one_vowel))
+}
+
+/// Whether two words are the same word, allowing for spelling and elision.
+pub fn same_word(a: &Word, b: &Word) -> bool {
+    a.spelling == b.spelling || shortens(a, b) || shortens(b, a)
+}
+
+/// Byte ranges in `text` of each run of its words matching the words of
+/// `query` in turn, spanning any apostrophes between them.
+pub fn find(text: &str, query: &[Word]) -> Vec<Range<usize>> {
+    find_runs(&words(text), query, same_word)
+}
+
+/// Byte ranges of each run of `words` that are, by `same`, the words of
+/// `query` in turn.
+pub(crate) fn find_runs(
+    words: &[Word],
+    query: &[Word],
+    same: impl Fn(&Word, &Word) -> bool,
+) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    words
+        .windows(query.len())
+        .filter(|run| run.iter().zip(query).all(|(a, b)| same(a, b)))
+        .map(|run| run[0].range.start..run[run.len() - 1].range.end)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found<'a>(text: &'a str, query: &str) -> Vec<&'a str> {
+        find(text, &words(query))
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_respell() {
+        assert_eq!(respell("Vidi"), "uidi");
+        assert_eq!(respell("Jacopo"), "iacopo");
+        assert_eq!(respell("pietà"), "pieta");
+    }
+
+    #[test]
+    fn test_words() {
+        let words = words("che ’l sol, ch’intrate");
+        let spellings: Vec<&str> = words.iter().map(|w| w.spelling.as_str()).collect();
+        assert_eq!(spellings, ["che", "l", "sol", "ch", "intrate"]);
+        assert!(words[1].cut_before && !words[1].cut_after);
+        assert!(words[3].cut_after);
+        // An apostrophe inside a word cuts the one before it, not the next
+        assert!(!words[4].cut_before);
+    }
+
+    #[test]
+    fn test_find() {
+        let text = "Lasciate ogne speranza, voi ch’intrate";
+        assert_eq!(found(text, "che intrate"), ["ch’intrate"]);
+        assert_eq!(found(text, "uoi"), ["voi"]);
+        assert_eq!(found("e vidi Jacopo", "vidi iacopo"), ["vidi Jacopo"]);
+        assert_eq!(found("che ’l sole", "che il sole"), ["che ’l sole"]);
+        assert_eq!(found("fra li occhi de’ pastori", "dei"), ["de"]);
+        assert_eq!(found("com’ io", "come io"), ["com’ io"]);
+        // Whole words, and an elision cuts no more than a vowel
+        assert!(found(text, "lascia").is_empty());
+        assert!(found("ch’intrate", "chiede").is_empty());
+        assert!(found(text, "").is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! The shapes of the structured output commands print with `--format json`,
+//! kept apart from the corpus types so those can change without breaking
+//! scripts that read it.
+//!
+//! Every document carries [`SCHEMA_VERSION`], and `duca schema` prints the
+//! JSON Schema of each, generated from these types so it can't drift.
+//!
+//! `--format quickfix` prints a line per verse instead, for editors' lists
+//! of locations, each naming its canto by a [`canto_uri`].
+
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::{Canto, SearchMatch, Verse};
+
+/// Version of the JSON shapes below. It goes up when a field is removed,
+/// renamed or changes meaning; fields may be added without a new version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A canto, or the excerpt of it asked for, as `canto --format json`
+/// prints it.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CantoOutput<'a> {
+    /// Version of the document's shape, raised when it changes incompatibly.
+    pub schema_version: u32,
+    /// The cantica, or part of another work.
+    pub cantica: &'a str,
+    pub number: u8,
+    pub roman_numeral: &'a str,
+    /// The canto's first line, by which it is traditionally cited.
+    pub incipit: &'a str,
+    pub verses: Vec<VerseOutput<'a>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerseOutput<'a> {
+    pub line_number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> CantoOutput<'a> {
+    /// `verses` of `canto`, which belongs to `cantica`.
+    pub fn new(
+        cantica: &'a str,
+        canto: &'a Canto,
+        verses: impl IntoIterator<Item = &'a Verse>,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            cantica,
+            number: canto.number,
+            roman_numeral: &canto.roman_numeral,
+            incipit: &canto.incipit,
+            verses: verses
+                .into_iter()
+                .map(|verse| VerseOutput {
+                    line_number: verse.line_number,
+                    text: &verse.text,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The matches of one pattern, as `search --format json` prints them.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SearchOutput<'a> {
+    /// Version of the document's shape, raised when it changes incompatibly.
+    pub schema_version: u32,
+    pub pattern: &'a str,
+    pub count: usize,
+    pub results: Vec<MatchOutput<'a>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MatchOutput<'a> {
+    /// The cantica, or part of another work.
+    pub cantica: &'a str,
+    pub canto: u8,
+    pub line: usize,
+    pub text: &'a str,
+    /// Byte ranges of `text` the pattern matched.
+    pub spans: Vec<Range<usize>>,
+}
+
+impl<'a> SearchOutput<'a> {
+    /// The matches of `pattern`, in the order given.
+    pub fn new(pattern: &'a str, results: Vec<MatchOutput<'a>>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            pattern,
+            count: results.len(),
+            results,
+        }
+    }
+}
+
+impl<'a> From<&'a SearchMatch<'a>> for MatchOutput<'a> {
+    fn from(m: &'a SearchMatch<'a>) -> Self {
+        Self {
+            cantica: m.cantica.name(),
+            canto: m.canto,
+            line: m.line,
+            text: m.text,
+            spans: m.spans.clone(),
+        }
+    }
+}
+
+/// The `duca://` address of a canto, such as `duca://inferno/5`: the file
+/// of the lines `--format quickfix` prints, and what rpc `lookup` takes as
+/// its `uri`.
+pub fn canto_uri(cantica: &str, canto: u8) -> String {
+    format!("duca://{}/{}", cantica.to_lowercase(), canto)
+}
+
+/// The cantica and canto a [`canto_uri`] addresses.
+pub fn parse_canto_uri(uri: &str) -> Option<(&str, u8)> {
+    let (cantica, canto) = uri.strip_prefix("duca://")?.rsplit_once('/')?;
+    Some((cantica, canto.parse().ok()?))
+}
+
+/// A verse as `--format quickfix` prints it, `duca://inferno/5:100: text`,
+/// the `file:line: message` that vim's quickfix list and others read.
+pub fn quickfix_line(cantica: &str, canto: u8, line: usize, text: &str) -> String {
+    format!("{}:{}: {}", canto_uri(cantica, canto), line, text)
+}
+
+/// A kind of document `--format json` prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Document {
+    Canto,
+    Search,
+}
+
+/// The JSON Schema of `document`.
+pub fn schema(document: Document) -> serde_json::Value {
+    let schema = match document {
+        Document::Canto => schema_for!(CantoOutput),
+        Document::Search => schema_for!(SearchOutput),
+    };
+    serde_json::to_value(schema).expect("schemas serialize to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_commedia;
+    use crate::CanticaId;
+
+    #[test]
+    fn test_quickfix_lines() {
+        assert_eq!(
+            quickfix_line(
+                "Inferno",
+                5,
+                100,
+                "Amor, ch’al cor gentil ratto s’apprende,"
+            ),
+            "duca://inferno/5:100: Amor, ch’al cor gentil ratto s’apprende,"
+        );
+        assert_eq!(parse_canto_uri("duca://inferno/5"), Some(("inferno", 5)));
+        assert_eq!(parse_canto_uri("duca://inferno/v"), None);
+        assert_eq!(parse_canto_uri("file://inferno/5"), None);
+    }
+
+    #[test]
+    fn test_documents_match_their_schemas() {
+        let commedia = load_commedia().unwrap();
+        let canto = commedia.canto(CanticaId::Inferno, 1).unwrap();
+        let document =
+            serde_json::to_value(CantoOutput::new("Inferno", canto, &canto.verses)).unwrap();
+        let canto_schema = schema(Document::Canto);
+        assert_eq!(document["schema_version"], SCHEMA_VERSION);
+        assert_fields_declared(&document, &canto_schema);
+        assert_fields_declared(
+            &document["verses"][0],
+            &canto_schema["definitions"]["VerseOutput"],
+        );
+
+        let matches = commedia.search("selva", None);
+        let results = matches.iter().map(MatchOutput::from).collect();
+        let document = serde_json::to_value(SearchOutput::new("selva", results)).unwrap();
+        let search_schema = schema(Document::Search);
+        assert_eq!(document["count"], matches.len());
+        assert_fields_declared(&document, &search_schema);
+        assert_fields_declared(
+            &document["results"][0],
+            &search_schema["definitions"]["MatchOutput"],
+        );
+    }
+
+    /// Every field of `object` is a required property of `schema`, and the
+    /// other way round.
+    fn assert_fields_declared(object: &serde_json::Value, schema: &serde_json::Value) {
+        let mut fields: Vec<&String> = object.as_object().unwrap().keys().collect();
+        let mut required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field.as_str().unwrap())
+            .collect();
+        fields.sort();
+        required.sort();
+        assert_eq!(fields, required);
+        for field in fields {
+            assert!(schema["properties"].get(field).is_some(), "{}", field);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::text;
+
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Print `output` to stdout, routing it through `$PAGER` when stdout is a
+/// terminal and the output would not fit on one screen.
+pub fn print(output: &str, no_pager: bool) -> Result<()> {
+    if !no_pager && should_page(output) {
+        if let Some(args) = pager_command(env::var("PAGER").ok()) {
+            if let Ok(mut child) = Command::new(&args[0])
+                .args(&args[1..])
+                .stdin(Stdio::piped())
+                .spawn()
+            {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // The user quitting the pager early closes the pipe; that's not an error
+                    if let Err(e) = stdin.write_all(output.as_bytes()) {
+                        if e.kind() != io::ErrorKind::BrokenPipe {
+                            return Err(e.into());
+                        }
+                    }
+                }
+                child.wait()?;
+                return Ok(());
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(output.as_bytes()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| e.into()),
+    }
+}
+
+fn should_page(output: &str) -> bool {
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+
+    match text::terminal_size() {
+        Some((_, rows)) => output.lines().count() >= rows,
+        None => false,
+    }
+}
+
+/// Split the pager command line, falling back to `less -R` when `$PAGER` is
+/// unset or blank.
+fn pager_command(pager_env: Option<String>) -> Option<Vec<String>> {
+    let pager = pager_env
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+
+    let args: Vec<String> = pager.split_whitespace().map(String::from).collect();
+    if args.is_empty() {
+        None
+    } else {
+        Some(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command_default() {
+        assert_eq!(
+            pager_command(None),
+            Some(vec!["less".to_string(), "-R".to_string()])
+        );
+        assert_eq!(
+            pager_command(Some("   ".to_string())),
+            Some(vec!["less".to_string(), "-R".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_pager_command_from_env() {
+        assert_eq!(
+            pager_command(Some("more".to_string())),
+            Some(vec!["more".to_string()])
+        );
+        assert_eq!(
+            pager_command(Some("less -FRX".to_string())),
+            Some(vec!["less".to_string(), "-FRX".to_string()])
+        );
+    }
+}
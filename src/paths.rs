@@ -0,0 +1,101 @@
+//! Where duca looks for the user's own files.
+
+use std::env;
+use std::path::PathBuf;
+
+/// duca's per-user data directory: `$XDG_DATA_HOME/duca`, falling back to
+/// `~/.local/share/duca`. `None` if neither variable is set.
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .map(|dir| dir.join("duca"))
+}
+
+/// Directory of downloads kept by `duca fetch`: `$DUCA_CACHE_DIR`, or
+/// `$XDG_CACHE_HOME/duca`, falling back to `~/.cache/duca`.
+pub fn cache_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_CACHE_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("XDG_CACHE_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join("duca"))
+        })
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/duca")))
+}
+
+/// Directory of apparatus files: `$DUCA_APPARATUS_DIR`, or `apparatus`
+/// inside [`data_dir`].
+pub fn apparatus_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_APPARATUS_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| data_dir().map(|dir| dir.join("apparatus")))
+}
+
+/// Directory of user-added works: `$DUCA_WORKS_DIR`, or `works` inside
+/// [`data_dir`].
+pub fn works_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_WORKS_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| data_dir().map(|dir| dir.join("works")))
+}
+
+/// Directory of user-supplied editions: `$DUCA_EDITIONS_DIR`, or `editions`
+/// inside [`data_dir`].
+pub fn editions_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_EDITIONS_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| data_dir().map(|dir| dir.join("editions")))
+}
+
+/// Directory of files registering audio recordings: `$DUCA_AUDIO_DIR`, or
+/// `audio` inside [`data_dir`].
+pub fn audio_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_AUDIO_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| data_dir().map(|dir| dir.join("audio")))
+}
+
+/// Directory of the state the user builds up while reading, such as notes
+/// and bookmarks, which may be shared between machines by putting it in a
+/// synced folder: `$DUCA_STATE_DIR`, or [`data_dir`].
+pub fn state_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_STATE_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(data_dir)
+}
+
+/// Directory of the user's notes and bookmarks: `$DUCA_NOTES_DIR`, or
+/// `notes` inside [`state_dir`].
+pub fn notes_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_NOTES_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| state_dir().map(|dir| dir.join("notes")))
+}
+
+/// The log of reading sessions the TUI times: `$DUCA_READING_LOG`, or
+/// `reading-log.tsv` inside [`state_dir`].
+pub fn reading_log() -> Option<PathBuf> {
+    env::var_os("DUCA_READING_LOG")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| state_dir().map(|dir| dir.join("reading-log.tsv")))
+}
+
+/// Directory of commentary files: `$DUCA_COMMENTARY_DIR`, or `commentary`
+/// inside [`data_dir`].
+pub fn commentary_dir() -> Option<PathBuf> {
+    env::var_os("DUCA_COMMENTARY_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| data_dir().map(|dir| dir.join("commentary")))
+}
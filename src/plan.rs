@@ -0,0 +1,228 @@
+//! A reading plan: the poem's hundred cantos in reading order, one a day
+//! from a start date, so `duca today` can say which canto is due.
+//!
+//! The plan starts on `$DUCA_PLAN_START` (a `YYYY-MM-DD` date) or, without
+//! one, on 1 January of the current year, and starts over once the hundred
+//! days are done.
+
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{CanticaId, DucaError};
+
+/// Days in the plan: one for each canto.
+pub const DAYS: usize = 100;
+
+/// The canto read on a `day` of the plan, from 1 to [`DAYS`]: the
+/// Inferno's 34 cantos, then the Purgatorio's 33 and the Paradiso's 33.
+pub fn canto(day: usize) -> (CanticaId, u8) {
+    let day = (day.clamp(1, DAYS) - 1) as u8;
+    match day {
+        0..=33 => (CanticaId::Inferno, day + 1),
+        34..=66 => (CanticaId::Purgatorio, day - 33),
+        _ => (CanticaId::Paradiso, day - 66),
+    }
+}
+
+/// A day of the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The date, if `day` is one of `month`'s.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(Self { year, month, day })
+    }
+
+    /// Today's date in UTC. The system clock has no time zone without a
+    /// time zone database, so a local date comes from `--date`.
+    pub fn today() -> Self {
+        Self::of(SystemTime::now())
+    }
+
+    /// The date in UTC at `time`.
+    pub fn of(time: SystemTime) -> Self {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    /// Days since 1 January 1970, negative before it.
+    pub fn days(self) -> i64 {
+        // Howard Hinnant's days_from_civil, with years starting in March
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The date `days` days after 1 January 1970.
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+
+    /// The date `days` days later, or earlier if negative.
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.days() + days)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = DucaError;
+
+    /// Parse a `YYYY-MM-DD` date.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DucaError::InvalidDate(s.to_string());
+        let mut parts = s.trim().splitn(3, '-');
+        let mut part = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (part()?, part()?, part()?);
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+        let number = |part: &str| part.parse::<u32>().map_err(|_| invalid());
+        Self::new(number(year)? as i32, number(month)?, number(day)?).ok_or_else(invalid)
+    }
+}
+
+/// One canto a day from `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Plan {
+    pub start: Date,
+}
+
+impl Plan {
+    pub fn new(start: Date) -> Self {
+        Self { start }
+    }
+
+    /// The plan starting on `$DUCA_PLAN_START`, or else on 1 January of
+    /// `today`'s year.
+    pub fn from_env(today: Date) -> Result<Self, DucaError> {
+        match env::var("DUCA_PLAN_START") {
+            Ok(start) if !start.trim().is_empty() => Ok(Self::new(start.parse()?)),
+            _ => Ok(Self::new(Date {
+                year: today.year,
+                month: 1,
+                day: 1,
+            })),
+        }
+    }
+
+    /// The day of the plan `date` falls on, from 1 to [`DAYS`], counting
+    /// again from 1 after the last; `None` before the plan starts.
+    pub fn day(&self, date: Date) -> Option<usize> {
+        let elapsed = date.days() - self.start.days();
+        (elapsed >= 0).then(|| elapsed as usize % DAYS + 1)
+    }
+
+    /// The date of a `day` of the plan's first round.
+    pub fn date(&self, day: usize) -> Date {
+        self.start.add_days(day as i64 - 1)
+    }
+
+    /// The first day of the round `date` falls in, or the plan's start if
+    /// `date` comes before it.
+    pub fn round_start(&self, date: Date) -> Date {
+        let elapsed = (date.days() - self.start.days()).max(0);
+        self.start.add_days(elapsed - elapsed % DAYS as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        let date: Date = "2024-02-29".parse().unwrap();
+        assert_eq!(date, Date::new(2024, 2, 29).unwrap());
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(Date::new(2000, 3, 1).unwrap().days(), 11_017);
+        assert_eq!(date.add_days(1).to_string(), "2024-03-01");
+        assert_eq!(date.add_days(-365).to_string(), "2023-03-01");
+        for days in [-800_000, -1, 0, 59, 365, 19_782, 800_000] {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-1",
+            "24-01-01",
+            "today",
+            "",
+        ] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_plan_cantos() {
+        assert_eq!(canto(1), (CanticaId::Inferno, 1));
+        assert_eq!(canto(34), (CanticaId::Inferno, 34));
+        assert_eq!(canto(35), (CanticaId::Purgatorio, 1));
+        assert_eq!(canto(67), (CanticaId::Purgatorio, 33));
+        assert_eq!(canto(68), (CanticaId::Paradiso, 1));
+        assert_eq!(canto(100), (CanticaId::Paradiso, 33));
+    }
+
+    #[test]
+    fn test_plan_days() {
+        let plan = Plan::new("2025-04-10".parse().unwrap());
+        assert_eq!(plan.day("2025-04-09".parse().unwrap()), None);
+        assert_eq!(plan.day(plan.start), Some(1));
+        assert_eq!(plan.day("2025-05-10".parse().unwrap()), Some(31));
+        assert_eq!(plan.date(100).to_string(), "2025-07-18");
+        assert_eq!(plan.day(plan.date(100)), Some(100));
+        assert_eq!(plan.day(plan.date(101)), Some(1));
+
+        assert_eq!(plan.round_start(plan.date(100)), plan.start);
+        assert_eq!(plan.round_start(plan.date(150)), plan.date(101));
+        assert_eq!(plan.round_start("2024-01-01".parse().unwrap()), plan.start);
+    }
+}
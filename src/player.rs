@@ -0,0 +1,164 @@
+use std::env;
+use std::io;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use duca::audio::Recording;
+use duca::CanticaId;
+
+/// The audio player: `$DUCA_AUDIO_PLAYER` when set, or else `afplay` on
+/// macOS and `mpv --no-video --really-quiet` elsewhere. The recording's
+/// file is appended as the last argument.
+pub fn command() -> Vec<String> {
+    command_from(env::var("DUCA_AUDIO_PLAYER").ok())
+}
+
+fn command_from(player_env: Option<String>) -> Vec<String> {
+    let command = player_env
+        .filter(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "macos") {
+                "afplay".to_string()
+            } else {
+                "mpv --no-video --really-quiet".to_string()
+            }
+        });
+    command.split_whitespace().map(String::from).collect()
+}
+
+/// A recording playing in the player. Dropping it stops the player.
+#[derive(Debug)]
+pub struct Playback {
+    child: Child,
+    pub cantica: CanticaId,
+    pub canto: u8,
+    /// When the player last started or resumed, if it's playing.
+    resumed: Option<Instant>,
+    /// Time played before then.
+    played: Duration,
+}
+
+impl Playback {
+    /// Start playing `recording` with `command`.
+    pub fn start(command: &[String], recording: &Recording) -> io::Result<Self> {
+        let Some((program, args)) = command.split_first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no audio player",
+            ));
+        };
+        let child = Command::new(program)
+            .args(args)
+            .arg(&recording.file)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(Self {
+            child,
+            cantica: recording.cantica,
+            canto: recording.canto,
+            resumed: Some(Instant::now()),
+            played: Duration::ZERO,
+        })
+    }
+
+    /// How far into the recording playback is, going by the clock.
+    pub fn position(&self) -> Duration {
+        self.played
+            + self
+                .resumed
+                .map_or(Duration::ZERO, |resumed| resumed.elapsed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.resumed.is_none()
+    }
+
+    /// Pause or resume the player by stopping and continuing its process.
+    pub fn toggle_pause(&mut self) -> io::Result<()> {
+        let signal = if self.is_paused() { "-CONT" } else { "-STOP" };
+        signal_process(self.child.id(), signal)?;
+        match self.resumed.take() {
+            Some(resumed) => self.played += resumed.elapsed(),
+            None => self.resumed = Some(Instant::now()),
+        }
+        Ok(())
+    }
+
+    /// Whether the player has exited, at the end of the recording or not.
+    pub fn finished(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        if self.is_paused() {
+            let _ = signal_process(self.child.id(), "-CONT");
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(unix)]
+fn signal_process(pid: u32, signal: &str) -> io::Result<()> {
+    let status = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("kill {} {} failed", signal, pid)))
+    }
+}
+
+#[cfg(not(unix))]
+fn signal_process(_pid: u32, _signal: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "pausing the player needs a Unix system",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::audio::Recordings;
+    use std::path::Path;
+
+    #[test]
+    fn test_player_command() {
+        assert_eq!(
+            command_from(Some("mpv --no-video".to_string())),
+            ["mpv", "--no-video"]
+        );
+        assert!(!command_from(None).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_playback() {
+        // `sleep 30` stands in for a player, the "file" being its argument
+        let recordings = Recordings::parse(
+            "[[recording]]\ncanto = \"Inferno 1\"\nfile = \"30\"\n",
+            "test",
+            Path::new(""),
+        )
+        .unwrap();
+        let recording = recordings.for_canto(CanticaId::Inferno, 1).unwrap();
+        let mut playback = Playback::start(&["sleep".to_string()], recording).unwrap();
+        assert!(!playback.finished());
+
+        playback.toggle_pause().unwrap();
+        assert!(playback.is_paused());
+        let paused_at = playback.position();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(playback.position(), paused_at);
+        playback.toggle_pause().unwrap();
+        assert!(!playback.is_paused());
+        assert!(playback.position() >= paused_at);
+        assert!(!playback.finished());
+    }
+}
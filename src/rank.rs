@@ -0,0 +1,361 @@
+//! BM25 ranking for `search --rank bm25` and the server's `rank=bm25`: the
+//! verses with any word of the query, most relevant first, rather than every
+//! verse with the whole pattern in reading order.
+//!
+//! Words are compared through the search's [`Pipeline`], so a ranking with
+//! lemmas counts every form of a word. The [word index](crate::words) finds
+//! the verses worth scoring; a word's rarity is the share of verses it's in,
+//! and [stopwords](crate::stopwords) count for a tenth as they do elsewhere.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::normalize::{Normalizer, Pipeline};
+use crate::orthography::{self, Word};
+use crate::words::{word_ranges, Posting};
+use crate::{
+    normalize, stopwords, CanticaId, Canto, DivinaCommedia, DucaError, SearchMatch, Verse,
+};
+
+/// How quickly more uses of a word in a verse stop adding to its score.
+const K1: f32 = 1.2;
+
+/// How much a long verse's score is cut for its length.
+const B: f32 = 0.75;
+
+/// The order search results come in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rank {
+    /// Verses with the whole pattern, in reading order.
+    #[default]
+    Order,
+    /// Verses with any of the pattern's words, by [`bm25`] score.
+    Bm25,
+}
+
+impl Rank {
+    pub fn name(self) -> &'static str {
+        match self {
+            Rank::Order => "order",
+            Rank::Bm25 => "bm25",
+        }
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Rank {
+    type Err = DucaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "order" | "canonical" => Ok(Rank::Order),
+            "bm25" | "relevance" => Ok(Rank::Bm25),
+            _ => Err(DucaError::InvalidRank(s.to_string())),
+        }
+    }
+}
+
+/// A verse ranked by [`bm25`], its words of the query highlighted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredMatch<'a> {
+    pub matched: SearchMatch<'a>,
+    pub score: f32,
+    /// Words in the verse.
+    pub length: usize,
+    /// Its length over the average verse's, by which long verses score less.
+    pub relative_length: f32,
+    /// What each word of the query the verse has adds to its score, in the
+    /// query's order.
+    pub terms: Vec<TermScore>,
+}
+
+/// What a word of the query adds to a verse's [`bm25`] score: its rarity
+/// in the poem, times how often the verse uses it, times its weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermScore {
+    /// The word as the query has it.
+    pub term: String,
+    /// Which words of the verse it is, counting from 1.
+    pub positions: Vec<usize>,
+    /// Its inverse document frequency: the fewer verses have it, the more.
+    pub idf: f32,
+    /// How much its uses count, saturating and cut for a long verse.
+    pub frequency: f32,
+    /// [`STOPWORD_WEIGHT`](stopwords::STOPWORD_WEIGHT) for a stopword, else 1.
+    pub weight: f32,
+    /// `idf * frequency * weight`.
+    pub score: f32,
+}
+
+/// A verse with a word of the query: the verse, how many words it has and
+/// where in it each word of the query is.
+struct Scored<'a> {
+    verse: SearchMatch<'a>,
+    length: usize,
+    positions: Vec<Vec<usize>>,
+}
+
+/// The verses of `commedia` with any word of `query`, by BM25 score through
+/// `pipeline`, best first and ties in reading order. Rarity is judged over
+/// the whole poem, even when `cantica_filter` keeps only one cantica.
+pub fn bm25<'a>(
+    commedia: &'a DivinaCommedia,
+    query: &str,
+    cantica_filter: Option<CanticaId>,
+    pipeline: &Pipeline,
+) -> Vec<ScoredMatch<'a>> {
+    let terms = query_terms(query, pipeline);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let poem = PoemLength::of(commedia.verses().map(|v| v.verse.text.as_str()));
+    let candidates = candidates(commedia, &terms, pipeline);
+    score(query, &terms, pipeline, cantica_filter, poem, candidates)
+}
+
+/// [`bm25`] over `verses`, every verse of the poem in reading order, for a
+/// poem read without its word index, so each verse is scored.
+pub fn bm25_among<'a>(
+    verses: Vec<SearchMatch<'a>>,
+    query: &str,
+    cantica_filter: Option<CanticaId>,
+    pipeline: &Pipeline,
+) -> Vec<ScoredMatch<'a>> {
+    let terms = query_terms(query, pipeline);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let poem = PoemLength::of(verses.iter().map(|v| v.text));
+    score(query, &terms, pipeline, cantica_filter, poem, verses)
+}
+
+/// The distinct words of `query`, in its order.
+fn query_terms(query: &str, pipeline: &Pipeline) -> Vec<Word> {
+    let mut terms: Vec<Word> = Vec::new();
+    for word in pipeline.words(query) {
+        if !terms.iter().any(|t| t.spelling == word.spelling) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+/// How many verses the poem has and how many words they average.
+#[derive(Clone, Copy)]
+struct PoemLength {
+    verses: usize,
+    average: f32,
+}
+
+impl PoemLength {
+    fn of<'a>(verses: impl Iterator<Item = &'a str>) -> Self {
+        let mut count = 0;
+        let mut words = 0;
+        for text in verses {
+            count += 1;
+            words += word_ranges(text).len();
+        }
+        Self {
+            verses: count,
+            average: words as f32 / count.max(1) as f32,
+        }
+    }
+}
+
+/// Score the `candidates` with a word of `terms`, the words of `query`.
+fn score<'a>(
+    query: &str,
+    terms: &[Word],
+    pipeline: &Pipeline,
+    cantica_filter: Option<CanticaId>,
+    poem: PoemLength,
+    candidates: Vec<SearchMatch<'a>>,
+) -> Vec<ScoredMatch<'a>> {
+    let weights: Vec<f32> = terms
+        .iter()
+        .map(|t| stopwords::weight(&query[t.range.clone()]))
+        .collect();
+
+    let same = |a: &Word, b: &Word| {
+        if pipeline.contains(Normalizer::Orthography) {
+            orthography::same_word(a, b)
+        } else {
+            a.spelling == b.spelling
+        }
+    };
+    let mut scored: Vec<Scored> = Vec::new();
+    for mut verse in candidates {
+        let verse_words = pipeline.words(verse.text);
+        let mut positions = vec![Vec::new(); terms.len()];
+        for (n, word) in verse_words.iter().enumerate() {
+            for (t, term) in terms.iter().enumerate() {
+                if same(word, term) {
+                    positions[t].push(n + 1);
+                    verse.spans.push(word.range.clone());
+                }
+            }
+        }
+        if positions.iter().any(|p| !p.is_empty()) {
+            scored.push(Scored {
+                length: word_ranges(verse.text).len(),
+                verse,
+                positions,
+            });
+        }
+    }
+
+    let idf: Vec<f32> = (0..terms.len())
+        .map(|t| {
+            let with = scored.iter().filter(|s| !s.positions[t].is_empty()).count() as f32;
+            (1.0 + (poem.verses as f32 - with + 0.5) / (with + 0.5)).ln()
+        })
+        .collect();
+
+    let mut results: Vec<ScoredMatch<'a>> = scored
+        .into_iter()
+        .filter(|s| cantica_filter.is_none_or(|filter| s.verse.cantica == filter))
+        .map(|s| {
+            let relative_length = s.length as f32 / poem.average.max(1.0);
+            let terms: Vec<TermScore> = s
+                .positions
+                .into_iter()
+                .enumerate()
+                .filter(|(_, positions)| !positions.is_empty())
+                .map(|(t, positions)| {
+                    let n = positions.len() as f32;
+                    let frequency = n * (K1 + 1.0) / (n + K1 * (1.0 - B + B * relative_length));
+                    TermScore {
+                        term: query[terms[t].range.clone()].to_string(),
+                        positions,
+                        idf: idf[t],
+                        frequency,
+                        weight: weights[t],
+                        score: idf[t] * frequency * weights[t],
+                    }
+                })
+                .collect();
+            let score = terms.iter().map(|t| t.score).sum();
+            let mut matched = s.verse;
+            matched.spans.sort_by_key(|span| span.start);
+            ScoredMatch {
+                matched,
+                score,
+                length: s.length,
+                relative_length,
+                terms,
+            }
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        let key = |m: &ScoredMatch| (m.matched.cantica, m.matched.canto, m.matched.line);
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| key(a).cmp(&key(b)))
+    });
+    results
+}
+
+/// Every verse that can have a word of `terms`, as yet without spans: the
+/// ones the word index lists for them, or with old spellings, whose
+/// elisions it doesn't know, or without an index, all.
+fn candidates<'a>(
+    commedia: &'a DivinaCommedia,
+    terms: &[Word],
+    pipeline: &Pipeline,
+) -> Vec<SearchMatch<'a>> {
+    let unmatched = |cantica: CanticaId, canto: &'a Canto, verse: &'a Verse| SearchMatch {
+        cantica,
+        canto: canto.number,
+        line: verse.line_number,
+        text: &verse.text,
+        spans: Vec::new(),
+    };
+    let index = commedia
+        .word_index()
+        .filter(|_| !pipeline.contains(Normalizer::Orthography));
+    let Some(index) = index else {
+        return commedia
+            .verses()
+            .map(|v| unmatched(v.cantica, v.canto, v.verse))
+            .collect();
+    };
+
+    let mut postings: Vec<Posting> = terms
+        .iter()
+        .flat_map(|term| {
+            if pipeline.contains(Normalizer::Lemma) {
+                index.with_stem(&term.spelling)
+            } else {
+                index.lookup(&normalize::fold(&term.spelling)).to_vec()
+            }
+        })
+        .collect();
+    postings.sort_unstable();
+    postings.dedup();
+    postings
+        .into_iter()
+        .filter_map(|p| {
+            let canto = commedia.canto(p.cantica, p.canto)?;
+            let line = usize::from(p.line);
+            let verse = canto.verses.iter().find(|v| v.line_number == line)?;
+            Some(unmatched(p.cantica, canto, verse))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_names() {
+        assert_eq!("bm25".parse::<Rank>().unwrap(), Rank::Bm25);
+        assert_eq!("Order".parse::<Rank>().unwrap(), Rank::Order);
+        assert_eq!(Rank::default().to_string(), "order");
+        assert!("best".parse::<Rank>().is_err());
+    }
+
+    #[test]
+    fn test_bm25() {
+        let commedia = crate::commedia().unwrap();
+        let pipeline = Pipeline::default();
+        let results = bm25(commedia, "selva oscura", None, &pipeline);
+        let first = &results[0].matched;
+        assert_eq!(
+            (first.cantica, first.canto, first.line),
+            (CanticaId::Inferno, 1, 2)
+        );
+        assert_eq!(&first.text[first.spans[0].clone()], "selva");
+        let explained: Vec<(&str, &[usize])> = results[0]
+            .terms
+            .iter()
+            .map(|t| (t.term.as_str(), t.positions.as_slice()))
+            .collect();
+        assert_eq!(explained, [("selva", &[5][..]), ("oscura", &[6][..])]);
+        assert_eq!(results[0].length, 6);
+        let sum: f32 = results[0].terms.iter().map(|t| t.idf * t.frequency).sum();
+        assert!((results[0].score - sum).abs() < 1e-4);
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+        // Any word will do: verses with only one of them follow
+        assert!(results.iter().any(|m| !m.matched.text.contains("selva")));
+        assert!(results.len() > commedia.search("selva oscura", None).len());
+
+        // The same verses, scored without the index
+        let mut scanned = commedia.clone();
+        scanned.get_mut(CanticaId::Inferno);
+        let scanned = bm25(&scanned, "selva oscura", None, &pipeline);
+        assert_eq!(scanned.len(), results.len());
+        assert_eq!(scanned[0].score, results[0].score);
+
+        let inferno = bm25(commedia, "selva", Some(CanticaId::Inferno), &pipeline);
+        assert!(inferno
+            .iter()
+            .all(|m| m.matched.cantica == CanticaId::Inferno));
+        assert!(bm25(commedia, "", None, &pipeline).is_empty());
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, Write};
+
+use crate::server::SearchHit;
+use duca::output;
+use duca::{Cantica, CanticaId, DivinaCommedia, DucaError, Pipeline};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A canto or line, by `cantica` and `canto` or by the `uri` of `--format
+/// quickfix`'s lines.
+#[derive(Debug, Deserialize)]
+struct LookupParams {
+    #[serde(default)]
+    cantica: String,
+    #[serde(default)]
+    canto: u8,
+    uri: Option<String>,
+    line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    pattern: String,
+    cantica: Option<String>,
+    /// The normalizers to search through, as `--normalize` takes them.
+    normalize: Option<String>,
+    /// Read the pattern as a regular expression, as `--regex` does.
+    #[serde(default)]
+    regex: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RandomParams {
+    cantica: Option<String>,
+}
+
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answer duca's own lookup/search/random methods over stdio.
+pub fn run(commedia: &DivinaCommedia, reader: impl BufRead, writer: impl Write) -> Result<()> {
+    serve(reader, writer, |method, params| {
+        dispatch(commedia, method, params)
+    })
+}
+
+/// Serve newline-delimited JSON-RPC 2.0 requests from `reader`, writing one
+/// response line per request to `writer` until the input is closed.
+/// `dispatch` maps a method name and its params to a result.
+pub fn serve<F>(reader: impl BufRead, mut writer: impl Write, mut dispatch: F) -> Result<()>
+where
+    F: FnMut(&str, Value) -> Result<Value, RpcError>,
+{
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = handle_line(&line, &mut dispatch) {
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle a single request line. Notifications (requests without an id)
+/// produce no response.
+pub fn handle_line<F>(line: &str, dispatch: F) -> Option<Value>
+where
+    F: FnOnce(&str, Value) -> Result<Value, RpcError>,
+{
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, e.to_string()),
+            ))
+        }
+    };
+
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, e.to_string()),
+            ))
+        }
+    };
+
+    let id = request.id.clone();
+    if request.jsonrpc.as_deref() != Some("2.0") {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "Expected \"jsonrpc\": \"2.0\""),
+        ));
+    }
+
+    let result = dispatch(&request.method, request.params);
+    let id = id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn dispatch(commedia: &DivinaCommedia, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "lookup" => lookup(commedia, parse_params(params)?),
+        "search" => search(commedia, parse_params(params)?),
+        "random" => random(commedia, parse_params(params)?),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+pub fn parse_params<T: for<'de> Deserialize<'de> + Default>(params: Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn parse_cantica(name: &str) -> Result<CanticaId, RpcError> {
+    name.parse()
+        .map_err(|e: DucaError| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn find_cantica<'a>(commedia: &'a DivinaCommedia, name: &str) -> Result<&'a Cantica, RpcError> {
+    parse_cantica(name).map(|id| commedia.get(id))
+}
+
+fn lookup(commedia: &DivinaCommedia, params: Option<LookupParams>) -> Result<Value, RpcError> {
+    let mut params = params.ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing params"))?;
+    if let Some(uri) = &params.uri {
+        let (cantica, canto) = output::parse_canto_uri(uri).ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("Invalid uri '{}': expected duca://cantica/canto", uri),
+            )
+        })?;
+        params.cantica = cantica.to_string();
+        params.canto = canto;
+    }
+    let cantica = find_cantica(commedia, &params.cantica)?;
+    let canto = cantica.canto(params.canto).ok_or_else(|| {
+        RpcError::new(
+            INVALID_PARAMS,
+            format!("Canto {} not found in {}", params.canto, cantica.name),
+        )
+    })?;
+
+    match params.line {
+        Some(line) => {
+            let verse = canto.verse(line).ok_or_else(|| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    format!(
+                        "Line {} not found in {} Canto {}",
+                        line, cantica.name, canto.roman_numeral
+                    ),
+                )
+            })?;
+            Ok(json!(SearchHit {
+                cantica: cantica.name.clone(),
+                canto: canto.number,
+                line: verse.line_number,
+                text: verse.text.clone(),
+            }))
+        }
+        None => Ok(json!({
+            "cantica": cantica.name,
+            "number": canto.number,
+            "roman_numeral": canto.roman_numeral,
+            "verses": canto.verses,
+        })),
+    }
+}
+
+fn search(commedia: &DivinaCommedia, params: Option<SearchParams>) -> Result<Value, RpcError> {
+    let params = params.ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing params"))?;
+    let cantica = params.cantica.as_deref().map(parse_cantica).transpose()?;
+    let pipeline: Pipeline = match params.normalize.as_deref() {
+        Some(steps) => steps
+            .parse()
+            .map_err(|e: DucaError| RpcError::new(INVALID_PARAMS, e.to_string()))?,
+        None => Pipeline::default(),
+    };
+
+    let results: Vec<SearchHit> = commedia
+        .search_with(&params.pattern, cantica, &pipeline.with_regex(params.regex))
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?
+        .into_iter()
+        .map(SearchHit::from)
+        .collect();
+
+    Ok(json!({ "count": results.len(), "results": results }))
+}
+
+fn random(commedia: &DivinaCommedia, params: RandomParams) -> Result<Value, RpcError> {
+    let filter = params.cantica.as_deref().map(parse_cantica).transpose()?;
+    let verses = || {
+        commedia
+            .verses()
+            .filter(move |v| filter.is_none_or(|id| v.cantica == id))
+    };
+
+    let total = verses().count();
+    if total == 0 {
+        return Err(RpcError::new(INVALID_PARAMS, "No verses available"));
+    }
+
+    let index = (RandomState::new().build_hasher().finish() % total as u64) as usize;
+    let v = verses()
+        .nth(index)
+        .expect("index is bounded by the total verse count");
+    Ok(json!(SearchHit {
+        cantica: v.cantica.to_string(),
+        canto: v.canto.number,
+        line: v.verse.line_number,
+        text: v.verse.text.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::{Canto, Verse};
+
+    fn create_test_commedia() -> DivinaCommedia {
+        let mut commedia = DivinaCommedia::new();
+        commedia.inferno.cantos.insert(
+            1,
+            Canto::new(
+                CanticaId::Inferno,
+                1,
+                vec![
+                    Verse {
+                        line_number: 1,
+                        text: "Nel mezzo del cammin di nostra vita".to_string(),
+                    },
+                    Verse {
+                        line_number: 2,
+                        text: "mi ritrovai per una selva oscura".to_string(),
+                    },
+                ],
+            ),
+        );
+        commedia
+    }
+
+    fn handle(commedia: &DivinaCommedia, line: &str) -> Option<Value> {
+        handle_line(line, |method, params| dispatch(commedia, method, params))
+    }
+
+    fn call(commedia: &DivinaCommedia, request: Value) -> Value {
+        handle(commedia, &request.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let commedia = create_test_commedia();
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "lookup",
+                   "params": {"cantica": "inferno", "canto": 1, "line": 2}}),
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(
+            response["result"]["text"],
+            "mi ritrovai per una selva oscura"
+        );
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "lookup",
+                   "params": {"cantica": "Inferno", "canto": 1}}),
+        );
+        assert_eq!(response["result"]["verses"].as_array().unwrap().len(), 2);
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "lookup",
+                   "params": {"cantica": "inferno", "canto": 9}}),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        // A quickfix line's file and line
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 4, "method": "lookup",
+                   "params": {"uri": "duca://inferno/1", "line": 2}}),
+        );
+        assert_eq!(
+            response["result"]["text"],
+            "mi ritrovai per una selva oscura"
+        );
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 5, "method": "lookup",
+                   "params": {"uri": "inferno/1"}}),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_search_and_random() {
+        let commedia = create_test_commedia();
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "search",
+                   "params": {"pattern": "selva"}}),
+        );
+        assert_eq!(response["result"]["count"], 1);
+        assert_eq!(response["result"]["results"][0]["line"], 2);
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "search",
+                   "params": {"pattern": "selve oscure", "normalize": "lemma"}}),
+        );
+        assert_eq!(response["result"]["count"], 1);
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "search",
+                   "params": {"pattern": "selva", "normalize": "stem"}}),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "search",
+                   "params": {"pattern": "selv[ae]", "regex": true}}),
+        );
+        assert_eq!(response["result"]["count"], 1);
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "search",
+                   "params": {"pattern": "selv(", "regex": true}}),
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "b", "method": "random"}),
+        );
+        assert_eq!(response["result"]["cantica"], "Inferno");
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": "c", "method": "random",
+                   "params": {"cantica": "paradiso"}}),
+        );
+        assert!(response["error"].is_object());
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let commedia = create_test_commedia();
+
+        let response = handle(&commedia, "{not json").unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+        let response = call(
+            &commedia,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "recite"}),
+        );
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = call(&commedia, json!({"id": 1, "method": "random"}));
+        assert_eq!(response["error"]["code"], INVALID_REQUEST);
+
+        // Notifications get no reply
+        let request = json!({"jsonrpc": "2.0", "method": "random"});
+        assert!(handle(&commedia, &request.to_string()).is_none());
+    }
+}
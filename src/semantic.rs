@@ -0,0 +1,253 @@
+//! Semantic search: find verses by meaning rather than by shared words.
+//!
+//! Verses and queries are embedded by any OpenAI-compatible `/embeddings`
+//! endpoint, by default a local [Ollama](https://ollama.com) serving
+//! `nomic-embed-text`. `duca embed` embeds every verse once and saves the
+//! vectors to `embeddings.bin`; a query then costs one request and a scan
+//! of about fourteen thousand dot products.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::{CanticaId, DivinaCommedia, DucaError, SearchMatch};
+
+/// Where `duca embed` writes verse embeddings and `search --semantic` reads them.
+pub const EMBEDDINGS_FILE: &str = "embeddings.bin";
+
+const DEFAULT_URL: &str = "http://localhost:11434/v1/embeddings";
+const DEFAULT_MODEL: &str = "nomic-embed-text";
+/// Verses sent per request while embedding the corpus.
+const BATCH_SIZE: usize = 64;
+
+/// A client for an OpenAI-compatible embeddings endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Embedder {
+    pub url: String,
+    pub model: String,
+}
+
+impl Embedder {
+    /// Configure from `DUCA_EMBEDDINGS_URL` and `DUCA_EMBEDDINGS_MODEL`,
+    /// defaulting to a local Ollama.
+    pub fn from_env() -> Self {
+        Self {
+            url: std::env::var("DUCA_EMBEDDINGS_URL").unwrap_or_else(|_| DEFAULT_URL.to_string()),
+            model: std::env::var("DUCA_EMBEDDINGS_MODEL")
+                .unwrap_or_else(|_| DEFAULT_MODEL.to_string()),
+        }
+    }
+
+    /// Embed each of `texts`, returning unit-length vectors in the same order.
+    pub fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, DucaError> {
+        let client = reqwest::blocking::Client::new();
+        let response: Value = client
+            .post(&self.url)
+            .json(&json!({ "model": self.model, "input": texts }))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.json())
+            .map_err(|e| DucaError::Semantic(format!("{}: {}", self.url, e)))?;
+
+        let data = response["data"]
+            .as_array()
+            .filter(|data| data.len() == texts.len())
+            .ok_or_else(|| DucaError::Semantic("unexpected embeddings response".to_string()))?;
+        data.iter()
+            .map(|item| {
+                let vector: Vec<f32> = serde_json::from_value(item["embedding"].clone())
+                    .map_err(|e| DucaError::Semantic(e.to_string()))?;
+                Ok(normalized(vector))
+            })
+            .collect()
+    }
+}
+
+/// A verse found by [`VerseEmbeddings::nearest`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub line: usize,
+    /// Cosine similarity to the query, from -1 to 1.
+    pub score: f32,
+}
+
+/// One unit-length embedding per verse, saved by `duca embed`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerseEmbeddings {
+    /// The model the vectors came from; queries must use the same one.
+    pub model: String,
+    verses: Vec<(CanticaId, u8, usize)>,
+    vectors: Vec<Vec<f32>>,
+}
+
+impl VerseEmbeddings {
+    /// Embed every verse of `commedia`, calling `progress` with the number
+    /// of verses done after each batch.
+    pub fn compute(
+        commedia: &DivinaCommedia,
+        embedder: &Embedder,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Self, DucaError> {
+        let all: Vec<_> = commedia.verses().collect();
+        let mut embeddings = Self {
+            model: embedder.model.clone(),
+            verses: Vec::with_capacity(all.len()),
+            vectors: Vec::with_capacity(all.len()),
+        };
+
+        for batch in all.chunks(BATCH_SIZE) {
+            let texts: Vec<&str> = batch.iter().map(|v| v.verse.text.as_str()).collect();
+            embeddings.vectors.extend(embedder.embed(&texts)?);
+            embeddings.verses.extend(
+                batch
+                    .iter()
+                    .map(|v| (v.cantica, v.canto.number, v.verse.line_number)),
+            );
+            progress(embeddings.verses.len(), all.len());
+        }
+        Ok(embeddings)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, DucaError> {
+        Ok(bincode::deserialize(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), DucaError> {
+        Ok(fs::write(path, bincode::serialize(self)?)?)
+    }
+
+    /// Number of embedded verses.
+    pub fn len(&self) -> usize {
+        self.verses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verses.is_empty()
+    }
+
+    /// The `limit` verses closest to `query`, most similar first.
+    pub fn nearest(
+        &self,
+        query: &[f32],
+        cantica_filter: Option<CanticaId>,
+        limit: usize,
+    ) -> Vec<SemanticMatch> {
+        let mut matches: Vec<SemanticMatch> = self
+            .verses
+            .iter()
+            .zip(&self.vectors)
+            .filter(|((id, _, _), _)| cantica_filter.is_none_or(|filter| *id == filter))
+            .map(|(&(cantica, canto, line), vector)| SemanticMatch {
+                cantica,
+                canto,
+                line,
+                score: dot(query, vector),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Embed `query` and return the closest verses of `commedia` as search
+    /// matches, most similar first and with nothing to highlight.
+    pub fn search<'a>(
+        &self,
+        commedia: &'a DivinaCommedia,
+        embedder: &Embedder,
+        query: &str,
+        cantica_filter: Option<CanticaId>,
+        limit: usize,
+    ) -> Result<Vec<SearchMatch<'a>>, DucaError> {
+        if embedder.model != self.model {
+            return Err(DucaError::Semantic(format!(
+                "{} was made with model '{}', not '{}'",
+                EMBEDDINGS_FILE, self.model, embedder.model
+            )));
+        }
+
+        let vector = embedder
+            .embed(&[query])?
+            .pop()
+            .expect("one vector per input");
+        Ok(self
+            .nearest(&vector, cantica_filter, limit)
+            .into_iter()
+            .filter_map(|m| {
+                let verse = commedia.verse(m.cantica, m.canto, m.line)?;
+                Some(SearchMatch {
+                    cantica: m.cantica,
+                    canto: m.canto,
+                    line: m.line,
+                    text: &verse.text,
+                    spans: Vec::new(),
+                })
+            })
+            .collect())
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Scale `vector` to unit length, so a dot product is a cosine similarity.
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = dot(&vector, &vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> VerseEmbeddings {
+        VerseEmbeddings {
+            model: "test".to_string(),
+            verses: vec![
+                (CanticaId::Inferno, 1, 1),
+                (CanticaId::Inferno, 1, 2),
+                (CanticaId::Paradiso, 33, 145),
+            ],
+            vectors: vec![
+                normalized(vec![1.0, 0.0]),
+                normalized(vec![1.0, 1.0]),
+                normalized(vec![0.0, 1.0]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_nearest() {
+        let embeddings = sample();
+
+        let results = embeddings.nearest(&[0.0, 1.0], None, 2);
+        let lines: Vec<usize> = results.iter().map(|m| m.line).collect();
+        assert_eq!(lines, [145, 2]);
+        assert!((results[0].score - 1.0).abs() < 1e-6);
+
+        let results = embeddings.nearest(&[0.0, 1.0], Some(CanticaId::Inferno), 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].line, 2);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("duca-embeddings-{}", std::process::id()));
+        sample().save(&path).unwrap();
+        assert_eq!(VerseEmbeddings::load(&path).unwrap(), sample());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(normalized(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(normalized(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+}
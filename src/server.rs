@@ -0,0 +1,596 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::str::FromStr;
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::browser;
+use duca::corpus::{CantoView, Corpus};
+#[cfg(feature = "tantivy")]
+use duca::index::FullTextIndex;
+use duca::rank::{self, Rank, ScoredMatch};
+use duca::{parse_canto_number, CanticaId, DivinaCommedia, DucaError, Pipeline, SearchMatch};
+
+/// Most results `/search/ranked` returns when no `limit` is given.
+#[cfg(feature = "tantivy")]
+const RANKED_LIMIT: usize = 50;
+
+/// Web reader assets, compiled into the binary so `serve` needs no files on disk.
+const STATIC_ASSETS: &[(&str, &str, &str)] = &[
+    (
+        "/",
+        "text/html; charset=utf-8",
+        include_str!("../web/index.html"),
+    ),
+    (
+        "/static/app.js",
+        "text/javascript; charset=utf-8",
+        include_str!("../web/app.js"),
+    ),
+    (
+        "/static/style.css",
+        "text/css; charset=utf-8",
+        include_str!("../web/style.css"),
+    ),
+];
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub cantica: String,
+    pub canto: u8,
+    pub line: usize,
+    pub text: String,
+}
+
+impl From<SearchMatch<'_>> for SearchHit {
+    fn from(m: SearchMatch<'_>) -> Self {
+        Self {
+            cantica: m.cantica.to_string(),
+            canto: m.canto,
+            line: m.line,
+            text: m.text.to_string(),
+        }
+    }
+}
+
+/// A search match as JSON, with where the pattern matched its text as
+/// `spans`, `[start, end]` offsets in characters, for the web reader to
+/// highlight.
+fn search_hit(m: SearchMatch<'_>) -> Value {
+    let chars = |byte: usize| m.text[..byte].chars().count();
+    let spans: Vec<[usize; 2]> = m
+        .spans
+        .iter()
+        .map(|span| [chars(span.start), chars(span.end)])
+        .collect();
+    let mut hit = json!(SearchHit::from(m));
+    hit["spans"] = json!(spans);
+    hit
+}
+
+/// The poem as the API reads it: decoded whole, or with the `mmap` feature
+/// `commedia.bin` mapped into memory, each request decoding only the cantos
+/// it reads, their text borrowed from the map.
+pub trait Source {
+    fn cantica_name(&self, cantica: CanticaId) -> &str;
+    fn canto_numbers(&self, cantica: CanticaId) -> Vec<u8>;
+    fn canto(&self, cantica: CanticaId, number: u8) -> Result<Option<CantoView<'_>>, DucaError>;
+    fn search(
+        &self,
+        pattern: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<SearchMatch<'_>>, DucaError>;
+    fn bm25(
+        &self,
+        query: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<ScoredMatch<'_>>, DucaError>;
+}
+
+impl Source for DivinaCommedia {
+    fn cantica_name(&self, cantica: CanticaId) -> &str {
+        &self.get(cantica).name
+    }
+
+    fn canto_numbers(&self, cantica: CanticaId) -> Vec<u8> {
+        self.get(cantica).cantos.keys().copied().collect()
+    }
+
+    fn canto(&self, cantica: CanticaId, number: u8) -> Result<Option<CantoView<'_>>, DucaError> {
+        Ok(DivinaCommedia::canto(self, cantica, number).map(CantoView::from))
+    }
+
+    fn search(
+        &self,
+        pattern: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<SearchMatch<'_>>, DucaError> {
+        self.search_with(pattern, cantica, pipeline)
+    }
+
+    fn bm25(
+        &self,
+        query: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<ScoredMatch<'_>>, DucaError> {
+        Ok(rank::bm25(self, query, cantica, pipeline))
+    }
+}
+
+impl<B: AsRef<[u8]>> Source for Corpus<B> {
+    fn cantica_name(&self, cantica: CanticaId) -> &str {
+        cantica.name()
+    }
+
+    fn canto_numbers(&self, cantica: CanticaId) -> Vec<u8> {
+        Corpus::canto_numbers(self, cantica)
+    }
+
+    fn canto(&self, cantica: CanticaId, number: u8) -> Result<Option<CantoView<'_>>, DucaError> {
+        self.canto_view(cantica, number)
+    }
+
+    fn search(
+        &self,
+        pattern: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<SearchMatch<'_>>, DucaError> {
+        self.search_with(pattern, cantica, pipeline)
+    }
+
+    fn bm25(
+        &self,
+        query: &str,
+        cantica: Option<CanticaId>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<ScoredMatch<'_>>, DucaError> {
+        Corpus::bm25(self, query, cantica, pipeline)
+    }
+}
+
+/// A JSON response produced by the router, independent of the HTTP transport.
+#[derive(Debug, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+pub fn serve(commedia: &DivinaCommedia, host: &str, port: u16, open: bool) -> Result<()> {
+    #[cfg(feature = "tantivy")]
+    {
+        let index = FullTextIndex::build(commedia)?;
+        listen(host, port, open, |url| route_indexed(&index, url))
+    }
+    #[cfg(not(feature = "tantivy"))]
+    listen(host, port, open, |url| route(commedia, url))
+}
+
+/// Like [`serve`], reading the poem from a mapped `commedia.bin`, so the
+/// server's memory stays flat however long it runs. `/search/ranked` isn't
+/// served, as its index needs the whole poem decoded.
+#[cfg(feature = "mmap")]
+pub fn serve_mapped(
+    corpus: &Corpus<memmap2::Mmap>,
+    host: &str,
+    port: u16,
+    open: bool,
+) -> Result<()> {
+    listen(host, port, open, |url| route(corpus, url))
+}
+
+/// Answer requests on `host:port`, the API's through `route`.
+fn listen(host: &str, port: u16, open: bool, route: impl Fn(&str) -> ApiResponse) -> Result<()> {
+    let server = Server::http((host, port)).map_err(|e| anyhow!("{}", e))?;
+    let url = format!("http://{}:{}/", host, port);
+    println!("Serving the Commedia on {}", url);
+
+    if open {
+        if let Err(e) = browser::open_url(&url) {
+            eprintln!("Could not open a browser: {}", e);
+        }
+    }
+
+    let json_header = Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+        .expect("static header is valid");
+    let cors_header =
+        Header::from_bytes("Access-Control-Allow-Origin", "*").expect("static header is valid");
+
+    for request in server.incoming_requests() {
+        if *request.method() == Method::Get {
+            if let Some((content_type, body)) = static_asset(request.url()) {
+                let header = Header::from_bytes("Content-Type", content_type)
+                    .expect("static header is valid");
+                let http_response = Response::from_string(body).with_header(header);
+                if let Err(e) = request.respond(http_response) {
+                    eprintln!("Failed to send response: {}", e);
+                }
+                continue;
+            }
+        }
+
+        let response = if *request.method() == Method::Get {
+            route(request.url())
+        } else {
+            ApiResponse::error(405, "Only GET requests are supported")
+        };
+
+        let http_response = Response::from_string(response.body.to_string())
+            .with_status_code(response.status)
+            .with_header(json_header.clone())
+            .with_header(cors_header.clone());
+
+        // A client hanging up mid-response shouldn't take the server down
+        if let Err(e) = request.respond(http_response) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Look up an embedded web reader asset, returning its content type and body.
+pub fn static_asset(url: &str) -> Option<(&'static str, &'static str)> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    STATIC_ASSETS
+        .iter()
+        .find(|(asset_path, _, _)| *asset_path == path)
+        .map(|&(_, content_type, body)| (content_type, body))
+}
+
+/// Dispatch a request path (including any query string) to an API handler.
+pub fn route(source: &impl Source, url: &str) -> ApiResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        ["api"] => ApiResponse::ok(json!({
+            "endpoints": [
+                "/cantica/{cantica}",
+                "/cantica/{cantica}/canto/{number}",
+                "/search?q={pattern}&cantica={cantica}&normalize={steps}&rank={order}&regex={true|false}",
+            ]
+        })),
+        ["cantica", name] => match name.parse() {
+            Ok(id) => ApiResponse::ok(json!({
+                "name": source.cantica_name(id),
+                "cantos": source.canto_numbers(id),
+            })),
+            Err(e) => ApiResponse::error(404, e.to_string()),
+        },
+        ["cantica", name, "canto", number] => {
+            let cantica: CanticaId = match name.parse() {
+                Ok(id) => id,
+                Err(e) => return ApiResponse::error(404, e.to_string()),
+            };
+            let Some(number) = parse_canto_number(number) else {
+                return ApiResponse::error(400, format!("Invalid canto number '{}'", number));
+            };
+            match source.canto(cantica, number) {
+                Ok(Some(canto)) => ApiResponse::ok(json!({
+                    "cantica": source.cantica_name(cantica),
+                    "number": canto.number,
+                    "roman_numeral": canto.roman_numeral,
+                    "verses": canto.verses,
+                })),
+                Ok(None) => ApiResponse::error(
+                    404,
+                    format!(
+                        "Canto {} not found in {}",
+                        number,
+                        source.cantica_name(cantica)
+                    ),
+                ),
+                Err(e) => ApiResponse::error(500, e.to_string()),
+            }
+        }
+        ["search"] => search(source, query),
+        _ => ApiResponse::error(404, format!("No such endpoint '{}'", path)),
+    }
+}
+
+/// Like [`route`], adding `/search/ranked`, which answers from a full-text index.
+#[cfg(feature = "tantivy")]
+pub fn route_indexed(index: &FullTextIndex, url: &str) -> ApiResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    if path.trim_end_matches('/') != "/search/ranked" {
+        return route(index.commedia(), url);
+    }
+
+    let mut pattern = None;
+    let mut cantica = None;
+    let mut limit = RANKED_LIMIT;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "q" => pattern = Some(value.into_owned()),
+            "cantica" => cantica = Some(value.into_owned()),
+            "limit" => match value.parse() {
+                Ok(n) => limit = n,
+                Err(_) => return ApiResponse::error(400, format!("Invalid limit '{}'", value)),
+            },
+            _ => {}
+        }
+    }
+
+    let Some(pattern) = pattern.filter(|p| !p.is_empty()) else {
+        return ApiResponse::error(400, "Missing search query parameter 'q'");
+    };
+    let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+        Ok(cantica) => cantica,
+        Err(e) => return ApiResponse::error(404, e.to_string()),
+    };
+
+    let results = match index.search(&pattern, cantica, limit) {
+        Ok(results) => results,
+        Err(e) => return ApiResponse::error(500, e.to_string()),
+    };
+    let results: Vec<Value> = results
+        .into_iter()
+        .map(|m| {
+            json!({
+                "cantica": m.cantica.to_string(),
+                "canto": m.canto,
+                "line": m.line,
+                "text": m.text,
+                "score": m.score,
+            })
+        })
+        .collect();
+
+    ApiResponse::ok(json!({
+        "query": pattern,
+        "count": results.len(),
+        "results": results,
+    }))
+}
+
+fn search(source: &impl Source, query: &str) -> ApiResponse {
+    let mut pattern = None;
+    let mut cantica = None;
+    let mut pipeline = Pipeline::default();
+    let mut rank = Rank::Order;
+    let mut regex = false;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "q" => pattern = Some(value.into_owned()),
+            "cantica" => cantica = Some(value.into_owned()),
+            "normalize" => match value.parse() {
+                Ok(steps) => pipeline = steps,
+                Err(e) => return ApiResponse::error(400, e.to_string()),
+            },
+            "rank" => match value.parse() {
+                Ok(order) => rank = order,
+                Err(e) => return ApiResponse::error(400, e.to_string()),
+            },
+            "regex" => regex = value == "true" || value == "1",
+            _ => {}
+        }
+    }
+
+    let Some(pattern) = pattern.filter(|p| !p.is_empty()) else {
+        return ApiResponse::error(400, "Missing search query parameter 'q'");
+    };
+    let cantica = match cantica.as_deref().map(CanticaId::from_str).transpose() {
+        Ok(cantica) => cantica,
+        Err(e) => return ApiResponse::error(404, e.to_string()),
+    };
+    if regex && rank == Rank::Bm25 {
+        return ApiResponse::error(400, "Ranking weighs words; a regex has none");
+    }
+    let pipeline = pipeline.with_regex(regex);
+
+    let results: Result<Vec<Value>, DucaError> = match rank {
+        Rank::Order => source
+            .search(&pattern, cantica, &pipeline)
+            .map(|matches| matches.into_iter().map(search_hit).collect()),
+        // Most relevant first, each with its score
+        Rank::Bm25 => source.bm25(&pattern, cantica, &pipeline).map(|matches| {
+            matches
+                .into_iter()
+                .map(|m| {
+                    let mut hit = search_hit(m.matched);
+                    hit["score"] = json!(m.score);
+                    hit
+                })
+                .collect()
+        }),
+    };
+    let results = match results {
+        Ok(results) => results,
+        Err(e @ DucaError::InvalidRegex { .. }) => return ApiResponse::error(400, e.to_string()),
+        Err(e) => return ApiResponse::error(500, e.to_string()),
+    };
+
+    ApiResponse::ok(json!({
+        "query": pattern,
+        "count": results.len(),
+        "results": results,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use duca::{CanticaId, Canto, Verse};
+
+    fn create_test_commedia() -> DivinaCommedia {
+        let mut commedia = DivinaCommedia::new();
+        commedia.inferno.cantos.insert(
+            1,
+            Canto::new(
+                CanticaId::Inferno,
+                1,
+                vec![
+                    Verse {
+                        line_number: 1,
+                        text: "Nel mezzo del cammin di nostra vita".to_string(),
+                    },
+                    Verse {
+                        line_number: 2,
+                        text: "mi ritrovai per una selva oscura".to_string(),
+                    },
+                ],
+            ),
+        );
+        commedia
+    }
+
+    #[test]
+    fn test_route_canto() {
+        let commedia = create_test_commedia();
+
+        let response = route(&commedia, "/cantica/inferno/canto/1");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["cantica"], "Inferno");
+        assert_eq!(response.body["roman_numeral"], "I");
+        assert_eq!(response.body["verses"][1]["line_number"], 2);
+
+        let response = route(&commedia, "/cantica/Inferno/canto/2");
+        assert_eq!(response.status, 404);
+
+        let response = route(&commedia, "/cantica/inferno/canto/I");
+        assert_eq!(response.status, 200);
+
+        let response = route(&commedia, "/cantica/inferno/canto/abc");
+        assert_eq!(response.status, 400);
+
+        let response = route(&commedia, "/cantica/limbo/canto/1");
+        assert_eq!(response.status, 404);
+        assert!(response.body["error"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid cantica"));
+    }
+
+    #[test]
+    fn test_route_cantica() {
+        let commedia = create_test_commedia();
+
+        let response = route(&commedia, "/cantica/inferno");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["cantos"], json!([1]));
+    }
+
+    #[test]
+    fn test_route_search() {
+        let commedia = create_test_commedia();
+
+        let response = route(&commedia, "/search?q=selva%20oscura&cantica=inferno");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["query"], "selva oscura");
+        assert_eq!(response.body["count"], 1);
+        assert_eq!(response.body["results"][0]["line"], 2);
+        assert_eq!(response.body["results"][0]["spans"], json!([[20, 32]]));
+        // A regex's spans are what it matched, for the reader to highlight;
+        // without asking for one, the pattern is plain text
+        let response = route(&commedia, "/search?q=selv.%20o&cantica=inferno&regex=true");
+        assert_eq!(response.body["results"][0]["spans"], json!([[20, 27]]));
+        let response = route(&commedia, "/search?q=selv.%20o&cantica=inferno");
+        assert_eq!(response.body["count"], 0);
+        let response = route(&commedia, "/search?q=selv(&regex=true");
+        assert_eq!(response.status, 400);
+
+        let response = route(&commedia, "/search?q=selva&cantica=paradiso");
+        assert_eq!(response.body["count"], 0);
+
+        let response = route(&commedia, "/search");
+        assert_eq!(response.status, 400);
+
+        let response = route(&commedia, "/search?q=selva&cantica=limbo");
+        assert_eq!(response.status, 404);
+
+        let response = route(&commedia, "/search?q=NEL%20MEZZO&normalize=fold");
+        assert_eq!(response.body["count"], 0);
+        let response = route(&commedia, "/search?q=selve%20oscure&normalize=lemma");
+        assert_eq!(response.body["count"], 1);
+        let response = route(&commedia, "/search?q=selva&normalize=stem");
+        assert_eq!(response.status, 400);
+
+        let response = route(&commedia, "/search?q=selva%20oscura%20vita&rank=bm25");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["count"], 2);
+        let results = &response.body["results"];
+        assert_eq!(results[0]["line"], 2);
+        assert!(results[0]["score"].as_f64() > results[1]["score"].as_f64());
+        let response = route(&commedia, "/search?q=selva&rank=best");
+        assert_eq!(response.status, 400);
+    }
+
+    #[cfg(feature = "tantivy")]
+    #[test]
+    fn test_route_ranked_search() {
+        let commedia = create_test_commedia();
+        let index = FullTextIndex::build(&commedia).unwrap();
+
+        let response = route_indexed(&index, "/search/ranked?q=%22selva%20oscura%22");
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["count"], 1);
+        assert_eq!(response.body["results"][0]["line"], 2);
+        assert!(response.body["results"][0]["score"].as_f64().unwrap() > 0.0);
+
+        let response = route_indexed(&index, "/search/ranked?q=camm*&limit=x");
+        assert_eq!(response.status, 400);
+
+        // Everything else falls through to the plain router
+        let response = route_indexed(&index, "/cantica/inferno/canto/1");
+        assert_eq!(response.status, 200);
+    }
+
+    #[test]
+    fn test_route_corpus() {
+        let commedia = create_test_commedia();
+        let corpus = Corpus::new(duca::corpus::to_binary(&commedia).unwrap()).unwrap();
+
+        // Read from the encoded corpus, every route answers as it does from
+        // the decoded poem
+        for url in [
+            "/cantica/inferno",
+            "/cantica/inferno/canto/1",
+            "/cantica/inferno/canto/2",
+            "/search?q=selva%20oscura",
+            "/search?q=selv.%20o&cantica=inferno",
+            "/search?q=selve%20oscure&normalize=lemma",
+            "/search?q=selva%20oscura%20vita&rank=bm25",
+        ] {
+            assert_eq!(route(&corpus, url), route(&commedia, url), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_route_unknown() {
+        let commedia = create_test_commedia();
+
+        assert_eq!(route(&commedia, "/api").status, 200);
+        assert_eq!(route(&commedia, "/nowhere").status, 404);
+    }
+
+    #[test]
+    fn test_static_assets() {
+        let (content_type, body) = static_asset("/").unwrap();
+        assert!(content_type.starts_with("text/html"));
+        assert!(body.contains("/static/app.js"));
+
+        let (content_type, _) = static_asset("/static/app.js?v=1").unwrap();
+        assert!(content_type.starts_with("text/javascript"));
+
+        assert!(static_asset("/static/missing.css").is_none());
+        assert!(static_asset("/search").is_none());
+    }
+}
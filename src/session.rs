@@ -0,0 +1,255 @@
+//! Reading sessions in the TUI: a timer for the status bar, reminders to
+//! take a break after so many minutes of reading, pomodoro-style, and a
+//! line for each session appended to the reading log when the TUI closes.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use duca::plan::Date;
+use duca::CanticaId;
+
+/// How long a break lasts unless `--break-length` or `$DUCA_BREAK_LENGTH`
+/// says otherwise.
+const BREAK_LENGTH: Duration = Duration::from_secs(5 * 60);
+
+/// Sessions shorter than this aren't logged.
+const SHORTEST_LOGGED: Duration = Duration::from_secs(60);
+
+/// Minutes from `flag`, or else from the environment variable `var`.
+fn minutes(flag: Option<u64>, var: &str) -> Option<Duration> {
+    flag.or_else(|| env::var(var).ok()?.trim().parse().ok())
+        .filter(|&minutes| minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes * 60))
+}
+
+/// Reading time after which a break is due: `--break-every`, or else
+/// `$DUCA_BREAK_EVERY`, in minutes. `None` reminds of no breaks.
+pub fn break_every(flag: Option<u64>) -> Option<Duration> {
+    minutes(flag, "DUCA_BREAK_EVERY")
+}
+
+/// How long a break lasts: `--break-length`, or else `$DUCA_BREAK_LENGTH`,
+/// in minutes, or five.
+pub fn break_length(flag: Option<u64>) -> Duration {
+    minutes(flag, "DUCA_BREAK_LENGTH").unwrap_or(BREAK_LENGTH)
+}
+
+/// What the status bar says of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Reading, for this long in all.
+    Reading(Duration),
+    /// Reading, and a break is due.
+    BreakDue(Duration),
+    /// On a break, with this long left.
+    OnBreak(Duration),
+}
+
+/// A reading session, timed from when the TUI opened. Times are passed in
+/// so tests can run the clock.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// When reading last started, or resumed after a break.
+    stretch: Instant,
+    /// Time read before the current stretch.
+    read: Duration,
+    /// When the current break started, if on one.
+    on_break: Option<Instant>,
+    break_every: Option<Duration>,
+    break_length: Duration,
+    breaks: usize,
+    /// The cantos read, in the order first read.
+    cantos: Vec<(CanticaId, u8)>,
+}
+
+impl Session {
+    pub fn new(break_every: Option<Duration>, break_length: Duration, now: Instant) -> Self {
+        Self {
+            stretch: now,
+            read: Duration::ZERO,
+            on_break: None,
+            break_every,
+            break_length,
+            breaks: 0,
+            cantos: Vec::new(),
+        }
+    }
+
+    /// Time spent reading, leaving out breaks.
+    pub fn read_for(&self, now: Instant) -> Duration {
+        match self.on_break {
+            Some(_) => self.read,
+            None => self.read + now.saturating_duration_since(self.stretch),
+        }
+    }
+
+    /// End a break whose time is up, reading on from when it ended.
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(on_break) = self.on_break {
+            let over = on_break + self.break_length;
+            if now >= over {
+                self.on_break = None;
+                self.stretch = over;
+            }
+        }
+    }
+
+    /// Take a break, or end one early.
+    pub fn toggle_break(&mut self, now: Instant) {
+        match self.on_break.take() {
+            Some(_) => self.stretch = now,
+            None => {
+                self.read += now.saturating_duration_since(self.stretch);
+                self.on_break = Some(now);
+                self.breaks += 1;
+            }
+        }
+    }
+
+    pub fn status(&self, now: Instant) -> Status {
+        if let Some(on_break) = self.on_break {
+            let taken = now.saturating_duration_since(on_break);
+            return Status::OnBreak(self.break_length.saturating_sub(taken));
+        }
+        let read = self.read_for(now);
+        match self.break_every {
+            Some(every) if now.saturating_duration_since(self.stretch) >= every => {
+                Status::BreakDue(read)
+            }
+            _ => Status::Reading(read),
+        }
+    }
+
+    /// Note that `canto` of `cantica` was read.
+    pub fn visit(&mut self, cantica: CanticaId, canto: u8) {
+        if !self.cantos.contains(&(cantica, canto)) {
+            self.cantos.push((cantica, canto));
+        }
+    }
+
+    /// The session's line in the reading log, tab-separated: the date it
+    /// started on, the minutes read, the breaks taken and the cantos read.
+    /// `None` if it was too short to log.
+    pub fn log_line(&self, date: Date, now: Instant) -> Option<String> {
+        let read = self.read_for(now);
+        if read < SHORTEST_LOGGED {
+            return None;
+        }
+        let cantos: Vec<String> = self
+            .cantos
+            .iter()
+            .map(|(cantica, canto)| format!("{} {}", cantica, canto))
+            .collect();
+        Some(format!(
+            "{}\t{}\t{}\t{}",
+            date,
+            read.as_secs() / 60,
+            self.breaks,
+            cantos.join(", ")
+        ))
+    }
+}
+
+/// A duration as the status bar shows it: `m:ss`, or `h:mm:ss` from an
+/// hour.
+pub fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// The dates of the sessions in the reading log `log` that read `canto`
+/// of `cantica`, in the log's order. Lines that don't parse are skipped.
+pub fn read_on(log: &str, cantica: CanticaId, canto: u8) -> Vec<Date> {
+    let name = format!("{} {}", cantica, canto);
+    log.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let date = fields.first()?.parse().ok()?;
+            fields
+                .get(3)?
+                .split(", ")
+                .any(|read| read == name)
+                .then_some(date)
+        })
+        .collect()
+}
+
+/// Append `line` to the reading log at `path`, creating it and its
+/// directory if need be.
+pub fn append_log(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut log = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(log, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_session_breaks() {
+        let start = Instant::now();
+        let at = |minutes: u64| start + MINUTE * minutes as u32;
+        let mut session = Session::new(Some(MINUTE * 25), MINUTE * 5, start);
+        assert_eq!(session.status(at(10)), Status::Reading(MINUTE * 10));
+        assert_eq!(session.status(at(25)), Status::BreakDue(MINUTE * 25));
+
+        // A break stops the reading time until it's over
+        session.toggle_break(at(26));
+        assert_eq!(session.status(at(28)), Status::OnBreak(MINUTE * 3));
+        session.tick(at(32));
+        assert_eq!(session.status(at(32)), Status::Reading(MINUTE * 27));
+        assert_eq!(session.status(at(56)), Status::BreakDue(MINUTE * 51));
+
+        // One ended early reads on from then
+        session.toggle_break(at(60));
+        session.toggle_break(at(61));
+        assert_eq!(session.status(at(70)), Status::Reading(MINUTE * 64));
+
+        session.visit(CanticaId::Inferno, 5);
+        session.visit(CanticaId::Inferno, 6);
+        session.visit(CanticaId::Inferno, 5);
+        let date = "2025-01-12".parse().unwrap();
+        assert_eq!(
+            session.log_line(date, at(70)).unwrap(),
+            "2025-01-12\t64\t2\tInferno 5, Inferno 6"
+        );
+        assert_eq!(
+            Session::new(None, BREAK_LENGTH, start).log_line(date, start),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_on() {
+        let log = "2025-01-12\t52\t2\tInferno 5, Inferno 6\n\
+                   not a session\n\
+                   2025-01-14\t20\t0\tInferno 6\n";
+        let dates: Vec<String> = read_on(log, CanticaId::Inferno, 6)
+            .iter()
+            .map(Date::to_string)
+            .collect();
+        assert_eq!(dates, ["2025-01-12", "2025-01-14"]);
+        assert!(read_on(log, CanticaId::Inferno, 16).is_empty());
+    }
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(clock(Duration::from_secs(65)), "1:05");
+        assert_eq!(clock(Duration::from_secs(3 * 3600 + 62)), "3:01:02");
+        assert_eq!(break_length(Some(10)), MINUTE * 10);
+        assert_eq!(break_every(Some(0)), None);
+    }
+}
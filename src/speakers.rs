@@ -0,0 +1,233 @@
+//! Who is speaking: a curated list of the poem's direct speech, attached to
+//! each canto when the text is parsed.
+
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+use crate::{normalize, CanticaId, DucaError, Passage, Verse};
+
+/// The speech list compiled into duca.
+const BUILTIN: &str = include_str!("../speakers/speakers.toml");
+
+/// A run of lines spoken by one character.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Speech {
+    pub speaker: String,
+    pub lines: RangeInclusive<usize>,
+}
+
+#[derive(Deserialize)]
+struct SpeakersFile {
+    #[serde(default)]
+    speech: Vec<RawSpeech>,
+}
+
+#[derive(Deserialize)]
+struct RawSpeech {
+    passage: String,
+    speaker: String,
+}
+
+/// Parse a speech list; `name` identifies it in errors.
+pub fn parse(content: &str, name: &str) -> Result<Vec<(Passage, String)>, DucaError> {
+    let invalid = |reason: String| DucaError::UserData {
+        path: name.to_string(),
+        reason,
+    };
+    let file: SpeakersFile =
+        toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+    file.speech
+        .into_iter()
+        .map(|raw| {
+            let passage: Passage = raw
+                .passage
+                .parse()
+                .map_err(|e: DucaError| invalid(e.to_string()))?;
+            Ok((passage, raw.speaker))
+        })
+        .collect()
+}
+
+fn builtin() -> &'static [(Passage, String)] {
+    static SPEECHES: OnceLock<Vec<(Passage, String)>> = OnceLock::new();
+    SPEECHES.get_or_init(|| parse(BUILTIN, "speakers.toml").expect("built-in speakers are valid"))
+}
+
+/// The built-in speeches of one canto, in the order they are listed.
+pub fn for_canto(cantica: CanticaId, canto: u8) -> Vec<Speech> {
+    builtin()
+        .iter()
+        .filter(|(passage, _)| passage.cantica == cantica && passage.canto == canto)
+        .filter_map(|(passage, speaker)| {
+            Some(Speech {
+                speaker: speaker.clone(),
+                lines: passage.lines.clone()?,
+            })
+        })
+        .collect()
+}
+
+/// The speaker of `line` among `speeches`. A speech quoted inside another
+/// covers fewer lines, so the narrowest speech containing the line wins.
+pub fn speaker_at(speeches: &[Speech], line: usize) -> Option<&str> {
+    speeches
+        .iter()
+        .filter(|speech| speech.lines.contains(&line))
+        .min_by_key(|speech| speech.lines.end() - speech.lines.start())
+        .map(|speech| speech.speaker.as_str())
+}
+
+/// A passage of direct speech found by its quotation marks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectSpeech<'a> {
+    pub lines: RangeInclusive<usize>,
+    /// The speaker, when the speech list names one.
+    pub speaker: Option<&'a str>,
+}
+
+/// Lines from each opening `«` to its closing `»`. Inner quotations use
+/// `“ ”`, so guillemets never nest.
+fn quoted_lines(verses: &[Verse]) -> Vec<RangeInclusive<usize>> {
+    let mut quotes = Vec::new();
+    let mut open = None;
+    for verse in verses {
+        for c in verse.text.chars() {
+            match c {
+                '«' if open.is_none() => open = Some(verse.line_number),
+                '»' => {
+                    if let Some(start) = open.take() {
+                        quotes.push(start..=verse.line_number);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    // A quotation left open runs to the end of the canto
+    if let (Some(start), Some(last)) = (open, verses.last()) {
+        quotes.push(start..=last.line_number);
+    }
+    quotes
+}
+
+/// The direct speech in `verses`, with speakers from `speeches`.
+///
+/// A speech interrupted by narration (`«...», rispuose, «...»`) is quoted in
+/// pieces. Pieces are joined when they share a line, or when the speech list
+/// puts them in the same speech; otherwise each is a speech of its own.
+pub fn direct_speech<'a>(verses: &[Verse], speeches: &'a [Speech]) -> Vec<DirectSpeech<'a>> {
+    let narrowest = |line: usize| {
+        speeches
+            .iter()
+            .filter(|speech| speech.lines.contains(&line))
+            .min_by_key(|speech| speech.lines.end() - speech.lines.start())
+    };
+
+    let mut found: Vec<(DirectSpeech, Option<&Speech>)> = Vec::new();
+    for lines in quoted_lines(verses) {
+        let listed = narrowest(*lines.start());
+        match found.last_mut() {
+            Some((last, last_listed))
+                if last.lines.end() == lines.start()
+                    || (listed.is_some() && *last_listed == listed) =>
+            {
+                last.lines = *last.lines.start()..=*lines.end();
+            }
+            _ => found.push((
+                DirectSpeech {
+                    speaker: listed.map(|speech| speech.speaker.as_str()),
+                    lines,
+                },
+                listed,
+            )),
+        }
+    }
+    found.into_iter().map(|(speech, _)| speech).collect()
+}
+
+/// Whether `speaker` is the one a `speaker:` search filter asks for: the
+/// folded name starts with the folded filter, so `virg` finds Virgilio.
+pub fn matches_filter(speaker: &str, filter: &str) -> bool {
+    normalize::fold(speaker).starts_with(&normalize::fold(filter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_speeches() {
+        let speeches = for_canto(CanticaId::Inferno, 5);
+        assert_eq!(speaker_at(&speeches, 16), Some("Minosse"));
+        assert_eq!(speaker_at(&speeches, 100), Some("Francesca"));
+        assert_eq!(speaker_at(&speeches, 1), None);
+        assert!(for_canto(CanticaId::Purgatorio, 1).is_empty());
+    }
+
+    #[test]
+    fn test_nested_speech() {
+        let speeches = for_canto(CanticaId::Inferno, 33);
+        assert_eq!(speaker_at(&speeches, 50), Some("Ugolino"));
+        assert_eq!(speaker_at(&speeches, 51), Some("Anselmuccio"));
+    }
+
+    #[test]
+    fn test_direct_speech() {
+        let verses: Vec<Verse> = [
+            "Quando vidi costui nel gran diserto,",
+            "«Miserere di me», gridai a lui,",
+            "«qual che tu sii, od ombra od omo certo!».",
+            "Rispuosemi: «Non omo, omo già fui,",
+            "e li parenti miei furon lombardi».",
+            "«Maestro», diss’ io, «dimmi",
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| Verse {
+            line_number: i + 1,
+            text: text.to_string(),
+        })
+        .collect();
+        let speeches = vec![
+            Speech {
+                speaker: "Dante".to_string(),
+                lines: 2..=3,
+            },
+            Speech {
+                speaker: "Virgilio".to_string(),
+                lines: 4..=5,
+            },
+        ];
+
+        let found = direct_speech(&verses, &speeches);
+        let lines: Vec<_> = found.iter().map(|s| s.lines.clone()).collect();
+        assert_eq!(lines, [2..=3, 4..=5, 6..=6]);
+        let speakers: Vec<_> = found.iter().map(|s| s.speaker).collect();
+        assert_eq!(speakers, [Some("Dante"), Some("Virgilio"), None]);
+
+        // Without a speech list, pieces on separate lines stay apart
+        let lines: Vec<_> = direct_speech(&verses, &[])
+            .into_iter()
+            .map(|s| s.lines)
+            .collect();
+        assert_eq!(lines, [2..=2, 3..=3, 4..=5, 6..=6]);
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        assert!(matches_filter("Virgilio", "virgilio"));
+        assert!(matches_filter("Virgilio", "virg"));
+        assert!(!matches_filter("Dante", "virgilio"));
+    }
+
+    #[test]
+    fn test_invalid_speakers() {
+        let error = parse(
+            "[[speech]]\npassage = \"Limbo 1\"\nspeaker = \"x\"",
+            "mine.toml",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("mine.toml"));
+    }
+}
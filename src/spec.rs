@@ -0,0 +1,139 @@
+//! How to find the cantos in a plain-text source: a small TOML spec, so
+//! texts laid out differently from the Gutenberg files can be parsed without
+//! code changes.
+//!
+//! A `spec.toml` beside the source texts replaces any of the defaults, which
+//! describe the Gutenberg files:
+//!
+//! ```toml
+//! # A section heading; the first group is its number, arabic or roman
+//! section = '^Canto\s+([IVXLCDM]+)\.?$'
+//! # Lines to leave out, such as the cantica's title before each canto
+//! skip = ['^\*\*\* ', 'Project Gutenberg', '^(Inferno|Purgatorio|Paradiso)$']
+//! # Parsing stops at the first line matching any of these
+//! end = ['^Updated editions will replace']
+//! ```
+//!
+//! Patterns are regular expressions matched against each trimmed line.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::DucaError;
+
+const SECTION: &str = r"^Canto\s+([IVXLCDM]+)\.?$";
+const SKIP: &[&str] = &[
+    r"^\*\*\* ",
+    "Project Gutenberg",
+    "^(Inferno|Purgatorio|Paradiso)$",
+];
+const END: &[&str] = &["^Updated editions will replace"];
+
+/// The markers that divide a source text into cantos.
+#[derive(Debug, Clone)]
+pub struct ParseSpec {
+    pub section: Regex,
+    pub skip: Vec<Regex>,
+    pub end: Vec<Regex>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecFile {
+    section: Option<String>,
+    skip: Option<Vec<String>>,
+    end: Option<Vec<String>>,
+}
+
+impl Default for ParseSpec {
+    /// The layout of the Project Gutenberg texts.
+    fn default() -> Self {
+        let compile = |patterns: &[&str]| patterns.iter().map(|p| Regex::new(p).unwrap()).collect();
+        Self {
+            section: Regex::new(SECTION).unwrap(),
+            skip: compile(SKIP),
+            end: compile(END),
+        }
+    }
+}
+
+impl ParseSpec {
+    /// Parse a spec file's content; `name` identifies it in errors.
+    pub fn parse(content: &str, name: &str) -> Result<Self, DucaError> {
+        let invalid = |reason: String| DucaError::UserData {
+            path: name.to_string(),
+            reason,
+        };
+        let file: SpecFile =
+            toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+        let compile = |pattern: &str| Regex::new(pattern).map_err(|e| invalid(e.to_string()));
+
+        let mut spec = Self::default();
+        if let Some(section) = file.section {
+            spec.section = compile(&section)?;
+            if spec.section.captures_len() < 2 {
+                return Err(invalid("section needs a group for the number".to_string()));
+            }
+        }
+        if let Some(skip) = file.skip {
+            spec.skip = skip.iter().map(|p| compile(p)).collect::<Result<_, _>>()?;
+        }
+        if let Some(end) = file.end {
+            spec.end = end.iter().map(|p| compile(p)).collect::<Result<_, _>>()?;
+        }
+        Ok(spec)
+    }
+
+    /// The spec in `dir/spec.toml`, or the default if there is none.
+    pub fn load(dir: &Path) -> Result<Self, DucaError> {
+        let path = dir.join("spec.toml");
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content, &path.display().to_string()),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn is_skipped(&self, line: &str) -> bool {
+        self.skip.iter().any(|re| re.is_match(line))
+    }
+
+    pub fn is_end(&self, line: &str) -> bool {
+        self.end.iter().any(|re| re.is_match(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_spec() {
+        let spec = ParseSpec::default();
+        assert_eq!(&spec.section.captures("Canto XXVI").unwrap()[1], "XXVI");
+        assert!(spec.is_skipped("*** END OF THE PROJECT GUTENBERG EBOOK ***"));
+        assert!(spec.is_end("Updated editions will replace the previous one"));
+        assert!(!spec.is_skipped("Nel mezzo del cammin di nostra vita"));
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = ParseSpec::parse(r#"section = '^CANTO (\d+)$'"#, "spec.toml").unwrap();
+        assert!(spec.section.is_match("CANTO 3"));
+        // Unset fields keep their defaults
+        assert!(spec.is_end("Updated editions will replace the previous one"));
+
+        let spec = ParseSpec::parse("skip = []\nend = ['^FINE$']", "spec.toml").unwrap();
+        assert!(!spec.is_skipped("*** START ***"));
+        assert!(spec.is_end("FINE"));
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        for content in ["section = 'Canto'", "section = '('", "sections = 'x'"] {
+            let error = ParseSpec::parse(content, "mine.toml").unwrap_err();
+            assert!(error.to_string().contains("mine.toml"), "{}", content);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+#[cfg(feature = "tui")]
+use std::time::Duration;
+
+/// Milliseconds of silence between verses unless asked otherwise.
+pub const DEFAULT_PAUSE_MS: u64 = 400;
+
+/// The text-to-speech command: `$DUCA_TTS_CMD` when set, such as
+/// `espeak-ng -v it -s 140`, or else `say` on macOS and `espeak-ng -v it`
+/// elsewhere. The text to speak is appended as the last argument.
+pub fn command() -> Vec<String> {
+    command_from(env::var("DUCA_TTS_CMD").ok())
+}
+
+fn command_from(tts_env: Option<String>) -> Vec<String> {
+    let command = tts_env
+        .filter(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "macos") {
+                "say".to_string()
+            } else {
+                "espeak-ng -v it".to_string()
+            }
+        });
+    command.split_whitespace().map(String::from).collect()
+}
+
+/// Speak `text` with `command`, waiting until it has been said. The
+/// command's output goes to `output`: the terminal for `read-aloud`,
+/// nowhere for the TUI.
+pub fn speak(command: &[String], text: &str, output: Stdio) -> io::Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no TTS command",
+        ));
+    };
+    let status = Command::new(program)
+        .args(args)
+        .arg(text)
+        .stdin(Stdio::null())
+        .stdout(output)
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", program, e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {}",
+            program, status
+        )))
+    }
+}
+
+/// Speak `verses` one at a time in the background with `pause` between
+/// them, so the TUI keeps running. Nothing reports a failure; the verses
+/// just go unspoken.
+#[cfg(feature = "tui")]
+pub fn speak_in_background(verses: Vec<String>, pause: Duration) {
+    let command = command();
+    std::thread::spawn(move || {
+        for verse in verses {
+            if speak(&command, &verse, Stdio::null()).is_err() {
+                break;
+            }
+            std::thread::sleep(pause);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tts_command() {
+        assert_eq!(
+            command_from(Some("espeak-ng -v it -s 140".to_string())),
+            ["espeak-ng", "-v", "it", "-s", "140"]
+        );
+        assert!(!command_from(Some("  ".to_string())).is_empty());
+        assert!(speak(&[], "Nel mezzo", Stdio::null()).is_err());
+    }
+}
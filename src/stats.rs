@@ -0,0 +1,314 @@
+//! Statistics of a canto: its size, the words that set it apart from the
+//! rest of the poem, and the chains of its terza rima; heatmaps of where in
+//! the poem a search matches; and how often each cantica uses a word.
+//!
+//! Rhymes are told apart by ear as Italian verse mostly ends: from the
+//! vowel of the second-to-last syllable, so `vita` rhymes with `smarrita`,
+//! or from the final vowel of a word stressed on it, such as `pietà`.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::words::tokens;
+use crate::{normalize, CanticaId, Canto, DivinaCommedia};
+
+/// Distinctive words listed for a canto.
+const KEYWORDS: usize = 8;
+
+/// Letters in the shortest keyword.
+const SHORTEST_KEYWORD: usize = 4;
+
+/// Shades of heatmap cells, from a canto with no matches to the most.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantoStats {
+    pub lines: usize,
+    pub words: usize,
+    /// Different words, folded.
+    pub distinct: usize,
+    /// The most [distinctive words](distinctive_words).
+    pub keywords: Vec<(String, usize)>,
+    /// Rhymes of the terza rima, each running through the tercets from
+    /// the middle line of one to the outer lines of the next.
+    pub rhyme_chains: usize,
+}
+
+/// How often each folded word appears in `canto`.
+fn counts(canto: &Canto) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for verse in &canto.verses {
+        for word in tokens(&normalize::fold(&verse.text)) {
+            *counts.entry(word.to_string()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Up to `limit` of the words `canto` uses most and the rest of `commedia`
+/// least, by tf-idf, with how often the canto uses them; most distinctive
+/// first.
+pub fn distinctive_words(
+    commedia: &DivinaCommedia,
+    canto: &Canto,
+    limit: usize,
+) -> Vec<(String, usize)> {
+    let counts = counts(canto);
+    let mut cantos = 0;
+    let mut spread: HashMap<&str, usize> = HashMap::new();
+    for (_, other) in commedia.cantos() {
+        cantos += 1;
+        let words: HashSet<String> = other
+            .verses
+            .iter()
+            .flat_map(|verse| {
+                tokens(&normalize::fold(&verse.text))
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for (word, _) in counts.iter().filter(|(word, _)| words.contains(*word)) {
+            *spread.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    // A word said once says little of the canto, however rare, and short
+    // ones are mostly the stubs of elisions, the cu of cu' and the od of od'
+    let mut keywords: Vec<(f64, &String, usize)> = counts
+        .iter()
+        .filter(|(word, &count)| count > 1 && word.chars().count() >= SHORTEST_KEYWORD)
+        .map(|(word, &count)| {
+            let in_cantos = spread.get(word.as_str()).copied().unwrap_or(1).max(1);
+            let idf = (cantos.max(1) as f64 / in_cantos as f64).ln();
+            (count as f64 * idf, word, count)
+        })
+        .filter(|(score, _, _)| *score > 0.0)
+        .collect();
+    keywords.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    keywords
+        .into_iter()
+        .take(limit)
+        .map(|(_, word, count)| (word.clone(), count))
+        .collect()
+}
+
+/// Statistics of `canto`, its keywords weighed against every canto of
+/// `commedia`.
+pub fn canto_stats(commedia: &DivinaCommedia, canto: &Canto) -> CantoStats {
+    let counts = counts(canto);
+    CantoStats {
+        lines: canto.verses.len(),
+        words: counts.values().sum(),
+        distinct: counts.len(),
+        keywords: distinctive_words(commedia, canto, KEYWORDS),
+        rhyme_chains: rhyme_chains(canto),
+    }
+}
+
+/// The rhyme of a verse, folded: its last word from the vowel of the
+/// second-to-last syllable, or its final vowel if accented. `None` for a
+/// verse without letters.
+pub fn rhyme(text: &str) -> Option<String> {
+    let word = text
+        .split(|c: char| !c.is_alphabetic())
+        .rfind(|word| !word.is_empty())?;
+    let last = word.chars().last()?;
+    if normalize::fold_char(last) != last.to_lowercase().next().unwrap_or(last) {
+        return Some(normalize::fold_char(last).to_string());
+    }
+    let folded = normalize::fold(word);
+    let vowels: Vec<usize> = folded
+        .char_indices()
+        .filter(|(_, c)| "aeiou".contains(*c))
+        .map(|(i, _)| i)
+        .collect();
+    let start = match vowels.len() {
+        0 => 0,
+        1 => vowels[0],
+        n => vowels[n - 2],
+    };
+    Some(folded[start..].to_string())
+}
+
+/// The rhyme chains of `canto`'s terza rima, ABA BCB CDC: lines two apart
+/// rhyming, but for the last of a tercet and the middle of the next, are
+/// links of a chain, and a line rhyming with neither starts one of its own.
+pub fn rhyme_chains(canto: &Canto) -> usize {
+    let rhymes: Vec<Option<String>> = canto.verses.iter().map(|v| rhyme(&v.text)).collect();
+    // Each line's chain, the index of the line that started it
+    let mut chain: BTreeMap<usize, usize> = BTreeMap::new();
+    for (i, rhyme) in rhymes.iter().enumerate() {
+        let linked = i >= 2 && i % 3 != 1 && rhyme.is_some() && rhymes[i - 2] == *rhyme;
+        let start = if linked { chain[&(i - 2)] } else { i };
+        chain.insert(i, start);
+    }
+    chain.iter().filter(|(line, start)| line == start).count()
+}
+
+/// A heatmap cell for each canto of `commedia`, in reading order, counting
+/// the `hits` in it: the canto of each verse a search matched, say.
+pub fn hits_by_canto(
+    commedia: &DivinaCommedia,
+    hits: impl IntoIterator<Item = (CanticaId, u8)>,
+) -> Vec<(CanticaId, u8, usize)> {
+    let mut counts: HashMap<(CanticaId, u8), usize> = HashMap::new();
+    for hit in hits {
+        *counts.entry(hit).or_insert(0) += 1;
+    }
+    commedia
+        .cantos()
+        .map(|(cantica, canto)| {
+            let count = counts.get(&(cantica, canto.number)).copied().unwrap_or(0);
+            (cantica, canto.number, count)
+        })
+        .collect()
+}
+
+/// The shade of a heatmap cell of `count` hits when the most in a cell is
+/// `most`: `·` for none, then `░`, `▒`, `▓` and `█` by quarters of the most.
+pub fn shade(count: usize, most: usize) -> char {
+    if count == 0 || most == 0 {
+        return SHADES[0];
+    }
+    SHADES[(count * 4).div_ceil(most).clamp(1, 4)]
+}
+
+/// How often a cantica uses some words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanticaFrequency {
+    pub cantica: CanticaId,
+    /// Words in the cantica.
+    pub words: usize,
+    /// How many of them are each term, in the order asked for.
+    pub counts: Vec<usize>,
+}
+
+impl CanticaFrequency {
+    /// Uses of the `term`th term per 10,000 words, so canticas of
+    /// different lengths compare.
+    pub fn rate(&self, term: usize) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.counts[term] as f64 * 10_000.0 / self.words as f64
+    }
+}
+
+/// Whether the folded `word` is `term`: the same word, folded, or for a
+/// term ending in `*`, any word it begins.
+fn is_term(word: &str, term: &str) -> bool {
+    match term.strip_suffix('*') {
+        Some(stem) => word.starts_with(stem),
+        None => word == term,
+    }
+}
+
+/// How often each cantica of `commedia` uses each of `terms`, whole words
+/// matched ignoring case and accents.
+pub fn frequencies(commedia: &DivinaCommedia, terms: &[String]) -> Vec<CanticaFrequency> {
+    let terms: Vec<String> = terms.iter().map(|term| normalize::fold(term)).collect();
+    let mut frequencies: Vec<CanticaFrequency> = Vec::new();
+    for (cantica, canto) in commedia.cantos() {
+        if frequencies.last().is_none_or(|f| f.cantica != cantica) {
+            frequencies.push(CanticaFrequency {
+                cantica,
+                words: 0,
+                counts: vec![0; terms.len()],
+            });
+        }
+        let frequency = frequencies.last_mut().expect("pushed above");
+        for (word, count) in counts(canto) {
+            frequency.words += count;
+            for (i, term) in terms.iter().enumerate() {
+                if is_term(&word, term) {
+                    frequency.counts[i] += count;
+                }
+            }
+        }
+    }
+    frequencies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_commedia, CanticaId};
+
+    #[test]
+    fn test_rhyme() {
+        assert_eq!(rhyme("Nel mezzo del cammin di nostra vita").unwrap(), "ita");
+        assert_eq!(rhyme("ché la diritta via era smarrita.").unwrap(), "ita");
+        assert_eq!(
+            rhyme("esta selva selvaggia e aspra e forte").unwrap(),
+            "orte"
+        );
+        assert_eq!(rhyme("che nel pensier rinova la paura!").unwrap(), "ura");
+        assert_eq!(
+            rhyme("Lasciate ogne speranza, voi ch'intrate».").unwrap(),
+            "ate"
+        );
+        assert_eq!(rhyme("pietà").unwrap(), "a");
+        assert_eq!(rhyme("..."), None);
+    }
+
+    #[test]
+    fn test_canto_stats() {
+        let commedia = load_commedia().unwrap();
+        let canto = commedia.canto(CanticaId::Inferno, 1).unwrap();
+        let stats = canto_stats(&commedia, canto);
+        assert_eq!(stats.lines, 136);
+        assert!(stats.words > 900 && stats.distinct < stats.words);
+        // 45 tercets and a closing line make a chain from the first
+        // tercet's outer lines and one from each middle line, but the
+        // "Sicilian" rhyme of desse, venisse and tremesse (44-48) is heard
+        // as two more breaks
+        assert_eq!(stats.rhyme_chains, 48);
+        let keywords: Vec<&str> = stats.keywords.iter().map(|(w, _)| w.as_str()).collect();
+        assert_eq!(keywords.len(), KEYWORDS);
+        assert!(keywords.contains(&"feltro"), "{:?}", keywords);
+        assert!(!keywords.contains(&"che"));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let commedia = load_commedia().unwrap();
+        let hits = commedia
+            .search("amor", None)
+            .into_iter()
+            .map(|m| (m.cantica, m.canto));
+        let cells = hits_by_canto(&commedia, hits);
+        assert_eq!(cells.len(), 100);
+        assert_eq!(cells[0], (CanticaId::Inferno, 1, 3));
+        assert_eq!(cells[34].0, CanticaId::Purgatorio);
+        let (_, _, most) = *cells.iter().max_by_key(|(_, _, count)| count).unwrap();
+        assert!(cells.iter().any(|&(_, _, count)| count == 0));
+
+        assert_eq!(shade(0, most), '·');
+        assert_eq!(shade(1, 20), '░');
+        assert_eq!(shade(10, 20), '▒');
+        assert_eq!(shade(11, 20), '▓');
+        assert_eq!(shade(most, most), '█');
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let commedia = load_commedia().unwrap();
+        let terms = ["Amore".to_string(), "amor*".to_string()];
+        let frequencies = frequencies(&commedia, &terms);
+        let canticas: Vec<CanticaId> = frequencies.iter().map(|f| f.cantica).collect();
+        assert_eq!(canticas, CanticaId::ALL);
+        for f in &frequencies {
+            assert!(f.words > 30_000, "{:?}", f);
+            // Every amore is an amor*, and amor, amori and the rest add more
+            assert!(f.counts[1] > f.counts[0] && f.counts[0] > 0, "{:?}", f);
+        }
+        // Love is spoken of more on the way up
+        let (inferno, paradiso) = (&frequencies[0], &frequencies[2]);
+        assert!(paradiso.rate(1) > inferno.rate(1));
+        assert_eq!(
+            inferno.rate(0),
+            inferno.counts[0] as f64 * 10_000.0 / inferno.words as f64
+        );
+        assert!(!is_term("amore", "amor"));
+        assert!(is_term("amorosa", "amor*"));
+    }
+}
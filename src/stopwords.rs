@@ -0,0 +1,59 @@
+//! The commonest words of Dante's Italian, down-weighted when ranking
+//! matches of several words, so a query like `la selva oscura` ranks verses
+//! by `selva` and `oscura` rather than by the `la` that nearly all of them
+//! share.
+//!
+//! The list is of articles, prepositions and their contractions,
+//! conjunctions, pronouns and the forms of `essere`, folded, with the stubs
+//! that elision leaves of them, such as the `ch` of `ch’` and the `l` of
+//! `’l`.
+
+use crate::normalize;
+
+/// How much a stopword counts towards a match's score, next to another
+/// word's one.
+pub const STOPWORD_WEIGHT: f32 = 0.1;
+
+/// The stopwords, folded and in sorted order.
+const STOPWORDS: [&str; 86] = [
+    "a", "ad", "ai", "al", "alla", "alle", "allo", "altro", "anche", "c", "ch", "che", "chi", "ci",
+    "co", "col", "come", "con", "cosi", "cui", "d", "da", "dal", "dalla", "de", "dei", "del",
+    "della", "di", "e", "ed", "el", "era", "fu", "gia", "gli", "i", "il", "in", "io", "l", "la",
+    "le", "li", "lo", "lor", "loro", "m", "ma", "me", "mi", "mio", "n", "ne", "nel", "nella",
+    "non", "o", "od", "or", "per", "piu", "poi", "quel", "quella", "questa", "questo", "s", "se",
+    "si", "sia", "so", "son", "su", "sua", "sue", "suo", "t", "te", "ti", "tu", "un", "una", "uno",
+    "v", "vi",
+];
+
+/// Whether `word`, folded, is one of the commonest words.
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS
+        .binary_search(&normalize::fold(word).as_str())
+        .is_ok()
+}
+
+/// How much `word` counts towards a ranking: [`STOPWORD_WEIGHT`] for a
+/// stopword, 1 for any other.
+pub fn weight(word: &str) -> f32 {
+    if is_stopword(word) {
+        STOPWORD_WEIGHT
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopwords() {
+        assert!(STOPWORDS.windows(2).all(|w| w[0] < w[1]));
+        assert!(is_stopword("che"));
+        assert!(is_stopword("Ché"));
+        assert!(is_stopword("più"));
+        assert!(!is_stopword("selva"));
+        assert_eq!(weight("la"), STOPWORD_WEIGHT);
+        assert_eq!(weight("oscura"), 1.0);
+    }
+}
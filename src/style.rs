@@ -0,0 +1,184 @@
+use clap::ValueEnum;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::ops::Range;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const DIM: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+
+/// How [`Styler::mark_ranges`] styles a range of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// A search match, highlighted.
+    Match,
+    /// A word with a glossary entry, underlined.
+    Gloss,
+    /// A Latin phrase, italicized.
+    Latin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Applies terminal styling to CLI output, or passes text through untouched
+/// when color is disabled.
+#[derive(Debug, Clone, Copy)]
+pub struct Styler {
+    enabled: bool,
+}
+
+impl Styler {
+    pub fn stdout(choice: ColorChoice) -> Self {
+        Self::new(choice, io::stdout().is_terminal())
+    }
+
+    pub fn stderr(choice: ColorChoice) -> Self {
+        Self::new(choice, io::stderr().is_terminal())
+    }
+
+    fn new(choice: ColorChoice, is_terminal: bool) -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        Self {
+            enabled: color_enabled(choice, no_color, is_terminal),
+        }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn header(&self, text: &str) -> String {
+        self.paint(BOLD, text)
+    }
+
+    pub fn location(&self, text: &str) -> String {
+        self.paint(CYAN, text)
+    }
+
+    pub fn line_number(&self, text: &str) -> String {
+        self.paint(YELLOW, text)
+    }
+
+    /// Secondary text such as commentary notes.
+    pub fn note(&self, text: &str) -> String {
+        self.paint(DIM, text)
+    }
+
+    pub fn highlight(&self, text: &str) -> String {
+        self.paint(BOLD_RED, text)
+    }
+
+    /// A word the glossary explains.
+    pub fn gloss(&self, text: &str) -> String {
+        self.paint(UNDERLINE, text)
+    }
+
+    pub fn latin(&self, text: &str) -> String {
+        self.paint(ITALIC, text)
+    }
+
+    pub fn error(&self, text: &str) -> String {
+        format!("{} {}", self.paint(BOLD_RED, "error:"), text)
+    }
+
+    /// Style the given byte ranges of `text`, which must be sorted and
+    /// non-overlapping.
+    pub fn mark_ranges(&self, text: &str, ranges: &[(Range<usize>, Mark)]) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, mark) in ranges {
+            if range.is_empty() {
+                continue;
+            }
+            result.push_str(&text[last..range.start]);
+            let marked = &text[range.clone()];
+            result.push_str(&match mark {
+                Mark::Match => self.highlight(marked),
+                Mark::Gloss => self.gloss(marked),
+                Mark::Latin => self.latin(marked),
+            });
+            last = range.end;
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+}
+
+/// `--color always` wins over `NO_COLOR`; `auto` colors only a terminal
+/// that hasn't opted out.
+fn color_enabled(choice: ColorChoice, no_color: bool, is_terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => is_terminal && !no_color,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_color_enabled_resolution() {
+        assert!(color_enabled(ColorChoice::Always, true, false));
+        assert!(!color_enabled(ColorChoice::Never, false, true));
+        assert!(color_enabled(ColorChoice::Auto, false, true));
+        assert!(!color_enabled(ColorChoice::Auto, true, true));
+        assert!(!color_enabled(ColorChoice::Auto, false, false));
+    }
+
+    #[test]
+    fn test_styler_disabled_is_passthrough() {
+        let styler = Styler { enabled: false };
+        assert_eq!(styler.header("Inferno Canto I"), "Inferno Canto I");
+        assert_eq!(styler.error("oops"), "error: oops");
+
+        assert_eq!(
+            styler.mark_ranges(
+                "per una selva oscura",
+                &[(8..13, Mark::Match), (14..20, Mark::Gloss)]
+            ),
+            "per una selva oscura"
+        );
+    }
+
+    #[test]
+    fn test_mark_ranges() {
+        let styler = Styler { enabled: true };
+        let text = "Per me si va per";
+        let regex = Regex::new("(?i)per").unwrap();
+        let ranges: Vec<(Range<usize>, Mark)> = regex
+            .find_iter(text)
+            .map(|m| (m.range(), Mark::Match))
+            .collect();
+
+        let highlighted = styler.mark_ranges(text, &ranges);
+        assert_eq!(
+            highlighted,
+            format!("{}Per{} me si va {}per{}", BOLD_RED, RESET, BOLD_RED, RESET)
+        );
+
+        let glossed = styler.mark_ranges("lo duca mio", &[(3..7, Mark::Gloss)]);
+        assert_eq!(glossed, format!("lo {}duca{} mio", UNDERLINE, RESET));
+    }
+}
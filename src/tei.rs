@@ -0,0 +1,176 @@
+//! TEI XML editions, the usual encoding for texts in the digital humanities.
+//!
+//! Each `<div type="canto">` becomes a canto and each `<l>` inside it a
+//! verse, numbered in order. The canto number comes from the div's `n`
+//! attribute, else its `<head>`, else its position. The cantica comes from an
+//! enclosing `<div type="cantica">` (or `part`, or `book`) named by its `n`
+//! or `<head>`, else from the file, as in `inferno.xml`:
+//!
+//! ```xml
+//! <TEI xmlns="http://www.tei-c.org/ns/1.0"><text><body>
+//!   <div type="cantica" n="Inferno">
+//!     <div type="canto" n="1">
+//!       <lg type="terzina">
+//!         <l n="1">Nel mezzo del cammin di nostra vita</l>
+//!         ...
+//! ```
+//!
+//! `<note>` elements inside a line are left out of its text.
+
+use roxmltree::{Document, Node};
+
+use crate::{parse_canto_number, CanticaId, Canto, DivinaCommedia, DucaError, Verse};
+
+/// Parse a TEI document into `commedia`, returning the number of cantos
+/// found. `cantica` is used for cantos outside any cantica div; `name`
+/// identifies the document in errors.
+pub fn parse(
+    content: &str,
+    name: &str,
+    cantica: Option<CanticaId>,
+    commedia: &mut DivinaCommedia,
+) -> Result<usize, DucaError> {
+    let invalid = |reason: String| DucaError::UserData {
+        path: name.to_string(),
+        reason,
+    };
+    let doc = Document::parse(content).map_err(|e| invalid(e.to_string()))?;
+
+    let mut count = 0;
+    for div in doc.descendants().filter(|n| is_div(n, &["canto"])) {
+        let id = div
+            .ancestors()
+            .filter(|n| is_div(n, &["cantica", "part", "book"]))
+            .find_map(|n| label(&n, |s| s.parse::<CanticaId>().ok()))
+            .or(cantica)
+            .ok_or_else(|| invalid("a canto is outside any cantica".to_string()))?;
+        let number = match label(&div, parse_canto_number) {
+            Some(number) => number,
+            None => commedia.get(id).cantos.keys().last().map_or(1, |n| n + 1),
+        };
+
+        let verses = div
+            .descendants()
+            .filter(|n| n.tag_name().name() == "l")
+            .enumerate()
+            .map(|(i, l)| Verse {
+                line_number: i + 1,
+                text: line_text(&l),
+            })
+            .collect();
+        commedia
+            .get_mut(id)
+            .cantos
+            .insert(number, Canto::new(id, number, verses));
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Whether `node` is a `<div>` whose `type` is one of `types`.
+fn is_div(node: &Node, types: &[&str]) -> bool {
+    node.tag_name().name() == "div"
+        && node
+            .attribute("type")
+            .is_some_and(|t| types.iter().any(|want| t.eq_ignore_ascii_case(want)))
+}
+
+/// What a div is called: `parse` applied to its `n` attribute, else to each
+/// word of its `<head>` from the last, so `Canto XXVI` gives 26.
+fn label<T>(div: &Node, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    if let Some(value) = div.attribute("n").and_then(&parse) {
+        return Some(value);
+    }
+    let head = div.children().find(|n| n.tag_name().name() == "head")?;
+    let text = line_text(&head);
+    text.split(|c: char| !c.is_alphanumeric())
+        .rev()
+        .find_map(&parse)
+}
+
+/// The text of an element without its notes, whitespace collapsed.
+fn line_text(node: &Node) -> String {
+    let text: String = node
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter(|n| !n.ancestors().any(|a| a.tag_name().name() == "note"))
+        .filter_map(|n| n.text())
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TEI xmlns="http://www.tei-c.org/ns/1.0">
+  <teiHeader><fileDesc><titleStmt><title>Commedia</title></titleStmt></fileDesc></teiHeader>
+  <text><body>
+    <div type="cantica" n="Inferno">
+      <div type="canto" n="1">
+        <head>Canto I</head>
+        <lg type="terzina">
+          <l n="1">Nel mezzo del cammin di nostra vita</l>
+          <l n="2">mi ritrovai per una <hi>selva</hi> oscura,<note>the dark wood</note></l>
+          <l n="3">ché la diritta via
+            era smarrita.</l>
+        </lg>
+      </div>
+    </div>
+    <div type="cantica"><head>Purgatorio</head>
+      <div type="canto"><head>Canto II</head>
+        <l>Già era 'l sole a l'orizzonte giunto</l>
+      </div>
+    </div>
+  </body></text>
+</TEI>"#;
+
+    #[test]
+    fn test_parse_tei() {
+        let mut commedia = DivinaCommedia::new();
+        assert_eq!(parse(SAMPLE, "sample.xml", None, &mut commedia), Ok(2));
+
+        let canto = commedia.canto(CanticaId::Inferno, 1).unwrap();
+        let lines: Vec<&str> = canto.verses.iter().map(|v| v.text.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Nel mezzo del cammin di nostra vita",
+                "mi ritrovai per una selva oscura,",
+                "ché la diritta via era smarrita.",
+            ]
+        );
+        assert_eq!(canto.title, "Inferno I");
+
+        let canto = commedia.canto(CanticaId::Purgatorio, 2).unwrap();
+        assert_eq!(
+            canto.verse(1).unwrap().text,
+            "Già era 'l sole a l'orizzonte giunto"
+        );
+    }
+
+    #[test]
+    fn test_cantica_from_file() {
+        let tei = r#"<TEI><text><body>
+            <div type="canto" n="3"><l>Per me si va ne la città dolente,</l></div>
+            <div type="canto"><l>Ruppemi l'alto sonno ne la testa</l></div>
+        </body></text></TEI>"#;
+
+        let mut commedia = DivinaCommedia::new();
+        assert!(parse(tei, "cantos.xml", None, &mut commedia).is_err());
+        assert_eq!(
+            parse(tei, "inferno.xml", Some(CanticaId::Inferno), &mut commedia),
+            Ok(2)
+        );
+        // A canto without a number follows the one before it
+        assert!(commedia.canto(CanticaId::Inferno, 4).is_some());
+    }
+
+    #[test]
+    fn test_invalid_tei() {
+        let mut commedia = DivinaCommedia::new();
+        let error = parse("<TEI><l>", "broken.xml", None, &mut commedia).unwrap_err();
+        assert!(error.to_string().contains("broken.xml"));
+    }
+}
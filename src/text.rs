@@ -0,0 +1,309 @@
+use std::io::{self, IsTerminal};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Width of the terminal stdout is attached to, or `None` when output is
+/// piped and should be left unwrapped.
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size().map(|(cols, _)| cols)
+}
+
+/// Columns and rows of the controlling terminal.
+#[cfg(feature = "tui")]
+pub fn terminal_size() -> Option<(usize, usize)> {
+    crossterm::terminal::size()
+        .ok()
+        .map(|(cols, rows)| (cols as usize, rows as usize))
+}
+
+/// Columns and rows of the controlling terminal, as exported by the shell.
+/// Builds without the `tui` feature have no terminal backend to ask.
+#[cfg(not(feature = "tui"))]
+pub fn terminal_size() -> Option<(usize, usize)> {
+    let var = |name| std::env::var(name).ok()?.parse().ok();
+    Some((var("COLUMNS")?, var("LINES")?))
+}
+
+/// Number of terminal columns `text` occupies.
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Shorten `text` to at most `width` columns, marking the cut with an
+/// ellipsis. The cut falls between graphemes, so an accent written as a
+/// combining character stays with its letter.
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut result = String::new();
+    let mut col = 0;
+    for grapheme in text.graphemes(true) {
+        let w = display_width(grapheme);
+        if col + w > width - 1 {
+            break;
+        }
+        col += w;
+        result.push_str(grapheme);
+    }
+    result.push('…');
+    result
+}
+
+/// Shorten `text` to at most `width` columns from the front, keeping its
+/// end and marking the cut with a leading ellipsis.
+#[cfg(feature = "tui")]
+pub fn truncate_start(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut kept = Vec::new();
+    let mut col = 0;
+    for grapheme in text.graphemes(true).rev() {
+        let w = display_width(grapheme);
+        if col + w > width - 1 {
+            break;
+        }
+        col += w;
+        kept.push(grapheme);
+    }
+    let mut result = String::from('…');
+    result.extend(kept.into_iter().rev());
+    result
+}
+
+/// `text` followed by enough spaces to fill `width` columns. Unlike
+/// `format!("{:<w$}")`, which counts chars, this lines up accented text.
+pub fn pad_end(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// `text` preceded by enough spaces to end at column `width`.
+#[cfg(feature = "tui")]
+pub fn pad_start(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(display_width(text));
+    format!("{}{}", " ".repeat(fill), text)
+}
+
+/// The line-number gutter printed before a verse, e.g. ` 42: `.
+pub fn gutter(line_number: usize) -> String {
+    format!("{:3}: ", line_number)
+}
+
+/// A keyword-in-context view of `text` in `width` columns, with the match
+/// at byte range `keyword` starting at column `before`: the context to its
+/// left is padded or cut from the front to fill exactly `before` columns,
+/// so a list of these lines the keywords up one under another.
+#[cfg(feature = "tui")]
+pub fn kwic(text: &str, keyword: Range<usize>, before: usize, width: usize) -> String {
+    let before = before.min(width);
+    let left = text[..keyword.start].trim_start();
+    let left = pad_start(&truncate_start(left, before), before);
+    let right = truncate(&text[keyword.start..], width - before);
+    left + &right
+}
+
+/// Split `text` into byte ranges that each fit in `width` columns, breaking
+/// at whitespace where possible and mid-word only when a word is too long.
+/// `width` is what's left of the terminal after any prefix, so however
+/// narrow it is the lines never overflow; below one column each line still
+/// takes a character.
+pub fn wrap_ranges(text: &str, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    while start < text.len() {
+        let rest = &text[start..];
+        if display_width(rest) <= width {
+            lines.push(start..text.len());
+            break;
+        }
+
+        let mut col = 0;
+        let mut end = start;
+        let mut last_space = None;
+        for (i, c) in rest.char_indices() {
+            let w = c.width().unwrap_or(0);
+            if col + w > width {
+                if c.is_whitespace() {
+                    last_space = Some(start + i);
+                }
+                break;
+            }
+            col += w;
+            if c.is_whitespace() {
+                last_space = Some(start + i);
+            }
+            end = start + i + c.len_utf8();
+        }
+
+        let (line_end, next_start) = match last_space {
+            Some(space) if space > start => (space, space),
+            // Always make progress, even if a single character overflows
+            _ if end == start => {
+                let len = rest.chars().next().map_or(1, char::len_utf8);
+                (start + len, start + len)
+            }
+            _ => (end, end),
+        };
+
+        let line = text[start..line_end].trim_end();
+        lines.push(start..start + line.len());
+
+        start = next_start;
+        while let Some(c) = text[start..].chars().next() {
+            if !c.is_whitespace() {
+                break;
+            }
+            start += c.len_utf8();
+        }
+    }
+
+    if lines.is_empty() {
+        lines.push(0..0);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(text: &str, width: usize) -> Vec<&str> {
+        wrap_ranges(text, width)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect()
+    }
+
+    #[test]
+    fn test_display_width_accents() {
+        assert_eq!(display_width("città"), 5);
+        assert_eq!(display_width("perché"), 6);
+        // Decomposed "e" + combining acute accent takes a single column
+        assert_eq!(display_width("perche\u{301}"), 6);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Inferno", 10), "Inferno");
+        assert_eq!(truncate("Purgatorio Canto XXXIII", 10), "Purgatori…");
+        assert_eq!(truncate("città dolente", 5), "citt…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_multibyte() {
+        // Cuts right after a two-byte letter, where byte slicing would panic
+        assert_eq!(truncate("perché mi", 7), "perché…");
+        assert_eq!(truncate("la città dolente", 9), "la città…");
+        // A decomposed accent isn't split from its letter
+        assert_eq!(truncate("perche\u{301} mi", 7), "perche\u{301}…");
+        assert_eq!(truncate("perche\u{301} mi", 6), "perch…");
+        assert!(display_width(&truncate("è è è è è è è è", 5)) <= 5);
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_truncate_start() {
+        assert_eq!(truncate_start("selva oscura", 20), "selva oscura");
+        assert_eq!(truncate_start("nel mezzo del cammin", 7), "…cammin");
+        assert_eq!(truncate_start("la città", 6), "…città");
+        assert_eq!(truncate_start("perche\u{301}", 3), "…he\u{301}");
+        assert_eq!(pad_start("è", 3), "  è");
+    }
+
+    #[test]
+    fn test_pad_by_display_width() {
+        assert_eq!(pad_end("città", 7), "città  ");
+        assert_eq!(pad_end("perche\u{301}", 7), "perche\u{301} ");
+        assert_eq!(pad_end("Purgatorio", 4), "Purgatorio");
+        assert_eq!(gutter(7), "  7: ");
+        assert_eq!(gutter(142), "142: ");
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_kwic_aligns_keywords() {
+        let lines = [
+            ("per me si va ne la città dolente", "città"),
+            ("ché la diritta via era smarrita", "via"),
+            (
+                "e più d'onor mi fecero ancora, ch'e' sì mi fecer de la loro schiera",
+                "fecer",
+            ),
+        ];
+        let views: Vec<String> = lines
+            .iter()
+            .map(|(text, word)| {
+                let start = text.rfind(word).unwrap();
+                kwic(text, start..start + word.len(), 12, 24)
+            })
+            .collect();
+        assert_eq!(views[0], "…i va ne la città dolen…");
+        for (view, (_, word)) in views.iter().zip(&lines) {
+            let at = view.find(word).unwrap();
+            assert_eq!(display_width(&view[..at]), 12, "{:?}", view);
+            assert!(display_width(view) <= 24, "{:?}", view);
+        }
+    }
+
+    #[test]
+    fn test_wrap_at_word_boundaries() {
+        let text = "Nel mezzo del cammin di nostra vita mi ritrovai per una selva oscura";
+        let lines = wrap(text, 25);
+        assert_eq!(
+            lines,
+            vec![
+                "Nel mezzo del cammin di",
+                "nostra vita mi ritrovai",
+                "per una selva oscura"
+            ]
+        );
+        assert!(lines.iter().all(|l| display_width(l) <= 25));
+    }
+
+    #[test]
+    fn test_wrap_counts_columns_not_bytes() {
+        // 19 columns of accented text fits in 19 despite being more bytes
+        let text = "è è è è è è è è è è";
+        assert_eq!(display_width(text), 19);
+        assert_eq!(wrap(text, 19), vec![text]);
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_words() {
+        let text = "a".repeat(45);
+        let lines = wrap(&text, 20);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), 20);
+        assert_eq!(lines[2].len(), 5);
+    }
+
+    #[test]
+    fn test_wrap_narrow_width() {
+        // What a narrow terminal leaves after the gutter, not widened
+        assert_eq!(wrap("selva oscura", 6), vec!["selva", "oscura"]);
+        assert_eq!(wrap("via", 0), vec!["v", "i", "a"]);
+    }
+
+    #[test]
+    fn test_wrap_short_text_unchanged() {
+        assert_eq!(wrap("selva oscura", 80), vec!["selva oscura"]);
+        assert_eq!(wrap("", 80), vec![""]);
+    }
+}
@@ -1,8 +1,14 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
@@ -11,67 +17,468 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use std::collections::HashSet;
+use std::env;
+use std::fs;
 use std::io;
-
-use crate::{Cantica, Canto, DivinaCommedia};
-
-pub struct App {
-    pub commedia: DivinaCommedia,
-    pub current_cantica: String,
+use std::ops::Range;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{tr, Text};
+use crate::player::{self, Playback};
+use crate::session::{self, Session, Status};
+use crate::style::ColorChoice;
+use crate::{browser, links, speech, text};
+use clap::ValueEnum;
+use duca::allusions;
+use duca::apparatus::Apparatus;
+use duca::audio::Recordings;
+use duca::commentary::Commentary;
+use duca::glossary::Glossary;
+use duca::notes::{Bookmark, NoteStore};
+use duca::paths;
+use duca::plan::Date;
+use duca::stats::{self, CantoStats};
+use duca::stopwords;
+use duca::words::word_ranges;
+use duca::xrefs::{self, Related};
+use duca::{normalize, Cantica, CanticaId, Canto, DivinaCommedia, Normalizer, Pipeline};
+
+pub struct App<'a> {
+    pub commedia: &'a DivinaCommedia,
+    pub current_cantica: CanticaId,
     pub current_canto: Option<u8>,
     pub cantica_list_state: ListState,
     pub canto_list_state: ListState,
+    /// Index of the first verse shown.
     pub verse_scroll: u16,
+    /// Index of the verse under the cursor in Browse mode: the one being
+    /// read, which actions such as Tab and `s` work on.
+    pub cursor: usize,
+    /// Verses kept shown above and below the cursor, as vim's `scrolloff`.
+    pub scrolloff: usize,
+    /// Rows inside the verse pane's borders when it was last drawn.
+    pub verse_height: u16,
     pub search_input: String,
-    pub search_results: Vec<SearchResult>,
-    pub filtered_results: Vec<SearchResult>,
+    pub search_results: Vec<SearchResult<'a>>,
+    pub filtered_results: Vec<SearchResult<'a>>,
     pub search_list_state: ListState,
+    /// Words of the poem completing the one being typed in the search box,
+    /// commonest first, for Tab to take the first of.
+    pub completions: Vec<&'a str>,
     pub mode: AppMode,
     pub fuzzy_matcher: SkimMatcherV2,
-    pub context_canto: Option<(String, u8)>,
+    /// Results scoring less than this are dropped, so only the closest
+    /// matches show.
+    pub fuzzy_threshold: i64,
+    /// The normalizers search runs through: the CLI's default, ignoring
+    /// case and accents, with accents, word forms and old spellings toggled
+    /// with Ctrl-a, Ctrl-l and Ctrl-s.
+    pub pipeline: Pipeline,
+    /// Whether search shows a heatmap of the cantos its results are in.
+    /// Toggled with Ctrl-t.
+    pub show_heatmap: bool,
+    pub context_canto: Option<(CanticaId, u8)>,
     pub context_highlight_line: Option<usize>,
+    pub commentary: Commentary,
+    pub show_commentary: bool,
+    /// Whether the panel of passages elsewhere that bear on the canto, from
+    /// the built-in cross-references, is shown.
+    pub show_related: bool,
+    pub show_stats: bool,
+    /// What the statistics panel shows, worked out for the last canto it
+    /// showed.
+    pub canto_info: Option<CantoInfo>,
+    pub apparatus: Apparatus,
+    pub show_variants: bool,
+    /// Whether the allusions to the Aeneid, the Bible and Ovid are shown
+    /// under the verses they start on.
+    pub show_allusions: bool,
+    /// Whether the verses with footnotes are marked with their numbers, and
+    /// the current footnote shown under the verses. Toggled with `F`.
+    pub show_footnotes: bool,
+    /// Index into the shown canto's footnotes of the one shown, cycled
+    /// with `.` and `,`.
+    pub footnote: usize,
+    pub glossary: Glossary,
+    /// Index of the selected word in the current line, chosen with Tab.
+    pub selected_word: Option<usize>,
+    /// The word and definition shown in the dictionary popup, if open.
+    pub definition: Option<(String, String)>,
+    /// The in-canto search, if one is open.
+    pub find: Option<Find>,
+    pub theme: Theme,
+    pub recordings: Recordings,
+    /// The recording of the shown canto, if one is playing or paused.
+    pub playback: Option<Playback>,
+    /// The line the playing recording has reached, when its timing tells.
+    pub playing_line: Option<usize>,
+    /// The reader's notes and bookmarks, if there is somewhere to keep them.
+    pub store: Option<NoteStore>,
+    /// Every bookmark, as the bookmark pane last loaded them.
+    pub bookmarks: Vec<(CanticaId, u8, Bookmark)>,
+    /// The tags typed into the bookmark pane, narrowing its list.
+    pub bookmark_filter: String,
+    pub bookmark_list_state: ListState,
+    /// The words the term view shows, with how often the canto uses them.
+    pub terms: Vec<(String, usize)>,
+    /// Places jumped from, oldest first, for Ctrl-o and Ctrl-i to walk.
+    pub jumps: Vec<(CanticaId, u8, usize)>,
+    /// Index into `jumps` of the place Ctrl-o and Ctrl-i last went to, or
+    /// its length when they haven't been used since the last jump.
+    pub jump_index: usize,
+    /// The reading session, when timed.
+    pub session: Option<Session>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct SearchResult {
-    pub cantica: String,
+pub struct SearchResult<'a> {
+    pub cantica: CanticaId,
     pub canto: u8,
     pub line: usize,
-    pub text: String,
+    pub text: &'a str,
+    /// Byte range in `text` of the first match, which previews line up on.
+    pub keyword: Option<Range<usize>>,
     pub score: i64,
 }
 
+/// What the statistics panel shows of a canto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantoInfo {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub stats: CantoStats,
+    pub notes: usize,
+    pub bookmarks: usize,
+    /// Dates of the timed sessions that read it.
+    pub read_on: Vec<Date>,
+}
+
+/// A search within the canto shown in Browse mode, started with `f`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Find {
+    pub query: String,
+    /// Whether keys still go to the query, until Enter.
+    pub editing: bool,
+    /// Line numbers of the matching verses, with the byte ranges matched.
+    pub matches: Vec<(usize, Vec<Range<usize>>)>,
+    /// Index into `matches` of the match jumped to last.
+    pub current: usize,
+}
+
+/// What a footnote is drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FootnoteKind {
+    Commentary,
+    Allusion,
+    Gloss,
+}
+
+/// A note on a verse of the canto shown in Browse mode, numbered in order
+/// down the canto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footnote {
+    pub kind: FootnoteKind,
+    /// The verse it's marked on, the first of the passage it's about.
+    pub line: usize,
+    /// What it's on: the passage and its commentary, the place alluded to,
+    /// or the word glossed.
+    pub title: String,
+    pub text: String,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     Browse,
     InteractiveSearch,
     ContextView,
+    Bookmarks,
+    /// The shown canto's distinctive words, sized by how often it uses them.
+    Terms,
 }
 
-impl App {
-    pub fn new(commedia: DivinaCommedia) -> Self {
+impl<'a> App<'a> {
+    pub fn new(commedia: &'a DivinaCommedia) -> Self {
         let mut cantica_list_state = ListState::default();
         cantica_list_state.select(Some(0));
 
         Self {
             commedia,
-            current_cantica: "Inferno".to_string(),
+            current_cantica: CanticaId::Inferno,
             current_canto: None,
             cantica_list_state,
             canto_list_state: ListState::default(),
             verse_scroll: 0,
+            cursor: 0,
+            scrolloff: 0,
+            verse_height: 0,
             search_input: String::new(),
             search_results: Vec::new(),
             filtered_results: Vec::new(),
             search_list_state: ListState::default(),
+            completions: Vec::new(),
             mode: AppMode::Browse,
-            fuzzy_matcher: SkimMatcherV2::default(),
+            fuzzy_matcher: FuzzyOptions::default().matcher(),
+            fuzzy_threshold: 0,
+            pipeline: Pipeline::default(),
+            show_heatmap: false,
             context_canto: None,
             context_highlight_line: None,
+            commentary: Commentary::default(),
+            show_commentary: false,
+            show_related: false,
+            show_stats: false,
+            canto_info: None,
+            apparatus: Apparatus::default(),
+            show_variants: false,
+            show_allusions: false,
+            show_footnotes: false,
+            footnote: 0,
+            glossary: Glossary::builtin(),
+            selected_word: None,
+            definition: None,
+            find: None,
+            theme: Theme::color(),
+            recordings: Recordings::default(),
+            playback: None,
+            playing_line: None,
+            store: None,
+            bookmarks: Vec::new(),
+            bookmark_filter: String::new(),
+            bookmark_list_state: ListState::default(),
+            terms: Vec::new(),
+            jumps: Vec::new(),
+            jump_index: 0,
+            session: None,
+        }
+    }
+
+    pub fn toggle_commentary(&mut self) {
+        self.show_commentary = !self.show_commentary;
+    }
+
+    pub fn toggle_related(&mut self) {
+        self.show_related = !self.show_related;
+    }
+
+    /// The cross-reference `X` follows from the shown canto: the first
+    /// whose passage here has the cursor's line, else the first after it,
+    /// else the canto's first.
+    pub fn related_at_cursor(&self) -> Option<Related<'static>> {
+        let (cantica, canto, line) = self.current_location()?;
+        let related = xrefs::for_canto(xrefs::builtin(), cantica, canto);
+        let covering = related
+            .iter()
+            .find(|r| r.here.contains(cantica, canto, line));
+        let after = related.iter().find(|r| xrefs::first_line(r.here) > line);
+        covering.or(after).or(related.first()).copied()
+    }
+
+    /// Go to the passage the cross-reference at the cursor leads to.
+    pub fn follow_related(&mut self) {
+        if let Some(related) = self.related_at_cursor() {
+            let there = related.there;
+            self.record_jump();
+            self.go_to(there.cantica, there.canto, xrefs::first_line(there));
+        }
+    }
+
+    /// Show or hide the statistics panel, worked out afresh when shown so
+    /// notes and sessions added since count.
+    pub fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+        self.canto_info = None;
+    }
+
+    /// The canto the reading area shows, which the side panels are about.
+    fn shown_canto(&self) -> Option<(CanticaId, u8)> {
+        match self.mode {
+            AppMode::Browse | AppMode::Terms => {
+                self.current_canto.map(|n| (self.current_cantica, n))
+            }
+            AppMode::ContextView => self.context_canto,
+            AppMode::InteractiveSearch | AppMode::Bookmarks => None,
+        }
+    }
+
+    /// Work out the statistics panel's canto, if it's shown and not the
+    /// one worked out last.
+    pub fn update_canto_info(&mut self) {
+        let Some((cantica, number)) = self.shown_canto().filter(|_| self.show_stats) else {
+            return;
+        };
+        if self
+            .canto_info
+            .as_ref()
+            .is_some_and(|info| (info.cantica, info.canto) == (cantica, number))
+        {
+            return;
+        }
+        let Some(canto) = self.commedia.canto(cantica, number) else {
+            return;
+        };
+        // A store or log that can't be read shows nothing read
+        let notes = self
+            .store
+            .as_ref()
+            .and_then(|store| store.load(cantica, number).ok());
+        let log = paths::reading_log()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        self.canto_info = Some(CantoInfo {
+            cantica,
+            canto: number,
+            stats: stats::canto_stats(self.commedia, canto),
+            notes: notes.as_ref().map_or(0, |notes| notes.notes.len()),
+            bookmarks: notes.as_ref().map_or(0, |notes| notes.bookmarks.len()),
+            read_on: session::read_on(&log, cantica, number),
+        });
+    }
+
+    pub fn toggle_variants(&mut self) {
+        self.show_variants = !self.show_variants;
+    }
+
+    pub fn toggle_allusions(&mut self) {
+        self.show_allusions = !self.show_allusions;
+    }
+
+    /// The footnotes of the canto shown in Browse mode: its commentary, its
+    /// allusions and a gloss of the first use of each glossed word, by the
+    /// verse they're marked on.
+    pub fn footnotes(&self) -> Vec<Footnote> {
+        let Some(canto) = self.get_current_canto() else {
+            return Vec::new();
+        };
+        let (cantica, number) = (self.current_cantica, canto.number);
+        let mut footnotes: Vec<Footnote> = self
+            .commentary
+            .for_canto(cantica, number)
+            .into_iter()
+            .map(|entry| Footnote {
+                kind: FootnoteKind::Commentary,
+                line: xrefs::first_line(&entry.passage),
+                title: format!("{}, {}", entry.passage, entry.source),
+                text: entry.text.clone(),
+            })
+            .collect();
+        footnotes.extend(
+            allusions::builtin()
+                .iter()
+                .filter(|a| a.passage.cantica == cantica && a.passage.canto == number)
+                .map(|allusion| Footnote {
+                    kind: FootnoteKind::Allusion,
+                    line: allusion.line(),
+                    title: allusion.reference.clone(),
+                    text: allusion.note.clone(),
+                }),
+        );
+        let mut glossed = HashSet::new();
+        for verse in &canto.verses {
+            for range in self.glossary.glossed_words(&verse.text) {
+                let Some(entry) = self.glossary.lookup(&verse.text[range]) else {
+                    continue;
+                };
+                if glossed.insert(entry.word.as_str()) {
+                    footnotes.push(Footnote {
+                        kind: FootnoteKind::Gloss,
+                        line: verse.line_number,
+                        title: entry.word.clone(),
+                        text: entry.definition.clone(),
+                    });
+                }
+            }
+        }
+        // Stable, so a verse's glosses keep the order of its words
+        footnotes.sort_by_key(|footnote| (footnote.line, footnote.kind));
+        footnotes
+    }
+
+    pub fn toggle_footnotes(&mut self) {
+        self.show_footnotes = !self.show_footnotes;
+    }
+
+    /// Whether the footnote pane takes rows under the verses.
+    fn shows_footnote_pane(&self) -> bool {
+        self.show_footnotes && self.mode == AppMode::Browse && self.current_canto.is_some()
+    }
+
+    /// Show the next footnote of the canto, wrapping around, and move the
+    /// cursor to its verse.
+    pub fn next_footnote(&mut self) {
+        self.cycle_footnote(true);
+    }
+
+    /// Show the previous footnote of the canto, wrapping around, and move
+    /// the cursor to its verse.
+    pub fn previous_footnote(&mut self) {
+        self.cycle_footnote(false);
+    }
+
+    /// Step through the canto's footnotes, or with them hidden show them
+    /// from the first at or after the cursor.
+    fn cycle_footnote(&mut self, forward: bool) {
+        let footnotes = self.footnotes();
+        let count = footnotes.len();
+        if count == 0 {
+            return;
+        }
+        self.footnote = if !self.show_footnotes {
+            let cursor = self.current_location().map_or(1, |(_, _, line)| line);
+            footnotes
+                .iter()
+                .position(|footnote| footnote.line >= cursor)
+                .unwrap_or(0)
+        } else if forward {
+            (self.footnote + 1) % count
+        } else {
+            (self.footnote.min(count - 1) + count - 1) % count
+        };
+        self.show_footnotes = true;
+        self.scroll_to_line(Some(footnotes[self.footnote].line));
+    }
+
+    /// Switch between accent-insensitive and exact search, and search again.
+    pub fn toggle_accent_folding(&mut self) {
+        self.toggle_normalizer(Normalizer::Fold);
+    }
+
+    /// Add `normalizer` to search's pipeline, or take it out, and search
+    /// again.
+    pub fn toggle_normalizer(&mut self, normalizer: Normalizer) {
+        self.pipeline = self.pipeline.toggle(normalizer);
+        if normalizer == Normalizer::Lemma {
+            self.pipeline = self.pipeline.with_regex(false);
         }
+        self.interactive_search();
+    }
+
+    /// Switch between reading the query as plain text and as a regular
+    /// expression, and search again. No regex matches word forms, so
+    /// reading one stops matching them.
+    pub fn toggle_regex(&mut self) {
+        self.pipeline = self
+            .pipeline
+            .with_regex(!self.pipeline.is_regex())
+            .without(Normalizer::Lemma);
+        self.interactive_search();
+    }
+
+    pub fn toggle_heatmap(&mut self) {
+        self.show_heatmap = !self.show_heatmap;
+    }
+
+    /// The search results in each canto, a heatmap cell each.
+    pub fn result_heatmap(&self) -> Vec<(CanticaId, u8, usize)> {
+        let hits = self
+            .filtered_results
+            .iter()
+            .map(|result| (result.cantica, result.canto));
+        stats::hits_by_canto(self.commedia, hits)
     }
 
     pub fn next_cantica(&mut self) {
@@ -89,6 +496,7 @@ impl App {
         self.update_current_cantica();
         self.canto_list_state.select(None);
         self.current_canto = None;
+        self.find = None;
     }
 
     pub fn previous_cantica(&mut self) {
@@ -106,6 +514,7 @@ impl App {
         self.update_current_cantica();
         self.canto_list_state.select(None);
         self.current_canto = None;
+        self.find = None;
     }
 
     pub fn next_canto(&mut self) {
@@ -125,6 +534,9 @@ impl App {
         self.canto_list_state.select(Some(i));
         self.update_current_canto();
         self.verse_scroll = 0;
+        self.cursor = 0;
+        self.selected_word = None;
+        self.find = None;
     }
 
     pub fn previous_canto(&mut self) {
@@ -144,85 +556,311 @@ impl App {
         self.canto_list_state.select(Some(i));
         self.update_current_canto();
         self.verse_scroll = 0;
+        self.cursor = 0;
+        self.selected_word = None;
+        self.find = None;
+    }
+
+    /// Open a search within the current canto, if one is shown.
+    pub fn start_find(&mut self) {
+        if self.current_canto.is_some() {
+            self.record_jump();
+            self.find = Some(Find {
+                editing: true,
+                ..Find::default()
+            });
+        }
+    }
+
+    /// Search the current canto for the find query again, and jump to the
+    /// first match.
+    pub fn update_find(&mut self) {
+        let Some(canto) = self.current_canto else {
+            return;
+        };
+        let commedia = self.commedia;
+        let cantica = self.current_cantica;
+        let Some(find) = &mut self.find else {
+            return;
+        };
+        find.matches = if find.query.trim().is_empty() {
+            Vec::new()
+        } else {
+            // A regex still being typed may not compile yet, and finds nothing
+            commedia
+                .matches_with(&find.query, Some(cantica), &self.pipeline)
+                .into_iter()
+                .flatten()
+                .filter(|m| m.canto == canto)
+                .map(|m| (m.line, m.spans))
+                .collect()
+        };
+        find.matches.sort_by_key(|(line, _)| *line);
+        find.current = 0;
+        self.jump_to_find_match();
+    }
+
+    /// Move to the next match of the find, wrapping around.
+    pub fn next_find_match(&mut self) {
+        self.record_jump();
+        if let Some(find) = &mut self.find {
+            if !find.matches.is_empty() {
+                find.current = (find.current + 1) % find.matches.len();
+            }
+        }
+        self.jump_to_find_match();
+    }
+
+    /// Move to the previous match of the find, wrapping around.
+    pub fn previous_find_match(&mut self) {
+        self.record_jump();
+        if let Some(find) = &mut self.find {
+            if !find.matches.is_empty() {
+                find.current = find
+                    .current
+                    .checked_sub(1)
+                    .unwrap_or(find.matches.len() - 1);
+            }
+        }
+        self.jump_to_find_match();
+    }
+
+    /// Scroll the current match of the find to the top of the verses.
+    fn jump_to_find_match(&mut self) {
+        let line = self
+            .find
+            .as_ref()
+            .and_then(|find| find.matches.get(find.current))
+            .map(|(line, _)| *line);
+        self.scroll_to_line(line);
+    }
+
+    /// The byte ranges the find matched in verse `line`, to highlight.
+    fn found_in(&self, line: usize) -> &[Range<usize>] {
+        self.find
+            .as_ref()
+            .and_then(|find| find.matches.iter().find(|(l, _)| *l == line))
+            .map_or(&[], |(_, spans)| spans.as_slice())
+    }
+
+    /// Verses kept shown above and below the cursor: the `scrolloff`, but
+    /// at most half the pane.
+    pub fn margin(&self) -> usize {
+        match self.verse_height {
+            0 => self.scrolloff,
+            height => self.scrolloff.min(usize::from(height - 1) / 2),
+        }
+    }
+
+    /// Verses in the shown canto.
+    fn verse_count(&self) -> usize {
+        self.get_current_canto()
+            .map_or(0, |canto| canto.verses.len())
+    }
+
+    /// Move the cursor down a verse, scrolling to keep it in view.
+    pub fn cursor_down(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.verse_count().saturating_sub(1));
+        self.selected_word = None;
+        self.follow_cursor();
+    }
+
+    /// Move the cursor up a verse, scrolling to keep it in view.
+    pub fn cursor_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+        self.selected_word = None;
+        self.follow_cursor();
+    }
+
+    /// Scroll just enough to show the margin around the cursor. Verses are
+    /// counted as a row each, though long ones wrap and variants add rows.
+    fn follow_cursor(&mut self) {
+        let margin = self.margin();
+        let mut top = usize::from(self.verse_scroll);
+        if self.cursor < top + margin {
+            top = self.cursor.saturating_sub(margin);
+        }
+        let height = usize::from(self.verse_height);
+        if height > 0 && self.cursor + margin >= top + height {
+            top = self.cursor + margin + 1 - height;
+        }
+        self.verse_scroll = top as u16;
+    }
+
+    /// Bring the cursor back inside the margin of the verses shown, as
+    /// scrolling with J and K carries it along; at the canto's ends it can
+    /// go all the way to the edge.
+    fn follow_scroll(&mut self) {
+        let margin = self.margin();
+        let top = usize::from(self.verse_scroll);
+        let last = self.verse_count().saturating_sub(1);
+        let height = usize::from(self.verse_height);
+        if top > 0 {
+            self.cursor = self.cursor.max(top + margin);
+        }
+        if height > 0 && top + height <= last {
+            self.cursor = self.cursor.min((top + height - 1).saturating_sub(margin));
+        }
+        self.cursor = self.cursor.min(last);
     }
 
     pub fn scroll_down(&mut self) {
         self.verse_scroll = self.verse_scroll.saturating_add(1);
+        if self.mode == AppMode::Browse {
+            let last = self.verse_count().saturating_sub(1);
+            self.verse_scroll = self.verse_scroll.min(last as u16);
+            self.follow_scroll();
+        }
+        self.selected_word = None;
     }
 
     pub fn scroll_up(&mut self) {
         self.verse_scroll = self.verse_scroll.saturating_sub(1);
+        if self.mode == AppMode::Browse {
+            self.follow_scroll();
+        }
+        self.selected_word = None;
     }
 
-    pub fn update_current_cantica(&mut self) {
-        self.current_cantica = match self.cantica_list_state.selected() {
-            Some(0) => "Inferno".to_string(),
-            Some(1) => "Purgatorio".to_string(),
-            Some(2) => "Paradiso".to_string(),
-            _ => "Inferno".to_string(),
+    /// Select the next word of the current line, wrapping around.
+    pub fn next_word(&mut self) {
+        let count = self.current_words().len();
+        if count > 0 {
+            self.selected_word = Some(self.selected_word.map_or(0, |i| (i + 1) % count));
+        }
+    }
+
+    /// Select the previous word of the current line, wrapping around.
+    pub fn previous_word(&mut self) {
+        let count = self.current_words().len();
+        if count > 0 {
+            self.selected_word = Some(match self.selected_word {
+                Some(i) if i > 0 => i - 1,
+                _ => count - 1,
+            });
+        }
+    }
+
+    /// Line number and byte range of the selected word.
+    pub fn selected_word_range(&self) -> Option<(usize, Range<usize>)> {
+        let (_, _, line) = self.current_location()?;
+        let range = self.current_words().get(self.selected_word?)?.clone();
+        Some((line, range))
+    }
+
+    fn current_words(&self) -> Vec<Range<usize>> {
+        self.current_location()
+            .and_then(|(cantica, canto, line)| self.commedia.verse(cantica, canto, line))
+            .map(|verse| word_ranges(&verse.text))
+            .unwrap_or_default()
+    }
+
+    /// Open the dictionary popup for the selected word, from the glossary or
+    /// else the `$DUCA_DICTIONARY_CMD` command.
+    pub fn define_selected_word(&mut self) {
+        let Some((cantica, canto, line)) = self.current_location() else {
+            return;
+        };
+        let Some((_, range)) = self.selected_word_range() else {
+            return;
         };
+        let Some(verse) = self.commedia.verse(cantica, canto, line) else {
+            return;
+        };
+        let word = verse.text[range].to_string();
+        let definition = match self.glossary.lookup(&word) {
+            Some(entry) => entry.definition.clone(),
+            None => external_definition(&word).unwrap_or_else(|| {
+                "Not in the glossary. Set DUCA_DICTIONARY_CMD to look words up \
+                 with an external dictionary."
+                    .to_string()
+            }),
+        };
+        self.definition = Some((word, definition));
+    }
+
+    pub fn update_current_cantica(&mut self) {
+        self.current_cantica = self
+            .cantica_list_state
+            .selected()
+            .and_then(|i| CanticaId::ALL.get(i).copied())
+            .unwrap_or(CanticaId::Inferno);
     }
 
     pub fn update_current_canto(&mut self) {
         if let Some(selected) = self.canto_list_state.selected() {
-            let cantica = self.get_current_cantica();
-            let mut canto_numbers: Vec<_> = cantica.cantos.keys().collect();
-            canto_numbers.sort();
-
-            if let Some(&&canto_num) = canto_numbers.get(selected) {
-                self.current_canto = Some(canto_num);
+            if let Some(number) = self
+                .get_current_cantica()
+                .canto_at(selected)
+                .map(|c| c.number)
+            {
+                if self.current_canto != Some(number) {
+                    self.footnote = 0;
+                }
+                self.current_canto = Some(number);
             }
         }
     }
 
     pub fn get_current_cantica(&self) -> &Cantica {
-        match self.current_cantica.as_str() {
-            "Inferno" => &self.commedia.inferno,
-            "Purgatorio" => &self.commedia.purgatorio,
-            "Paradiso" => &self.commedia.paradiso,
-            _ => &self.commedia.inferno,
-        }
+        self.commedia.get(self.current_cantica)
     }
 
     pub fn get_current_canto(&self) -> Option<&Canto> {
         if let Some(canto_num) = self.current_canto {
-            self.get_current_cantica().cantos.get(&canto_num)
+            self.commedia.canto(self.current_cantica, canto_num)
         } else {
             None
         }
     }
 
     pub fn interactive_search(&mut self) {
+        self.update_completions();
         if self.search_input.trim().is_empty() {
             self.filtered_results.clear();
             self.search_list_state.select(None);
             return;
         }
 
-        // Get all results from the basic search
-        let basic_results = self.commedia.search(&self.search_input, None);
-
-        // Convert to SearchResult and apply fuzzy matching
-        let mut scored_results: Vec<SearchResult> = basic_results
+        // Score each match as the search yields it, borrowing its text, both
+        // put through the pipeline but for case, which is the fuzzy
+        // matcher's to judge, and the query's common words counting least.
+        // A match of word forms or old spellings the fuzzy matcher can't see
+        // scores least
+        let commedia = self.commedia;
+        let fuzzy_matcher = &self.fuzzy_matcher;
+        let threshold = self.fuzzy_threshold;
+        let pipeline = self.pipeline;
+        let scoring = pipeline.without(Normalizer::Lowercase);
+        let query = self.search_input.as_str();
+        let normalized_query = scoring.normalize(query);
+        let query_words = weighted_words(&normalized_query);
+        let mut scratch = String::new();
+        let mut scored_results: Vec<SearchResult<'a>> = commedia
+            .matches_with(query, None, &pipeline)
             .into_iter()
-            .filter_map(|(cantica, canto, line, text)| {
-                self.fuzzy_matcher
-                    .fuzzy_match(&text, &self.search_input)
-                    .map(|score| SearchResult {
-                        cantica,
-                        canto,
-                        line,
-                        text,
-                        score,
-                    })
+            .flatten()
+            .filter_map(|m| {
+                scoring.normalize_into(m.text, &mut scratch);
+                let score = relevance(fuzzy_matcher, &scratch, &query_words);
+                (score >= threshold).then(|| SearchResult {
+                    cantica: m.cantica,
+                    canto: m.canto,
+                    line: m.line,
+                    text: m.text,
+                    keyword: m.spans.first().cloned(),
+                    score,
+                })
             })
             .collect();
 
-        // Sort by score (highest first)
-        scored_results.sort_by(|a, b| b.score.cmp(&a.score));
-
-        // Take top 50 results for performance
-        scored_results.truncate(50);
+        // Keep the top 50 by score (highest first), ties in reading order
+        let by_score = |r: &SearchResult| (std::cmp::Reverse(r.score), r.cantica, r.canto, r.line);
+        if scored_results.len() > 50 {
+            scored_results.select_nth_unstable_by_key(50, by_score);
+            scored_results.truncate(50);
+        }
+        scored_results.sort_unstable_by_key(by_score);
 
         self.filtered_results = scored_results;
         self.search_list_state
@@ -233,9 +871,40 @@ impl App {
             });
     }
 
+    /// The word being typed at the end of the search box: its byte range.
+    fn typed_word(&self) -> Option<Range<usize>> {
+        word_ranges(&self.search_input)
+            .pop()
+            .filter(|range| range.end == self.search_input.len())
+    }
+
+    /// Look up the words of the poem completing the one being typed, in the
+    /// vocabulary of the word index.
+    pub fn update_completions(&mut self) {
+        let commedia = self.commedia;
+        self.completions = match (commedia.word_index(), self.typed_word()) {
+            (Some(index), Some(range)) => {
+                index.completions(&normalize::fold(&self.search_input[range]), COMPLETIONS)
+            }
+            _ => Vec::new(),
+        };
+    }
+
+    /// Complete the word being typed with the first completion, and search
+    /// for it.
+    pub fn accept_completion(&mut self) {
+        if let (Some(word), Some(range)) = (self.completions.first(), self.typed_word()) {
+            self.search_input.replace_range(range, word);
+            self.interactive_search();
+        }
+    }
+
     pub fn enter_search_mode(&mut self) {
+        // Where a result is opened from, for Ctrl-o to come back to
+        self.record_jump();
         self.mode = AppMode::InteractiveSearch;
         self.search_input.clear();
+        self.completions.clear();
         self.filtered_results.clear();
         self.search_list_state.select(None);
     }
@@ -243,12 +912,297 @@ impl App {
     pub fn enter_context_view(&mut self) {
         if let Some(selected) = self.search_list_state.selected() {
             if let Some(result) = self.filtered_results.get(selected) {
-                self.context_canto = Some((result.cantica.clone(), result.canto));
+                self.context_canto = Some((result.cantica, result.canto));
                 self.context_highlight_line = Some(result.line);
                 self.mode = AppMode::ContextView;
-                self.verse_scroll = result.line.saturating_sub(10) as u16;
+                let above = self.margin().max(CONTEXT_ABOVE);
+                self.verse_scroll = (result.line - 1).saturating_sub(above) as u16;
+            }
+        }
+    }
+
+    /// The passage being read: the highlighted line in context view, or the
+    /// verse under the cursor while browsing a canto.
+    pub fn current_location(&self) -> Option<(CanticaId, u8, usize)> {
+        match self.mode {
+            AppMode::Browse => {
+                let canto = self.get_current_canto()?;
+                let line = canto
+                    .verses
+                    .get(self.cursor)
+                    .or(canto.verses.last())
+                    .map_or(1, |verse| verse.line_number);
+                Some((self.current_cantica, canto.number, line))
+            }
+            AppMode::ContextView => {
+                let (cantica, canto) = self.context_canto?;
+                Some((cantica, canto, self.context_highlight_line.unwrap_or(1)))
+            }
+            AppMode::InteractiveSearch | AppMode::Bookmarks | AppMode::Terms => None,
+        }
+    }
+
+    /// Open the term view of the shown canto, if one is shown.
+    pub fn open_terms(&mut self) {
+        let Some(canto) = self.get_current_canto() else {
+            return;
+        };
+        let mut terms = stats::distinctive_words(self.commedia, canto, CLOUD_TERMS);
+        // Laid out alphabetically, so the biggest aren't all at the top
+        terms.sort();
+        self.terms = terms;
+        self.mode = AppMode::Terms;
+    }
+
+    /// Open the bookmark pane, loading the bookmarks afresh so those added
+    /// from the command line since show up.
+    pub fn open_bookmarks(&mut self) {
+        // Where a bookmark is gone to from, for Ctrl-o to come back to
+        self.record_jump();
+        // A store that can't be read shows no bookmarks
+        let cantos = self.store.as_ref().and_then(|store| store.all().ok());
+        self.bookmarks = cantos
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|notes| {
+                let (cantica, canto) = (notes.cantica, notes.canto);
+                notes
+                    .bookmarks
+                    .into_iter()
+                    .map(move |bookmark| (cantica, canto, bookmark))
+            })
+            .collect();
+        self.bookmark_filter.clear();
+        self.mode = AppMode::Bookmarks;
+        self.filter_bookmarks();
+    }
+
+    /// The bookmarks the pane lists: those with a tag starting with each
+    /// word typed into its filter, so several words narrow it further.
+    pub fn filtered_bookmarks(&self) -> Vec<&(CanticaId, u8, Bookmark)> {
+        let words: Vec<String> = self
+            .bookmark_filter
+            .split_whitespace()
+            .map(|word| word.trim_start_matches('#').to_lowercase())
+            .collect();
+        self.bookmarks
+            .iter()
+            .filter(|(_, _, bookmark)| {
+                words
+                    .iter()
+                    .all(|word| bookmark.tags.iter().any(|tag| tag.starts_with(word)))
+            })
+            .collect()
+    }
+
+    /// Select the first bookmark the filter leaves, if any.
+    pub fn filter_bookmarks(&mut self) {
+        let any = !self.filtered_bookmarks().is_empty();
+        self.bookmark_list_state.select(any.then_some(0));
+    }
+
+    pub fn next_bookmark(&mut self) {
+        let len = self.filtered_bookmarks().len();
+        if len > 0 {
+            let i = self
+                .bookmark_list_state
+                .selected()
+                .map_or(0, |i| (i + 1) % len);
+            self.bookmark_list_state.select(Some(i));
+        }
+    }
+
+    pub fn previous_bookmark(&mut self) {
+        let len = self.filtered_bookmarks().len();
+        if len > 0 {
+            let i = match self.bookmark_list_state.selected() {
+                Some(i) if i > 0 => i - 1,
+                _ => len - 1,
+            };
+            self.bookmark_list_state.select(Some(i));
+        }
+    }
+
+    /// Browse the canto of the selected bookmark, its line at the top.
+    pub fn go_to_bookmark(&mut self) {
+        let selected = self.bookmark_list_state.selected().and_then(|i| {
+            let (cantica, canto, bookmark) = self.filtered_bookmarks().get(i).copied()?;
+            Some((*cantica, *canto, bookmark.line))
+        });
+        if let Some((cantica, canto, line)) = selected {
+            self.go_to(cantica, canto, line);
+        }
+    }
+
+    /// Remember the current passage before jumping away from it, forgetting
+    /// the places Ctrl-o had gone back past.
+    pub fn record_jump(&mut self) {
+        let Some(here) = self.current_location() else {
+            return;
+        };
+        self.jumps.truncate(self.jump_index);
+        if self.jumps.last() != Some(&here) {
+            self.jumps.push(here);
+        }
+        if self.jumps.len() > JUMPLIST_LEN {
+            self.jumps.remove(0);
+        }
+        self.jump_index = self.jumps.len();
+    }
+
+    /// Go back to the place jumped from before the current one, as vim's
+    /// Ctrl-o does. The first time, the current passage is remembered so
+    /// Ctrl-i can return to it.
+    pub fn jump_back(&mut self) {
+        if self.jump_index >= self.jumps.len() {
+            match self.current_location() {
+                Some(here) => {
+                    if self.jumps.last() != Some(&here) {
+                        self.jumps.push(here);
+                    }
+                    self.jump_index = self.jumps.len() - 1;
+                }
+                None => self.jump_index = self.jumps.len(),
+            }
+        }
+        if self.jump_index == 0 {
+            return;
+        }
+        self.jump_index -= 1;
+        let (cantica, canto, line) = self.jumps[self.jump_index];
+        self.go_to(cantica, canto, line);
+    }
+
+    /// Undo a Ctrl-o, as vim's Ctrl-i does.
+    pub fn jump_forward(&mut self) {
+        if self.jump_index + 1 < self.jumps.len() {
+            self.jump_index += 1;
+            let (cantica, canto, line) = self.jumps[self.jump_index];
+            self.go_to(cantica, canto, line);
+        }
+    }
+
+    /// Browse `canto` of `cantica`, scrolled to `line`.
+    pub fn go_to(&mut self, cantica: CanticaId, canto: u8, line: usize) {
+        let cantica_index = CanticaId::ALL.iter().position(|&c| c == cantica);
+        self.cantica_list_state.select(cantica_index);
+        self.update_current_cantica();
+        let canto_index = self
+            .get_current_cantica()
+            .cantos
+            .keys()
+            .position(|&number| number == canto);
+        self.canto_list_state.select(canto_index);
+        self.current_canto = None;
+        self.update_current_canto();
+        self.mode = AppMode::Browse;
+        self.find = None;
+        self.verse_scroll = 0;
+        self.cursor = 0;
+        self.scroll_to_line(Some(line));
+    }
+
+    /// Speak the tercet at the current passage with `$DUCA_TTS_CMD`, in the
+    /// background.
+    pub fn speak_tercet(&self) {
+        let tercet = self.current_location().and_then(|(cantica, canto, line)| {
+            self.commedia.canto(cantica, canto)?.tercet_at(line)
+        });
+        if let Some(tercet) = tercet {
+            let verses = tercet.iter().map(|verse| verse.text.clone()).collect();
+            speech::speak_in_background(verses, Duration::from_millis(speech::DEFAULT_PAUSE_MS));
+        }
+    }
+
+    /// Play the shown canto's recording, or pause or resume it if it's
+    /// already playing.
+    pub fn toggle_playback(&mut self) {
+        let Some(canto) = self.current_canto else {
+            return;
+        };
+        if let Some(playback) = &mut self.playback {
+            if (playback.cantica, playback.canto) == (self.current_cantica, canto) {
+                // Nowhere to report a player that can't pause; it plays on
+                let _ = playback.toggle_pause();
+                return;
             }
         }
+        self.playback = self
+            .recordings
+            .for_canto(self.current_cantica, canto)
+            .and_then(|recording| Playback::start(&player::command(), recording).ok());
+        self.playing_line = None;
+    }
+
+    /// Follow the recording: stop it once it ends or another canto is
+    /// shown, and otherwise scroll the line it has reached to the top.
+    pub fn follow_playback(&mut self) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        let shown = (Some(playback.cantica), Some(playback.canto));
+        if playback.finished() || shown != (Some(self.current_cantica), self.current_canto) {
+            self.playback = None;
+            self.playing_line = None;
+            return;
+        }
+        let seconds = playback.position().as_secs_f64();
+        let line = self.get_current_canto().and_then(|canto| {
+            let recording = self
+                .recordings
+                .for_canto(self.current_cantica, canto.number)?;
+            recording.line_at(seconds, canto.verses.len())
+        });
+        if line.is_some() && line != self.playing_line {
+            self.playing_line = line;
+            self.scroll_to_line(line);
+        }
+    }
+
+    /// Follow the timed session: end a break whose time is up, and note the
+    /// canto being read.
+    pub fn follow_session(&mut self, now: Instant) {
+        let location = self.current_location();
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        session.tick(now);
+        if let Some((cantica, canto, _)) = location {
+            session.visit(cantica, canto);
+        }
+    }
+
+    /// Take a break from the timed session, or end one early.
+    pub fn toggle_break(&mut self, now: Instant) {
+        if let Some(session) = &mut self.session {
+            session.toggle_break(now);
+        }
+    }
+
+    /// Put the cursor on verse `line` of the shown canto, scrolled to the
+    /// top but for the margin above it.
+    fn scroll_to_line(&mut self, line: Option<usize>) {
+        let index = line.and_then(|line| {
+            self.get_current_canto()?
+                .verses
+                .iter()
+                .position(|verse| verse.line_number == line)
+        });
+        if let Some(index) = index {
+            self.cursor = index;
+            self.verse_scroll = index.saturating_sub(self.margin()) as u16;
+            self.selected_word = None;
+        }
+    }
+
+    /// Open the current passage on the commentary site from `$DUCA_WEB_URL`.
+    pub fn open_web(&self) {
+        if let Some((cantica, canto, line)) = self.current_location() {
+            let url = links::url(&links::template(None), cantica, canto, line);
+            // Nowhere to report a failure without leaving the TUI; the key
+            // simply does nothing
+            let _ = browser::open_url(&url);
+        }
     }
 
     pub fn exit_context_view(&mut self) {
@@ -259,6 +1213,7 @@ impl App {
 
     pub fn clear_search(&mut self) {
         self.search_input.clear();
+        self.completions.clear();
         self.search_results.clear();
         self.filtered_results.clear();
         self.search_list_state.select(None);
@@ -304,30 +1259,268 @@ impl App {
     }
 
     pub fn get_context_canto(&self) -> Option<&Canto> {
-        if let Some((cantica_name, canto_num)) = &self.context_canto {
-            let cantica = match cantica_name.as_str() {
-                "Inferno" => &self.commedia.inferno,
-                "Purgatorio" => &self.commedia.purgatorio,
-                "Paradiso" => &self.commedia.paradiso,
-                _ => return None,
-            };
-            cantica.cantos.get(canto_num)
+        if let Some((cantica, canto_num)) = self.context_canto {
+            self.commedia.canto(cantica, canto_num)
         } else {
             None
         }
     }
 }
 
-pub fn run_tui(commedia: DivinaCommedia) -> Result<()> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// The words of a search query with how much each counts towards a
+/// result's score, [stopwords](stopwords) least.
+fn weighted_words(query: &str) -> Vec<(&str, f32)> {
+    word_ranges(query)
+        .into_iter()
+        .map(|range| {
+            let word = &query[range];
+            (word, stopwords::weight(word))
+        })
+        .collect()
+}
 
-    let app = App::new(commedia);
-    let res = run_app(&mut terminal, app);
+/// How well `text` matches the query's `words`: the fuzzy score of each
+/// word, weighted, so a multi-word query ranks results by its uncommon
+/// words rather than by the `che` and `la` that nearly every verse has.
+fn relevance(matcher: &SkimMatcherV2, text: &str, words: &[(&str, f32)]) -> i64 {
+    let score: f32 = words
+        .iter()
+        .filter_map(|(word, weight)| Some(matcher.fuzzy_match(text, word)? as f32 * weight))
+        .sum();
+    score.round() as i64
+}
 
+/// Run `$DUCA_DICTIONARY_CMD` with `word` appended and return what it
+/// printed, if it succeeded with any output.
+fn external_definition(word: &str) -> Option<String> {
+    let command = env::var("DUCA_DICTIONARY_CMD").ok()?;
+    let mut parts = command.split_whitespace();
+    let output = Command::new(parts.next()?)
+        .args(parts)
+        .arg(word)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
+
+/// The styles the TUI draws with, in color or, for monochrome terminals
+/// and readers who can't tell its colors apart, with only bold, underline
+/// and reverse video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Line numbers in the gutter.
+    pub gutter: Style,
+    /// The selected entry of a list.
+    pub selection: Style,
+    /// The text typed into the search box.
+    pub input: Style,
+    /// Text matched by an in-canto find.
+    pub found: Style,
+    /// The speaker's name at the start of a speech.
+    pub speaker: Style,
+    /// The passage a commentary note is on.
+    pub passage: Style,
+    /// The search result shown in the context view, and its line number.
+    pub context_line: Style,
+    pub context_gutter: Style,
+    /// Line numbers of the other verses in the context view.
+    pub context_other_gutter: Style,
+    /// The verse a playing recording has reached.
+    pub playing: Style,
+    /// Words of the term view, from the most used to the least.
+    pub cloud: [Style; 4],
+}
+
+impl Theme {
+    pub fn color() -> Self {
+        Self {
+            gutter: Style::default().fg(Color::Yellow),
+            selection: Style::default()
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            input: Style::default().fg(Color::Yellow),
+            found: Style::default().fg(Color::Black).bg(Color::Yellow),
+            speaker: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::DIM),
+            passage: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            context_line: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            context_gutter: Style::default().fg(Color::Red),
+            context_other_gutter: Style::default().fg(Color::Cyan),
+            playing: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            cloud: [
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+                Style::default().fg(Color::Cyan),
+                Style::default().add_modifier(Modifier::DIM),
+            ],
+        }
+    }
+
+    pub fn monochrome() -> Self {
+        Self {
+            gutter: Style::default(),
+            selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            input: Style::default().add_modifier(Modifier::BOLD),
+            found: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            speaker: Style::default().add_modifier(Modifier::DIM),
+            passage: Style::default().add_modifier(Modifier::BOLD),
+            context_line: Style::default().add_modifier(Modifier::BOLD),
+            context_gutter: Style::default().add_modifier(Modifier::REVERSED),
+            context_other_gutter: Style::default(),
+            playing: Style::default().add_modifier(Modifier::BOLD),
+            cloud: [
+                Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                Style::default().add_modifier(Modifier::BOLD),
+                Style::default(),
+                Style::default().add_modifier(Modifier::DIM),
+            ],
+        }
+    }
+
+    /// The theme asked for by `tui --monochrome`, else by `$DUCA_TUI_THEME`
+    /// (`color` or `mono`), else by `--color` and, in `auto`, `NO_COLOR`.
+    pub fn choose(monochrome: bool, color: ColorChoice) -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if is_monochrome(monochrome, env::var("DUCA_TUI_THEME").ok(), color, no_color) {
+            Self::monochrome()
+        } else {
+            Self::color()
+        }
+    }
+}
+
+/// How the TUI's fuzzy scoring treats case, as in skim and fzf.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FuzzyCase {
+    /// Any case matches any, as search does.
+    #[default]
+    Ignore,
+    /// Case matters only when the query has a capital.
+    Smart,
+    /// Case always matters.
+    Respect,
+}
+
+/// How the TUI scores search results, and how well one must score to show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FuzzyOptions {
+    pub case: FuzzyCase,
+    pub threshold: i64,
+}
+
+impl FuzzyOptions {
+    /// The options asked for by `tui --fuzzy-case` and `--fuzzy-threshold`,
+    /// else by `$DUCA_FUZZY_CASE` and `$DUCA_FUZZY_THRESHOLD`, else ignoring
+    /// case and keeping every result.
+    pub fn choose(case: Option<FuzzyCase>, threshold: Option<i64>) -> Self {
+        let case = case.or_else(|| {
+            let name = env::var("DUCA_FUZZY_CASE").ok()?;
+            FuzzyCase::from_str(name.trim(), true).ok()
+        });
+        let threshold =
+            threshold.or_else(|| env::var("DUCA_FUZZY_THRESHOLD").ok()?.trim().parse().ok());
+        Self {
+            case: case.unwrap_or_default(),
+            threshold: threshold.unwrap_or(0),
+        }
+    }
+
+    /// A fuzzy matcher treating case as asked.
+    pub fn matcher(&self) -> SkimMatcherV2 {
+        let matcher = SkimMatcherV2::default();
+        match self.case {
+            FuzzyCase::Ignore => matcher.ignore_case(),
+            FuzzyCase::Smart => matcher.smart_case(),
+            FuzzyCase::Respect => matcher.respect_case(),
+        }
+    }
+}
+
+/// The margin asked for by `tui --scrolloff`, else by `$DUCA_SCROLLOFF`,
+/// else none.
+pub fn scrolloff(flag: Option<usize>) -> usize {
+    flag.or_else(|| env::var("DUCA_SCROLLOFF").ok()?.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn is_monochrome(flag: bool, theme: Option<String>, color: ColorChoice, no_color: bool) -> bool {
+    if flag {
+        return true;
+    }
+    match theme.as_deref().map(str::trim) {
+        Some("mono" | "monochrome") => return true,
+        Some("color" | "colour") => return false,
+        _ => {}
+    }
+    match color {
+        ColorChoice::Always => false,
+        ColorChoice::Never => true,
+        ColorChoice::Auto => no_color,
+    }
+}
+
+pub fn run_tui(
+    commedia: &DivinaCommedia,
+    theme: Theme,
+    scrolloff: usize,
+    fuzzy: FuzzyOptions,
+    store: Option<NoteStore>,
+    session: Option<Session>,
+) -> Result<()> {
+    let mut app = App::new(commedia);
+    app.theme = theme;
+    app.scrolloff = scrolloff;
+    app.fuzzy_matcher = fuzzy.matcher();
+    app.fuzzy_threshold = fuzzy.threshold;
+    app.store = store;
+    app.session = session;
+    let started = Date::today();
+    app.commentary = Commentary::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Commentary::default()
+    });
+    app.apparatus = Apparatus::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Apparatus::default()
+    });
+    app.recordings = Recordings::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Recordings::default()
+    });
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Where the terminal can, tell Ctrl-i from Tab, which it otherwise sends
+    let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        )?;
+    }
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = run_app(&mut terminal, &mut app);
+
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
@@ -340,31 +1533,147 @@ pub fn run_tui(commedia: DivinaCommedia) -> Result<()> {
         println!("{err:?}");
     }
 
+    let logged = app
+        .session
+        .as_ref()
+        .and_then(|session| session.log_line(started, Instant::now()));
+    if let (Some(line), Some(log)) = (logged, paths::reading_log()) {
+        if let Err(e) = session::append_log(&log, &line) {
+            eprintln!("{}: {}", log.display(), e);
+        }
+    }
+
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+/// Verses the context view shows above the search result, or the margin if
+/// more.
+const CONTEXT_ABOVE: usize = 9;
+
+/// How many words the term view shows.
+const CLOUD_TERMS: usize = 40;
+
+/// How many completions of a word the search box offers.
+const COMPLETIONS: usize = 6;
+
+/// How many places the jumplist remembers.
+const JUMPLIST_LEN: usize = 100;
+
+/// How often the verses follow a playing recording.
+const PLAYBACK_TICK: Duration = Duration::from_millis(250);
+
+/// How often the session timer is redrawn.
+const SESSION_TICK: Duration = Duration::from_secs(1);
 
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    loop {
+        app.follow_playback();
+        app.follow_session(Instant::now());
+        terminal.draw(|f| ui(f, app))?;
+
+        // While a recording plays or the session is timed, redraw as they
+        // move on even without keys
+        let tick = if app.playback.is_some() {
+            Some(PLAYBACK_TICK)
+        } else {
+            app.session.as_ref().map(|_| SESSION_TICK)
+        };
+        if let Some(tick) = tick {
+            if !event::poll(tick)? {
+                continue;
+            }
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == KeyEventKind::Press {
+                // Any key closes the dictionary popup
+                if app.definition.take().is_some() {
+                    continue;
+                }
                 match app.mode {
+                    // Typing the query of an in-canto search
+                    AppMode::Browse if app.find.as_ref().is_some_and(|find| find.editing) => {
+                        match key.code {
+                            KeyCode::Esc => app.find = None,
+                            KeyCode::Enter => {
+                                if let Some(find) = &mut app.find {
+                                    find.editing = false;
+                                }
+                            }
+                            KeyCode::Backspace => {
+                                if let Some(find) = &mut app.find {
+                                    find.query.pop();
+                                }
+                                app.update_find();
+                            }
+                            KeyCode::Char(c) => {
+                                if let Some(find) = &mut app.find {
+                                    find.query.push(c);
+                                }
+                                app.update_find();
+                            }
+                            _ => {}
+                        }
+                    }
                     AppMode::Browse => match key.code {
                         KeyCode::Char('q') => return Ok(()),
                         KeyCode::Char('h') | KeyCode::Left => app.previous_cantica(),
                         KeyCode::Char('l') | KeyCode::Right => app.next_cantica(),
-                        KeyCode::Char('j') | KeyCode::Down => app.next_canto(),
-                        KeyCode::Char('k') | KeyCode::Up => app.previous_canto(),
+                        KeyCode::Char('j') | KeyCode::Down if app.current_canto.is_some() => {
+                            app.cursor_down()
+                        }
+                        KeyCode::Char('k') | KeyCode::Up if app.current_canto.is_some() => {
+                            app.cursor_up()
+                        }
+                        KeyCode::Char('j') | KeyCode::Down | KeyCode::Char(']') => app.next_canto(),
+                        KeyCode::Char('k') | KeyCode::Up | KeyCode::Char('[') => {
+                            app.previous_canto()
+                        }
                         KeyCode::Char('J') => app.scroll_down(),
+                        KeyCode::Char('K') if app.selected_word.is_some() => {
+                            app.define_selected_word()
+                        }
                         KeyCode::Char('K') => app.scroll_up(),
+                        KeyCode::Tab => app.next_word(),
+                        KeyCode::BackTab => app.previous_word(),
+                        KeyCode::Esc if app.find.is_some() => app.find = None,
+                        KeyCode::Esc => app.selected_word = None,
                         KeyCode::Char('/') => app.enter_search_mode(),
-                        KeyCode::Enter => {
+                        KeyCode::Char('f') => app.start_find(),
+                        // Ctrl-i arrives as Tab unless the terminal tells them
+                        // apart, so Ctrl-n goes forward too
+                        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.jump_forward()
+                        }
+                        KeyCode::Char('n') => app.next_find_match(),
+                        KeyCode::Char('N') => app.previous_find_match(),
+                        KeyCode::Char('c') => app.toggle_commentary(),
+                        KeyCode::Char('x') => app.toggle_related(),
+                        KeyCode::Char('X') => app.follow_related(),
+                        KeyCode::Char('v') => app.toggle_variants(),
+                        KeyCode::Char('a') => app.toggle_allusions(),
+                        KeyCode::Char('F') => app.toggle_footnotes(),
+                        KeyCode::Char('.') => app.next_footnote(),
+                        KeyCode::Char(',') => app.previous_footnote(),
+                        KeyCode::Char('w') => app.open_web(),
+                        KeyCode::Char('s') if key.modifiers == KeyModifiers::NONE => {
+                            app.speak_tercet()
+                        }
+                        KeyCode::Char('p') => app.toggle_playback(),
+                        KeyCode::Char('b') => app.open_bookmarks(),
+                        KeyCode::Char('z') => app.toggle_break(Instant::now()),
+                        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.jump_back()
+                        }
+                        KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.jump_forward()
+                        }
+                        KeyCode::Char('i') => app.toggle_stats(),
+                        KeyCode::Char('t') => app.open_terms(),
+                        KeyCode::Enter
                             if app.current_canto.is_none()
-                                && app.canto_list_state.selected().is_some()
-                            {
-                                app.update_current_canto();
-                            }
+                                && app.canto_list_state.selected().is_some() =>
+                        {
+                            app.update_current_canto();
                         }
                         _ => {}
                     },
@@ -376,7 +1685,23 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                         }
                         KeyCode::Down => app.next_search_result(),
                         KeyCode::Up => app.previous_search_result(),
+                        KeyCode::Tab => app.accept_completion(),
                         KeyCode::Enter => app.enter_context_view(),
+                        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.toggle_accent_folding()
+                        }
+                        KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.toggle_normalizer(Normalizer::Lemma)
+                        }
+                        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.toggle_normalizer(Normalizer::Orthography)
+                        }
+                        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.toggle_regex()
+                        }
+                        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.toggle_heatmap()
+                        }
                         KeyCode::Char('j') => app.next_search_result(),
                         KeyCode::Char('k') => app.previous_search_result(),
                         KeyCode::Char(c) => {
@@ -386,9 +1711,51 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                         _ => {}
                     },
                     AppMode::ContextView => match key.code {
+                        KeyCode::Esc if app.selected_word.is_some() => app.selected_word = None,
                         KeyCode::Char('q') | KeyCode::Esc => app.exit_context_view(),
+                        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.jump_back()
+                        }
                         KeyCode::Char('J') | KeyCode::Down => app.scroll_down(),
+                        KeyCode::Char('K') if app.selected_word.is_some() => {
+                            app.define_selected_word()
+                        }
                         KeyCode::Char('K') | KeyCode::Up => app.scroll_up(),
+                        KeyCode::Tab => app.next_word(),
+                        KeyCode::BackTab => app.previous_word(),
+                        KeyCode::Char('c') => app.toggle_commentary(),
+                        KeyCode::Char('x') => app.toggle_related(),
+                        KeyCode::Char('X') => app.follow_related(),
+                        KeyCode::Char('i') => app.toggle_stats(),
+                        KeyCode::Char('v') => app.toggle_variants(),
+                        KeyCode::Char('a') => app.toggle_allusions(),
+                        KeyCode::Char('w') => app.open_web(),
+                        KeyCode::Char('s') if key.modifiers == KeyModifiers::NONE => {
+                            app.speak_tercet()
+                        }
+                        _ => {}
+                    },
+                    AppMode::Bookmarks => match key.code {
+                        KeyCode::Esc => app.mode = AppMode::Browse,
+                        KeyCode::Enter => app.go_to_bookmark(),
+                        KeyCode::Down => app.next_bookmark(),
+                        KeyCode::Up => app.previous_bookmark(),
+                        KeyCode::Backspace => {
+                            app.bookmark_filter.pop();
+                            app.filter_bookmarks();
+                        }
+                        KeyCode::Char(c) => {
+                            app.bookmark_filter.push(c);
+                            app.filter_bookmarks();
+                        }
+                        _ => {}
+                    },
+                    AppMode::Terms => match key.code {
+                        KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Esc | KeyCode::Char('t') => app.mode = AppMode::Browse,
+                        KeyCode::Char('c') => app.toggle_commentary(),
+                        KeyCode::Char('x') => app.toggle_related(),
+                        KeyCode::Char('i') => app.toggle_stats(),
                         _ => {}
                     },
                 }
@@ -397,11 +1764,34 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
     }
 }
 
+/// The panels beside the reading area, from top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Commentary,
+    Related,
+    Stats,
+}
+
 fn ui(f: &mut Frame, app: &mut App) {
+    // A timed session takes the bottom row for its status bar
+    let mut area = f.size();
+    if let Some(session) = &app.session {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(area);
+        area = rows[0];
+        render_session(f, rows[1], session, &app.theme);
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-        .split(f.size());
+        .split(area);
+    app.verse_height = chunks[1].height.saturating_sub(2);
+    if app.shows_footnote_pane() {
+        app.verse_height = app.verse_height.saturating_sub(FOOTNOTE_HEIGHT);
+    }
 
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -411,52 +1801,399 @@ fn ui(f: &mut Frame, app: &mut App) {
     render_cantica_list(f, left_chunks[0], app);
     render_canto_list(f, left_chunks[1], app);
 
+    // The commentary, related passages and statistics panels share the
+    // reading area with whichever canto is shown, one above the other as
+    // many as are open
+    app.update_canto_info();
+    let shown_canto = app.shown_canto();
+    let panels: Vec<Panel> = [
+        (app.show_commentary, Panel::Commentary),
+        (app.show_related, Panel::Related),
+        (app.show_stats, Panel::Stats),
+    ]
+    .into_iter()
+    .filter_map(|(shown, panel)| shown.then_some(panel))
+    .collect();
+    let (main_area, side_areas) = match shown_canto {
+        Some(canto) if !panels.is_empty() => {
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+                .split(chunks[1]);
+            let share = 100 / panels.len() as u16;
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Percentage(share); panels.len()])
+                .split(areas[1]);
+            let sides: Vec<(Panel, Rect)> =
+                panels.iter().copied().zip(rows.iter().copied()).collect();
+            (areas[0], Some((sides, canto)))
+        }
+        _ => (chunks[1], None),
+    };
+
     match app.mode {
-        AppMode::Browse => render_verse_display(f, chunks[1], app),
-        AppMode::InteractiveSearch => render_interactive_search(f, chunks[1], app),
-        AppMode::ContextView => render_context_view(f, chunks[1], app),
+        AppMode::Browse => render_verse_display(f, main_area, app),
+        AppMode::InteractiveSearch => render_interactive_search(f, main_area, app),
+        AppMode::ContextView => render_context_view(f, main_area, app),
+        AppMode::Bookmarks => render_bookmarks(f, main_area, app),
+        AppMode::Terms => render_terms(f, main_area, app),
+    }
+    if let Some((sides, (cantica, canto))) = side_areas {
+        for (panel, area) in sides {
+            match panel {
+                Panel::Commentary => render_commentary(f, area, app, cantica, canto),
+                Panel::Related => render_related(f, area, app, cantica, canto),
+                Panel::Stats => {
+                    if let Some(info) = &app.canto_info {
+                        render_stats(f, area, info, &app.theme);
+                    }
+                }
+            }
+        }
+    }
+    if let Some((word, definition)) = &app.definition {
+        render_definition(f, main_area, word, definition);
     }
 }
 
-fn render_cantica_list(f: &mut Frame, area: Rect, app: &mut App) {
-    let canticas = ["Inferno", "Purgatorio", "Paradiso"];
-    let items: Vec<ListItem> = canticas
+fn render_session(f: &mut Frame, area: Rect, session: &Session, theme: &Theme) {
+    let (text, style) = match session.status(Instant::now()) {
+        Status::Reading(read) => (
+            tr(Text::TuiSessionReading, &[&session::clock(read)]),
+            Style::default().add_modifier(Modifier::DIM),
+        ),
+        Status::BreakDue(read) => (
+            tr(Text::TuiSessionBreakDue, &[&session::clock(read)]),
+            theme.found,
+        ),
+        Status::OnBreak(left) => (
+            tr(Text::TuiSessionBreak, &[&session::clock(left)]),
+            theme.found,
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(text)
+            .style(style)
+            .alignment(Alignment::Right),
+        area,
+    );
+}
+
+fn render_definition(f: &mut Frame, area: Rect, word: &str, definition: &str) {
+    let width = area.width.saturating_mul(3) / 4;
+    let height = (area.height / 2).max(5).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let paragraph = Paragraph::new(definition)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&format!("{} (any key to close)", word), popup)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+/// A margin tag naming the speaker on the first line of each speech.
+fn speaker_tag(canto: &Canto, line: usize, theme: &Theme) -> Option<Span<'static>> {
+    let speaker = canto.speaker(line)?;
+    if line > 1 && canto.speaker(line - 1) == Some(speaker) {
+        return None;
+    }
+    Some(Span::styled(format!("[{}] ", speaker), theme.speaker))
+}
+
+/// The variant readings of a verse, one indented line each, when the
+/// variants are shown.
+fn variant_lines(app: &App, cantica: CanticaId, canto: u8, line: usize) -> Vec<Line<'static>> {
+    if !app.show_variants {
+        return Vec::new();
+    }
+    app.apparatus
+        .for_line(cantica, canto, line)
+        .into_iter()
+        .map(|variant| {
+            Line::from(Span::styled(
+                format!("     [{}] {}", variant.source, variant),
+                Style::default().add_modifier(Modifier::DIM),
+            ))
+        })
+        .collect()
+}
+
+/// The allusions starting at a verse, one indented line each naming the
+/// place alluded to, when the allusions are shown.
+fn allusion_lines(app: &App, cantica: CanticaId, canto: u8, line: usize) -> Vec<Line<'static>> {
+    if !app.show_allusions {
+        return Vec::new();
+    }
+    allusions::for_line(allusions::builtin(), cantica, canto, line)
+        .into_iter()
+        .map(|allusion| {
+            Line::from(Span::styled(
+                format!("     [{}] {}", allusion.reference, allusion.note),
+                Style::default().add_modifier(Modifier::DIM),
+            ))
+        })
+        .collect()
+}
+
+/// Rows the footnote pane takes under the verses, borders included.
+const FOOTNOTE_HEIGHT: u16 = 7;
+
+/// `n` in superscript digits, as a footnote's marker.
+fn superscript(n: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    n.to_string()
+        .chars()
+        .filter_map(|digit| digit.to_digit(10))
+        .map(|digit| DIGITS[digit as usize])
+        .collect()
+}
+
+/// The markers of the footnotes on verse `line`, numbered from one down
+/// the canto, the shown one, `current`, highlighted.
+fn footnote_markers(
+    footnotes: &[Footnote],
+    line: usize,
+    current: usize,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    footnotes
         .iter()
-        .map(|cantica| {
-            let content = cantica.to_string();
-            ListItem::new(content)
+        .enumerate()
+        .filter(|(_, footnote)| footnote.line == line)
+        .map(|(i, _)| {
+            let style = if i == current {
+                theme.found
+            } else {
+                theme.passage
+            };
+            Span::styled(format!(" {}", superscript(i + 1)), style)
         })
+        .collect()
+}
+
+/// The pane under the verses showing footnote `current` of `footnotes`.
+fn render_footnote(
+    f: &mut Frame,
+    area: Rect,
+    footnotes: &[Footnote],
+    current: usize,
+    theme: &Theme,
+) {
+    let (title, lines) = match footnotes.get(current) {
+        Some(footnote) => (
+            tr(Text::TuiFootnote, &[&(current + 1), &footnotes.len()]),
+            vec![
+                Line::from(Span::styled(
+                    format!("{} {}", superscript(current + 1), footnote.title),
+                    theme.passage,
+                )),
+                Line::from(footnote.text.as_str()),
+            ],
+        ),
+        None => (
+            tr(Text::TuiFootnotes, &[]),
+            vec![Line::from(tr(Text::TuiNoFootnotes, &[]))],
+        ),
+    };
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&title, area)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+/// `style` italicized and dimmed if the verse at `line` quotes Latin.
+fn latin_style(canto: &Canto, line: usize, style: Style) -> Style {
+    if canto.is_latin(line) {
+        style.add_modifier(Modifier::ITALIC | Modifier::DIM)
+    } else {
+        style
+    }
+}
+
+/// The spans of a verse's text, with glossed words underlined, `selected`
+/// (a word's byte range) reversed and `found` (ranges matched by an
+/// in-canto search) highlighted as the theme says.
+fn verse_spans<'a>(
+    text: &'a str,
+    style: Style,
+    theme: &Theme,
+    glossary: &Glossary,
+    selected: Option<Range<usize>>,
+    found: &[Range<usize>],
+) -> Vec<Span<'a>> {
+    let mut marks: Vec<(Range<usize>, Style)> = glossary
+        .glossed_words(text)
+        .into_iter()
+        .map(|range| (range, Style::default().add_modifier(Modifier::UNDERLINED)))
+        .collect();
+    marks.extend(selected.map(|range| (range, Style::default().add_modifier(Modifier::REVERSED))));
+    marks.extend(found.iter().map(|range| (range.clone(), theme.found)));
+
+    // Marks can overlap, a match spanning two words say, so cut the text at
+    // every edge and style each piece with all the marks covering it
+    let mut edges: Vec<usize> = marks
+        .iter()
+        .flat_map(|(range, _)| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+
+    edges
+        .windows(2)
+        .map(|piece| {
+            let piece = piece[0]..piece[1];
+            let style = marks
+                .iter()
+                .filter(|(range, _)| range.start <= piece.start && piece.end <= range.end)
+                .fold(style, |style, (_, mark)| style.patch(*mark));
+            Span::styled(&text[piece], style)
+        })
+        .collect()
+}
+
+fn render_commentary(f: &mut Frame, area: Rect, app: &App, cantica: CanticaId, canto: u8) {
+    let entries = app.commentary.for_canto(cantica, canto);
+    let mut lines = Vec::new();
+    for entry in &entries {
+        lines.push(Line::from(vec![
+            Span::styled(entry.passage.to_string(), app.theme.passage),
+            Span::styled(
+                format!("  {}", entry.source),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        ]));
+        lines.push(Line::from(entry.text.as_str()));
+        lines.push(Line::from(""));
+    }
+    if entries.is_empty() {
+        lines.push(Line::from(tr(Text::TuiNoCommentary, &[])));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiCommentary, &[])),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+fn render_related(f: &mut Frame, area: Rect, app: &App, cantica: CanticaId, canto: u8) {
+    let related = xrefs::for_canto(xrefs::builtin(), cantica, canto);
+    let followed = app.related_at_cursor();
+    let mut lines = Vec::new();
+    for r in &related {
+        let marker = if Some(*r) == followed { "> " } else { "  " };
+        lines.push(Line::from(vec![
+            Span::raw(marker),
+            Span::styled(format!("{} → {}", r.here, r.there), app.theme.passage),
+            Span::styled(
+                format!("  {}", r.kind),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        ]));
+        lines.push(Line::from(format!("  {}", r.note)));
+        lines.push(Line::from(""));
+    }
+    if related.is_empty() {
+        lines.push(Line::from(tr(Text::TuiNoRelated, &[])));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiRelated, &[])),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+fn render_stats(f: &mut Frame, area: Rect, info: &CantoInfo, theme: &Theme) {
+    let stats = &info.stats;
+    let mut lines = vec![
+        Line::from(tr(
+            Text::TuiStatsSize,
+            &[&stats.lines, &stats.words, &stats.distinct],
+        )),
+        Line::from(tr(Text::TuiStatsRhymes, &[&stats.rhyme_chains])),
+        Line::from(""),
+        Line::from(Span::styled(tr(Text::TuiStatsKeywords, &[]), theme.passage)),
+    ];
+    lines.extend(
+        stats
+            .keywords
+            .iter()
+            .map(|(word, count)| Line::from(format!("  {} ({})", word, count))),
+    );
+    lines.push(Line::from(""));
+    lines.push(Line::from(tr(
+        Text::TuiStatsNotes,
+        &[&info.notes, &info.bookmarks],
+    )));
+    lines.push(Line::from(match info.read_on.last() {
+        Some(last) => tr(Text::TuiStatsRead, &[&info.read_on.len(), last]),
+        None => tr(Text::TuiStatsUnread, &[]),
+    }));
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiStats, &[])),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+fn render_cantica_list(f: &mut Frame, area: Rect, app: &mut App) {
+    let items: Vec<ListItem> = CanticaId::ALL
+        .iter()
+        .map(|cantica| ListItem::new(cantica.name()))
         .collect();
 
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Cantica"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiCanticas, &[])),
         )
+        .highlight_style(app.theme.selection)
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, area, &mut app.cantica_list_state);
 }
 
 fn render_canto_list(f: &mut Frame, area: Rect, app: &mut App) {
-    let cantica = app.get_current_cantica();
-    let mut canto_numbers: Vec<_> = cantica.cantos.keys().collect();
-    canto_numbers.sort();
-
-    let items: Vec<ListItem> = canto_numbers
-        .iter()
-        .map(|&&num| ListItem::new(format!("Canto {}", num)))
+    let items: Vec<ListItem> = app
+        .get_current_cantica()
+        .cantos
+        .keys()
+        .map(|num| ListItem::new(format!("Canto {}", num)))
         .collect();
 
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Cantos"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiCantos, &[])),
         )
+        .highlight_style(app.theme.selection)
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, area, &mut app.canto_list_state);
@@ -464,24 +2201,94 @@ fn render_canto_list(f: &mut Frame, area: Rect, app: &mut App) {
 
 fn render_verse_display(f: &mut Frame, area: Rect, app: &App) {
     let title = if let Some(canto) = app.get_current_canto() {
-        format!("{} Canto {}", app.current_cantica, canto.roman_numeral)
+        let mut title = format!("{} Canto {}", app.current_cantica, canto.roman_numeral);
+        if let Some(find) = &app.find {
+            title.push_str(&find_status(find));
+        }
+        if let Some(playback) = &app.playback {
+            let status = if playback.is_paused() {
+                Text::TuiPaused
+            } else {
+                Text::TuiPlaying
+            };
+            title.push_str(&tr(status, &[]));
+        }
+        title
     } else {
-        format!("{} - Select a Canto", app.current_cantica)
+        tr(Text::TuiSelectCanto, &[&app.current_cantica])
     };
+    let title = fit_title(&title, area);
 
     if let Some(canto) = app.get_current_canto() {
+        // The footnotes, when shown, mark their verses and take a pane
+        // under them for the current one
+        let footnotes = if app.show_footnotes {
+            app.footnotes()
+        } else {
+            Vec::new()
+        };
+        let current = app.footnote.min(footnotes.len().saturating_sub(1));
+        let area = if app.shows_footnote_pane() {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(FOOTNOTE_HEIGHT)].as_ref())
+                .split(area);
+            render_footnote(f, rows[1], &footnotes, current, &app.theme);
+            rows[0]
+        } else {
+            area
+        };
+        let selected = app.selected_word_range();
+        let cursor = app.current_location().map(|(_, _, line)| line);
         let verses: Vec<Line> = canto
             .verses
             .iter()
             .skip(app.verse_scroll as usize)
-            .map(|verse| {
-                Line::from(vec![
-                    Span::styled(
-                        format!("{:3}: ", verse.line_number),
-                        Style::default().fg(Color::Yellow),
-                    ),
-                    Span::raw(&verse.text),
-                ])
+            .flat_map(|verse| {
+                let word = selected
+                    .clone()
+                    .filter(|(line, _)| *line == verse.line_number)
+                    .map(|(_, range)| range);
+                let gutter = if cursor == Some(verse.line_number) {
+                    app.theme.context_gutter
+                } else {
+                    app.theme.gutter
+                };
+                let mut spans = vec![Span::styled(text::gutter(verse.line_number), gutter)];
+                spans.extend(speaker_tag(canto, verse.line_number, &app.theme));
+                let style = if app.playing_line == Some(verse.line_number) {
+                    app.theme.playing
+                } else {
+                    Style::default()
+                };
+                spans.extend(verse_spans(
+                    &verse.text,
+                    latin_style(canto, verse.line_number, style),
+                    &app.theme,
+                    &app.glossary,
+                    word,
+                    app.found_in(verse.line_number),
+                ));
+                spans.extend(footnote_markers(
+                    &footnotes,
+                    verse.line_number,
+                    current,
+                    &app.theme,
+                ));
+                let mut lines = vec![Line::from(spans)];
+                lines.extend(variant_lines(
+                    app,
+                    app.current_cantica,
+                    canto.number,
+                    verse.line_number,
+                ));
+                lines.extend(allusion_lines(
+                    app,
+                    app.current_cantica,
+                    canto.number,
+                    verse.line_number,
+                ));
+                lines
             })
             .collect();
 
@@ -491,20 +2298,10 @@ fn render_verse_display(f: &mut Frame, area: Rect, app: &App) {
 
         f.render_widget(paragraph, area);
     } else {
-        let help_text = vec![
-            Line::from("Navigation:"),
-            Line::from("h/← l/→  - Switch Cantica"),
-            Line::from("j/↓ k/↑  - Select Canto"),
-            Line::from("J K      - Scroll verses"),
-            Line::from("/        - Interactive Search (fzf-like)"),
-            Line::from("q        - Quit"),
-            Line::from(""),
-            Line::from("Search Features:"),
-            Line::from("• Live filtering as you type"),
-            Line::from("• Fuzzy matching with scoring"),
-            Line::from("• Enter to view in context"),
-            Line::from("• Esc to return"),
-        ];
+        let help_text: Vec<Line> = tr(Text::TuiHelp, &[])
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect();
 
         let paragraph = Paragraph::new(help_text)
             .block(Block::default().borders(Borders::ALL).title(title.clone()))
@@ -514,97 +2311,328 @@ fn render_verse_display(f: &mut Frame, area: Rect, app: &App) {
     }
 }
 
+/// How an in-canto search stands, for the end of the verses' title.
+fn find_status(find: &Find) -> String {
+    if find.editing {
+        tr(Text::TuiFinding, &[&find.query])
+    } else if find.matches.is_empty() {
+        tr(Text::TuiNotFound, &[&find.query])
+    } else {
+        tr(
+            Text::TuiFound,
+            &[&find.query, &(find.current + 1), &find.matches.len()],
+        )
+    }
+}
+
+/// `title` cut to fit the top border of a bordered block filling `area`.
+fn fit_title(title: &str, area: Rect) -> String {
+    text::truncate(title, usize::from(area.width).saturating_sub(2))
+}
+
+/// Columns taken by the longest reference, `Purgatorio 33.145`.
+const REFERENCE_WIDTH: usize = 17;
+
+/// A search result as one list line of at most `width` columns: the
+/// reference in a fixed column, then the verse with its match lined up a
+/// third of the way across, so the keywords of a list of results stack.
+fn result_preview(result: &SearchResult, width: usize) -> String {
+    let reference = format!("{} {}.{}", result.cantica, result.canto, result.line);
+    let verse_width = width.saturating_sub(REFERENCE_WIDTH + 2);
+    let verse = match &result.keyword {
+        Some(keyword) => text::kwic(result.text, keyword.clone(), verse_width / 3, verse_width),
+        None => text::truncate(result.text, verse_width),
+    };
+    let line = format!("{}  {}", text::pad_end(&reference, REFERENCE_WIDTH), verse);
+    text::truncate(&line, width)
+}
+
 fn render_interactive_search(f: &mut Frame, area: Rect, app: &mut App) {
+    // Completions of the word being typed take a row under the search box,
+    // and the heatmap, when shown, a row for each cantica before the results
+    let completions_height = u16::from(!app.completions.is_empty());
+    let heatmap_height = if app.show_heatmap {
+        CanticaId::ALL.len() as u16 + 2
+    } else {
+        0
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Length(completions_height),
+                Constraint::Length(heatmap_height),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
         .split(area);
+    if !app.completions.is_empty() {
+        let completions = tr(Text::TuiCompletions, &[&app.completions.join("  ")]);
+        f.render_widget(
+            Paragraph::new(text::truncate(&completions, usize::from(chunks[1].width)))
+                .style(Style::default().add_modifier(Modifier::DIM)),
+            chunks[1],
+        );
+    }
+    if app.show_heatmap {
+        render_heatmap(f, chunks[2], app);
+    }
+    let chunks = [chunks[0], chunks[3]];
 
-    // Search input box
+    // Search input box, its title saying how words are matched
+    let mut matching = vec![if app.pipeline.contains(Normalizer::Fold) {
+        tr(Text::TuiAccentsIgnored, &[])
+    } else {
+        tr(Text::TuiAccentsExact, &[])
+    }];
+    if app.pipeline.contains(Normalizer::Lemma) {
+        matching.push(tr(Text::TuiWordForms, &[]));
+    }
+    if app.pipeline.contains(Normalizer::Orthography) {
+        matching.push(tr(Text::TuiOldSpellings, &[]));
+    }
+    if app.pipeline.is_regex() {
+        matching.push(tr(Text::TuiRegex, &[]));
+    }
+    let title = tr(Text::TuiSearch, &[&matching.join(", ")]);
     let input = Paragraph::new(app.search_input.as_str())
-        .style(Style::default().fg(Color::Yellow))
+        .style(app.theme.input)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Interactive Search (type to filter)"),
+                .title(fit_title(&title, chunks[0])),
         );
     f.render_widget(input, chunks[0]);
 
-    // Live results
+    // Live results, each cut to the list's width inside its borders and
+    // highlight symbol
+    let width = usize::from(chunks[1].width).saturating_sub(4);
     let items: Vec<ListItem> = app
         .filtered_results
         .iter()
-        .map(|result| {
-            let preview = if result.text.len() > 80 {
-                format!("{}...", &result.text[..77])
-            } else {
-                result.text.clone()
-            };
-            ListItem::new(format!(
-                "{} {}.{}: {}",
-                result.cantica, result.canto, result.line, preview
-            ))
-        })
+        .map(|result| ListItem::new(result_preview(result, width)))
         .collect();
 
     let results_title = if app.filtered_results.is_empty() && !app.search_input.is_empty() {
-        "No matches found".to_string()
+        tr(Text::TuiNoMatches, &[])
     } else {
-        format!(
-            "Results ({}) - Enter to view context",
-            app.filtered_results.len()
-        )
+        tr(Text::TuiResults, &[&app.filtered_results.len()])
     };
 
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(results_title))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&results_title, chunks[1])),
         )
+        .highlight_style(app.theme.selection)
         .highlight_symbol("► ");
 
     f.render_stateful_widget(list, chunks[1], &mut app.search_list_state);
 }
 
+fn render_heatmap(f: &mut Frame, area: Rect, app: &App) {
+    let cells = app.result_heatmap();
+    let most = cells.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+    let lines: Vec<Line> = CanticaId::ALL
+        .iter()
+        .map(|&id| {
+            let row: String = cells
+                .iter()
+                .filter(|(cantica, _, _)| *cantica == id)
+                .map(|&(_, _, count)| stats::shade(count, most))
+                .collect();
+            Line::from(vec![
+                Span::styled(text::pad_end(id.name(), 12), app.theme.gutter),
+                Span::raw(row),
+            ])
+        })
+        .collect();
+    let title = tr(Text::TuiHeatmap, &[]);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(fit_title(&title, area)),
+    );
+    f.render_widget(paragraph, area);
+}
+
+/// A bookmark as one list line of at most `width` columns: its reference,
+/// then its label or else its verse, then its tags.
+fn bookmark_preview(
+    app: &App,
+    cantica: CanticaId,
+    canto: u8,
+    bookmark: &Bookmark,
+    width: usize,
+) -> String {
+    let reference = format!("{} {}.{}", cantica, canto, bookmark.line);
+    let text = match &bookmark.label {
+        Some(label) => label.as_str(),
+        None => app
+            .commedia
+            .verse(cantica, canto, bookmark.line)
+            .map_or("", |verse| verse.text.as_str()),
+    };
+    let mut line = format!("{}  {}", text::pad_end(&reference, REFERENCE_WIDTH), text);
+    for tag in &bookmark.tags {
+        line.push_str(&format!(" #{}", tag));
+    }
+    text::truncate(&line, width)
+}
+
+/// How big a word of the term view is drawn, from 0 for the biggest to 3,
+/// by how often it's used against the most used.
+fn cloud_size(count: usize, most: usize) -> usize {
+    match count * 4 {
+        n if n >= most * 3 => 0,
+        n if n >= most * 2 => 1,
+        n if n >= most => 2,
+        _ => 3,
+    }
+}
+
+/// The words of the term view, sized as a terminal can: the biggest
+/// letter-spaced in capitals, the next in capitals, and the rest styled by
+/// the theme from bold to dim.
+fn cloud_spans(terms: &[(String, usize)], theme: &Theme) -> Vec<Span<'static>> {
+    let most = terms.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let mut spans = Vec::new();
+    for (i, (word, count)) in terms.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw("   "));
+        }
+        let size = cloud_size(*count, most);
+        let text = match size {
+            0 => word
+                .to_uppercase()
+                .chars()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(" "),
+            1 => word.to_uppercase(),
+            _ => word.clone(),
+        };
+        spans.push(Span::styled(text, theme.cloud[size]));
+    }
+    spans
+}
+
+fn render_terms(f: &mut Frame, area: Rect, app: &App) {
+    let title = match app.get_current_canto() {
+        Some(canto) => tr(
+            Text::TuiTerms,
+            &[&app.current_cantica, &canto.roman_numeral],
+        ),
+        None => String::new(),
+    };
+    let body = if app.terms.is_empty() {
+        Line::from(tr(Text::TuiNoTerms, &[]))
+    } else {
+        Line::from(cloud_spans(&app.terms, &app.theme))
+    };
+    let paragraph = Paragraph::new(vec![Line::from(""), body])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&title, area)),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, area);
+}
+
+fn render_bookmarks(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+
+    let title = tr(Text::TuiBookmarkTags, &[]);
+    let input = Paragraph::new(app.bookmark_filter.as_str())
+        .style(app.theme.input)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&title, chunks[0])),
+        );
+    f.render_widget(input, chunks[0]);
+
+    let width = usize::from(chunks[1].width).saturating_sub(4);
+    let bookmarks = app.filtered_bookmarks();
+    let items: Vec<ListItem> = bookmarks
+        .iter()
+        .map(|(cantica, canto, bookmark)| {
+            ListItem::new(bookmark_preview(app, *cantica, *canto, bookmark, width))
+        })
+        .collect();
+    let list_title = if bookmarks.is_empty() {
+        tr(Text::TuiNoBookmarks, &[])
+    } else {
+        tr(Text::TuiBookmarks, &[&bookmarks.len()])
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(fit_title(&list_title, chunks[1])),
+        )
+        .highlight_style(app.theme.selection)
+        .highlight_symbol("► ");
+
+    f.render_stateful_widget(list, chunks[1], &mut app.bookmark_list_state);
+}
+
 fn render_context_view(f: &mut Frame, area: Rect, app: &App) {
     if let Some(canto) = app.get_context_canto() {
         let title = if let Some((cantica, _canto_num)) = &app.context_canto {
-            format!(
-                "{} Canto {} - Context View (Esc to return)",
-                cantica, canto.roman_numeral
-            )
+            tr(Text::TuiContextTitle, &[cantica, &canto.roman_numeral])
         } else {
-            "Context View".to_string()
+            tr(Text::TuiContext, &[])
         };
+        let title = fit_title(&title, area);
 
+        let selected = app.selected_word_range();
         let verses: Vec<Line> = canto
             .verses
             .iter()
             .skip(app.verse_scroll as usize)
-            .map(|verse| {
-                let style = if Some(verse.line_number) == app.context_highlight_line {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD)
+            .flat_map(|verse| {
+                let word = selected
+                    .clone()
+                    .filter(|(line, _)| *line == verse.line_number)
+                    .map(|(_, range)| range);
+                let (style, gutter) = if Some(verse.line_number) == app.context_highlight_line {
+                    (app.theme.context_line, app.theme.context_gutter)
                 } else {
-                    Style::default()
+                    (Style::default(), app.theme.context_other_gutter)
                 };
 
-                Line::from(vec![
-                    Span::styled(
-                        format!("{:3}: ", verse.line_number),
-                        Style::default().fg(
-                            if Some(verse.line_number) == app.context_highlight_line {
-                                Color::Red
-                            } else {
-                                Color::Cyan
-                            },
-                        ),
-                    ),
-                    Span::styled(&verse.text, style),
-                ])
+                let mut spans = vec![Span::styled(text::gutter(verse.line_number), gutter)];
+                spans.extend(speaker_tag(canto, verse.line_number, &app.theme));
+                let style = latin_style(canto, verse.line_number, style);
+                spans.extend(verse_spans(
+                    &verse.text,
+                    style,
+                    &app.theme,
+                    &app.glossary,
+                    word,
+                    &[],
+                ));
+                let mut lines = vec![Line::from(spans)];
+                if let Some((cantica, _)) = app.context_canto {
+                    lines.extend(variant_lines(app, cantica, canto.number, verse.line_number));
+                    lines.extend(allusion_lines(
+                        app,
+                        cantica,
+                        canto.number,
+                        verse.line_number,
+                    ));
+                }
+                lines
             })
             .collect();
 
@@ -614,8 +2642,11 @@ fn render_context_view(f: &mut Frame, area: Rect, app: &App) {
 
         f.render_widget(paragraph, area);
     } else {
-        let paragraph = Paragraph::new("No context available")
-            .block(Block::default().borders(Borders::ALL).title("Context View"));
+        let paragraph = Paragraph::new(tr(Text::TuiNoContext, &[])).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tr(Text::TuiContext, &[])),
+        );
         f.render_widget(paragraph, area);
     }
 }
@@ -623,16 +2654,16 @@ fn render_context_view(f: &mut Frame, area: Rect, app: &App) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Canto, DivinaCommedia, Verse};
+    use duca::{Canto, DivinaCommedia, Verse};
 
     fn create_test_commedia() -> DivinaCommedia {
         let mut commedia = DivinaCommedia::new();
 
         // Add test canto to Inferno
-        let canto1 = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![
+        let canto1 = Canto::new(
+            CanticaId::Inferno,
+            1,
+            vec![
                 Verse {
                     line_number: 1,
                     text: "Nel mezzo del cammin di nostra vita".to_string(),
@@ -646,14 +2677,14 @@ mod tests {
                     text: "ché la diritta via era smarrita".to_string(),
                 },
             ],
-        };
+        );
         commedia.inferno.cantos.insert(1, canto1);
 
         // Add test canto to Purgatorio
-        let canto1_purg = Canto {
-            number: 1,
-            roman_numeral: "I".to_string(),
-            verses: vec![
+        let canto1_purg = Canto::new(
+            CanticaId::Purgatorio,
+            1,
+            vec![
                 Verse {
                     line_number: 1,
                     text: "Per correr miglior acque alza le vele".to_string(),
@@ -663,7 +2694,7 @@ mod tests {
                     text: "omai la navicella del mio ingegno".to_string(),
                 },
             ],
-        };
+        );
         commedia.purgatorio.cantos.insert(1, canto1_purg);
 
         commedia
@@ -672,9 +2703,9 @@ mod tests {
     #[test]
     fn test_app_new() {
         let commedia = create_test_commedia();
-        let app = App::new(commedia);
+        let app = App::new(&commedia);
 
-        assert_eq!(app.current_cantica, "Inferno");
+        assert_eq!(app.current_cantica, CanticaId::Inferno);
         assert_eq!(app.mode, AppMode::Browse);
         assert!(app.search_input.is_empty());
         assert!(app.search_results.is_empty());
@@ -685,30 +2716,30 @@ mod tests {
     #[test]
     fn test_cantica_navigation() {
         let commedia = create_test_commedia();
-        let mut app = App::new(commedia);
+        let mut app = App::new(&commedia);
 
         // Test next cantica
-        assert_eq!(app.current_cantica, "Inferno");
+        assert_eq!(app.current_cantica, CanticaId::Inferno);
         app.next_cantica();
-        assert_eq!(app.current_cantica, "Purgatorio");
+        assert_eq!(app.current_cantica, CanticaId::Purgatorio);
         app.next_cantica();
-        assert_eq!(app.current_cantica, "Paradiso");
+        assert_eq!(app.current_cantica, CanticaId::Paradiso);
         app.next_cantica();
-        assert_eq!(app.current_cantica, "Inferno"); // Should wrap around
+        assert_eq!(app.current_cantica, CanticaId::Inferno); // Should wrap around
 
         // Test previous cantica
         app.previous_cantica();
-        assert_eq!(app.current_cantica, "Paradiso");
+        assert_eq!(app.current_cantica, CanticaId::Paradiso);
         app.previous_cantica();
-        assert_eq!(app.current_cantica, "Purgatorio");
+        assert_eq!(app.current_cantica, CanticaId::Purgatorio);
         app.previous_cantica();
-        assert_eq!(app.current_cantica, "Inferno");
+        assert_eq!(app.current_cantica, CanticaId::Inferno);
     }
 
     #[test]
     fn test_canto_navigation() {
         let commedia = create_test_commedia();
-        let mut app = App::new(commedia);
+        let mut app = App::new(&commedia);
 
         // Initially no canto selected
         assert_eq!(app.current_canto, None);
@@ -719,7 +2750,7 @@ mod tests {
 
         // Navigate to Purgatorio
         app.next_cantica();
-        assert_eq!(app.current_cantica, "Purgatorio");
+        assert_eq!(app.current_cantica, CanticaId::Purgatorio);
         app.next_canto();
         assert_eq!(app.current_canto, Some(1));
     }
@@ -727,14 +2758,15 @@ mod tests {
     #[test]
     fn test_search_result_structure() {
         let result = SearchResult {
-            cantica: "Inferno".to_string(),
+            cantica: CanticaId::Inferno,
             canto: 1,
             line: 2,
-            text: "test verse".to_string(),
+            text: "test verse",
+            keyword: None,
             score: 100,
         };
 
-        assert_eq!(result.cantica, "Inferno");
+        assert_eq!(result.cantica, CanticaId::Inferno);
         assert_eq!(result.canto, 1);
         assert_eq!(result.line, 2);
         assert_eq!(result.text, "test verse");
@@ -742,9 +2774,838 @@ mod tests {
     }
 
     #[test]
-    fn test_app_mode_changes() {
+    fn test_result_preview() {
+        let mut result = SearchResult {
+            cantica: CanticaId::Inferno,
+            canto: 3,
+            line: 1,
+            text: "Per me si va ne la città dolente,",
+            keyword: None,
+            score: 0,
+        };
+        assert_eq!(
+            result_preview(&result, 80),
+            "Inferno 3.1        Per me si va ne la città dolente,"
+        );
+        // Cutting just after "città" mustn't split its last letter
+        assert_eq!(
+            result_preview(&result, 44),
+            "Inferno 3.1        Per me si va ne la città…"
+        );
+
+        // With a match, the verse is aligned on it
+        let start = result.text.find("città").unwrap();
+        result.keyword = Some(start..start + "città".len());
+        let preview = result_preview(&result, 49);
+        assert_eq!(preview, "Inferno 3.1        …va ne la città dolente,");
+        let at = preview.find("città").unwrap();
+        assert_eq!(text::display_width(&preview[..at]), 19 + 10);
+    }
+
+    #[test]
+    fn test_interactive_search() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+
+        app.search_input = "la".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 2);
+        assert_eq!(app.search_list_state.selected(), Some(0));
+        assert!(app
+            .filtered_results
+            .windows(2)
+            .all(|w| w[0].score >= w[1].score));
+
+        // Results borrow their text from the corpus
+        let verse = commedia.verse(CanticaId::Inferno, 1, 3).unwrap();
+        let result = app
+            .filtered_results
+            .iter()
+            .find(|r| r.cantica == CanticaId::Inferno)
+            .unwrap();
+        assert_eq!(result.text.as_ptr(), verse.text.as_ptr());
+
+        app.search_input = "  ".to_string();
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        assert_eq!(app.search_list_state.selected(), None);
+    }
+
+    #[test]
+    fn test_toggle_accent_folding() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        assert!(app.pipeline.contains(Normalizer::Fold));
+
+        app.search_input = "che la".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 1);
+        assert_eq!(app.filtered_results[0].line, 3);
+
+        // "ché" no longer matches "che" once accents must match
+        app.toggle_accent_folding();
+        assert!(!app.pipeline.contains(Normalizer::Fold));
+        assert!(app.filtered_results.is_empty());
+
+        app.search_input = "ché la".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 1);
+
+        app.toggle_accent_folding();
+        app.search_input = "CHE LA".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 1);
+    }
+
+    #[test]
+    fn test_stopword_relevance() {
+        let matcher = SkimMatcherV2::default();
+        let query = weighted_words("la selva oscura");
+        assert_eq!(query[0], ("la", stopwords::STOPWORD_WEIGHT));
+        assert_eq!(query[1], ("selva", 1.0));
+
+        // The uncommon words decide, however well the common ones match
+        let la = relevance(&matcher, "la la la selva", &weighted_words("la"));
+        let selva = relevance(&matcher, "la la la selva", &weighted_words("selva"));
+        assert!(la * 5 < selva);
+        assert!(
+            relevance(&matcher, "ché la diritta via", &query)
+                < relevance(&matcher, "mi ritrovai per una selva oscura", &query)
+        );
+        assert_eq!(relevance(&matcher, "selva", &[]), 0);
+    }
+
+    #[test]
+    fn test_fuzzy_options() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.search_input = "nel".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 1);
+        let score = app.filtered_results[0].score;
+
+        // The search still ignores case, but with case respected a result
+        // scores only where the query's case is matched
+        let respect = FuzzyOptions::choose(Some(FuzzyCase::Respect), Some(1));
+        app.fuzzy_matcher = respect.matcher();
+        app.fuzzy_threshold = respect.threshold;
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        app.search_input = "Nel".to_string();
+        app.interactive_search();
+        assert_eq!(app.filtered_results.len(), 1);
+
+        // Too high a threshold drops it
+        app.fuzzy_threshold = score.max(app.filtered_results[0].score) + 1;
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+
+        let smart = FuzzyOptions::choose(Some(FuzzyCase::Smart), None).matcher();
+        assert!(smart.fuzzy_match("Nel mezzo", "nel").is_some());
+        assert!(smart.fuzzy_match("nel mezzo", "Nel").is_none());
+        assert_eq!(FuzzyOptions::default().case, FuzzyCase::Ignore);
+    }
+
+    #[test]
+    fn test_search_completions() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        app.enter_search_mode();
+
+        app.search_input = "selva Smarr".to_string();
+        app.interactive_search();
+        assert!(app.completions.contains(&"smarrita"));
+        assert!(app.completions.len() <= COMPLETIONS);
+        assert!(app.completions.iter().all(|w| w.starts_with("smarr")));
+        let first = app.completions[0];
+        app.accept_completion();
+        assert_eq!(app.search_input, format!("selva {}", first));
+
+        // Nothing is offered once the word is done
+        app.search_input.push(' ');
+        app.interactive_search();
+        assert!(app.completions.is_empty());
+        app.accept_completion();
+        assert_eq!(app.search_input, format!("selva {} ", first));
+
+        app.clear_search();
+        assert!(app.completions.is_empty());
+    }
+
+    #[test]
+    fn test_toggle_normalizers() {
         let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+
+        app.search_input = "acqua alza".to_string();
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        app.toggle_normalizer(Normalizer::Lemma);
+        assert_eq!(app.filtered_results.len(), 1);
+        assert_eq!(
+            app.filtered_results[0].text,
+            "Per correr miglior acque alza le vele"
+        );
+        app.toggle_normalizer(Normalizer::Lemma);
+
+        app.search_input = "nostra uita".to_string();
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        app.toggle_normalizer(Normalizer::Orthography);
+        assert_eq!(app.filtered_results.len(), 1);
+        assert_eq!(app.filtered_results[0].line, 1);
+        app.toggle_normalizer(Normalizer::Orthography);
+
+        // A regex finds nothing until it's read as one, or while it's unfinished
+        app.search_input = "^mi .* oscura".to_string();
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        app.toggle_regex();
+        assert_eq!(app.filtered_results.len(), 1);
+        app.search_input = "^mi (".to_string();
+        app.interactive_search();
+        assert!(app.filtered_results.is_empty());
+        app.toggle_normalizer(Normalizer::Lemma);
+        assert!(!app.pipeline.is_regex());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_playback() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        // Inferno 1 read at a line every 10ms, by `sleep` standing in for a player
+        app.recordings = Recordings::parse(
+            "[[recording]]\ncanto = \"Inferno 1\"\nfile = \"30\"\nend = 1.36\n",
+            "test",
+            std::path::Path::new(""),
+        )
+        .unwrap();
+        app.toggle_playback();
+        assert!(app.playback.is_none(), "no canto is shown yet");
+
+        app.next_canto();
+        let recording = app.recordings.for_canto(CanticaId::Inferno, 1).unwrap();
+        app.playback = Some(Playback::start(&["sleep".to_string()], recording).unwrap());
+        std::thread::sleep(Duration::from_millis(300));
+        app.follow_playback();
+        let line = app.playing_line.unwrap();
+        assert!(line >= 30, "{}", line);
+        assert_eq!(usize::from(app.verse_scroll), line - 1);
+
+        app.next_canto();
+        app.follow_playback();
+        assert!(app.playback.is_none());
+        assert_eq!(app.playing_line, None);
+    }
+
+    #[test]
+    fn test_find_in_canto() {
+        let commedia = duca::commedia().unwrap();
         let mut app = App::new(commedia);
+        app.start_find();
+        assert_eq!(app.find, None);
+
+        app.next_canto();
+        app.start_find();
+        app.find.as_mut().unwrap().query = "amor".to_string();
+        app.update_find();
+        let find = app.find.clone().unwrap();
+        assert!(find.editing);
+        assert!(find.matches.len() > 1);
+        assert!(find.matches.iter().all(|(_, spans)| !spans.is_empty()));
+        let (first, _) = find.matches[0];
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, first)));
+        assert!(!app.found_in(first).is_empty());
+
+        // Moving between matches scrolls each to the top, wrapping around
+        app.next_find_match();
+        let (second, _) = find.matches[1];
+        assert_eq!(
+            app.current_location(),
+            Some((CanticaId::Inferno, 1, second))
+        );
+        app.previous_find_match();
+        app.previous_find_match();
+        let (last, _) = *find.matches.last().unwrap();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, last)));
+
+        // The find belongs to its canto
+        app.next_canto();
+        assert_eq!(app.find, None);
+        assert_eq!(app.mode, AppMode::Browse);
+    }
+
+    #[test]
+    fn test_verse_spans_highlight_found_text() {
+        let glossary = Glossary::builtin();
+        let text = "lo duca mio altrui";
+        let spans = verse_spans(
+            text,
+            Style::default(),
+            &Theme::color(),
+            &glossary,
+            None,
+            &[5..11, 12..14],
+        );
+        let found: String = spans
+            .iter()
+            .filter(|span| span.style.bg == Some(Color::Yellow))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(found, "ca mioal");
+        // The glossed "duca" keeps its underline where the match covers it
+        let both = spans.iter().find(|span| span.content == "ca").unwrap();
+        assert!(both.style.add_modifier.contains(Modifier::UNDERLINED));
+        let rebuilt: String = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(rebuilt, text);
+    }
+
+    #[test]
+    fn test_toggle_commentary() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        assert!(!app.show_commentary);
+        assert!(app.commentary.is_empty());
+
+        app.toggle_commentary();
+        assert!(app.show_commentary);
+        app.toggle_commentary();
+        assert!(!app.show_commentary);
+    }
+
+    #[test]
+    fn test_toggle_variants() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.apparatus = Apparatus::parse(
+            "[[variant]]\nline = \"Inferno 1.2\"\nlemma = \"selva\"\nreading = \"silva\"",
+            "test",
+        )
+        .unwrap();
+        assert!(variant_lines(&app, CanticaId::Inferno, 1, 2).is_empty());
+
+        app.toggle_variants();
+        let lines = variant_lines(&app, CanticaId::Inferno, 1, 2);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].spans[0].content, "     [test] selva] silva");
+        assert!(variant_lines(&app, CanticaId::Inferno, 1, 1).is_empty());
+    }
+
+    #[test]
+    fn test_toggle_allusions() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        assert!(allusion_lines(&app, CanticaId::Inferno, 1, 1).is_empty());
+
+        app.toggle_allusions();
+        let lines = allusion_lines(&app, CanticaId::Inferno, 1, 1);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].spans[0]
+            .content
+            .starts_with("     [Isaiah 38.10] "));
+        assert!(allusion_lines(&app, CanticaId::Inferno, 1, 2).is_empty());
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let mut app = App::new(duca::commedia().unwrap());
+        let json = r#"{"entry": [{"passage": "Inferno 1.31-33", "text": "The leopard."}]}"#;
+        app.commentary = Commentary::parse(json, duca::commentary::Format::Json, "mine").unwrap();
+        app.current_canto = Some(1);
+
+        let footnotes = app.footnotes();
+        assert_eq!(footnotes[0].kind, FootnoteKind::Allusion);
+        assert_eq!(
+            (footnotes[0].line, footnotes[0].title.as_str()),
+            (1, "Isaiah 38.10")
+        );
+        assert!(footnotes.windows(2).all(|w| w[0].line <= w[1].line));
+        let leopard = footnotes
+            .iter()
+            .position(|f| f.kind == FootnoteKind::Commentary)
+            .unwrap();
+        assert_eq!(footnotes[leopard].line, 31);
+        assert_eq!(footnotes[leopard].title, "Inferno 1.31-33, mine");
+        assert_eq!(footnotes[leopard + 1].title, "lonza");
+        // Each word glossed at its first use only
+        assert_eq!(footnotes.iter().filter(|f| f.title == "lasso").count(), 1);
+
+        // The first press shows the footnotes from the cursor, the next
+        // cycle through them, wrapping around
+        app.cursor = 29;
+        app.next_footnote();
+        assert!(app.show_footnotes);
+        assert_eq!(app.footnote, leopard);
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 31)));
+        app.next_footnote();
+        assert_eq!(app.footnote, leopard + 1);
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 32)));
+        app.footnote = 0;
+        app.previous_footnote();
+        assert_eq!(app.footnote, footnotes.len() - 1);
+        app.go_to(CanticaId::Inferno, 2, 1);
+        assert_eq!(app.footnote, 0);
+
+        let markers = footnote_markers(&footnotes, 31, leopard, &app.theme);
+        assert_eq!(markers.len(), 1);
+        assert_eq!(markers[0].content, format!(" {}", superscript(leopard + 1)));
+        assert_eq!(markers[0].style, app.theme.found);
+        assert_eq!(superscript(105), "¹⁰⁵");
+    }
+
+    #[test]
+    fn test_select_and_define_word() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.current_canto = Some(1);
+        app.cursor = 2;
+
+        // "ché la diritta via era smarrita"
+        app.previous_word();
+        assert_eq!(app.selected_word_range(), Some((3, 24..32)));
+        app.next_word();
+        assert_eq!(app.selected_word_range(), Some((3, 0..4)));
+
+        app.define_selected_word();
+        let (word, definition) = app.definition.clone().unwrap();
+        assert_eq!(word, "ché");
+        assert!(definition.contains("DUCA_DICTIONARY_CMD"));
+
+        app.glossary = Glossary::parse(
+            "[[entry]]\nword = \"che\"\ndefinition = \"for, because\"",
+            "test",
+        )
+        .unwrap();
+        app.define_selected_word();
+        assert_eq!(
+            app.definition,
+            Some(("ché".to_string(), "for, because".to_string()))
+        );
+
+        app.scroll_up();
+        assert_eq!(app.selected_word, None);
+    }
+
+    #[test]
+    fn test_verse_spans_mark_glossed_and_selected_words() {
+        let glossary = Glossary::builtin();
+        let text = "lo duca mio altrui";
+        let spans = verse_spans(
+            text,
+            Style::default(),
+            &Theme::color(),
+            &glossary,
+            Some(12..18),
+            &[],
+        );
+        let marked: Vec<(&str, Modifier)> = spans
+            .iter()
+            .filter(|span| !span.style.add_modifier.is_empty())
+            .map(|span| (span.content.as_ref(), span.style.add_modifier))
+            .collect();
+        assert_eq!(
+            marked,
+            [
+                ("duca", Modifier::UNDERLINED),
+                ("altrui", Modifier::UNDERLINED | Modifier::REVERSED)
+            ]
+        );
+        let rebuilt: String = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(rebuilt, text);
+    }
+
+    #[test]
+    fn test_monochrome_theme() {
+        let theme = Theme::monochrome();
+        for style in [
+            theme.gutter,
+            theme.selection,
+            theme.input,
+            theme.found,
+            theme.speaker,
+            theme.passage,
+            theme.context_line,
+            theme.context_gutter,
+            theme.context_other_gutter,
+            theme.playing,
+        ] {
+            assert_eq!((style.fg, style.bg), (None, None));
+        }
+        // Found text stands out from a glossed word and a selected one
+        let glossary = Glossary::builtin();
+        let spans = verse_spans(
+            "lo duca mio",
+            Style::default(),
+            &theme,
+            &glossary,
+            Some(0..2),
+            std::slice::from_ref(&(8..11)),
+        );
+        let modifiers: Vec<Modifier> = spans.iter().map(|span| span.style.add_modifier).collect();
+        assert_eq!(
+            modifiers,
+            [
+                Modifier::REVERSED,
+                Modifier::empty(),
+                Modifier::UNDERLINED,
+                Modifier::empty(),
+                Modifier::REVERSED | Modifier::BOLD
+            ]
+        );
+
+        assert!(is_monochrome(
+            true,
+            Some("color".to_string()),
+            ColorChoice::Always,
+            false
+        ));
+        assert!(is_monochrome(
+            false,
+            Some("mono".to_string()),
+            ColorChoice::Always,
+            false
+        ));
+        assert!(!is_monochrome(
+            false,
+            Some("color".to_string()),
+            ColorChoice::Never,
+            true
+        ));
+        assert!(is_monochrome(false, None, ColorChoice::Never, false));
+        assert!(!is_monochrome(false, None, ColorChoice::Always, true));
+        assert!(is_monochrome(false, None, ColorChoice::Auto, true));
+        assert!(!is_monochrome(false, None, ColorChoice::Auto, false));
+    }
+
+    #[test]
+    fn test_speaker_tag() {
+        let commedia = duca::commedia().unwrap();
+        let canto = commedia.canto(CanticaId::Inferno, 5).unwrap();
+        let tag = speaker_tag(canto, 88, &Theme::color()).unwrap();
+        assert_eq!(tag.content, "[Francesca] ");
+        // Only the first line of a speech is tagged
+        assert!(speaker_tag(canto, 89, &Theme::color()).is_none());
+        assert!(speaker_tag(canto, 1, &Theme::color()).is_none());
+    }
+
+    #[test]
+    fn test_current_location() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        assert_eq!(app.current_location(), None);
+
+        app.current_canto = Some(1);
+        app.cursor = 1;
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 2)));
+        app.cursor = 10;
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 3)));
+
+        app.mode = AppMode::ContextView;
+        app.context_canto = Some((CanticaId::Purgatorio, 1));
+        app.context_highlight_line = Some(1);
+        assert_eq!(app.current_location(), Some((CanticaId::Purgatorio, 1, 1)));
+    }
+
+    #[test]
+    fn test_bookmark_pane() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.open_bookmarks();
+        assert_eq!(app.mode, AppMode::Bookmarks);
+        assert_eq!(app.bookmark_list_state.selected(), None);
+
+        let bookmark = |line, tags: &[&str]| Bookmark {
+            line,
+            label: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        app.bookmarks = vec![
+            (CanticaId::Inferno, 1, bookmark(2, &["exam", "selva"])),
+            (
+                CanticaId::Purgatorio,
+                1,
+                bookmark(2, &["exam", "navicella"]),
+            ),
+        ];
+        app.bookmark_filter = "#ex".to_string();
+        app.filter_bookmarks();
+        assert_eq!(app.filtered_bookmarks().len(), 2);
+        assert_eq!(app.bookmark_list_state.selected(), Some(0));
+
+        // More tags narrow the list to the bookmarks with all of them
+        app.bookmark_filter = "exam navi".to_string();
+        app.filter_bookmarks();
+        assert_eq!(app.filtered_bookmarks().len(), 1);
+        assert_eq!(
+            bookmark_preview(&app, CanticaId::Inferno, 1, &app.bookmarks[0].2, 80),
+            "Inferno 1.2        mi ritrovai per una selva oscura #exam #selva"
+        );
+
+        app.go_to_bookmark();
+        assert_eq!(app.mode, AppMode::Browse);
+        assert_eq!(app.current_location(), Some((CanticaId::Purgatorio, 1, 2)));
+    }
+
+    #[test]
+    fn test_related_passages() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        app.follow_related();
+        assert_eq!(app.current_location(), None, "no canto, nothing to follow");
+
+        app.go_to(CanticaId::Inferno, 1, 100);
+        let related = app.related_at_cursor().unwrap();
+        assert_eq!(related.here.to_string(), "Inferno 1.100-111");
+        assert_eq!(related.there.to_string(), "Purgatorio 20.10-15");
+
+        // Followed as a jump, for Ctrl-o to come back from
+        app.follow_related();
+        assert_eq!(
+            app.current_location(),
+            Some((CanticaId::Purgatorio, 20, 10))
+        );
+        app.jump_back();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 100)));
+
+        // Past the last cross-reference, the canto's first is followed
+        app.go_to(CanticaId::Inferno, 1, 130);
+        assert_eq!(
+            app.related_at_cursor().unwrap().here.to_string(),
+            "Inferno 1.1-3"
+        );
+        app.toggle_related();
+        assert!(app.show_related);
+    }
+
+    #[test]
+    fn test_jumplist() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.current_canto = Some(1);
+        app.canto_list_state.select(Some(0));
+
+        // Scrolling isn't a jump; going to a bookmark is
+        app.scroll_down();
+        app.bookmarks = vec![(
+            CanticaId::Purgatorio,
+            1,
+            Bookmark {
+                line: 2,
+                label: None,
+                tags: Vec::new(),
+            },
+        )];
+        app.mode = AppMode::Bookmarks;
+        app.bookmark_list_state.select(Some(0));
+        app.record_jump();
+        assert!(app.jumps.is_empty(), "only browsing records jumps");
+        app.mode = AppMode::Browse;
+        app.record_jump();
+        app.go_to_bookmark();
+        app.scroll_down();
+        app.scroll_down();
+        assert_eq!(app.jumps, [(CanticaId::Inferno, 1, 2)]);
+
+        app.jump_back();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 2)));
+        app.jump_back();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 2)));
+        app.jump_forward();
+        assert_eq!(app.current_location(), Some((CanticaId::Purgatorio, 1, 2)));
+        app.jump_forward();
+        assert_eq!(app.current_location(), Some((CanticaId::Purgatorio, 1, 2)));
+
+        // A new jump after going back forgets the places gone back past
+        app.jump_back();
+        app.record_jump();
+        app.go_to(CanticaId::Inferno, 1, 3);
+        assert_eq!(app.jumps, [(CanticaId::Inferno, 1, 2)]);
+        app.jump_back();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 2)));
+        app.jump_forward();
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 3)));
+    }
+
+    #[test]
+    fn test_scrolloff() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        app.next_canto();
+        assert_eq!(app.verse_scroll, 0);
+
+        // The verse read from keeps the margin above it, but not past the
+        // canto's start, and the cursor stays on the one scrolled to
+        app.scrolloff = 3;
+        app.scroll_to_line(Some(40));
+        assert_eq!(app.verse_scroll, 36);
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 40)));
+        app.scroll_to_line(Some(2));
+        assert_eq!(app.verse_scroll, 0);
+
+        // A short pane keeps the verse off its bottom edge as well
+        app.verse_height = 5;
+        assert_eq!(app.margin(), 2);
+        app.scroll_to_line(Some(40));
+        assert_eq!(app.verse_scroll, 37);
+
+        assert_eq!(scrolloff(Some(4)), 4);
+    }
+
+    #[test]
+    fn test_reading_cursor() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        app.next_canto();
+        app.verse_height = 10;
+        app.scrolloff = 2;
+
+        // The cursor moves down the verses shown before the pane scrolls,
+        // which it does to keep the margin below the cursor
+        for _ in 0..7 {
+            app.cursor_down();
+        }
+        assert_eq!(app.verse_scroll, 0);
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 8)));
+        app.cursor_down();
+        assert_eq!(app.verse_scroll, 1);
+        app.cursor_up();
+        app.cursor_up();
+        app.cursor_up();
+        assert_eq!((app.verse_scroll, app.cursor), (1, 5));
+        app.cursor_up();
+        app.cursor_up();
+        app.cursor_up();
+        assert_eq!((app.verse_scroll, app.cursor), (0, 2));
+
+        // Scrolling the pane carries the cursor along at the margin
+        for _ in 0..5 {
+            app.scroll_down();
+        }
+        assert_eq!((app.verse_scroll, app.cursor), (5, 7));
+        app.scroll_up();
+        app.scroll_up();
+        app.scroll_up();
+        app.scroll_up();
+        assert_eq!((app.verse_scroll, app.cursor), (1, 7));
+        app.scroll_up();
+        app.scroll_up();
+        assert_eq!((app.verse_scroll, app.cursor), (0, 7));
+
+        // The cursor stops at the canto's last verse, and a new canto
+        // starts it from the top
+        for _ in 0..200 {
+            app.cursor_down();
+        }
+        assert_eq!(app.current_location(), Some((CanticaId::Inferno, 1, 136)));
+        assert_eq!(app.verse_scroll, 128);
+        app.next_canto();
+        assert_eq!((app.verse_scroll, app.cursor), (0, 0));
+    }
+
+    #[test]
+    fn test_stats_panel() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.next_canto();
+        app.update_canto_info();
+        assert_eq!(app.canto_info, None, "worked out only when shown");
+
+        app.toggle_stats();
+        app.update_canto_info();
+        let info = app.canto_info.clone().unwrap();
+        assert_eq!((info.cantica, info.canto), (CanticaId::Inferno, 1));
+        assert_eq!(info.stats.lines, 3);
+        assert_eq!((info.notes, info.bookmarks), (0, 0));
+
+        // Another canto is worked out when shown, and hiding forgets it
+        app.next_cantica();
+        app.next_canto();
+        app.update_canto_info();
+        assert_eq!(
+            app.canto_info.as_ref().unwrap().cantica,
+            CanticaId::Purgatorio
+        );
+        app.toggle_stats();
+        assert_eq!(app.canto_info, None);
+    }
+
+    #[test]
+    fn test_term_view() {
+        let commedia = duca::commedia().unwrap();
+        let mut app = App::new(commedia);
+        app.open_terms();
+        assert_eq!(app.mode, AppMode::Browse, "no canto, no terms");
+
+        app.next_canto();
+        app.open_terms();
+        assert_eq!(app.mode, AppMode::Terms);
+        assert!(app.terms.len() > 10);
+        assert!(app.terms.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(app.terms.iter().any(|(word, _)| word == "feltro"));
+
+        let terms = [
+            ("lupa".to_string(), 8),
+            ("selva".to_string(), 5),
+            ("sole".to_string(), 3),
+            ("veltro".to_string(), 1),
+        ];
+        let words: Vec<String> = cloud_spans(&terms, &Theme::monochrome())
+            .iter()
+            .map(|span| span.content.to_string())
+            .filter(|text| !text.trim().is_empty())
+            .collect();
+        assert_eq!(words, ["L U P A", "SELVA", "sole", "veltro"]);
+        assert_eq!(cloud_size(1, 8), 3);
+    }
+
+    #[test]
+    fn test_result_heatmap() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        app.toggle_heatmap();
+        assert!(app.show_heatmap);
+        app.search_input = "selva".to_string();
+        app.interactive_search();
+        let cells = app.result_heatmap();
+        assert_eq!(cells.len(), commedia.cantos().count());
+        let hits: usize = cells.iter().map(|(_, _, count)| count).sum();
+        assert_eq!(hits, app.filtered_results.len());
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn test_timed_session() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
+        let start = Instant::now();
+        let minutes = |minutes: u32| start + Duration::from_secs(60) * minutes;
+        // Without a timed session, z does nothing
+        app.toggle_break(start);
+        app.follow_session(start);
+
+        app.session = Some(Session::new(None, Duration::from_secs(300), start));
+        app.follow_session(start);
+        app.next_canto();
+        app.follow_session(minutes(1));
+        app.toggle_break(minutes(2));
+        app.follow_session(minutes(8));
+        let session = app.session.as_ref().unwrap();
+        assert_eq!(
+            session.status(minutes(10)),
+            Status::Reading(Duration::from_secs(60) * 5)
+        );
+        let date = "2025-01-12".parse().unwrap();
+        assert_eq!(
+            session.log_line(date, minutes(10)).unwrap(),
+            "2025-01-12\t5\t1\tInferno 1"
+        );
+    }
+
+    #[test]
+    fn test_app_mode_changes() {
+        let commedia = create_test_commedia();
+        let mut app = App::new(&commedia);
 
         assert_eq!(app.mode, AppMode::Browse);
 
@@ -759,7 +3620,7 @@ mod tests {
     #[test]
     fn test_search_input_handling() {
         let commedia = create_test_commedia();
-        let mut app = App::new(commedia);
+        let mut app = App::new(&commedia);
 
         assert!(app.search_input.is_empty());
 
@@ -773,7 +3634,7 @@ mod tests {
     #[test]
     fn test_verse_scrolling() {
         let commedia = create_test_commedia();
-        let mut app = App::new(commedia);
+        let mut app = App::new(&commedia);
 
         assert_eq!(app.verse_scroll, 0);
 
@@ -787,7 +3648,7 @@ mod tests {
     #[test]
     fn test_get_current_cantica() {
         let commedia = create_test_commedia();
-        let app = App::new(commedia);
+        let app = App::new(&commedia);
 
         let current = app.get_current_cantica();
         assert_eq!(current.name, "Inferno");
@@ -797,7 +3658,7 @@ mod tests {
     #[test]
     fn test_fuzzy_matcher_integration() {
         let commedia = create_test_commedia();
-        let app = App::new(commedia);
+        let app = App::new(&commedia);
 
         // Test that fuzzy matcher is initialized
         let score = app.fuzzy_matcher.fuzzy_match("test", "test");
@@ -811,16 +3672,15 @@ mod tests {
     #[test]
     fn test_context_canto_tracking() {
         let commedia = create_test_commedia();
-        let mut app = App::new(commedia);
+        let mut app = App::new(&commedia);
 
         assert_eq!(app.context_canto, None);
         assert_eq!(app.context_highlight_line, None);
 
-        app.context_canto = Some(("Inferno".to_string(), 1));
+        app.context_canto = Some((CanticaId::Inferno, 1));
         app.context_highlight_line = Some(2);
 
-        assert_eq!(app.context_canto, Some(("Inferno".to_string(), 1)));
+        assert_eq!(app.context_canto, Some((CanticaId::Inferno, 1)));
         assert_eq!(app.context_highlight_line, Some(2));
     }
 }
-
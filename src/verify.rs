@@ -0,0 +1,292 @@
+//! Checking a corpus against what the Commedia is known to contain: 34, 33
+//! and 33 cantos, the line count of every canto, 14,233 lines in all, and no
+//! empty or misnumbered verses. A parser regression or a damaged data file
+//! shows up here as a [`Deviation`] instead of as quietly missing text.
+
+use std::fmt;
+
+use crate::{CanticaId, DivinaCommedia};
+
+/// Lines in the whole poem.
+pub const TOTAL_LINES: usize = 14_233;
+
+/// A canto with fewer lines than this was surely cut short, in any edition:
+/// the shortest, Inferno VI and XI, have 115.
+pub const SHORT_CANTO: usize = 100;
+
+/// Lines in each canto of each cantica, in order.
+const CANTO_LINES: [&[usize]; 3] = [
+    &[
+        136, 142, 136, 151, 142, 115, 130, 130, 133, 136, 115, 139, 151, 142, 124, 136, 136, 136,
+        133, 130, 139, 151, 148, 151, 151, 142, 136, 142, 139, 148, 145, 139, 157, 139,
+    ],
+    &[
+        136, 133, 145, 139, 136, 151, 136, 139, 145, 139, 142, 136, 154, 151, 145, 145, 139, 145,
+        145, 151, 136, 154, 133, 154, 139, 148, 142, 148, 154, 145, 145, 160, 145,
+    ],
+    &[
+        142, 148, 130, 142, 139, 142, 148, 148, 142, 148, 139, 145, 142, 139, 148, 154, 142, 136,
+        148, 148, 142, 154, 139, 154, 139, 142, 148, 139, 145, 148, 142, 151, 145,
+    ],
+];
+
+/// The number of lines canto `canto` of `cantica` should have, or `None`
+/// if there is no such canto.
+pub fn expected_lines(cantica: CanticaId, canto: u8) -> Option<usize> {
+    CANTO_LINES[cantica as usize]
+        .get(usize::from(canto).checked_sub(1)?)
+        .copied()
+}
+
+/// Some way a corpus differs from the Commedia.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deviation {
+    CantoCount {
+        cantica: CanticaId,
+        found: usize,
+        expected: usize,
+    },
+    MissingCanto {
+        cantica: CanticaId,
+        canto: u8,
+    },
+    UnexpectedCanto {
+        cantica: CanticaId,
+        canto: u8,
+    },
+    LineCount {
+        cantica: CanticaId,
+        canto: u8,
+        found: usize,
+        expected: usize,
+    },
+    /// A canto shorter than [`SHORT_CANTO`].
+    ShortCanto {
+        cantica: CanticaId,
+        canto: u8,
+        lines: usize,
+    },
+    EmptyVerse {
+        cantica: CanticaId,
+        canto: u8,
+        line: usize,
+    },
+    /// A verse whose line number isn't its position in the canto.
+    Misnumbered {
+        cantica: CanticaId,
+        canto: u8,
+        position: usize,
+        line: usize,
+    },
+    TotalLines {
+        found: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Deviation::CantoCount {
+                cantica,
+                found,
+                expected,
+            } => write!(f, "{} has {} cantos, expected {}", cantica, found, expected),
+            Deviation::MissingCanto { cantica, canto } => {
+                write!(f, "{} {} is missing", cantica, canto)
+            }
+            Deviation::UnexpectedCanto { cantica, canto } => {
+                write!(f, "{} {} shouldn't exist", cantica, canto)
+            }
+            Deviation::LineCount {
+                cantica,
+                canto,
+                found,
+                expected,
+            } => write!(
+                f,
+                "{} {} has {} lines, expected {}",
+                cantica, canto, found, expected
+            ),
+            Deviation::ShortCanto {
+                cantica,
+                canto,
+                lines,
+            } => write!(f, "{} {} has only {} lines", cantica, canto, lines),
+            Deviation::EmptyVerse {
+                cantica,
+                canto,
+                line,
+            } => write!(f, "{} {}.{} is empty", cantica, canto, line),
+            Deviation::Misnumbered {
+                cantica,
+                canto,
+                position,
+                line,
+            } => write!(
+                f,
+                "{} {}: verse {} is numbered {}",
+                cantica, canto, position, line
+            ),
+            Deviation::TotalLines { found, expected } => {
+                write!(f, "The poem has {} lines, expected {}", found, expected)
+            }
+        }
+    }
+}
+
+/// Every way `commedia` differs from the Commedia, cantica by cantica and
+/// then in the total; empty if it passes.
+pub fn verify(commedia: &DivinaCommedia) -> Vec<Deviation> {
+    let mut deviations = Vec::new();
+    let mut total = 0;
+
+    for (id, cantica) in commedia.canticas() {
+        let expected = CANTO_LINES[id as usize];
+        if cantica.cantos.len() != expected.len() {
+            deviations.push(Deviation::CantoCount {
+                cantica: id,
+                found: cantica.cantos.len(),
+                expected: expected.len(),
+            });
+        }
+        for number in (1..=expected.len() as u8).filter(|n| !cantica.cantos.contains_key(n)) {
+            deviations.push(Deviation::MissingCanto {
+                cantica: id,
+                canto: number,
+            });
+        }
+
+        for (&number, canto) in &cantica.cantos {
+            total += canto.verses.len();
+            match expected_lines(id, number) {
+                Some(expected) if expected != canto.verses.len() => {
+                    deviations.push(Deviation::LineCount {
+                        cantica: id,
+                        canto: number,
+                        found: canto.verses.len(),
+                        expected,
+                    })
+                }
+                Some(_) => {}
+                None => deviations.push(Deviation::UnexpectedCanto {
+                    cantica: id,
+                    canto: number,
+                }),
+            }
+            for (position, verse) in (1..).zip(&canto.verses) {
+                if verse.line_number != position {
+                    deviations.push(Deviation::Misnumbered {
+                        cantica: id,
+                        canto: number,
+                        position,
+                        line: verse.line_number,
+                    });
+                }
+                if verse.text.trim().is_empty() {
+                    deviations.push(Deviation::EmptyVerse {
+                        cantica: id,
+                        canto: number,
+                        line: position,
+                    });
+                }
+            }
+        }
+    }
+
+    if total != TOTAL_LINES {
+        deviations.push(Deviation::TotalLines {
+            found: total,
+            expected: TOTAL_LINES,
+        });
+    }
+    deviations
+}
+
+/// The deviations that betray a damaged source in any edition, not just
+/// one with the Gutenberg text's line counts: cantos missing before a
+/// cantica's last, and cantos shorter than [`SHORT_CANTO`].
+pub fn suspicious(commedia: &DivinaCommedia) -> Vec<Deviation> {
+    let mut deviations = Vec::new();
+    for (id, cantica) in commedia.canticas() {
+        let last = cantica.cantos.keys().last().copied().unwrap_or(0);
+        for number in (1..last).filter(|n| !cantica.cantos.contains_key(n)) {
+            deviations.push(Deviation::MissingCanto {
+                cantica: id,
+                canto: number,
+            });
+        }
+        for (&number, canto) in &cantica.cantos {
+            if canto.verses.len() < SHORT_CANTO {
+                deviations.push(Deviation::ShortCanto {
+                    cantica: id,
+                    canto: number,
+                    lines: canto.verses.len(),
+                });
+            }
+        }
+    }
+    deviations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_commedia, Verse};
+
+    #[test]
+    fn test_builtin_corpus_verifies() {
+        let commedia = load_commedia().unwrap();
+        assert_eq!(verify(&commedia), Vec::new());
+        assert_eq!(
+            CANTO_LINES.iter().flat_map(|c| c.iter()).sum::<usize>(),
+            TOTAL_LINES
+        );
+    }
+
+    #[test]
+    fn test_deviations() {
+        let mut commedia = load_commedia().unwrap();
+        let inferno = &mut commedia.inferno.cantos;
+        inferno.remove(&34);
+        let canto = inferno.get_mut(&1).unwrap();
+        canto.verses[2].text.clear();
+        canto.verses.push(Verse {
+            line_number: 200,
+            text: "Inferno".to_string(),
+        });
+
+        let deviations = verify(&commedia);
+        let messages: Vec<String> = deviations.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Inferno has 33 cantos, expected 34",
+                "Inferno 34 is missing",
+                "Inferno 1 has 137 lines, expected 136",
+                "Inferno 1.3 is empty",
+                "Inferno 1: verse 137 is numbered 200",
+                "The poem has 14095 lines, expected 14233",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suspicious() {
+        let mut commedia = load_commedia().unwrap();
+        assert_eq!(suspicious(&commedia), Vec::new());
+
+        let purgatorio = &mut commedia.purgatorio.cantos;
+        purgatorio.remove(&2);
+        purgatorio.get_mut(&5).unwrap().verses.truncate(40);
+        commedia.paradiso.cantos.remove(&33);
+        let messages: Vec<String> = suspicious(&commedia)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            ["Purgatorio 2 is missing", "Purgatorio 5 has only 40 lines"]
+        );
+    }
+}
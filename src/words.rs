@@ -0,0 +1,440 @@
+//! A word → verse inverted index, built by `duca parse` and stored in
+//! `commedia.bin`, so literal searches visit only the verses that can match,
+//! with the [lemma index](LemmaIndex) of its words for `search --lemma`.
+//! Its words, in order, are the vocabulary the TUI completes the search box
+//! from and a search that finds nothing suggests spellings from.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range};
+use std::sync::OnceLock;
+
+use crate::lemma::LemmaIndex;
+use crate::{normalize, CanticaId, DivinaCommedia, DucaError};
+
+/// A verse containing a word. Stored packed into a `u32` to keep the
+/// hundred thousand or so postings small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "u32", try_from = "u32")]
+pub struct Posting {
+    pub cantica: CanticaId,
+    pub canto: u8,
+    pub line: u16,
+}
+
+impl From<Posting> for u32 {
+    fn from(p: Posting) -> Self {
+        (p.cantica.index() as u32) << 24 | u32::from(p.canto) << 16 | u32::from(p.line)
+    }
+}
+
+impl TryFrom<u32> for Posting {
+    type Error = DucaError;
+
+    fn try_from(packed: u32) -> Result<Self, Self::Error> {
+        let cantica = CanticaId::ALL
+            .get((packed >> 24) as usize)
+            .copied()
+            .ok_or_else(|| DucaError::DataLoad("corrupt word index".to_string()))?;
+        Ok(Self {
+            cantica,
+            canto: (packed >> 16) as u8,
+            line: packed as u16,
+        })
+    }
+}
+
+/// Every folded word of the poem with the verses it appears in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordIndex {
+    words: BTreeMap<String, Vec<Posting>>,
+    lemmas: LemmaIndex,
+    /// The words' suffixes in sorted order, built on the first search
+    /// [`containing`](Self::containing) a needle.
+    #[serde(skip)]
+    suffixes: OnceLock<Suffixes>,
+}
+
+/// Every suffix of every word of the index, sorted, so the words containing
+/// a needle are those with a suffix in the run starting with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Suffixes {
+    /// The index's words, in order.
+    words: Vec<String>,
+    /// (index into `words`, byte offset of the suffix) for each suffix.
+    suffixes: Vec<(u32, u16)>,
+}
+
+impl Suffixes {
+    fn build<'a>(words: impl Iterator<Item = &'a str>) -> Self {
+        let words: Vec<String> = words.map(str::to_string).collect();
+        let mut suffixes: Vec<(u32, u16)> = words
+            .iter()
+            .enumerate()
+            .flat_map(|(w, word)| {
+                word.char_indices()
+                    .map(move |(offset, _)| (w as u32, offset as u16))
+            })
+            .collect();
+        suffixes.sort_unstable_by(|&a, &b| {
+            let suffix = |(w, offset): (u32, u16)| &words[w as usize][usize::from(offset)..];
+            suffix(a).cmp(suffix(b))
+        });
+        Self { words, suffixes }
+    }
+
+    fn suffix(&self, (w, offset): (u32, u16)) -> &str {
+        &self.words[w as usize][usize::from(offset)..]
+    }
+
+    /// The words containing `needle`, each once.
+    fn containing(&self, needle: &str) -> impl Iterator<Item = &str> {
+        let start = self.suffixes.partition_point(|&s| self.suffix(s) < needle);
+        let len = self.suffixes[start..].partition_point(|&s| self.suffix(s).starts_with(needle));
+        let mut words: Vec<u32> = self.suffixes[start..start + len]
+            .iter()
+            .map(|&(w, _)| w)
+            .collect();
+        words.sort_unstable();
+        words.dedup();
+        words.into_iter().map(|w| self.words[w as usize].as_str())
+    }
+}
+
+impl WordIndex {
+    /// Index every verse of `commedia` by its folded words. A word broken
+    /// with a hyphen at the end of a verse is indexed whole, under that verse.
+    pub fn build(commedia: &DivinaCommedia) -> Self {
+        let mut words: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+        for (cantica, canto) in commedia.cantos() {
+            for (i, verse) in canto.verses.iter().enumerate() {
+                let posting = Posting {
+                    cantica,
+                    canto: canto.number,
+                    line: verse.line_number as u16,
+                };
+                let text = canto
+                    .verses
+                    .get(i + 1)
+                    .and_then(|next| normalize::join_hyphenated(&verse.text, &next.text))
+                    .map_or_else(|| verse.text.clone(), |(joined, _)| joined);
+                for word in tokens(&normalize::fold(&text)) {
+                    let postings = words.entry(word.to_string()).or_default();
+                    if postings.last() != Some(&posting) {
+                        postings.push(posting);
+                    }
+                }
+            }
+        }
+        let lemmas = LemmaIndex::build(words.keys().map(String::as_str));
+        Self {
+            words,
+            lemmas,
+            suffixes: OnceLock::new(),
+        }
+    }
+
+    /// Whether the index can answer a search for `needle`: a match of a
+    /// needle made only of letters and digits never spans two words.
+    pub fn covers(needle: &str) -> bool {
+        !needle.is_empty() && needle.chars().all(char::is_alphanumeric)
+    }
+
+    /// Verses with a word exactly equal to the folded `word`.
+    pub fn lookup(&self, word: &str) -> &[Posting] {
+        self.words.get(word).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The words of the index grouped by their stems.
+    pub fn lemmas(&self) -> &LemmaIndex {
+        &self.lemmas
+    }
+
+    /// Verses with a word sharing the stem of `word`, in reading order.
+    pub fn with_lemma(&self, word: &str) -> Vec<Posting> {
+        self.with_stem(&crate::lemma::lemma(word))
+    }
+
+    /// Verses with a word whose stem is `stem`, in reading order.
+    pub fn with_stem(&self, stem: &str) -> Vec<Posting> {
+        let mut postings: Vec<Posting> = self
+            .lemmas
+            .stem_forms(stem)
+            .iter()
+            .flat_map(|form| self.lookup(form).iter().copied())
+            .collect();
+        postings.sort_unstable();
+        postings.dedup();
+        postings
+    }
+
+    /// Verses with a word containing the folded `needle`, in reading order,
+    /// found by binary search of the words' sorted suffixes.
+    pub fn containing(&self, needle: &str) -> Vec<Posting> {
+        let suffixes = self
+            .suffixes
+            .get_or_init(|| Suffixes::build(self.words.keys().map(String::as_str)));
+        let mut postings: Vec<Posting> = suffixes
+            .containing(needle)
+            .flat_map(|word| self.lookup(word).iter().copied())
+            .collect();
+        postings.sort_unstable();
+        postings.dedup();
+        postings
+    }
+
+    /// Up to `limit` words of the poem longer than the folded `prefix` and
+    /// starting with it, commonest first and ties in alphabetical order.
+    pub fn completions(&self, prefix: &str, limit: usize) -> Vec<&str> {
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let mut completions: Vec<(&String, usize)> = self
+            .words
+            .range::<str, _>((Bound::Excluded(prefix), Bound::Unbounded))
+            .take_while(|(word, _)| word.starts_with(prefix))
+            .map(|(word, postings)| (word, postings.len()))
+            .collect();
+        completions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        completions
+            .into_iter()
+            .take(limit)
+            .map(|(word, _)| word.as_str())
+            .collect()
+    }
+
+    /// Words of the poem within a small edit distance of the folded
+    /// `word`, nearest and then commonest first: one edit for a word of up
+    /// to four letters, two for a longer one. Empty if the poem has `word`.
+    pub fn near(&self, word: &str) -> Vec<&str> {
+        if self.words.contains_key(word) {
+            return Vec::new();
+        }
+        let length = word.chars().count();
+        let most = if length <= 4 { 1 } else { 2 };
+        let mut near: Vec<(usize, usize, &str)> = self
+            .words
+            .iter()
+            .filter(|(other, _)| other.chars().count().abs_diff(length) <= most)
+            .map(|(other, postings)| (edit_distance(word, other), postings.len(), other.as_str()))
+            .filter(|(distance, _, _)| *distance <= most)
+            .collect();
+        near.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        near.into_iter().map(|(_, _, other)| other).collect()
+    }
+
+    /// `pattern` with each word the poem doesn't have replaced by the
+    /// [nearest](Self::near) word it does, folded: `selva oscurra` gives
+    /// `selva oscura`. None when every word is the poem's, or one is
+    /// nowhere near any.
+    pub fn suggest(&self, pattern: &str) -> Option<String> {
+        let mut suggestion = String::new();
+        let mut last = 0;
+        for range in word_ranges(pattern) {
+            let word = normalize::fold(&pattern[range.clone()]);
+            if self.words.contains_key(&word) {
+                continue;
+            }
+            let nearest = *self.near(&word).first()?;
+            suggestion.push_str(&pattern[last..range.start]);
+            suggestion.push_str(nearest);
+            last = range.end;
+        }
+        if last == 0 {
+            return None;
+        }
+        suggestion.push_str(&pattern[last..]);
+        Some(suggestion)
+    }
+
+    /// Number of distinct words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Byte ranges of the words of `text`, split the same way the index splits
+/// them.
+pub fn word_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                ranges.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push(s..text.len());
+    }
+    ranges
+}
+
+/// The fewest letters inserted, deleted or substituted to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Split folded text into words at anything that isn't a letter or digit.
+pub(crate) fn tokens(folded: &str) -> impl Iterator<Item = &str> {
+    folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Canto, Verse};
+
+    fn sample() -> DivinaCommedia {
+        let mut commedia = DivinaCommedia::new();
+        let verses = ["Nel mezzo del cammin di nostra vita", "ché la diritta via"]
+            .iter()
+            .zip(1..)
+            .map(|(text, line_number)| Verse {
+                line_number,
+                text: text.to_string(),
+            })
+            .collect();
+        commedia
+            .inferno
+            .cantos
+            .insert(1, Canto::new(CanticaId::Inferno, 1, verses));
+        commedia
+    }
+
+    #[test]
+    fn test_lookup() {
+        let index = WordIndex::build(&sample());
+        let posting = Posting {
+            cantica: CanticaId::Inferno,
+            canto: 1,
+            line: 2,
+        };
+        assert_eq!(index.lookup("che"), [posting]);
+        assert_eq!(index.lookup("via"), [posting]);
+        assert!(index.lookup("ché").is_empty());
+        assert!(index.lookup("selva").is_empty());
+    }
+
+    #[test]
+    fn test_with_lemma() {
+        let index = WordIndex::build(&sample());
+        assert_eq!(index.lemmas().forms("vite"), ["vita"]);
+        let lines: Vec<u16> = index.with_lemma("vite").iter().map(|p| p.line).collect();
+        assert_eq!(lines, [1]);
+        assert!(index.with_lemma("selve").is_empty());
+    }
+
+    #[test]
+    fn test_containing() {
+        let index = WordIndex::build(&sample());
+        let lines: Vec<u16> = index.containing("vi").iter().map(|p| p.line).collect();
+        assert_eq!(lines, [1, 2]);
+        assert!(index.containing("xyz").is_empty());
+        // Inside a word as well as at either end
+        let lines: Vec<u16> = index.containing("zz").iter().map(|p| p.line).collect();
+        assert_eq!(lines, [1]);
+        let lines: Vec<u16> = index.containing("tta").iter().map(|p| p.line).collect();
+        assert_eq!(lines, [2]);
+        // Every suffix search agrees with a scan of the words
+        let commedia = crate::commedia().unwrap();
+        let index = commedia.word_index().unwrap();
+        for needle in ["amor", "mor", "zz", "ù", "e", "ggi", "xyz"] {
+            let mut scanned: Vec<Posting> = index
+                .words
+                .iter()
+                .filter(|(word, _)| word.contains(needle))
+                .flat_map(|(_, postings)| postings.iter().copied())
+                .collect();
+            scanned.sort_unstable();
+            scanned.dedup();
+            assert_eq!(index.containing(needle), scanned, "{}", needle);
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let index = WordIndex::build(&sample());
+        assert_eq!(index.near("camin"), ["cammin"]);
+        // Nearest first
+        assert_eq!(index.near("vitta"), ["vita", "via"]);
+        assert!(index.near("vita").is_empty());
+        // One edit at most for a short word
+        assert!(index.near("dsl").contains(&"del"));
+        assert!(index.near("dxx").is_empty());
+        assert_eq!(
+            index.suggest("Nel mezo del camin").as_deref(),
+            Some("Nel mezzo del cammin")
+        );
+        assert_eq!(index.suggest("nostra vita"), None);
+        assert_eq!(index.suggest("nostra selva"), None);
+    }
+
+    #[test]
+    fn test_completions() {
+        let index = WordIndex::build(&sample());
+        assert_eq!(index.completions("n", 5), ["nel", "nostra"]);
+        assert_eq!(index.completions("vi", 5), ["via", "vita"]);
+        assert_eq!(index.completions("vi", 1), ["via"]);
+        // The word itself is no completion
+        assert!(index.completions("via", 5).is_empty());
+        assert!(index.completions("", 5).is_empty());
+        assert!(index.completions("z", 5).is_empty());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("smaritta", "smarrita"), 2);
+        assert_eq!(edit_distance("selva", "selva"), 0);
+        assert_eq!(edit_distance("pieta", "pietà"), 1);
+        assert_eq!(edit_distance("", "via"), 3);
+    }
+
+    #[test]
+    fn test_posting_packing() {
+        let posting = Posting {
+            cantica: CanticaId::Paradiso,
+            canto: 33,
+            line: 145,
+        };
+        assert_eq!(Posting::try_from(u32::from(posting)), Ok(posting));
+        assert!(Posting::try_from(3 << 24).is_err());
+    }
+
+    #[test]
+    fn test_word_ranges() {
+        let text = "ch’i’ fui, più che pria";
+        let words: Vec<&str> = word_ranges(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(words, ["ch", "i", "fui", "più", "che", "pria"]);
+        assert!(word_ranges("« »").is_empty());
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(WordIndex::covers("selva"));
+        assert!(!WordIndex::covers("selva oscura"));
+        assert!(!WordIndex::covers("ch'io"));
+        assert!(!WordIndex::covers(""));
+    }
+}
@@ -0,0 +1,307 @@
+//! A library of works: the Commedia, and texts the user adds beside it such
+//! as the Vita Nuova.
+//!
+//! A work is divided into named parts (the Commedia's canticas), each a run
+//! of numbered sections (its cantos) of lines. User works are directories in
+//! [`paths::works_dir`] holding a `work.toml` that names the work and its
+//! parts, with one plain-text file per part divided into sections as
+//! [`ParseSpec`] describes, by the defaults or by a `spec.toml` beside it:
+//!
+//! ```toml
+//! title = "Vita Nuova"
+//! author = "Dante Alighieri"
+//!
+//! [[part]]
+//! name = "Vita Nuova"
+//! file = "vita-nuova.txt"
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::{
+    normalize, parse_sections_from, paths, Canto, DivinaCommedia, DucaError, Matcher, ParseSpec,
+    Pipeline,
+};
+
+/// The work duca reads unless told otherwise.
+pub const COMMEDIA: &str = "commedia";
+
+/// A work in the library.
+#[derive(Debug, Clone)]
+pub struct Work {
+    /// The name the work is selected by, e.g. `vita-nuova`.
+    pub id: String,
+    pub title: String,
+    pub author: String,
+    pub parts: Vec<Part>,
+}
+
+/// A named part of a work and its sections, in order.
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub name: String,
+    pub sections: BTreeMap<u8, Canto>,
+}
+
+/// A line matched by [`Work::search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkMatch<'a> {
+    pub part: &'a str,
+    pub section: u8,
+    pub line: usize,
+    pub text: &'a str,
+    /// Byte ranges of each pattern match within `text`, for highlighting.
+    pub spans: Vec<std::ops::Range<usize>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkFile {
+    title: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default, alias = "parts")]
+    part: Vec<RawPart>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPart {
+    name: String,
+    file: String,
+}
+
+impl Work {
+    /// The Commedia as a work, its canticas as parts.
+    pub fn commedia(commedia: &DivinaCommedia) -> Self {
+        Self {
+            id: COMMEDIA.to_string(),
+            title: "Divina Commedia".to_string(),
+            author: "Dante Alighieri".to_string(),
+            parts: commedia
+                .canticas()
+                .map(|(id, cantica)| Part {
+                    name: id.name().to_string(),
+                    sections: cantica.cantos.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Read the work in `dir` under the name `id`.
+    pub fn load_dir(dir: &Path, id: &str) -> Result<Self, DucaError> {
+        let invalid = |path: &Path, reason: String| DucaError::UserData {
+            path: path.display().to_string(),
+            reason,
+        };
+        let read = |path: &Path| fs::read_to_string(path).map_err(|e| invalid(path, e.to_string()));
+
+        let manifest = dir.join("work.toml");
+        let file: WorkFile = toml::from_str(&read(&manifest)?)
+            .map_err(|e| invalid(&manifest, e.message().to_string()))?;
+        let spec = ParseSpec::load(dir)?;
+
+        let mut parts = Vec::new();
+        for raw in file.part {
+            let path = dir.join(&raw.file);
+            let text = fs::File::open(&path).map_err(|e| invalid(&path, e.to_string()))?;
+            let sections = parse_sections_from(BufReader::new(text), &spec)
+                .map_err(|e| match e {
+                    DucaError::DataLoad(reason) => invalid(&path, reason),
+                    e => e,
+                })?
+                .into_iter()
+                .map(|(number, verses)| (number, Canto::section(&raw.name, number, verses)))
+                .collect();
+            parts.push(Part {
+                name: raw.name,
+                sections,
+            });
+        }
+        Ok(Self {
+            id: id.to_string(),
+            title: file.title,
+            author: file.author,
+            parts,
+        })
+    }
+
+    /// How many sections the work has across its parts.
+    pub fn section_count(&self) -> usize {
+        self.parts.iter().map(|p| p.sections.len()).sum()
+    }
+
+    /// How many lines the work has.
+    pub fn line_count(&self) -> usize {
+        self.parts
+            .iter()
+            .flat_map(|p| p.sections.values())
+            .map(|s| s.verses.len())
+            .sum()
+    }
+
+    /// The part called `name`, ignoring case and accents; a prefix will do
+    /// when it names just one part.
+    pub fn part(&self, name: &str) -> Option<&Part> {
+        let name = normalize::fold(name.trim());
+        if let Some(part) = self.parts.iter().find(|p| normalize::fold(&p.name) == name) {
+            return Some(part);
+        }
+        let mut prefixed = self
+            .parts
+            .iter()
+            .filter(|p| !name.is_empty() && normalize::fold(&p.name).starts_with(&name));
+        match (prefixed.next(), prefixed.next()) {
+            (Some(part), None) => Some(part),
+            _ => None,
+        }
+    }
+
+    /// Lines matching `pattern` in reading order, within one part if given.
+    /// Matching works as in [`DivinaCommedia::search`], without speakers.
+    pub fn search(&self, pattern: &str, part: Option<&Part>) -> Vec<WorkMatch<'_>> {
+        self.search_with(pattern, part, &Pipeline::default())
+            .expect("a plain pattern always compiles")
+    }
+
+    /// [`Work::search`] through `pipeline`, as in
+    /// [`DivinaCommedia::search_with`].
+    pub fn search_with(
+        &self,
+        pattern: &str,
+        part: Option<&Part>,
+        pipeline: &Pipeline,
+    ) -> Result<Vec<WorkMatch<'_>>, DucaError> {
+        let matcher = Matcher::new(pattern, pipeline)?;
+        let mut scratch = String::new();
+        let mut results = Vec::new();
+        for p in self
+            .parts
+            .iter()
+            .filter(|p| part.is_none_or(|part| part.name == p.name))
+        {
+            for section in p.sections.values() {
+                for (i, verse) in section.verses.iter().enumerate() {
+                    let folded = section.normalized.get(i).map(String::as_str);
+                    let spans = matcher.find(&verse.text, folded, &mut scratch);
+                    if !spans.is_empty() {
+                        results.push(WorkMatch {
+                            part: &p.name,
+                            section: section.number,
+                            line: verse.line_number,
+                            text: &verse.text,
+                            spans,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// The names of the works in the library, the Commedia first and the user's
+/// own in name order.
+pub fn available() -> Vec<String> {
+    let mut user: Vec<String> = paths::works_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("work.toml").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != COMMEDIA)
+        .collect();
+    user.sort();
+
+    std::iter::once(COMMEDIA.to_string()).chain(user).collect()
+}
+
+/// Load a work by name. The Commedia comes from the selected edition.
+pub fn load(id: &str) -> Result<Work, DucaError> {
+    if id == COMMEDIA {
+        return Ok(Work::commedia(crate::commedia()?));
+    }
+    match paths::works_dir().map(|dir| dir.join(id)) {
+        Some(dir) if dir.join("work.toml").is_file() => Work::load_dir(&dir, id),
+        _ => Err(DucaError::UnknownWork {
+            name: id.to_string(),
+            available: available(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CanticaId;
+
+    /// A small Vita Nuova read from a directory named after `test`.
+    fn sample_work(test: &str) -> Work {
+        let dir = std::env::temp_dir().join(format!("duca-work-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("work.toml"),
+            "title = \"Vita Nuova\"\n\n[[part]]\nname = \"Vita Nuova\"\nfile = \"vn.txt\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("spec.toml"), r"section = '^([IVXL]+)\.$'").unwrap();
+        fs::write(
+            dir.join("vn.txt"),
+            "LA VITA NUOVA\n\nI.\nIn quella parte del libro de la mia memoria\n\nII.\nNove fiate già appresso lo mio nascimento\ntornato lo cielo de la luce\n",
+        )
+        .unwrap();
+        let work = Work::load_dir(&dir, "vita-nuova").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        work
+    }
+
+    #[test]
+    fn test_load_work() {
+        let work = sample_work("load");
+        assert_eq!(work.title, "Vita Nuova");
+        let part = work.part("vita").unwrap();
+        assert_eq!(part.sections.len(), 2);
+        let section = &part.sections[&2];
+        assert_eq!(section.title, "Vita Nuova II");
+        assert_eq!(
+            section.verse(2).unwrap().text,
+            "tornato lo cielo de la luce"
+        );
+    }
+
+    #[test]
+    fn test_search_work() {
+        let work = sample_work("search");
+        let matches = work.search("nascimento", None);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            (matches[0].part, matches[0].section, matches[0].line),
+            ("Vita Nuova", 2, 1)
+        );
+        assert_eq!(&matches[0].text[matches[0].spans[0].clone()], "nascimento");
+    }
+
+    #[test]
+    fn test_commedia_work() {
+        let work = load(COMMEDIA).unwrap();
+        assert_eq!(work.parts.len(), 3);
+        let inferno = work.part("inf").unwrap();
+        assert_eq!(inferno.name, CanticaId::Inferno.name());
+        assert_eq!(inferno.sections.len(), 34);
+        // "Pa" could be either of two parts
+        assert!(work.part("p").is_none());
+        assert!(work.part("Paradiso").is_some());
+    }
+
+    #[test]
+    fn test_unknown_work() {
+        let error = load("no-such-work").unwrap_err();
+        assert!(error.to_string().contains("commedia"));
+        assert_eq!(error.exit_code(), 2);
+    }
+}
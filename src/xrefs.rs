@@ -0,0 +1,185 @@
+//! Cross-references between passages of the poem: prophecies and where
+//! they're taken up, images it returns to and words it repeats, from a
+//! curated list compiled into duca. Each is listed once and shown from
+//! both of its passages.
+
+use serde::Deserialize;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{CanticaId, DucaError, Passage};
+
+/// The cross-references compiled into duca.
+const BUILTIN: &str = include_str!("../xrefs/xrefs.toml");
+
+/// How two passages bear on each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A prophecy and where it's taken up or fulfilled.
+    Prophecy,
+    /// An image the poem returns to.
+    Imagery,
+    /// Words the poem repeats.
+    Echo,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Prophecy => "prophecy",
+            Kind::Imagery => "imagery",
+            Kind::Echo => "echo",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Two passages that bear on each other, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossReference {
+    pub kind: Kind,
+    pub passage: Passage,
+    pub related: Passage,
+    pub note: String,
+}
+
+/// A cross-reference seen from one of its passages, `here`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Related<'a> {
+    pub kind: Kind,
+    pub here: &'a Passage,
+    pub there: &'a Passage,
+    pub note: &'a str,
+}
+
+#[derive(Deserialize)]
+struct XrefsFile {
+    #[serde(default)]
+    xref: Vec<RawXref>,
+}
+
+#[derive(Deserialize)]
+struct RawXref {
+    kind: Kind,
+    passage: String,
+    related: String,
+    note: String,
+}
+
+/// Parse a list of cross-references; `name` identifies it in errors.
+pub fn parse(content: &str, name: &str) -> Result<Vec<CrossReference>, DucaError> {
+    let invalid = |reason: String| DucaError::UserData {
+        path: name.to_string(),
+        reason,
+    };
+    let file: XrefsFile = toml::from_str(content).map_err(|e| invalid(e.message().to_string()))?;
+    file.xref
+        .into_iter()
+        .map(|raw| {
+            let passage = |text: &str| {
+                text.parse::<Passage>()
+                    .map_err(|e: DucaError| invalid(e.to_string()))
+            };
+            Ok(CrossReference {
+                kind: raw.kind,
+                passage: passage(&raw.passage)?,
+                related: passage(&raw.related)?,
+                note: raw.note,
+            })
+        })
+        .collect()
+}
+
+/// The built-in cross-references, in the order they are listed.
+pub fn builtin() -> &'static [CrossReference] {
+    static XREFS: OnceLock<Vec<CrossReference>> = OnceLock::new();
+    XREFS.get_or_init(|| parse(BUILTIN, "xrefs.toml").expect("built-in cross-references are valid"))
+}
+
+/// The first line of `passage`, 1 for a whole canto.
+pub fn first_line(passage: &Passage) -> usize {
+    passage.lines.as_ref().map_or(1, |lines| *lines.start())
+}
+
+/// The cross-references of `xrefs` with a passage in the given canto, seen
+/// from there, by the line they start on and then in reading order.
+pub fn for_canto(xrefs: &[CrossReference], cantica: CanticaId, canto: u8) -> Vec<Related<'_>> {
+    let in_canto = |passage: &Passage| passage.cantica == cantica && passage.canto == canto;
+    let mut related: Vec<Related> = xrefs
+        .iter()
+        .flat_map(|xref| {
+            let forward = in_canto(&xref.passage).then_some(Related {
+                kind: xref.kind,
+                here: &xref.passage,
+                there: &xref.related,
+                note: &xref.note,
+            });
+            let back = in_canto(&xref.related).then_some(Related {
+                kind: xref.kind,
+                here: &xref.related,
+                there: &xref.passage,
+                note: &xref.note,
+            });
+            forward.into_iter().chain(back)
+        })
+        .collect();
+    related.sort_by_key(|r| {
+        let there = (r.there.cantica, r.there.canto, first_line(r.there));
+        (first_line(r.here), there)
+    });
+    related
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_xrefs() {
+        let commedia = crate::commedia().unwrap();
+        for xref in builtin() {
+            for passage in [&xref.passage, &xref.related] {
+                let canto = commedia.canto(passage.cantica, passage.canto).unwrap();
+                if let Some(lines) = &passage.lines {
+                    assert!(canto.verse(*lines.end()).is_some(), "{}", passage);
+                }
+            }
+        }
+
+        let inferno = for_canto(builtin(), CanticaId::Inferno, 1);
+        assert_eq!(inferno[0].here.to_string(), "Inferno 1.1-3");
+        assert!(inferno
+            .windows(2)
+            .all(|w| first_line(w[0].here) <= first_line(w[1].here)));
+        // Seen from the other end too
+        let paradiso = for_canto(builtin(), CanticaId::Paradiso, 17);
+        assert!(paradiso
+            .iter()
+            .any(|r| r.there.to_string() == "Inferno 6.64-72" && r.kind == Kind::Prophecy));
+        assert!(for_canto(builtin(), CanticaId::Purgatorio, 2).is_empty());
+    }
+
+    #[test]
+    fn test_parse_xrefs() {
+        let xrefs = parse(
+            "[[xref]]\nkind = \"echo\"\npassage = \"Inferno 3.136\"\n\
+             related = \"Inferno 5\"\nnote = \"Falling\"\n",
+            "test.toml",
+        )
+        .unwrap();
+        assert_eq!(xrefs[0].related.lines, None);
+        assert_eq!(
+            for_canto(&xrefs, CanticaId::Inferno, 5)[0].here.to_string(),
+            "Inferno 5"
+        );
+
+        let bad = "[[xref]]\nkind = \"rhyme\"\npassage = \"Inferno 1\"\nrelated = \"Inferno 2\"\nnote = \"\"\n";
+        assert!(parse(bad, "test.toml").is_err());
+    }
+}
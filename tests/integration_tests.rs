@@ -1,26 +1,33 @@
-use std::process::Command;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
+use std::process::Command;
 
 #[test]
 fn test_cli_help_command() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
     cmd.arg("--help");
-    
-    cmd.assert()
+
+    let assert = cmd
+        .assert()
         .success()
-        .stdout(predicate::str::contains("Read Dante's Divine Comedy from your terminal"))
+        .stdout(predicate::str::contains(
+            "Read Dante's Divine Comedy from your terminal",
+        ))
         .stdout(predicate::str::contains("search"))
         .stdout(predicate::str::contains("canto"))
-        .stdout(predicate::str::contains("tui"))
         .stdout(predicate::str::contains("parse"));
+
+    #[cfg(feature = "tui")]
+    assert.stdout(predicate::str::contains("tui"));
+    #[cfg(not(feature = "tui"))]
+    assert.stdout(predicate::str::contains(" tui ").not());
 }
 
 #[test]
 fn test_cli_search_command() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "stelle"]);
-    
+    cmd.args(["search", "stelle"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Found"))
@@ -31,8 +38,8 @@ fn test_cli_search_command() {
 #[test]
 fn test_cli_search_with_cantica_filter() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "stelle", "-c", "inferno"]);
-    
+    cmd.args(["search", "stelle", "-c", "inferno"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("matches for 'stelle'"))
@@ -42,18 +49,681 @@ fn test_cli_search_with_cantica_filter() {
 #[test]
 fn test_cli_search_no_matches() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "xyznomatch123"]);
-    
+    cmd.args(["search", "xyznomatch123"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("No matches found"));
 }
 
+#[test]
+fn test_cli_search_suggestions() {
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "-f", "-"])
+        .write_stdin("smaritta\nselva oscurra\nxyznomatch123\n");
+    cmd.assert().success().stdout(
+        "No matches found for 'smaritta' — did you mean 'smarrita'?\n\n\
+         No matches found for 'selva oscurra' — did you mean 'selva oscura'?\n\n\
+         No matches found for 'xyznomatch123'\n",
+    );
+
+    // A regex gets no suggestion
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "--regex", "smaritt[ao]"]);
+    cmd.assert()
+        .success()
+        .stdout("No matches found for 'smaritt[ao]'\n");
+}
+
+#[test]
+fn test_cli_search_summary() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva"]);
+    cmd.assert().success().stdout(predicate::str::ends_with(
+        "\nInferno: 17, Purgatorio: 10, Paradiso: 0\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--summary-only", "-c", "purg"]);
+    cmd.assert().success().stdout("Purgatorio: 10\n");
+
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "-f", "-", "--summary-only"])
+        .write_stdin("selva\nxyznomatch123\n");
+    cmd.assert().success().stdout(
+        "'selva': Inferno: 17, Purgatorio: 10, Paradiso: 0\n\
+         'xyznomatch123': Inferno: 0, Purgatorio: 0, Paradiso: 0\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--summary-only", "--picker"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_cli_search_heatmap() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--heatmap", "-c", "purg"]);
+    cmd.assert().success().stdout(
+        "'selva': 10 matching verses, the most in Purgatorio 14 (2)\n\n\
+         Purgatorio  ·▒···▒······▒█·▒···········█···█·\n\
+         \x20           1        10        20        30\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "xyznomatch123", "--heatmap"]);
+    cmd.assert()
+        .success()
+        .stdout("No matches found for 'xyznomatch123'\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--heatmap", "--picker"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_cli_search_lemma() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "occhio", "--lemma", "-c", "inf", "--summary-only"]);
+    cmd.assert().success().stdout("Inferno: 67\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "occhio", "-c", "inf", "--summary-only"]);
+    cmd.assert().success().stdout("Inferno: 22\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "occhio bello", "--lemma", "-c", "purg"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Purgatorio 27.136: Mentre che vegnan lieti li occhi belli",
+    ));
+}
+
+#[test]
+fn test_cli_search_modern() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "voi che intrate", "--modern"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Inferno 3.9: Lasciate ogne speranza, voi ch’intrate",
+        ))
+        .stdout(predicate::str::contains(
+            "Inferno: 1, Purgatorio: 0, Paradiso: 0",
+        ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "voi che intrate"]);
+    cmd.assert()
+        .success()
+        .stdout("No matches found for 'voi che intrate'\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "dei raggio", "--modern", "--lemma", "-c", "inf"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Inferno 1.17: vestite già de’ raggi del pianeta",
+    ));
+}
+
+#[test]
+fn test_cli_search_normalize() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "Amor", "--normalize", "fold", "--summary-only"]);
+    cmd.assert()
+        .success()
+        .stdout("Inferno: 3, Purgatorio: 4, Paradiso: 3\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "pietà", "--normalize", "none", "-c", "inf"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Inferno 4.21:"))
+        .stdout(predicate::str::contains("Inferno 1.21:").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "amor", "--normalize", "stem"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Unknown normalizer 'stem'"));
+}
+
+#[test]
+fn test_cli_search_rank_bm25() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva oscura", "--rank", "bm25"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 27 matches for 'selva oscura':\n\nInferno 1.2: mi ritrovai per una selva oscura,\n",
+        ))
+        .stdout(predicate::str::contains("Inferno 21.6: e vidila mirabilmente oscura."));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "search",
+        "selva oscura",
+        "--rank",
+        "bm25",
+        "--explain",
+        "-c",
+        "inf",
+    ]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Inferno 1.2: mi ritrovai per una selva oscura,\n    \
+         score 14.82 (6 words, 0.84 of the average verse)\n      \
+         selva at word 5: idf 6.65 × frequency 1.07 × weight 1.0 = 7.11\n      \
+         oscura at word 6: idf 7.21 × frequency 1.07 × weight 1.0 = 7.72\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--explain"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--explain explains"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--rank", "best"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Unknown ranking 'best'"));
+}
+
+#[test]
+fn test_cli_compare_freq() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["compare-freq", "amore", "ODIO"]);
+    cmd.assert().success().stdout(
+        "Uses per 10,000 words of each cantica\n\n\
+         amore\n\
+         Inferno     ██████                           2.1 (7)\n\
+         Purgatorio  ██████████████████               5.9 (20)\n\
+         Paradiso    ██████████████████████████████   9.6 (32)\n\n\
+         ODIO\n\
+         Inferno     ███                              0.9 (3)\n\
+         Purgatorio  ██                               0.6 (2)\n\
+         Paradiso                                     0.0 (0)\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["compare-freq", "amor*"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Paradiso    ██████████████████████████████  26.3 (88)",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("compare-freq");
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_cli_italian() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_LANG", "it")
+        .args(["search", "selva", "-c", "purg"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Trovate 10 corrispondenze per 'selva':",
+        ))
+        .stdout(predicate::str::contains("Purgatorio: 10"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_LANG", "it").args(["canto", "paradiso", "34"]);
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("Canto 34 non trovato in Paradiso"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env_remove("DUCA_LANG")
+        .env("LC_ALL", "it_IT.UTF-8")
+        .arg("--help");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Mostra un canto"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_LANG", "en")
+        .env("LC_ALL", "it_IT.UTF-8")
+        .arg("--help");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Show specific canto"));
+}
+
+#[test]
+fn test_cli_accessible() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "--accessible",
+        "canto",
+        "inferno",
+        "1",
+        "--around",
+        "4",
+        "--context",
+        "1",
+        "--color",
+        "always",
+    ]);
+    cmd.assert().success().stdout(
+        "Inferno, canto I, lines 3 to 5 of 136.\n\n\
+         Line 3: ché la diritta via era smarrita.\n\
+         Line 4, cited: Ahi quanto a dir qual era è cosa dura\n\
+         Line 5: esta selva selvaggia e aspra e forte\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_ACCESSIBLE", "1")
+        .args(["search", "selva", "-c", "purg"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Match 1 of 10, Purgatorio canto 2 line 52: La turba",
+        ))
+        .stdout(predicate::str::ends_with(
+            "\nMatches by cantica: Purgatorio: 10\n",
+        ));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.args(["read", "Purgatorio 1"]).write_stdin("\nq\n");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Purgatorio, canto I, 136 lines.\nLines 1 to 3:\n",
+    ));
+}
+
+#[test]
+fn test_cli_read_aloud() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_TTS_CMD", "echo spoken:").args([
+        "read-aloud",
+        "inferno",
+        "1",
+        "--from",
+        "2",
+        "--to",
+        "3",
+        "--pause",
+        "0",
+    ]);
+    cmd.assert().success().stdout(
+        "  2: mi ritrovai per una selva oscura,\n\
+         spoken: mi ritrovai per una selva oscura,\n\
+         \x20 3: ché la diritta via era smarrita.\n\
+         spoken: ché la diritta via era smarrita.\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_TTS_CMD", "echo")
+        .args(["read-aloud", "inferno", "1", "--from", "130"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "136: Allor si mosse, e io li tenni dietro.\nAllor si mosse, e io li tenni dietro.\n",
+        ))
+        .stdout(predicate::str::contains("  1: ").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["read-aloud", "inferno", "1", "--to", "200"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("the canto has lines 1 to 136"));
+}
+
+#[test]
+fn test_cli_today() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_PLAN_START", "2025-01-01")
+        .args(["today", "--date", "2025-01-12"]);
+    cmd.assert()
+        .success()
+        .stdout("Day 12 of 100: Inferno XII\nEra lo loco ov’ a scender la riva\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["today", "--start", "2026-01-01", "--date", "2025-12-31"]);
+    cmd.assert()
+        .success()
+        .stdout("The reading plan starts on 2026-01-01\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_NOTIFY_CMD", "echo notified:").args([
+        "today",
+        "--start",
+        "2025-01-01",
+        "--date",
+        "2025-04-10",
+        "--notify",
+    ]);
+    cmd.assert().success().stdout(
+        "notified: Day 100 of 100: Paradiso XXXIII «Vergine Madre, figlia del tuo figlio,\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_NOTIFY_CMD", "duca-no-such-notifier")
+        .args(["today", "--notify"]);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "no notifier found: duca-no-such-notifier isn't installed",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_PLAN_START", "April").arg("today");
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid date 'April'"));
+}
+
+#[test]
+fn test_cli_plan() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["plan", "--start", "2025-01-01", "--date", "2025-06-01"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "2025-04-11    1  Inferno I         Nel mezzo del cammin di nostra vita\n",
+        ))
+        .stdout(predicate::str::contains(
+            "2025-07-19  100  Paradiso XXXIII   «Vergine Madre, figlia del tuo figlio,\n",
+        ));
+
+    let dir = std::env::temp_dir().join(format!("duca-cli-plan-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("feed.xml");
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "plan",
+        "--start",
+        "2025-01-01",
+        "--date",
+        "2025-01-03",
+        "--rss",
+    ])
+    .arg(&path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Wrote "));
+    let feed = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(feed.matches("<item>").count(), 3);
+    assert!(feed.contains("<title>Day 3 of 100: Inferno III</title>"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_notes() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-notes-{}", std::process::id()));
+    let duca = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_NOTES_DIR", &dir).args(args);
+        cmd.assert()
+    };
+
+    duca(&["note", "add", "Inferno 5.100-106", "Amor, Amor, Amor"])
+        .success()
+        .stdout(predicate::str::starts_with("Noted Inferno 5.100-106 in "));
+    duca(&[
+        "bookmark",
+        "add",
+        "Inferno 5.121",
+        "--label",
+        "Nessun maggior dolore",
+    ])
+    .success();
+    duca(&["bookmark", "add", "Inferno 5.82"]).success();
+    assert_eq!(
+        std::fs::read_to_string(dir.join("inferno/05.md")).unwrap(),
+        "# Inferno 5\n\n## Bookmarks\n\n- 82\n- 121: Nessun maggior dolore\n\n\
+         ## Notes\n\n### 100-106\n\nAmor, Amor, Amor\n"
+    );
+
+    duca(&["note", "list", "Inferno 5.103"])
+        .success()
+        .stdout("Inferno 5.100-106\n  Amor, Amor, Amor\n\n");
+    duca(&["note", "list", "Inferno 6"])
+        .success()
+        .stdout("No notes\n");
+    duca(&["bookmark", "list"]).success().stdout(
+        "Inferno 5.82       Quali colombe dal disio chiamate\n\
+         Inferno 5.121      Nessun maggior dolore\n",
+    );
+
+    // Tags, and bookmarks with all the tags asked for
+    duca(&["bookmark", "add", "Inferno 5.82", "--tag", "similes"]).success();
+    duca(&[
+        "bookmark",
+        "add",
+        "Inferno 5.121",
+        "--tag",
+        "#Exam",
+        "--tag",
+        "similes",
+    ])
+    .success();
+    duca(&["bookmark", "list", "--tag", "similes", "--tag", "exam"])
+        .success()
+        .stdout("Inferno 5.121      Nessun maggior dolore #exam #similes\n");
+    duca(&["bookmark", "remove", "Inferno 5.121", "--tag", "exam"]).success();
+    duca(&["bookmark", "list", "--tag", "exam"])
+        .success()
+        .stdout("No bookmarks tagged #exam\n");
+    duca(&["bookmark", "add", "Inferno 5.1", "--tag", "2nd"])
+        .code(1)
+        .stderr(predicate::str::contains("Invalid tag '2nd'"));
+
+    duca(&["note", "add", "Inferno 5", "no lines"])
+        .code(2)
+        .stderr(predicate::str::contains("name the lines"));
+    duca(&["bookmark", "remove", "Inferno 5.1"])
+        .code(1)
+        .stderr(predicate::str::contains("No bookmark on Inferno 5.1"));
+
+    duca(&["note", "remove", "Inferno 5.100-106"]).success();
+    duca(&["bookmark", "remove", "Inferno 5.82"]).success();
+    duca(&["bookmark", "remove", "Inferno 5.121"]).success();
+    assert!(!dir.join("inferno/05.md").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_data() {
+    let root = std::env::temp_dir().join(format!("duca-cli-data-{}", std::process::id()));
+    let (home, work) = (root.join("home"), root.join("work"));
+    let duca = |dir: &std::path::Path, args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_NOTES_DIR", dir)
+            .env("DUCA_READING_LOG", dir.join("reading-log.tsv"))
+            .args(args);
+        cmd.assert()
+    };
+
+    duca(&home, &["bookmark", "add", "Paradiso 33.145"]).success();
+    std::fs::write(
+        home.join("reading-log.tsv"),
+        "2025-01-12\t52\t2\tParadiso 33\n",
+    )
+    .unwrap();
+    duca(
+        &home,
+        &[
+            "note",
+            "add",
+            "Paradiso 33.142-145",
+            "Il poema finisce con stelle",
+        ],
+    )
+    .success();
+    duca(&work, &["note", "add", "Inferno 1.1", "Nel mezzo"]).success();
+    let backup = root.join("backup.json");
+    duca(
+        &home,
+        &["data", "export", "--out", backup.to_str().unwrap()],
+    )
+    .success()
+    .stdout(predicate::str::starts_with(
+        "Exported 1 bookmark(s), 1 note(s) and 1 reading session(s) to ",
+    ));
+
+    duca(&work, &["data", "import", backup.to_str().unwrap()])
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Imported 1 bookmark(s), 0 label(s), 0 tag(s), 1 note(s) and 1 reading session(s) from ",
+        ));
+    duca(&work, &["data", "import", backup.to_str().unwrap()])
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Imported 0 bookmark(s), 0 label(s), 0 tag(s), 0 note(s) and 0 reading session(s) from ",
+        ));
+    duca(&work, &["data", "export"])
+        .success()
+        .stdout(predicate::str::contains("\"text\": \"Nel mezzo\""))
+        .stdout(predicate::str::contains("\"line\": 145"))
+        .stdout(predicate::str::contains(
+            "2025-01-12\\t52\\t2\\tParadiso 33",
+        ));
+
+    std::fs::write(&backup, "{\"version\": 1, \"notes\": [{}]}").unwrap();
+    duca(&work, &["data", "import", backup.to_str().unwrap()])
+        .code(1)
+        .stderr(predicate::str::contains("Could not read"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_cli_export_org() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-org-{}", std::process::id()));
+    let duca = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_NOTES_DIR", &dir).args(args);
+        cmd.assert()
+    };
+
+    duca(&["bookmark", "add", "Inferno 1.2", "--tag", "selva"]).success();
+    duca(&["note", "add", "Inferno 1.1-3", "Il cammino comincia"]).success();
+    let output = duca(&["export", "org"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let org = String::from_utf8(output).unwrap();
+    assert!(org.starts_with(
+        "#+title: Divina Commedia: notes and bookmarks\n\
+         \n\
+         * Inferno I\n\
+         ** Inferno 1.2 :selva:\n\
+         #+begin_quote\n\
+         mi ritrovai per una selva oscura,\n\
+         #+end_quote\n\
+         ** Note on Inferno 1.1-3\n\
+         ["
+    ));
+    assert!(org.ends_with("]\nIl cammino comincia\n"));
+
+    let path = dir.join("commedia.org");
+    duca(&["export", "org", "--out", path.to_str().unwrap()])
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Exported the notes and bookmarks of 1 canto(s) to ",
+        ));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), org);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_export_obsidian() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-obsidian-{}", std::process::id()));
+    let vault = dir.join("vault");
+    let duca = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_NOTES_DIR", dir.join("notes")).args(args);
+        cmd.assert()
+    };
+
+    duca(&["note", "add", "Inferno 1.1-3", "Compare Purg. 1.1"]).success();
+    duca(&["export", "obsidian", vault.to_str().unwrap()])
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Exported 100 canto(s) and the notes of 1 to ",
+        ));
+    let canto = std::fs::read_to_string(vault.join("Inferno/Inferno 01.md")).unwrap();
+    assert!(canto.contains("\nNel mezzo del cammin di nostra vita ^line-1\n"));
+    let notes = std::fs::read_to_string(vault.join("Notes/Inferno 01 notes.md")).unwrap();
+    assert!(notes.contains("### [[Inferno 01#^line-1|Inferno 1.1-3]]\n"));
+    assert!(notes.contains("Compare [[Purgatorio 01#^line-1|Purg. 1.1]]"));
+    assert!(vault.join("Purgatorio/Purgatorio 01.md").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_state_dir() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-state-{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_STATE_DIR", &dir)
+        .env_remove("DUCA_NOTES_DIR")
+        .args(["bookmark", "add", "Purgatorio 1.1"]);
+    cmd.assert().success();
+    assert!(dir.join("notes/purgatorio/01.md").exists());
+    assert!(!dir.join("notes/.lock").exists(), "the lock is released");
+
+    // A sync service's newer conflicted copy wins over the file
+    let file = std::fs::File::options()
+        .write(true)
+        .open(dir.join("notes/purgatorio/01.md"))
+        .unwrap();
+    file.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(60))
+        .unwrap();
+    std::fs::write(
+        dir.join("notes/purgatorio/01 (conflicted copy 2025-01-02).md"),
+        "# Purgatorio 1\n\n## Bookmarks\n\n- 13: Dolce color d'oriental zaffiro\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_STATE_DIR", &dir)
+        .env_remove("DUCA_NOTES_DIR")
+        .args(["bookmark", "list"]);
+    cmd.assert()
+        .success()
+        .stdout("Purgatorio 1.13    Dolce color d'oriental zaffiro\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_cli_encrypted_notes() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-age-{}", std::process::id()));
+    let duca = |passphrase: &str, args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_NOTES_DIR", &dir)
+            .env("DUCA_NOTES_PASSPHRASE", passphrase)
+            .args(args);
+        cmd.assert()
+    };
+    duca(
+        "",
+        &["note", "add", "Inferno 3.9", "Lasciate ogne speranza"],
+    )
+    .success();
+    duca("caronte", &["note", "encrypt"])
+        .success()
+        .stdout(predicate::str::contains("Encrypted 1 canto file(s)"));
+    assert!(dir.join("inferno/03.md.age").exists());
+    assert!(!dir.join("inferno/03.md").exists());
+
+    duca("caronte", &["note", "list"])
+        .success()
+        .stdout(predicate::str::contains("Lasciate ogne speranza"));
+    duca("minosse", &["note", "list"])
+        .failure()
+        .stderr(predicate::str::contains("wrong passphrase"));
+
+    duca("caronte", &["note", "decrypt"]).success();
+    assert!(dir.join("inferno/03.md").exists());
+    duca("", &["note", "list"])
+        .success()
+        .stdout(predicate::str::contains("Lasciate ogne speranza"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn test_cli_canto_command() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "inferno", "1"]);
-    
+    cmd.args(["canto", "inferno", "1"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Inferno Canto I"))
@@ -63,28 +733,29 @@ fn test_cli_canto_command() {
 #[test]
 fn test_cli_invalid_cantica() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "invalid", "1"]);
-    
+    cmd.args(["canto", "invalid", "1"]);
+
     cmd.assert()
-        .success()
+        .code(2)
         .stderr(predicate::str::contains("Invalid cantica"));
 }
 
 #[test]
 fn test_cli_invalid_canto_number() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "inferno", "99"]);
-    
+    cmd.args(["canto", "inferno", "99"]);
+
     cmd.assert()
-        .success()
-        .stdout(predicate::str::contains("Canto 99 not found"));
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("Canto 99 not found in Inferno"));
 }
 
 #[test]
 fn test_cli_paradiso_canto() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "paradiso", "33"]);
-    
+    cmd.args(["canto", "paradiso", "33"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Paradiso Canto XXXIII"));
@@ -93,8 +764,8 @@ fn test_cli_paradiso_canto() {
 #[test]
 fn test_cli_purgatorio_canto() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "purgatorio", "1"]);
-    
+    cmd.args(["canto", "purgatorio", "1"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Purgatorio Canto I"));
@@ -103,8 +774,8 @@ fn test_cli_purgatorio_canto() {
 #[test]
 fn test_cli_search_case_insensitive() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "AMOR"]);
-    
+    cmd.args(["search", "AMOR"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("matches for 'AMOR'"));
@@ -113,8 +784,8 @@ fn test_cli_search_case_insensitive() {
 #[test]
 fn test_cli_search_special_characters() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "città"]);
-    
+    cmd.args(["search", "città"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("matches for 'città'"));
@@ -123,10 +794,10 @@ fn test_cli_search_special_characters() {
 #[test]
 fn test_cli_no_subcommand() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    
+
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("Usage: duca <COMMAND>"));
+        .stderr(predicate::str::contains("Usage: duca [OPTIONS] <COMMAND>"));
 }
 
 #[test]
@@ -134,17 +805,16 @@ fn test_cli_version_info() {
     // Test that the binary can be executed (basic smoke test)
     let mut cmd = Command::cargo_bin("duca").unwrap();
     cmd.arg("--help");
-    
-    cmd.assert()
-        .success();
+
+    cmd.assert().success();
 }
 
 #[test]
 fn test_cli_canto_number_boundary() {
     // Test that numbers > 255 are rejected by clap
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["canto", "inferno", "256"]);
-    
+    cmd.args(["canto", "inferno", "256"]);
+
     cmd.assert()
         .failure()
         .stderr(predicate::str::contains("256 is not in 0..=255"));
@@ -152,21 +822,1338 @@ fn test_cli_canto_number_boundary() {
 
 #[test]
 fn test_cli_search_with_regex_special_chars() {
-    // Test search with characters that could break regex
+    // Without --regex, regex syntax is plain text the poem doesn't have
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", ".*"]);
-    
-    // Should not crash, should handle regex escaping
+    cmd.args(["search", ".*"]);
     cmd.assert()
-        .success();
+        .success()
+        .stdout(predicate::str::starts_with("No matches found for '.*'"));
+}
+
+#[test]
+fn test_cli_search_regex() {
+    // Matched through the pipeline, so accents and case are ignored
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "search", "--regex", "--format", "quickfix", "PIETA\\b", "-c", "inferno",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("pietà"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "--regex", "selv("]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid regex 'selv('"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "--regex", "--rank", "bm25", "selv."]);
+    cmd.assert().failure();
 }
 
 #[test]
 fn test_cli_multiple_word_search() {
     let mut cmd = Command::cargo_bin("duca").unwrap();
-    cmd.args(&["search", "mezzo del"]);
-    
+    cmd.args(["search", "mezzo del"]);
+
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("mezzo del"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_cli_canto_plain() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "1", "--plain"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Nel mezzo del cammin di nostra vita\n",
+        ))
+        .stdout(predicate::str::contains("Inferno Canto I").not())
+        .stdout(predicate::str::contains("  1: ").not());
+}
+
+#[test]
+fn test_cli_no_pager_flag() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["--no-pager", "canto", "inferno", "1"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Inferno Canto I"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "stelle", "--no-pager"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("matches for 'stelle'"));
+}
+
+#[test]
+fn test_cli_color_modes() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--color", "always"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b["));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--color", "never"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+
+    // Output to a pipe is uncolored by default
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "1"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_cli_rpc_session() {
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.arg("rpc").write_stdin(concat!(
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "lookup", "params": {"cantica": "inferno", "canto": 1, "line": 1}}"#,
+        "\n",
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "search", "params": {"pattern": "selva oscura"}}"#,
+        "\n",
+    ));
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(r#""id":1"#))
+        .stdout(predicate::str::contains(
+            "Nel mezzo del cammin di nostra vita",
+        ))
+        .stdout(predicate::str::contains(r#""id":2"#))
+        .stdout(predicate::str::contains("selva oscura"));
+}
+
+#[test]
+fn test_cli_completions() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["completions", "bash"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("_duca()"))
+        .stdout(predicate::str::contains("purgatorio"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["completions", "fish"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("complete -c duca"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["completions", "fish", "--dynamic"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("COMPLETE=fish duca --"));
+
+    // The script calls duca back with the words typed so far
+    let dir = std::env::temp_dir().join(format!("duca-cli-completions-{}", std::process::id()));
+    let complete = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("COMPLETE", "fish")
+            .env("DUCA_NOTES_DIR", &dir)
+            .arg("--")
+            .args(args);
+        cmd.assert().success()
+    };
+    complete(&["duca", "canto", "purg", "3"]).stdout(
+        "3\tAvvegna che la subitana fuga\n\
+         30\tQuando il settentrïon del primo cielo,\n\
+         31\t«O tu che se’ di là dal fiume sacro»,\n\
+         32\tTant’ eran li occhi miei fissi e attenti\n\
+         33\t‘Deus, venerunt gentes’, alternando\n",
+    );
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_NOTES_DIR", &dir).args([
+        "bookmark",
+        "add",
+        "Inferno 5.121",
+        "--label",
+        "Nessun maggior dolore",
+        "--tag",
+        "sorrow",
+    ]);
+    cmd.assert().success();
+    complete(&["duca", "bookmark", "remove", "Inf"])
+        .stdout("Inferno 5.121\tNessun maggior dolore\n");
+    complete(&["duca", "bookmark", "list", "--tag", "s"]).stdout("sorrow\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_man_pages() {
+    let out_dir = std::env::temp_dir().join(format!("duca-man-{}", std::process::id()));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("man").arg(&out_dir);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("duca.1"));
+
+    let main_page = std::fs::read_to_string(out_dir.join("duca.1")).unwrap();
+    assert!(main_page.contains(".TH duca"));
+    assert!(out_dir.join("duca-canto.1").exists());
+    assert!(out_dir.join("duca-search.1").exists());
+    // Hidden commands don't get their own page
+    assert!(!out_dir.join("duca-man.1").exists());
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn test_cli_search_picker_formats() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva oscura", "--picker"]);
+
+    cmd.assert()
+        .success()
+        .stdout("Inferno 1.2: mi ritrovai per una selva oscura,\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva oscura", "--print0"]);
+
+    cmd.assert()
+        .success()
+        .stdout("Inferno 1.2: mi ritrovai per una selva oscura,\0");
+}
+
+#[test]
+fn test_cli_open_citation() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "open-citation",
+        "Inferno 1.2: mi ritrovai per una selva oscura,",
+        "--context",
+        "1",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Inferno Canto I"))
+        .stdout(predicate::str::contains("Nel mezzo del cammin"))
+        .stdout(predicate::str::contains(">   2: mi ritrovai"))
+        .stdout(predicate::str::contains("Ahi quanto").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["open-citation", "nowhere"]);
+
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid citation"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["open-citation", "Paradiso 34.1"]);
+
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("Canto 34 not found in Paradiso"));
+}
+
+#[test]
+fn test_cli_tercet() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["tercet", "Inferno 5.101"]);
+    cmd.assert().success().stdout(
+        "Inferno Canto V\n\n100: Amor, ch’al cor gentil ratto s’apprende,\n\
+         101: prese costui de la bella persona\n\
+         102: che mi fu tolta; e ’l modo ancor m’offende.\n",
+    );
+
+    // The closing line of a canto is a tercet of its own
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["tercet", "par 33.145", "--plain"]);
+    cmd.assert()
+        .success()
+        .stdout("l’amor che move il sole e l’altre stelle.\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["tercet", "Inferno 1.137"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("the canto has 136 lines"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["tercet", "Inferno 1"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid citation"));
+}
+
+#[test]
+fn test_cli_search_patterns_from_stdin() {
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "-f", "-"])
+        .write_stdin("selva oscura\n\nxyznomatch123\nstelle\n");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 1 matches for 'selva oscura'",
+        ))
+        .stdout(predicate::str::contains(
+            "No matches found for 'xyznomatch123'",
+        ))
+        .stdout(predicate::str::contains("matches for 'stelle'"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("search");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("<PATTERN>"));
+}
+
+#[test]
+fn test_cli_cantica_abbreviations() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "Inf", "1"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Inferno Canto I"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "paradise", "33"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Paradiso Canto XXXIII"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "stelle", "-c", "PAR"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Paradiso"))
+        .stdout(predicate::str::contains("Inferno").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "stelle", "-c", "limbo"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid cantica"));
+}
+
+#[test]
+fn test_cli_canto_roman_numerals() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "XXVI"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Inferno Canto XXVI"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "paradiso", "xxxiii"]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Paradiso Canto XXXIII"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "IIII"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid canto number 'IIII'"));
+}
+
+#[test]
+fn test_cli_canto_commentary() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-commentary-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("notes.toml"),
+        r#"
+source = "Test notes"
+
+[[entry]]
+passage = "Inferno 1"
+text = "The dark wood."
+
+[[entry]]
+passage = "Inferno 1.1-3"
+text = "The opening tercet."
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_COMMENTARY_DIR", &dir)
+        .args(["canto", "inferno", "1", "--commentary"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[Test notes] Inferno 1: The dark wood.",
+        ))
+        .stdout(
+            predicate::str::is_match(
+                r"  3: [^\n]*\n\s+\[Test notes\] Inferno 1\.1-3: The opening tercet\.\n  4: ",
+            )
+            .unwrap(),
+        );
+
+    // Without the flag no notes are shown
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_COMMENTARY_DIR", &dir)
+        .args(["canto", "inferno", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Test notes").not());
+
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_COMMENTARY_DIR", &dir)
+        .args(["canto", "inferno", "1", "--commentary"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("broken"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_allusions() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "purgatorio", "30", "--allusions"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        " 48: conosco i segni de l’antica fiamma’.\n     [Aeneid 4.23] «Agnosco veteris",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "purgatorio", "30"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Aeneid").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["allusions", "inferno"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Inferno 1.1: Isaiah 38.10\n  1: Nel mezzo del cammin di nostra vita\n     «In dimidio",
+        ))
+        .stdout(predicate::str::contains("Metamorphoses"))
+        .stdout(predicate::str::contains("Purgatorio").not());
+}
+
+#[test]
+fn test_cli_canto_variants() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-apparatus-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("variants.toml"),
+        r#"
+source = "Test apparatus"
+
+[[variant]]
+line = "Inferno 1.4"
+lemma = "Ahi quanto"
+reading = "E quanto"
+witnesses = "Ash, Ham"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_APPARATUS_DIR", &dir)
+        .args(["canto", "inferno", "1", "--variants"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "  4: Ahi quanto a dir qual era è cosa dura\n     [Test apparatus] Ahi quanto] E quanto (Ash, Ham)\n  5: ",
+    ));
+
+    // Without the flag no variants are shown
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_APPARATUS_DIR", &dir)
+        .args(["canto", "inferno", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Test apparatus").not());
+
+    std::fs::write(dir.join("broken.toml"), "[[variant]]\nline = \"Inferno\"").unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_APPARATUS_DIR", &dir)
+        .args(["canto", "inferno", "1", "--variants"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("broken"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_canto_highlight() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "--color",
+        "always",
+        "canto",
+        "inferno",
+        "5",
+        "--highlight",
+        "amor",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\x1b[1;31mAmor\x1b[0m, ch’al cor gentil",
+        ))
+        .stdout(predicate::str::contains(
+            "s’ancise \x1b[1;31mamor\x1b[0mosa",
+        ))
+        // Glossed words outside the matches are still underlined
+        .stdout(predicate::str::contains("\x1b[4mratto\x1b[0m"));
+
+    // Uncolored, the canto reads as usual
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "5", "--highlight", "amor"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("100: Amor, ch’al cor gentil"))
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_cli_canto_around() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "canto",
+        "inferno",
+        "26",
+        "--around",
+        "118",
+        "--context",
+        "1",
+    ]);
+    cmd.assert().success().stdout(
+        "Inferno Canto XXVI\n\n  117: di retro al sol, del mondo sanza gente.\n\
+         > 118: Considerate la vostra semenza:\n  119: fatti non foste a viver come bruti,\n",
+    );
+
+    // Near the start of the canto the window is cut short
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "26", "--around", "1", "--plain"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let lines = String::from_utf8(output).unwrap();
+    assert_eq!(lines.lines().count(), 6);
+    assert!(lines.starts_with("Godi, Fiorenza, poi che se’ sì grande\n"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "26", "--around", "143"]);
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("Inferno XXVI.143"))
+        .stderr(predicate::str::contains("142 lines"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "26", "--context", "3"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_cli_canto_json() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "I", "--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let canto: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(canto["schema_version"], 1);
+    assert_eq!(canto["cantica"], "Inferno");
+    assert_eq!(canto["number"], 1);
+    assert_eq!(canto["roman_numeral"], "I");
+    assert_eq!(canto["incipit"], "Nel mezzo del cammin di nostra vita");
+    let verses = canto["verses"].as_array().unwrap();
+    assert_eq!(verses.len(), 136);
+    assert_eq!(verses[1]["line_number"], 2);
+    assert_eq!(verses[1]["text"], "mi ritrovai per una selva oscura,");
+
+    // An excerpt holds just the lines asked for
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "canto",
+        "inferno",
+        "26",
+        "--around",
+        "118",
+        "--context",
+        "2",
+    ]);
+    cmd.args(["--format", "json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let canto: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let lines: Vec<_> = canto["verses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["line_number"].as_u64().unwrap())
+        .collect();
+    assert_eq!(lines, [116, 117, 118, 119, 120]);
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "1", "--format", "json", "--plain"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_cli_search_json_and_schema() {
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "-f", "-", "--format", "json"])
+        .write_stdin("selva oscura\nxyznomatch123\n");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let documents: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(documents.len(), 2);
+    assert_eq!(documents[0]["schema_version"], 1);
+    assert_eq!(documents[0]["pattern"], "selva oscura");
+    assert_eq!(documents[0]["count"], 1);
+    let result = &documents[0]["results"][0];
+    assert_eq!(result["cantica"], "Inferno");
+    assert_eq!(result["line"], 2);
+    assert_eq!(
+        result["spans"][0],
+        serde_json::json!({"start": 20, "end": 32})
+    );
+    assert_eq!(documents[1]["count"], 0);
+
+    for command in ["canto", "search"] {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.args(["schema", command]);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        let schema: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert!(schema["$schema"]
+            .as_str()
+            .unwrap()
+            .contains("json-schema.org"));
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&"schema_version".into()));
+    }
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva", "--format", "json", "--picker"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn test_cli_quickfix() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "selva oscura", "--format", "quickfix"]);
+    cmd.assert()
+        .success()
+        .stdout("duca://inferno/1:2: mi ritrovai per una selva oscura,\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args([
+        "canto",
+        "inf",
+        "V",
+        "--around",
+        "100",
+        "--context",
+        "1",
+        "--format",
+        "quickfix",
+    ]);
+    cmd.assert().success().stdout(
+        "duca://inferno/5:99: per aver pace co’ seguaci sui.\n\
+         duca://inferno/5:100: Amor, ch’al cor gentil ratto s’apprende,\n\
+         duca://inferno/5:101: prese costui de la bella persona\n",
+    );
+
+    // An editor jumps to the line with rpc's lookup of its file
+    let mut cmd = assert_cmd::Command::cargo_bin("duca").unwrap();
+    cmd.arg("rpc").write_stdin(
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "lookup", "params": {"uri": "duca://inferno/5", "line": 100}}"#,
+    );
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Amor, ch’al cor gentil"));
+}
+
+#[test]
+fn test_cli_open_web_print() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env_remove("DUCA_WEB_URL")
+        .args(["open-web", "inferno", "5", "100", "--print"]);
+    cmd.assert()
+        .success()
+        .stdout("https://digitaldante.columbia.edu/dante/divine-comedy/inferno/inferno-5/\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WEB_URL", "https://example.org/{abbr}/{canto}#{line}")
+        .args(["open-web", "par", "XXXIII", "145", "--print"]);
+    cmd.assert()
+        .success()
+        .stdout("https://example.org/Par/33#145\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["open-web", "inferno", "40", "--print"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn test_cli_gloss() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["gloss", "Convien"]);
+    cmd.assert().success().stdout(predicate::str::starts_with(
+        "convene (conven, convien, convenne): it is fitting",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["gloss", "selva"]);
+    cmd.assert().success().stdout("No gloss for 'selva'\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("gloss");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("altrui: "))
+        .stdout(predicate::str::contains("uopo: "));
+
+    // Glossed words are underlined in colored canto output only
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["--color", "always", "canto", "inferno", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[4maltrui\x1b[0m"));
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["canto", "inferno", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_cli_latin() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("latin");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Inferno 34.1: «Vexilla regis prodeunt inferni",
+        ))
+        .stdout(predicate::str::contains("Paradiso 15.28: "));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["latin", "purgatorio"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Purgatorio 16.19: Pur ‘Agnus Dei’",
+        ))
+        .stdout(predicate::str::contains("Inferno").not())
+        .stdout(predicate::str::contains("Paradiso").not());
+
+    // Only the Latin is italicized
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["--color", "always", "latin", "purgatorio"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Pur ‘\x1b[3mAgnus Dei\x1b[0m’"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["latin", "limbo"]);
+    cmd.assert().failure().code(2);
+}
+
+#[test]
+fn test_cli_editions() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-editions-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("petrocchi")).unwrap();
+    std::fs::write(
+        dir.join("petrocchi/inferno.txt"),
+        "Canto I\n\nNel mezzo del cammin di nostra vita\nmi ritrovai per una selva oscura,\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir).arg("editions");
+    cmd.assert()
+        .success()
+        .stdout("gutenberg (built in) *\npetrocchi\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["--edition", "petrocchi", "canto", "inferno", "1"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  2: mi ritrovai per una selva oscura,",
+        ))
+        .stdout(predicate::str::contains("  3: ").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["search", "selva", "--edition", "petrocchi"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 matches"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["--edition", "petrochi", "canto", "inferno", "1"]);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Unknown edition 'petrochi'. Available: gutenberg, petrocchi",
+        ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_edition_parse_spec() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-spec-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("numbered")).unwrap();
+    std::fs::write(
+        dir.join("numbered/inferno.txt"),
+        "INFERNO\n\nCANTO 1\nNel mezzo del cammin di nostra vita\n[p. 1]\nmi ritrovai per una selva oscura,\nFINE\nnot a verse\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("numbered/spec.toml"),
+        "section = '^CANTO (\\d+)$'\nskip = ['^\\[p\\. \\d+\\]$']\nend = ['^FINE$']\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir).args([
+        "--edition",
+        "numbered",
+        "canto",
+        "inferno",
+        "1",
+        "--plain",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("Nel mezzo del cammin di nostra vita\nmi ritrovai per una selva oscura,\n");
+
+    std::fs::write(dir.join("numbered/spec.toml"), "section = '^CANTO'\n").unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["--edition", "numbered", "canto", "inferno", "1"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("spec.toml"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_tei_edition() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-tei-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("tei")).unwrap();
+    std::fs::write(
+        dir.join("tei/commedia.xml"),
+        r#"<TEI xmlns="http://www.tei-c.org/ns/1.0"><text><body>
+  <div type="cantica" n="Paradiso">
+    <div type="canto" n="33">
+      <lg><l n="1">Vergine Madre, figlia del tuo figlio,</l>
+      <l n="2">umile e alta più che creatura,<note>Bernard's prayer</note></l></lg>
+    </div>
+  </div>
+</body></text></TEI>"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["--edition", "tei", "canto", "paradiso", "33"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "  2: umile e alta più che creatura,\n",
+        ))
+        .stdout(predicate::str::contains("Bernard").not());
+
+    std::fs::write(dir.join("tei/commedia.xml"), "<TEI><l>").unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .args(["--edition", "tei", "canto", "paradiso", "33"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("commedia.xml"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_work() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-works-{}", std::process::id()));
+    let work = dir.join("vita-nuova");
+    std::fs::create_dir_all(&work).unwrap();
+    std::fs::write(
+        work.join("work.toml"),
+        "title = \"Vita Nuova\"\n\n[[part]]\nname = \"Vita Nuova\"\nfile = \"vn.txt\"\n",
+    )
+    .unwrap();
+    std::fs::write(work.join("spec.toml"), r"section = '^([IVXL]+)\.$'").unwrap();
+    std::fs::write(
+        work.join("vn.txt"),
+        "LA VITA NUOVA\n\nI.\nIn quella parte del libro de la mia memoria\n\nII.\nNove fiate già appresso lo mio nascimento\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["--work", "vita-nuova", "canto", "vita", "II"]);
+    cmd.assert()
+        .success()
+        .stdout("Vita Nuova II\n\n  1: Nove fiate già appresso lo mio nascimento\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["--work", "vita-nuova", "canto", "vita", "43"]);
+    cmd.assert().code(1).stderr(predicate::str::contains(
+        "Section 43 not found in Vita Nuova",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["search", "memoria", "--work", "vita-nuova"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Vita Nuova 1.1: In quella parte del libro de la mia memoria",
+    ));
+
+    // The Commedia is still the default
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir).args(["search", "memoria"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Vita Nuova").not());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["--work", "vita-nuova", "canto", "inferno", "1"]);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("No part 'inferno' in Vita Nuova"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["--work", "vita-nuova", "latin"]);
+    cmd.assert().failure().code(2);
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_WORKS_DIR", &dir)
+        .args(["--work", "convivio", "canto", "i", "1"]);
+    cmd.assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Unknown work 'convivio'. Available: commedia, vita-nuova",
+        ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_works_management() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-install-{}", std::process::id()));
+    let sources = dir.join("sources");
+    std::fs::create_dir_all(sources.join("vita-nuova")).unwrap();
+    std::fs::create_dir_all(sources.join("petrocchi")).unwrap();
+    std::fs::write(
+        sources.join("vita-nuova/work.toml"),
+        "title = \"Vita Nuova\"\nauthor = \"Dante Alighieri\"\n\n[[part]]\nname = \"Vita Nuova\"\nfile = \"vn.txt\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        sources.join("vita-nuova/spec.toml"),
+        r"section = '^([IVXL]+)\.$'",
+    )
+    .unwrap();
+    std::fs::write(
+        sources.join("vita-nuova/vn.txt"),
+        "I.\nIn quella parte del libro de la mia memoria\n\nII.\nNove fiate già appresso lo mio nascimento\nquasi a uno medesimo punto\n",
+    )
+    .unwrap();
+    std::fs::write(
+        sources.join("petrocchi/inferno.txt"),
+        "Canto I\n\nNel mezzo del cammin di nostra vita\n",
+    )
+    .unwrap();
+    let duca = || {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_WORKS_DIR", dir.join("works"))
+            .env("DUCA_EDITIONS_DIR", dir.join("editions"))
+            .env("NO_COLOR", "1");
+        cmd
+    };
+
+    duca()
+        .args(["works", "add"])
+        .arg(sources.join("vita-nuova"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed work 'vita-nuova'"));
+    duca()
+        .args(["works", "add", "--name", "pet"])
+        .arg(sources.join("petrocchi"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed edition 'pet'"));
+
+    // Adding under a name that's taken needs --force
+    duca()
+        .args(["works", "add"])
+        .arg(sources.join("vita-nuova"))
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("already installed"));
+    duca()
+        .args(["works", "add", "--force"])
+        .arg(sources.join("vita-nuova"))
+        .assert()
+        .success();
+    duca()
+        .args(["works", "add", "--name", "commedia"])
+        .arg(sources.join("vita-nuova"))
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Can't install 'commedia': that name is built in",
+        ));
+
+    duca()
+        .args(["works", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("commedia"))
+        .stdout(predicate::str::contains("vita-nuova"))
+        .stdout(predicate::str::contains("gutenberg"))
+        .stdout(predicate::str::contains("pet "));
+
+    duca()
+        .args(["works", "info", "vita-nuova"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Author:   Dante Alighieri"))
+        .stdout(predicate::str::contains("Sections: 2"))
+        .stdout(predicate::str::contains("Lines:    3"))
+        .stdout(predicate::str::contains("SHA-256:  "))
+        .stdout(predicate::str::contains("changed").not());
+
+    // Editing an installed file is noticed
+    std::fs::write(
+        dir.join("works/vita-nuova/vn.txt"),
+        "I.\nIn quella parte del libro de la mia memoria\n",
+    )
+    .unwrap();
+    duca()
+        .args(["works", "info", "vita-nuova"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Lines:    1"))
+        .stdout(predicate::str::contains("(changed since it was added)"));
+
+    duca()
+        .args(["works", "info", "pet"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("edition of the Commedia"))
+        .stdout(predicate::str::contains("Sections: 1"));
+    duca()
+        .args(["works", "info", "commedia"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sections: 100"))
+        .stdout(predicate::str::contains("Origin:   built in"));
+
+    duca()
+        .args(["works", "remove", "pet"])
+        .assert()
+        .success()
+        .stdout("Removed edition 'pet'\n");
+    duca()
+        .args(["works", "remove", "pet"])
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Unknown work 'pet'. Available: vita-nuova",
+        ));
+    assert!(!dir.join("editions/pet").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_fetch() {
+    use std::sync::{Arc, Mutex};
+
+    let dir = std::env::temp_dir().join(format!("duca-cli-fetch-{}", std::process::id()));
+    let text = Arc::new(Mutex::new("Canto I\n\nNel mezzo del cammin".to_string()));
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr());
+    let served = Arc::clone(&text);
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let body = format!("{} {}", request.url(), served.lock().unwrap());
+            request
+                .respond(tiny_http::Response::from_string(body))
+                .unwrap();
+        }
+    });
+    let duca = |url: &str| {
+        let mut cmd = Command::cargo_bin("duca").unwrap();
+        cmd.env("DUCA_CACHE_DIR", dir.join("cache"))
+            .env("DUCA_FETCH_URL", url)
+            .arg("fetch")
+            .arg(dir.join("out"));
+        cmd
+    };
+
+    duca(&url)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("inferno.txt (downloaded)"))
+        .stdout(predicate::str::contains("paradiso.txt (downloaded)"));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("out/purgatorio.txt")).unwrap(),
+        "/998/pg998.txt Canto I\n\nNel mezzo del cammin"
+    );
+    assert!(dir.join("cache/checksums.toml").is_file());
+
+    // Cached copies need no network
+    duca("http://127.0.0.1:9")
+        .arg("--offline")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("inferno.txt (from the cache)"));
+
+    // A text that changed upstream is refused unless forced
+    *text.lock().unwrap() = "Canto I\n\nNel mezo del cammin".to_string();
+    duca(&url)
+        .arg("--refresh")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("changed upstream"));
+    duca(&url)
+        .args(["--refresh", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "inferno.txt (downloaded, replacing a changed copy)",
+        ));
+    assert!(std::fs::read_to_string(dir.join("out/inferno.txt"))
+        .unwrap()
+        .ends_with("Nel mezo del cammin"));
+
+    // An emptied cache can't serve offline
+    std::fs::remove_dir_all(dir.join("cache")).unwrap();
+    duca(&url)
+        .arg("--offline")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no good copy in the cache"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_verify() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("verify");
+    cmd.assert()
+        .success()
+        .stdout("Edition 'gutenberg' verified: 100 cantos, 14233 lines\n");
+
+    // A truncated edition is reported, canto by canto
+    let dir = std::env::temp_dir().join(format!("duca-cli-verify-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("partial")).unwrap();
+    std::fs::write(
+        dir.join("partial/inferno.txt"),
+        "Canto I\n\nNel mezzo del cammin di nostra vita\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.env("DUCA_EDITIONS_DIR", &dir)
+        .env("NO_COLOR", "1")
+        .args(["--edition", "partial", "verify"]);
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "Inferno has 1 cantos, expected 34",
+        ))
+        .stdout(predicate::str::contains(
+            "Inferno 1 has 1 lines, expected 136",
+        ))
+        .stdout(predicate::str::contains("Paradiso 33 is missing"))
+        .stdout(predicate::str::contains(
+            "The poem has 1 lines, expected 14233",
+        ))
+        .stderr(predicate::str::contains(
+            "Edition 'partial' failed verification",
+        ));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_parse_paths() {
+    let dir = std::env::temp_dir().join(format!("duca-cli-parse-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sources")).unwrap();
+    std::fs::write(
+        dir.join("sources/inferno.txt"),
+        "Canto I\n\nNel mezzo del cammin di nostra vita\nmi ritrovai per una selva oscura,\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("sources/paradiso.txt"),
+        "Canto I\n\nLa gloria di colui che tutto move\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("parse")
+        .arg(dir.join("sources/*.txt"))
+        .arg("--output")
+        .arg(dir.join("out"));
+    cmd.env("NO_COLOR", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("inferno.txt: 1 cantos\n"))
+        .stdout(predicate::str::contains(
+            "Inferno: 1 cantos, 2 lines\n  lines per canto: 2\n",
+        ))
+        .stdout(predicate::str::contains("Purgatorio: 0 cantos, 0 lines\n"))
+        .stderr(predicate::str::contains(
+            "warning: Paradiso 1 has only 1 lines",
+        ));
+    let json = std::fs::read_to_string(dir.join("out/commedia.json")).unwrap();
+    assert!(json.contains("La gloria di colui che tutto move"));
+    assert!(dir.join("out/commedia.bin").is_file());
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.arg("parse").arg(dir.join("sources/*.xml"));
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("no files match"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_speeches() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["speeches", "inferno"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Inferno 1.65-66 (Dante)\n 65: «Miserere di me», gridai a lui,",
+        ))
+        .stdout(predicate::str::contains("Inferno 5.121-138 (Francesca)"))
+        .stdout(predicate::str::contains("Purgatorio").not());
+
+    // Speeches outside the speaker list are still cited, without a name
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["speeches", "purgatorio"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Purgatorio 1.40-41\n"));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["speeches", "limbo"]);
+    cmd.assert().failure().code(2);
+}
+
+#[test]
+fn test_cli_graph_characters() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["graph", "characters"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("graph characters {\n"))
+        .stdout(predicate::str::contains("    \"Francesca\" [cantos=1];\n"))
+        .stdout(predicate::str::contains(
+            "    \"Dante\" -- \"Francesca\" [weight=1, label=1];\n",
+        ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["graph", "characters", "--format", "gexf"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "<gexf xmlns=\"http://gexf.net/1.3\"",
+        ))
+        .stdout(predicate::str::contains("label=\"Francesca\""));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["graph", "characters", "--format", "png"]);
+    cmd.assert().failure().code(2);
+}
+
+#[test]
+fn test_cli_xref() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["xref", "inferno", "1"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Inferno 1.121-123 → Purgatorio 30.31-33 (prophecy)\n  Virgil promises",
+    ));
+
+    // From the other end
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["xref", "purgatorio", "XXX"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Purgatorio 30.31-33 → Inferno 1.121-123 (prophecy)",
+    ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["xref", "purgatorio", "2"]);
+    cmd.assert()
+        .success()
+        .stdout("No cross-references listed for Purgatorio 2\n");
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["xref", "inferno", "35"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_cli_search_speaker_filter() {
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "speaker:ulisse virtute"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 matches"))
+        .stdout(predicate::str::contains(
+            "Inferno 26.120: ma per seguir virtute e canoscenza",
+        ));
+
+    let mut cmd = Command::cargo_bin("duca").unwrap();
+    cmd.args(["search", "speaker:bernardo", "--picker"]);
+    cmd.assert().success().stdout(predicate::str::starts_with(
+        "Paradiso 33.1: «Vergine Madre, figlia del tuo figlio,",
+    ));
+}
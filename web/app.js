@@ -0,0 +1,125 @@
+"use strict";
+
+const state = { cantica: "inferno", canto: null };
+
+const $ = (selector) => document.querySelector(selector);
+
+function escapeHtml(text) {
+  const div = document.createElement("div");
+  div.textContent = text;
+  return div.innerHTML;
+}
+
+// `text` with the `spans` a search matched, [start, end] offsets in
+// characters as the server gives them, marked
+function highlight(text, spans) {
+  const chars = Array.from(text);
+  let html = "";
+  let at = 0;
+  for (const [start, end] of spans) {
+    if (start < at) {
+      continue;
+    }
+    html += escapeHtml(chars.slice(at, start).join("")) +
+      `<mark>${escapeHtml(chars.slice(start, end).join(""))}</mark>`;
+    at = end;
+  }
+  return html + escapeHtml(chars.slice(at).join(""));
+}
+
+async function api(path) {
+  const response = await fetch(path);
+  const body = await response.json();
+  if (!response.ok) {
+    throw new Error(body.error || response.statusText);
+  }
+  return body;
+}
+
+async function showCantica(cantica) {
+  state.cantica = cantica;
+  document.querySelectorAll("#cantica-tabs button").forEach((button) => {
+    button.classList.toggle("active", button.dataset.cantica === cantica);
+  });
+
+  const data = await api(`/cantica/${cantica}`);
+  $("#canto-list").innerHTML = data.cantos
+    .map((n) => `<li><a href="#/${cantica}/${n}" data-canto="${n}">${n}</a></li>`)
+    .join("");
+}
+
+async function showCanto(cantica, number, highlightLine) {
+  if (cantica !== state.cantica) {
+    await showCantica(cantica);
+  }
+  state.canto = number;
+  document.querySelectorAll("#canto-list a").forEach((link) => {
+    link.classList.toggle("active", Number(link.dataset.canto) === number);
+  });
+
+  try {
+    const canto = await api(`/cantica/${cantica}/canto/${number}`);
+    $("#title").textContent = `${canto.cantica} — Canto ${canto.roman_numeral}`;
+    $("#content").innerHTML = canto.verses
+      .map((verse) => {
+        const cls = verse.line_number === highlightLine ? "verse highlight" : "verse";
+        return `<div class="${cls}" id="line-${verse.line_number}">` +
+          `<span class="num">${verse.line_number}</span>` +
+          `<span>${escapeHtml(verse.text)}</span></div>`;
+      })
+      .join("");
+    if (highlightLine) {
+      document.getElementById(`line-${highlightLine}`)?.scrollIntoView({ block: "center" });
+    } else {
+      window.scrollTo(0, 0);
+    }
+  } catch (error) {
+    $("#title").textContent = error.message;
+    $("#content").innerHTML = "";
+  }
+}
+
+async function search(query, cantica) {
+  const params = new URLSearchParams({ q: query });
+  if (cantica) {
+    params.set("cantica", cantica);
+  }
+  const data = await api(`/search?${params}`);
+
+  $("#title").textContent = `${data.count} risultati per «${query}»`;
+  $("#content").innerHTML = data.count === 0
+    ? '<p class="empty">Nessun risultato.</p>'
+    : data.results
+      .map((r) => {
+        const href = `#/${r.cantica.toLowerCase()}/${r.canto}/${r.line}`;
+        const text = highlight(r.text, r.spans || []);
+        return `<div class="result"><a href="${href}">${r.cantica} ${r.canto}.${r.line}</a>${text}</div>`;
+      })
+      .join("");
+}
+
+function route() {
+  const [cantica, canto, line] = location.hash.replace(/^#\//, "").split("/");
+  if (cantica && canto) {
+    showCanto(cantica, Number(canto), line ? Number(line) : null);
+  } else {
+    showCantica(cantica || state.cantica);
+  }
+}
+
+document.querySelectorAll("#cantica-tabs button").forEach((button) => {
+  button.addEventListener("click", () => {
+    location.hash = `#/${button.dataset.cantica}`;
+  });
+});
+
+$("#search-form").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const query = $("#search-input").value.trim();
+  if (query) {
+    search(query, $("#search-cantica").value);
+  }
+});
+
+window.addEventListener("hashchange", route);
+route();